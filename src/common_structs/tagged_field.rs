@@ -10,6 +10,23 @@ pub struct TaggedField {
     data: Bytes,
 }
 
+impl TaggedField {
+    pub fn new(field_tag: usize, data: impl Into<Bytes>) -> Self {
+        Self {
+            field_tag,
+            data: data.into(),
+        }
+    }
+
+    pub fn field_tag(&self) -> usize {
+        self.field_tag
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
 impl KafkaDeseriarize for TaggedField {
     type Error = io::Error;
     type DependentData<'a> = ();
@@ -20,7 +37,7 @@ impl KafkaDeseriarize for TaggedField {
     {
         let field_tag: usize = reader.read_varint()?;
         let length = reader.read_varint()?;
-        let mut data = Vec::with_capacity(length);
+        let mut data = vec![0u8; length];
         reader.read_exact(&mut data)?;
         Ok(TaggedField {
             field_tag,
@@ -0,0 +1,491 @@
+//! Broker configuration, read from a `server.properties` file and command-line
+//! overrides.
+//!
+//! The command line mirrors `kafka-server-start.sh`:
+//!
+//! ```text
+//! codecrafters-kafka [server.properties] [--override key=value]...
+//! ```
+//!
+//! Keys this broker does not know are ignored, so a stock Kafka
+//! `server.properties` can be used as is.
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::{
+    consts::broker::{
+        DEFAULT_CONNECTIONS_MAX_IDLE_MS, DEFAULT_GROUP_MAX_SESSION_TIMEOUT_MS,
+        DEFAULT_GROUP_MIN_SESSION_TIMEOUT_MS, DEFAULT_HOST, DEFAULT_MAX_CONNECTIONS,
+        DEFAULT_MAX_INCREMENTAL_FETCH_SESSION_CACHE_SLOTS, DEFAULT_MAX_REQUEST_SIZE,
+        DEFAULT_NODE_ID, DEFAULT_NUM_PARTITIONS, DEFAULT_OFFSET_METADATA_MAX_BYTES, DEFAULT_PORT,
+    },
+    records::compression::Compression,
+    storage::{LogConfig, DEFAULT_LOG_DIR},
+};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("line {line_number} of the config file is not a `key=value` pair: {line}")]
+    InvalidLine { line_number: usize, line: String },
+    #[error("invalid value {value:?} for {key}: {reason}")]
+    InvalidValue {
+        key: &'static str,
+        value: String,
+        reason: String,
+    },
+    #[error("invalid command line: {0}")]
+    InvalidArgument(String),
+}
+
+impl ConfigError {
+    fn invalid_value(key: &'static str, value: &str, reason: impl ToString) -> Self {
+        Self::InvalidValue {
+            key,
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// A `NAME://host:port` entry of `listeners` or `advertised.listeners`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listener {
+    pub name: String,
+    /// Empty to bind every interface.
+    pub host: String,
+    pub port: u16,
+}
+
+impl FromStr for Listener {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, address) = s
+            .split_once("://")
+            .ok_or_else(|| format!("{s:?} is not of the form NAME://host:port"))?;
+        if name.is_empty() {
+            return Err(format!("{s:?} has no listener name"));
+        }
+        let (host, port) = address
+            .rsplit_once(':')
+            .ok_or_else(|| format!("{s:?} has no port"))?;
+        let port = port
+            .parse()
+            .map_err(|_| format!("{port:?} is not a valid port"))?;
+        // IPv6 hosts are written in brackets
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        Ok(Self {
+            name: name.to_string(),
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}:{}", self.name, self.host, self.port)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BrokerConfig {
+    /// `node.id`
+    pub node_id: i32,
+    /// The `listeners` entry clients connect to.
+    pub listener: Listener,
+    /// Address handed out in Metadata responses, from `advertised.listeners`.
+    pub advertised_listener: Listener,
+    /// `log.dirs`, or `log.dir`. Partitions and the metadata log live in the
+    /// first one; the others only have to belong to the same cluster.
+    pub log_dirs: Vec<PathBuf>,
+    /// `num.partitions`
+    pub num_partitions: i32,
+    /// `auto.create.topics.enable`
+    pub auto_create_topics_enable: bool,
+    /// `max.connections`
+    pub max_connections: usize,
+    /// `socket.request.max.bytes`
+    pub max_request_size: usize,
+    /// `connections.max.idle.ms`
+    pub connections_max_idle: Duration,
+    /// `compression.type`; `None` for `producer`, which keeps the codec of
+    /// each produced batch.
+    pub compression_type: Option<Compression>,
+    /// `group.min.session.timeout.ms`
+    pub group_min_session_timeout: Duration,
+    /// `group.max.session.timeout.ms`
+    pub group_max_session_timeout: Duration,
+    /// `offset.metadata.max.bytes`
+    pub offset_metadata_max_bytes: usize,
+    /// `max.incremental.fetch.session.cache.slots`
+    pub fetch_session_cache_slots: usize,
+    /// `log.segment.bytes`, `log.roll.ms` (or `log.roll.hours`) and
+    /// `log.index.interval.bytes`
+    pub log: LogConfig,
+}
+
+impl Default for BrokerConfig {
+    fn default() -> Self {
+        let listener = Listener {
+            name: "PLAINTEXT".to_string(),
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+        };
+        Self {
+            node_id: DEFAULT_NODE_ID,
+            advertised_listener: listener.clone(),
+            listener,
+            log_dirs: vec![PathBuf::from(DEFAULT_LOG_DIR)],
+            num_partitions: DEFAULT_NUM_PARTITIONS,
+            auto_create_topics_enable: true,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            connections_max_idle: Duration::from_millis(DEFAULT_CONNECTIONS_MAX_IDLE_MS),
+            compression_type: None,
+            group_min_session_timeout: Duration::from_millis(DEFAULT_GROUP_MIN_SESSION_TIMEOUT_MS),
+            group_max_session_timeout: Duration::from_millis(DEFAULT_GROUP_MAX_SESSION_TIMEOUT_MS),
+            offset_metadata_max_bytes: DEFAULT_OFFSET_METADATA_MAX_BYTES,
+            fetch_session_cache_slots: DEFAULT_MAX_INCREMENTAL_FETCH_SESSION_CACHE_SLOTS,
+            log: LogConfig::default(),
+        }
+    }
+}
+
+impl BrokerConfig {
+    /// The directory partitions and the metadata log are kept in.
+    pub fn log_dir(&self) -> &Path {
+        &self.log_dirs[0]
+    }
+
+    /// Build the config from command-line arguments, without the program name.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut args = args.into_iter();
+        let mut config_file = None;
+        let mut overrides = Vec::new();
+        while let Some(arg) = args.next() {
+            if arg == "--override" {
+                let pair = args.next().ok_or_else(|| {
+                    ConfigError::InvalidArgument("--override needs a key=value".to_string())
+                })?;
+                let (key, value) = pair.split_once('=').ok_or_else(|| {
+                    ConfigError::InvalidArgument(format!("{pair:?} is not a key=value pair"))
+                })?;
+                overrides.push((key.trim().to_string(), value.trim().to_string()));
+            } else if arg.starts_with("--") {
+                return Err(ConfigError::InvalidArgument(format!(
+                    "unknown option {arg}"
+                )));
+            } else if config_file.is_none() {
+                config_file = Some(arg);
+            } else {
+                return Err(ConfigError::InvalidArgument(format!(
+                    "unexpected argument {arg}"
+                )));
+            }
+        }
+        let mut properties = match config_file {
+            Some(path) => read_properties_file(path)?,
+            None => HashMap::new(),
+        };
+        properties.extend(overrides);
+        Self::from_properties(&properties)
+    }
+
+    /// Build and validate the config from `key=value` properties, falling
+    /// back to the defaults for missing keys.
+    pub fn from_properties(properties: &HashMap<String, String>) -> Result<Self, ConfigError> {
+        let get = |key: &str| properties.get(key).map(String::as_str);
+        let mut config = Self::default();
+
+        if let Some(value) = get("node.id") {
+            config.node_id = parse_number("node.id", value)?;
+            if config.node_id < 0 {
+                return Err(ConfigError::invalid_value(
+                    "node.id",
+                    value,
+                    "must not be negative",
+                ));
+            }
+        }
+
+        if let Some(value) = get("listeners") {
+            let controller_listeners: Vec<&str> = get("controller.listener.names")
+                .map(|names| names.split(',').map(str::trim).collect())
+                .unwrap_or_default();
+            let listeners = parse_listeners("listeners", value)?;
+            // a combined-mode node also lists the controller listener; serve
+            // clients on the first one that is not for the controller
+            config.listener = listeners
+                .into_iter()
+                .find(|listener| !controller_listeners.contains(&listener.name.as_str()))
+                .ok_or_else(|| {
+                    ConfigError::invalid_value("listeners", value, "no broker listener")
+                })?;
+        }
+        config.advertised_listener = match get("advertised.listeners") {
+            Some(value) => parse_listeners("advertised.listeners", value)?
+                .into_iter()
+                .find(|listener| listener.name == config.listener.name)
+                .ok_or_else(|| {
+                    ConfigError::invalid_value(
+                        "advertised.listeners",
+                        value,
+                        format!("no entry for listener {}", config.listener.name),
+                    )
+                })?,
+            None => config.listener.clone(),
+        };
+        if config.advertised_listener.host.is_empty() {
+            config.advertised_listener.host = DEFAULT_HOST.to_string();
+        }
+
+        if let Some((key, value)) = [("log.dirs", get("log.dirs")), ("log.dir", get("log.dir"))]
+            .into_iter()
+            .find_map(|(key, value)| Some((key, value?)))
+        {
+            config.log_dirs = value
+                .split(',')
+                .map(str::trim)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .collect();
+            if config.log_dirs.is_empty() {
+                return Err(ConfigError::invalid_value(key, value, "is empty"));
+            }
+        }
+
+        if let Some(value) = get("num.partitions") {
+            config.num_partitions = parse_number("num.partitions", value)?;
+            if config.num_partitions < 1 {
+                return Err(ConfigError::invalid_value(
+                    "num.partitions",
+                    value,
+                    "must be at least 1",
+                ));
+            }
+        }
+        if let Some(value) = get("auto.create.topics.enable") {
+            config.auto_create_topics_enable = value.parse().map_err(|_| {
+                ConfigError::invalid_value("auto.create.topics.enable", value, "expected a boolean")
+            })?;
+        }
+        if let Some(value) = get("max.connections") {
+            config.max_connections = parse_positive("max.connections", value)?;
+        }
+        if let Some(value) = get("socket.request.max.bytes") {
+            config.max_request_size = parse_positive("socket.request.max.bytes", value)?;
+        }
+        if let Some(value) = get("connections.max.idle.ms") {
+            config.connections_max_idle =
+                Duration::from_millis(parse_positive("connections.max.idle.ms", value)?);
+        }
+        if let Some(value) = get("compression.type") {
+            config.compression_type = match value {
+                "producer" => None,
+                codec => Some(codec.parse().map_err(|reason| {
+                    ConfigError::invalid_value("compression.type", value, reason)
+                })?),
+            };
+        }
+        if let Some(value) = get("group.min.session.timeout.ms") {
+            config.group_min_session_timeout =
+                Duration::from_millis(parse_positive("group.min.session.timeout.ms", value)?);
+        }
+        if let Some(value) = get("group.max.session.timeout.ms") {
+            config.group_max_session_timeout =
+                Duration::from_millis(parse_positive("group.max.session.timeout.ms", value)?);
+        }
+        if config.group_min_session_timeout > config.group_max_session_timeout {
+            return Err(ConfigError::invalid_value(
+                "group.max.session.timeout.ms",
+                &config.group_max_session_timeout.as_millis().to_string(),
+                "must not be below group.min.session.timeout.ms",
+            ));
+        }
+        if let Some(value) = get("offset.metadata.max.bytes") {
+            config.offset_metadata_max_bytes = parse_number("offset.metadata.max.bytes", value)?;
+        }
+        if let Some(value) = get("max.incremental.fetch.session.cache.slots") {
+            config.fetch_session_cache_slots =
+                parse_number("max.incremental.fetch.session.cache.slots", value)?;
+        }
+        if let Some(value) = get("log.segment.bytes") {
+            config.log.segment_bytes = parse_positive("log.segment.bytes", value)?;
+        }
+        if let Some(value) = get("log.roll.ms") {
+            config.log.segment_ms = parse_positive("log.roll.ms", value)?;
+        } else if let Some(value) = get("log.roll.hours") {
+            let hours: i64 = parse_positive("log.roll.hours", value)?;
+            config.log.segment_ms = hours.checked_mul(60 * 60 * 1000).ok_or_else(|| {
+                ConfigError::invalid_value("log.roll.hours", value, "is too large")
+            })?;
+        }
+        if let Some(value) = get("log.index.interval.bytes") {
+            config.log.index_interval_bytes = parse_positive("log.index.interval.bytes", value)?;
+        }
+        Ok(config)
+    }
+}
+
+/// Parse the `key=value` (or `key:value`) lines of a Java properties file,
+/// skipping blank lines and `#`/`!` comments.
+fn read_properties_file(path: impl AsRef<Path>) -> Result<HashMap<String, String>, ConfigError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_properties(&contents)
+}
+
+pub(crate) fn parse_properties(contents: &str) -> Result<HashMap<String, String>, ConfigError> {
+    let mut properties = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(['=', ':'])
+            .ok_or_else(|| ConfigError::InvalidLine {
+                line_number: index + 1,
+                line: line.to_string(),
+            })?;
+        properties.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(properties)
+}
+
+fn parse_listeners(key: &'static str, value: &str) -> Result<Vec<Listener>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|listener| !listener.is_empty())
+        .map(|listener| {
+            listener
+                .parse()
+                .map_err(|reason| ConfigError::invalid_value(key, value, reason))
+        })
+        .collect()
+}
+
+fn parse_number<T: FromStr>(key: &'static str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::invalid_value(key, value, "expected a number"))
+}
+
+fn parse_positive<T: FromStr + Default + PartialOrd>(
+    key: &'static str,
+    value: &str,
+) -> Result<T, ConfigError> {
+    let number: T = parse_number(key, value)?;
+    if number <= T::default() {
+        return Err(ConfigError::invalid_value(key, value, "must be positive"));
+    }
+    Ok(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(contents: &str) -> Result<BrokerConfig, ConfigError> {
+        BrokerConfig::from_properties(&parse_properties(contents).unwrap())
+    }
+
+    #[test]
+    fn test_kraft_server_properties() {
+        let config = config(
+            "# combined mode\n\
+             process.roles=broker,controller\n\
+             node.id=3\n\
+             controller.listener.names=CONTROLLER\n\
+             listeners=CONTROLLER://:9093,PLAINTEXT://:19092\n\
+             advertised.listeners=PLAINTEXT://kafka.local:19092,CONTROLLER://localhost:9093\n\
+             log.dirs=/var/lib/kafka\n\
+             num.partitions = 3\n\
+             log.segment.bytes=1048576\n\
+             log.roll.hours=1\n\
+             compression.type=zstd\n",
+        )
+        .unwrap();
+        assert_eq!(config.node_id, 3);
+        assert_eq!(config.listener.to_string(), "PLAINTEXT://:19092");
+        assert_eq!(config.advertised_listener.host, "kafka.local");
+        assert_eq!(config.log_dirs, [PathBuf::from("/var/lib/kafka")]);
+        assert_eq!(config.num_partitions, 3);
+        assert_eq!(config.log.segment_bytes, 1048576);
+        assert_eq!(config.log.segment_ms, 3_600_000);
+        assert_eq!(config.compression_type, Some(Compression::Zstd));
+    }
+
+    #[test]
+    fn test_overrides_win_over_file() {
+        let dir = std::env::temp_dir().join(format!("config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("server.properties");
+        fs::write(&path, "node.id=3\nlisteners=PLAINTEXT://:9092\n").unwrap();
+
+        let args = [
+            path.to_str().unwrap(),
+            "--override",
+            "node.id=5",
+            "--override",
+            "listeners=PLAINTEXT://127.0.0.1:9094",
+        ];
+        let config = BrokerConfig::from_args(args.map(String::from)).unwrap();
+        assert_eq!(config.node_id, 5);
+        assert_eq!(config.listener.port, 9094);
+        // advertised.listeners falls back to the listener
+        assert_eq!(config.advertised_listener.host, "127.0.0.1");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_several_log_dirs() {
+        let config = config("log.dirs=/a, /b,").unwrap();
+        assert_eq!(config.log_dirs, [PathBuf::from("/a"), PathBuf::from("/b")]);
+        assert_eq!(config.log_dir(), Path::new("/a"));
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        assert!(matches!(
+            config("node.id=-1"),
+            Err(ConfigError::InvalidValue { key: "node.id", .. })
+        ));
+        assert!(matches!(
+            config("listeners=PLAINTEXT://:notaport"),
+            Err(ConfigError::InvalidValue {
+                key: "listeners",
+                ..
+            })
+        ));
+        assert!(matches!(
+            config("log.dirs= , "),
+            Err(ConfigError::InvalidValue {
+                key: "log.dirs",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_properties("just a line"),
+            Err(ConfigError::InvalidLine { line_number: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,23 @@
+/// Node id of this broker, which also acts as the (combined mode) controller.
+pub const DEFAULT_NODE_ID: i32 = 1;
+/// Address the broker listens on and advertises to clients.
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 9092;
+/// Partition count for topics created without an explicit count.
+pub const DEFAULT_NUM_PARTITIONS: i32 = 1;
+/// Connections served at once; further clients wait to be accepted.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+/// Largest request frame accepted, as `socket.request.max.bytes`.
+pub const DEFAULT_MAX_REQUEST_SIZE: usize = 100 * 1024 * 1024;
+/// Idle connections are closed after this long, as `connections.max.idle.ms`.
+pub const DEFAULT_CONNECTIONS_MAX_IDLE_MS: u64 = 10 * 60 * 1000;
+/// Bounds on the session timeout a group member may ask for, as
+/// `group.min.session.timeout.ms` and `group.max.session.timeout.ms`.
+pub const DEFAULT_GROUP_MIN_SESSION_TIMEOUT_MS: u64 = 6 * 1000;
+pub const DEFAULT_GROUP_MAX_SESSION_TIMEOUT_MS: u64 = 30 * 60 * 1000;
+/// Longest metadata string stored with a committed offset, as
+/// `offset.metadata.max.bytes`.
+pub const DEFAULT_OFFSET_METADATA_MAX_BYTES: usize = 4096;
+/// Incremental fetch sessions cached at once, as
+/// `max.incremental.fetch.session.cache.slots`.
+pub const DEFAULT_MAX_INCREMENTAL_FETCH_SESSION_CACHE_SLOTS: usize = 1000;
@@ -0,0 +1,21 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const CREATE_PARTITIONS_API_KEY: i16 = 37;
+pub const CREATE_PARTITIONS_MIN_VERSION: i16 = 0;
+pub const CREATE_PARTITIONS_MAX_VERSION: i16 = 3;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportCreatePartitionsRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+}
+
+impl SupportCreatePartitionsRequestVersion {
+    /// Versions 2 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V2
+    }
+}
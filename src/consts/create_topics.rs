@@ -0,0 +1,23 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const CREATE_TOPICS_API_KEY: i16 = 19;
+pub const CREATE_TOPICS_MIN_VERSION: i16 = 2;
+pub const CREATE_TOPICS_MAX_VERSION: i16 = 7;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportCreateTopicsRequestVersion {
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+}
+
+impl SupportCreateTopicsRequestVersion {
+    /// Versions 5 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V5
+    }
+}
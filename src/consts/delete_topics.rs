@@ -0,0 +1,28 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const DELETE_TOPICS_API_KEY: i16 = 20;
+pub const DELETE_TOPICS_MIN_VERSION: i16 = 1;
+pub const DELETE_TOPICS_MAX_VERSION: i16 = 6;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportDeleteTopicsRequestVersion {
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+}
+
+impl SupportDeleteTopicsRequestVersion {
+    /// Versions 4 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V4
+    }
+
+    /// Versions 6 and above name topics by name or by id.
+    pub fn uses_topic_ids(&self) -> bool {
+        *self >= Self::V6
+    }
+}
@@ -1,3 +1,6 @@
 pub const DESCRIBE_TOPIC_PARTITIONS_API_KEY: i16 = 75;
 pub const DESCRIBE_TOPIC_MIN_VERSION: i16 = 0;
-pub const DESCRIBE_TOPIC_MAX_VERSION: i16 = 0;
\ No newline at end of file
+pub const DESCRIBE_TOPIC_MAX_VERSION: i16 = 0;
+/// Cap on `response_partition_limit`, as Kafka's
+/// `max.request.partition.size.limit`.
+pub const DESCRIBE_TOPIC_MAX_PARTITIONS: i32 = 2000;
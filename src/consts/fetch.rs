@@ -1,16 +1,14 @@
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 pub const FETCH_API_KEY: i16 = 1;
-pub const FETCH_MIN_VERSION: i16 = 0;
+/// Versions below 4 predate record batches (message format v2), which is the
+/// only format the log stores.
+pub const FETCH_MIN_VERSION: i16 = 4;
 pub const FETCH_MAX_VERSION: i16 = 16;
 
 #[repr(i16)]
-#[derive(Debug, TryFromPrimitive, IntoPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
 pub enum SupportFetchRequestVersion {
-    V0 = 0,
-    V1 = 1,
-    V2 = 2,
-    V3 = 3,
     V4 = 4,
     V5 = 5,
     V6 = 6,
@@ -24,4 +22,16 @@ pub enum SupportFetchRequestVersion {
     V14 = 14,
     V15 = 15,
     V16 = 16,
-}
\ No newline at end of file
+}
+
+impl SupportFetchRequestVersion {
+    /// Versions 12 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V12
+    }
+
+    /// Versions 13 and above address topics by id instead of by name.
+    pub fn uses_topic_ids(&self) -> bool {
+        *self >= Self::V13
+    }
+}
@@ -0,0 +1,32 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const FIND_COORDINATOR_API_KEY: i16 = 10;
+pub const FIND_COORDINATOR_MIN_VERSION: i16 = 0;
+pub const FIND_COORDINATOR_MAX_VERSION: i16 = 4;
+
+/// `key_type` of a lookup for a consumer group coordinator.
+pub const GROUP_KEY_TYPE: i8 = 0;
+/// `key_type` of a lookup for a transaction coordinator.
+pub const TRANSACTION_KEY_TYPE: i8 = 1;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportFindCoordinatorRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+}
+
+impl SupportFindCoordinatorRequestVersion {
+    /// Versions 3 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V3
+    }
+
+    /// Versions 4 and above look up several keys at once.
+    pub fn is_batched(&self) -> bool {
+        *self >= Self::V4
+    }
+}
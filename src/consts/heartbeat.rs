@@ -0,0 +1,22 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const HEARTBEAT_API_KEY: i16 = 12;
+pub const HEARTBEAT_MIN_VERSION: i16 = 0;
+pub const HEARTBEAT_MAX_VERSION: i16 = 4;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportHeartbeatRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+}
+
+impl SupportHeartbeatRequestVersion {
+    /// Versions 4 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V4
+    }
+}
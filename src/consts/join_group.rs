@@ -0,0 +1,33 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const JOIN_GROUP_API_KEY: i16 = 11;
+pub const JOIN_GROUP_MIN_VERSION: i16 = 0;
+pub const JOIN_GROUP_MAX_VERSION: i16 = 9;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportJoinGroupRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+    V9 = 9,
+}
+
+impl SupportJoinGroupRequestVersion {
+    /// Versions 6 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V6
+    }
+
+    /// Versions 4 and above must rejoin with the member id handed out in a
+    /// `MEMBER_ID_REQUIRED` response before they become members.
+    pub fn requires_known_member_id(&self) -> bool {
+        *self >= Self::V4
+    }
+}
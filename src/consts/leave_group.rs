@@ -0,0 +1,29 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const LEAVE_GROUP_API_KEY: i16 = 13;
+pub const LEAVE_GROUP_MIN_VERSION: i16 = 0;
+pub const LEAVE_GROUP_MAX_VERSION: i16 = 5;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportLeaveGroupRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+}
+
+impl SupportLeaveGroupRequestVersion {
+    /// Versions 4 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V4
+    }
+
+    /// Versions 3 and above remove a batch of members, each by member id or
+    /// group instance id.
+    pub fn is_batched(&self) -> bool {
+        *self >= Self::V3
+    }
+}
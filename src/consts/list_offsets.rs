@@ -0,0 +1,35 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const LIST_OFFSETS_API_KEY: i16 = 2;
+pub const LIST_OFFSETS_MIN_VERSION: i16 = 1;
+pub const LIST_OFFSETS_MAX_VERSION: i16 = 8;
+
+/// Special `timestamp` values a partition can be queried with.
+pub const LATEST_TIMESTAMP: i64 = -1;
+pub const EARLIEST_TIMESTAMP: i64 = -2;
+pub const MAX_TIMESTAMP: i64 = -3;
+/// Same as [`EARLIEST_TIMESTAMP`] while every segment is kept locally.
+pub const EARLIEST_LOCAL_TIMESTAMP: i64 = -4;
+
+/// `isolation_level` of a consumer that only reads committed transactions.
+pub const READ_COMMITTED: i8 = 1;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportListOffsetsRequestVersion {
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+}
+
+impl SupportListOffsetsRequestVersion {
+    /// Versions 6 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V6
+    }
+}
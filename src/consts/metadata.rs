@@ -0,0 +1,30 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const METADATA_API_KEY: i16 = 3;
+pub const METADATA_MIN_VERSION: i16 = 0;
+pub const METADATA_MAX_VERSION: i16 = 12;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportMetadataRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+    V9 = 9,
+    V10 = 10,
+    V11 = 11,
+    V12 = 12,
+}
+
+impl SupportMetadataRequestVersion {
+    /// Versions 9 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V9
+    }
+}
@@ -0,0 +1,24 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const OFFSET_COMMIT_API_KEY: i16 = 8;
+pub const OFFSET_COMMIT_MIN_VERSION: i16 = 2;
+pub const OFFSET_COMMIT_MAX_VERSION: i16 = 8;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportOffsetCommitRequestVersion {
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+}
+
+impl SupportOffsetCommitRequestVersion {
+    /// Versions 8 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V8
+    }
+}
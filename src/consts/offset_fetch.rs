@@ -0,0 +1,30 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const OFFSET_FETCH_API_KEY: i16 = 9;
+pub const OFFSET_FETCH_MIN_VERSION: i16 = 1;
+pub const OFFSET_FETCH_MAX_VERSION: i16 = 8;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportOffsetFetchRequestVersion {
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+}
+
+impl SupportOffsetFetchRequestVersion {
+    /// Versions 6 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V6
+    }
+
+    /// Versions 8 and above fetch the offsets of several groups at once.
+    pub fn is_batched(&self) -> bool {
+        *self >= Self::V8
+    }
+}
@@ -0,0 +1,26 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const PRODUCE_API_KEY: i16 = 0;
+pub const PRODUCE_MIN_VERSION: i16 = 3;
+pub const PRODUCE_MAX_VERSION: i16 = 11;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportProduceRequestVersion {
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+    V9 = 9,
+    V10 = 10,
+    V11 = 11,
+}
+
+impl SupportProduceRequestVersion {
+    /// Versions 9 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V9
+    }
+}
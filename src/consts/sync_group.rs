@@ -0,0 +1,23 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+pub const SYNC_GROUP_API_KEY: i16 = 14;
+pub const SYNC_GROUP_MIN_VERSION: i16 = 0;
+pub const SYNC_GROUP_MAX_VERSION: i16 = 5;
+
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryFromPrimitive, IntoPrimitive)]
+pub enum SupportSyncGroupRequestVersion {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+}
+
+impl SupportSyncGroupRequestVersion {
+    /// Versions 4 and above use the compact (flexible) encoding with tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        *self >= Self::V4
+    }
+}
@@ -0,0 +1,454 @@
+//! Incremental fetch sessions (KIP-227).
+//!
+//! A consumer that keeps fetching the same partitions opens a session with a
+//! fetch of epoch 0. The broker remembers the session's partitions and where
+//! each is read from, so every later fetch, sent with the next epoch, only
+//! lists the partitions that were added or moved and the ones to forget. Its
+//! response in turn leaves out the partitions with nothing new to report.
+//!
+//! Sessions live in a cache with a fixed number of slots. When it is full, a
+//! new session takes the slot of the session idle the longest if that one was
+//! idle for over [`EVICTION_INTERVAL`], or else of the smallest session if the
+//! new one has more partitions. Otherwise the fetch goes on without a session.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use crate::response::error_code::KafkaError;
+
+/// Epoch of a fetch that opens a new session.
+pub const INITIAL_EPOCH: i32 = 0;
+/// Epoch of a fetch that closes its session, or does without one.
+pub const FINAL_EPOCH: i32 = -1;
+/// Session id of a fetch without a session.
+pub const INVALID_SESSION_ID: i32 = 0;
+
+/// Sessions idle for longer may be evicted to make room for any new session.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(120);
+
+/// A partition as fetch requests name it: by topic name before version 13,
+/// by topic id from then on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionPartition {
+    pub topic: String,
+    pub topic_id: [u8; 16],
+    pub partition: i32,
+}
+
+/// Where a partition is read from, as last requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchPosition {
+    pub fetch_offset: i64,
+    pub partition_max_bytes: i32,
+    /// Leader epoch the fetcher knows of, -1 if it does not say.
+    pub current_leader_epoch: i32,
+    /// Epoch of the last batch the fetcher read, -1 if it does not say.
+    pub last_fetched_epoch: i32,
+}
+
+/// The offsets a fetch response reports for a partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionOffsets {
+    pub high_watermark: i64,
+    pub last_stable_offset: i64,
+    pub log_start_offset: i64,
+}
+
+/// How a fetch request reads, once applied to its session.
+#[derive(Debug)]
+pub struct FetchContext {
+    /// Session reported in the response, [`INVALID_SESSION_ID`] without one.
+    pub session_id: i32,
+    /// Whether the response leaves out partitions with nothing new.
+    pub incremental: bool,
+    /// Partitions to read, in response order.
+    pub partitions: Vec<(SessionPartition, FetchPosition)>,
+}
+
+impl FetchContext {
+    /// A fetch of `partitions` outside of any session.
+    pub fn sessionless(partitions: Vec<(SessionPartition, FetchPosition)>) -> Self {
+        Self {
+            session_id: INVALID_SESSION_ID,
+            incremental: false,
+            partitions,
+        }
+    }
+}
+
+struct CachedPartition {
+    position: FetchPosition,
+    /// Offsets in the last response that included the partition.
+    sent: Option<PartitionOffsets>,
+}
+
+struct Session {
+    /// Epoch the next fetch of the session has to carry.
+    epoch: i32,
+    /// In the order responses list them.
+    partitions: Vec<(SessionPartition, CachedPartition)>,
+    last_used: Instant,
+}
+
+impl Session {
+    fn new(partitions: &[(SessionPartition, FetchPosition)], now: Instant) -> Self {
+        let partitions = partitions
+            .iter()
+            .map(|(partition, position)| {
+                let cached = CachedPartition {
+                    position: *position,
+                    sent: None,
+                };
+                (partition.clone(), cached)
+            })
+            .collect();
+        Self {
+            epoch: next_epoch(INITIAL_EPOCH),
+            partitions,
+            last_used: now,
+        }
+    }
+
+    fn get_mut(&mut self, partition: &SessionPartition) -> Option<&mut CachedPartition> {
+        self.partitions
+            .iter_mut()
+            .find(|(key, _)| key == partition)
+            .map(|(_, cached)| cached)
+    }
+}
+
+/// Epoch following `epoch`, wrapping around to 1.
+fn next_epoch(epoch: i32) -> i32 {
+    if epoch == i32::MAX {
+        1
+    } else {
+        epoch + 1
+    }
+}
+
+pub struct FetchSessionCache {
+    max_sessions: usize,
+    sessions: Mutex<HashMap<i32, Session>>,
+}
+
+impl FetchSessionCache {
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            max_sessions,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Apply a fetch of `partitions`, forgetting `forgotten`, to the session
+    /// given by `session_id` and `epoch`.
+    ///
+    /// A full fetch, of epoch 0 or -1, closes the session it names; epoch 0
+    /// then opens a new session with `partitions`, if the cache has room. An
+    /// incremental fetch updates its session and reads all of its partitions.
+    pub fn new_context(
+        &self,
+        session_id: i32,
+        epoch: i32,
+        partitions: Vec<(SessionPartition, FetchPosition)>,
+        forgotten: Vec<SessionPartition>,
+    ) -> Result<FetchContext, KafkaError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+
+        if epoch == INITIAL_EPOCH || epoch == FINAL_EPOCH {
+            if session_id != INVALID_SESSION_ID {
+                sessions.remove(&session_id);
+            }
+            let mut context = FetchContext::sessionless(partitions);
+            if epoch == INITIAL_EPOCH {
+                context.session_id = self.create(&mut sessions, &context.partitions, now);
+            }
+            return Ok(context);
+        }
+
+        let session = sessions
+            .get_mut(&session_id)
+            .ok_or(KafkaError::FetchSessionIdNotFound)?;
+        if session.epoch != epoch {
+            return Err(KafkaError::InvalidFetchSessionEpoch);
+        }
+        for (partition, position) in partitions {
+            match session.get_mut(&partition) {
+                Some(cached) => cached.position = position,
+                None => session.partitions.push((
+                    partition,
+                    CachedPartition {
+                        position,
+                        sent: None,
+                    },
+                )),
+            }
+        }
+        if !forgotten.is_empty() {
+            let forgotten: HashSet<_> = forgotten.into_iter().collect();
+            session
+                .partitions
+                .retain(|(partition, _)| !forgotten.contains(partition));
+        }
+        session.epoch = next_epoch(epoch);
+        session.last_used = now;
+
+        Ok(FetchContext {
+            session_id,
+            incremental: true,
+            partitions: session
+                .partitions
+                .iter()
+                .map(|(partition, cached)| (partition.clone(), cached.position))
+                .collect(),
+        })
+    }
+
+    /// Record what a response of the fetch `context` reports for each of its
+    /// partitions: their offsets and whether they have records or an error.
+    /// Returns whether each partition goes into the response.
+    ///
+    /// An incremental response only includes the partitions with records, an
+    /// error or offsets that changed since the session last sent them. Those
+    /// move to the end of the session, so that partitions left out of a
+    /// response limited by `max_bytes` come first the next time.
+    pub fn update<'a>(
+        &self,
+        context: &FetchContext,
+        responses: impl IntoIterator<Item = (&'a SessionPartition, PartitionOffsets, bool)>,
+    ) -> Vec<bool> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(&context.session_id);
+        let Some(session) = session.filter(|_| context.session_id != INVALID_SESSION_ID) else {
+            return responses.into_iter().map(|_| true).collect();
+        };
+        let mut included = HashSet::new();
+        let keep = responses
+            .into_iter()
+            .map(|(partition, offsets, has_data)| {
+                // the partition may have been forgotten since it was read
+                let Some(cached) = session.get_mut(partition) else {
+                    return !context.incremental;
+                };
+                let changed = has_data || cached.sent != Some(offsets);
+                cached.sent = Some(offsets);
+                if context.incremental && changed {
+                    included.insert(partition.clone());
+                }
+                changed || !context.incremental
+            })
+            .collect();
+        if !included.is_empty() {
+            let (sent, unsent) = std::mem::take(&mut session.partitions)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(partition, _)| included.contains(partition));
+            session.partitions = unsent;
+            session.partitions.extend(sent);
+        }
+        keep
+    }
+
+    /// Add a session of `partitions`, evicting another one if the cache is
+    /// full. Returns its id, or [`INVALID_SESSION_ID`] if there is no room.
+    fn create(
+        &self,
+        sessions: &mut HashMap<i32, Session>,
+        partitions: &[(SessionPartition, FetchPosition)],
+        now: Instant,
+    ) -> i32 {
+        if sessions.len() >= self.max_sessions && !self.evict(sessions, partitions.len(), now) {
+            return INVALID_SESSION_ID;
+        }
+        let session_id = loop {
+            let session_id = Uuid::new_v4().as_u128() as i32 & i32::MAX;
+            if session_id != INVALID_SESSION_ID && !sessions.contains_key(&session_id) {
+                break session_id;
+            }
+        };
+        sessions.insert(session_id, Session::new(partitions, now));
+        session_id
+    }
+
+    /// Evict the session idle the longest if it is stale, or else the
+    /// smallest session if it has fewer than `size` partitions.
+    fn evict(&self, sessions: &mut HashMap<i32, Session>, size: usize, now: Instant) -> bool {
+        let stale = sessions
+            .iter()
+            .filter(|(_, session)| now.duration_since(session.last_used) > EVICTION_INTERVAL)
+            .min_by_key(|(_, session)| session.last_used)
+            .map(|(session_id, _)| *session_id);
+        let victim = stale.or_else(|| {
+            sessions
+                .iter()
+                .min_by_key(|(_, session)| (session.partitions.len(), session.last_used))
+                .filter(|(_, session)| session.partitions.len() < size)
+                .map(|(session_id, _)| *session_id)
+        });
+        match victim {
+            Some(session_id) => {
+                sessions.remove(&session_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partition(topic: &str, partition: i32) -> SessionPartition {
+        SessionPartition {
+            topic: topic.to_string(),
+            topic_id: [0; 16],
+            partition,
+        }
+    }
+
+    fn fetch(topic: &str, index: i32, fetch_offset: i64) -> (SessionPartition, FetchPosition) {
+        let position = FetchPosition {
+            fetch_offset,
+            partition_max_bytes: 1024,
+            current_leader_epoch: -1,
+            last_fetched_epoch: -1,
+        };
+        (partition(topic, index), position)
+    }
+
+    fn offsets(high_watermark: i64) -> PartitionOffsets {
+        PartitionOffsets {
+            high_watermark,
+            last_stable_offset: high_watermark,
+            log_start_offset: 0,
+        }
+    }
+
+    #[test]
+    fn test_incremental_session() {
+        let cache = FetchSessionCache::new(10);
+        let full = cache
+            .new_context(
+                0,
+                INITIAL_EPOCH,
+                vec![fetch("foo", 0, 0), fetch("foo", 1, 0)],
+                Vec::new(),
+            )
+            .unwrap();
+        assert_ne!(full.session_id, INVALID_SESSION_ID);
+        assert!(!full.incremental);
+        let (foo0, foo1) = (partition("foo", 0), partition("foo", 1));
+        let keep = cache.update(
+            &full,
+            [(&foo0, offsets(5), true), (&foo1, offsets(0), false)],
+        );
+        assert_eq!(keep, [true, true]);
+
+        // foo-0 moved on, foo-1 is unchanged, bar-0 is new
+        let session_id = full.session_id;
+        let context = cache
+            .new_context(
+                session_id,
+                1,
+                vec![fetch("foo", 0, 5), fetch("bar", 0, 0)],
+                Vec::new(),
+            )
+            .unwrap();
+        assert!(context.incremental);
+        let read: Vec<_> = context
+            .partitions
+            .iter()
+            .map(|(p, pos)| (p.clone(), pos.fetch_offset))
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (foo0.clone(), 5),
+                (foo1.clone(), 0),
+                (partition("bar", 0), 0)
+            ]
+        );
+        let bar0 = partition("bar", 0);
+        let keep = cache.update(
+            &context,
+            [
+                (&foo0, offsets(5), false),
+                (&foo1, offsets(0), false),
+                (&bar0, offsets(3), true),
+            ],
+        );
+        assert_eq!(keep, [false, false, true]);
+
+        // forgetting foo-1; a wrong or unknown epoch or id is refused
+        let context = cache
+            .new_context(session_id, 2, Vec::new(), vec![foo1.clone()])
+            .unwrap();
+        let read: Vec<_> = context.partitions.into_iter().map(|(p, _)| p).collect();
+        assert_eq!(read, [foo0, bar0]);
+        assert_eq!(
+            cache
+                .new_context(session_id, 2, Vec::new(), Vec::new())
+                .unwrap_err(),
+            KafkaError::InvalidFetchSessionEpoch
+        );
+        assert_eq!(
+            cache
+                .new_context(session_id ^ 1, 3, Vec::new(), Vec::new())
+                .unwrap_err(),
+            KafkaError::FetchSessionIdNotFound
+        );
+
+        // a final fetch closes the session
+        let context = cache
+            .new_context(session_id, FINAL_EPOCH, Vec::new(), Vec::new())
+            .unwrap();
+        assert_eq!(context.session_id, INVALID_SESSION_ID);
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let cache = FetchSessionCache::new(1);
+        let small = cache
+            .new_context(0, INITIAL_EPOCH, vec![fetch("foo", 0, 0)], Vec::new())
+            .unwrap();
+        assert_ne!(small.session_id, INVALID_SESSION_ID);
+
+        // no larger than the cached session: goes without one
+        let same = cache
+            .new_context(0, INITIAL_EPOCH, vec![fetch("bar", 0, 0)], Vec::new())
+            .unwrap();
+        assert_eq!(same.session_id, INVALID_SESSION_ID);
+
+        // larger: takes the slot of the smaller session
+        let large = cache
+            .new_context(
+                0,
+                INITIAL_EPOCH,
+                vec![fetch("bar", 0, 0), fetch("bar", 1, 0)],
+                Vec::new(),
+            )
+            .unwrap();
+        assert_ne!(large.session_id, INVALID_SESSION_ID);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache
+                .new_context(small.session_id, 1, Vec::new(), Vec::new())
+                .unwrap_err(),
+            KafkaError::FetchSessionIdNotFound
+        );
+        assert!(cache
+            .new_context(large.session_id, 1, Vec::new(), Vec::new())
+            .is_ok());
+    }
+}
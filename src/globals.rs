@@ -1,5 +1,20 @@
-use std::sync::{Arc, OnceLock, RwLock};
-use crate::records::RecordBatch;
+use crate::fetch_session::FetchSessionCache;
+use crate::group::GroupCoordinator;
+use crate::metadata::MetadataImage;
+use crate::producer_id::ProducerIdManager;
+use crate::storage::log_dir::MetaProperties;
+use crate::storage::LogManager;
+use std::sync::{OnceLock, RwLock};
 
 // 定义全局变量，使用标准库的OnceLock
-pub static RECORD_BATCHES: OnceLock<Arc<RwLock<Vec<RecordBatch>>>> = OnceLock::new();
\ No newline at end of file
+pub static METADATA_IMAGE: OnceLock<RwLock<MetadataImage>> = OnceLock::new();
+
+pub static LOG_MANAGER: OnceLock<LogManager> = OnceLock::new();
+
+pub static GROUP_COORDINATOR: OnceLock<GroupCoordinator> = OnceLock::new();
+
+pub static META_PROPERTIES: OnceLock<MetaProperties> = OnceLock::new();
+
+pub static FETCH_SESSION_CACHE: OnceLock<FetchSessionCache> = OnceLock::new();
+
+pub static PRODUCER_ID_MANAGER: OnceLock<ProducerIdManager> = OnceLock::new();
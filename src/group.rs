@@ -0,0 +1,881 @@
+//! Group coordinator for the classic consumer rebalance protocol.
+//!
+//! A rebalance takes a group from `PreparingRebalance`, where every member
+//! has to (re)join, through `CompletingRebalance`, where the leader hands out
+//! the assignments with SyncGroup, to `Stable`. JoinGroup and follower
+//! SyncGroup calls block their handler thread until the step they wait for is
+//! done. There is no timer thread: session and rebalance timeouts are checked
+//! on every coordinator call and by the blocked calls while they wait.
+//!
+//! Committed offsets are kept in an [`OffsetStore`] that writes through to
+//! the `__consumer_offsets` log.
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use crate::{
+    response::error_code::KafkaError,
+    storage::{partition_log::now_ms, LogManager, CONSUMER_OFFSETS_TOPIC},
+};
+
+pub mod offsets;
+mod state;
+
+pub use offsets::{CommittedOffset, OffsetKey, OffsetStore};
+pub use state::GroupState;
+use state::{Group, Member};
+
+/// How often blocked calls wake up to check timeouts when nothing happens.
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A rebalance protocol a member supports, e.g. `range`, with the metadata
+/// the leader needs to assign it partitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Protocol {
+    pub name: String,
+    pub metadata: Vec<u8>,
+}
+
+/// What a member sends with JoinGroup.
+#[derive(Debug, Clone)]
+pub struct JoinParams {
+    pub group_id: String,
+    /// Empty on the first join.
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub client_id: String,
+    pub session_timeout: Duration,
+    pub rebalance_timeout: Duration,
+    pub protocol_type: String,
+    /// Most preferred first.
+    pub protocols: Vec<Protocol>,
+    /// JoinGroup v4+: a member without an id gets one with
+    /// `MEMBER_ID_REQUIRED` and has to join again with it.
+    pub require_known_member_id: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinedMember {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub metadata: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinResult {
+    pub error_code: KafkaError,
+    pub generation_id: i32,
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    pub leader: String,
+    pub member_id: String,
+    /// Every member with its metadata for the chosen protocol; only sent to
+    /// the leader.
+    pub members: Vec<JoinedMember>,
+}
+
+impl JoinResult {
+    pub fn error(error_code: KafkaError, member_id: impl Into<String>) -> Self {
+        Self {
+            error_code,
+            generation_id: -1,
+            protocol_type: None,
+            protocol_name: None,
+            leader: String::new(),
+            member_id: member_id.into(),
+            members: Vec::new(),
+        }
+    }
+}
+
+/// What a member sends with SyncGroup.
+#[derive(Debug, Clone)]
+pub struct SyncParams {
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    /// SyncGroup v5+, checked against the group when present.
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    /// `(member_id, assignment)` pairs, only sent by the leader.
+    pub assignments: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncResult {
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    pub assignment: Vec<u8>,
+}
+
+/// A member leaving, by member id, `group.instance.id`, or both.
+#[derive(Debug, Clone)]
+pub struct LeavingMember {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+}
+
+/// One partition of an OffsetCommit.
+#[derive(Debug, Clone)]
+pub struct PartitionCommit {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    /// `-1` when unknown.
+    pub leader_epoch: i32,
+    pub metadata: String,
+}
+
+/// Every consumer group this broker coordinates.
+pub struct GroupCoordinator {
+    min_session_timeout: Duration,
+    max_session_timeout: Duration,
+    groups: Mutex<HashMap<String, Group>>,
+    /// Signalled whenever a group changes state, for blocked calls.
+    changed: Condvar,
+    /// Locked after `groups` when both are needed.
+    offsets: Mutex<OffsetStore>,
+}
+
+impl GroupCoordinator {
+    pub fn new(min_session_timeout: Duration, max_session_timeout: Duration) -> Self {
+        Self {
+            min_session_timeout,
+            max_session_timeout,
+            groups: Mutex::new(HashMap::new()),
+            changed: Condvar::new(),
+            offsets: Mutex::new(OffsetStore::default()),
+        }
+    }
+
+    /// Rebuild the committed offsets from partition 0 of `__consumer_offsets`,
+    /// which new commits are then appended to.
+    pub fn load_offsets(&self, log_manager: &LogManager) -> io::Result<()> {
+        let log = log_manager.get_or_create(CONSUMER_OFFSETS_TOPIC, 0)?;
+        let store = OffsetStore::load(log)?;
+        println!("Loaded {} committed offsets", store.len());
+        *self.offsets.lock().unwrap() = store;
+        Ok(())
+    }
+
+    /// Current state of the group, `None` if it never existed.
+    pub fn group_state(&self, group_id: &str) -> Option<GroupState> {
+        let groups = self.groups.lock().unwrap();
+        groups.get(group_id).map(|group| group.state)
+    }
+
+    /// Add or refresh a member and block until the rebalance it joined
+    /// completes, or answer right away if no rebalance is needed.
+    pub fn join_group(&self, join: JoinParams) -> JoinResult {
+        if join.group_id.is_empty() {
+            return JoinResult::error(KafkaError::InvalidGroupId, join.member_id);
+        }
+        if join.session_timeout < self.min_session_timeout
+            || join.session_timeout > self.max_session_timeout
+        {
+            return JoinResult::error(KafkaError::InvalidSessionTimeout, join.member_id);
+        }
+
+        let now = Instant::now();
+        let mut groups = self.groups.lock().unwrap();
+        if !join.member_id.is_empty() && !groups.contains_key(&join.group_id) {
+            return JoinResult::error(KafkaError::UnknownMemberId, join.member_id);
+        }
+        let group = groups
+            .entry(join.group_id.clone())
+            .or_insert_with(Group::new);
+        if group.expire_members(now) {
+            self.changed.notify_all();
+        }
+        if !group.supports(&join.member_id, &join.protocol_type, &join.protocols) {
+            return JoinResult::error(KafkaError::InconsistentGroupProtocol, join.member_id);
+        }
+
+        let member_id = if join.member_id.is_empty() {
+            let member_id = format!("{}-{}", join.client_id, Uuid::new_v4());
+            match &join.group_instance_id {
+                Some(instance_id) => {
+                    if let Some(old_member_id) = group.static_members.get(instance_id).cloned() {
+                        group.replace_static_member(&old_member_id, &member_id);
+                    }
+                }
+                None if join.require_known_member_id => {
+                    group
+                        .pending_members
+                        .insert(member_id.clone(), now + join.session_timeout);
+                    return JoinResult::error(KafkaError::MemberIdRequired, member_id);
+                }
+                None => {}
+            }
+            member_id
+        } else {
+            if let Some(instance_id) = &join.group_instance_id {
+                if group
+                    .static_members
+                    .get(instance_id)
+                    .is_some_and(|member_id| *member_id != join.member_id)
+                {
+                    return JoinResult::error(KafkaError::FencedInstanceId, join.member_id);
+                }
+            }
+            if group.pending_members.remove(&join.member_id).is_none()
+                && !group.members.contains_key(&join.member_id)
+            {
+                return JoinResult::error(KafkaError::UnknownMemberId, join.member_id);
+            }
+            join.member_id.clone()
+        };
+
+        if group.members.is_empty() {
+            group.protocol_type = Some(join.protocol_type.clone());
+        }
+        let changed = match group.members.get_mut(&member_id) {
+            Some(member) => {
+                let changed = member.protocols != join.protocols;
+                member.session_timeout = join.session_timeout;
+                member.rebalance_timeout = join.rebalance_timeout;
+                member.protocols = join.protocols.clone();
+                member.last_heartbeat = now;
+                changed
+            }
+            None => {
+                if let Some(instance_id) = &join.group_instance_id {
+                    group
+                        .static_members
+                        .insert(instance_id.clone(), member_id.clone());
+                }
+                group.members.insert(
+                    member_id.clone(),
+                    Member {
+                        group_instance_id: join.group_instance_id.clone(),
+                        session_timeout: join.session_timeout,
+                        rebalance_timeout: join.rebalance_timeout,
+                        protocols: join.protocols.clone(),
+                        assignment: Vec::new(),
+                        last_heartbeat: now,
+                        joined: false,
+                    },
+                );
+                true
+            }
+        };
+
+        // a follower rejoining with the same protocols keeps its generation
+        match group.state {
+            GroupState::Stable if !changed && !group.is_leader(&member_id) => {
+                return group.join_result(&member_id)
+            }
+            GroupState::CompletingRebalance if !changed => return group.join_result(&member_id),
+            _ => {}
+        }
+
+        group.prepare_rebalance(now);
+        if let Some(member) = group.members.get_mut(&member_id) {
+            member.joined = true;
+        }
+        if !group
+            .leader_id
+            .as_ref()
+            .is_some_and(|leader_id| group.members.contains_key(leader_id))
+        {
+            group.leader_id = Some(member_id.clone());
+        }
+        let generation_id = group.generation_id;
+        group.try_complete_join(now);
+        self.changed.notify_all();
+
+        loop {
+            let group = groups
+                .get_mut(&join.group_id)
+                .expect("groups are never removed");
+            if !group.members.contains_key(&member_id) {
+                return JoinResult::error(KafkaError::UnknownMemberId, member_id);
+            }
+            if group.generation_id != generation_id {
+                return group.join_result(&member_id);
+            }
+            let now = Instant::now();
+            if group.try_complete_join(now) {
+                self.changed.notify_all();
+                continue;
+            }
+            let timeout = group
+                .rebalance_deadline
+                .map_or(EXPIRY_CHECK_INTERVAL, |deadline| {
+                    deadline.saturating_duration_since(now)
+                })
+                .min(EXPIRY_CHECK_INTERVAL);
+            groups = self.changed.wait_timeout(groups, timeout).unwrap().0;
+        }
+    }
+
+    /// Store the leader's assignments and return the member's own, blocking
+    /// followers until the leader has synced.
+    pub fn sync_group(&self, sync: SyncParams) -> Result<SyncResult, KafkaError> {
+        let mut groups = self.groups.lock().unwrap();
+        let group = groups
+            .get_mut(&sync.group_id)
+            .ok_or(KafkaError::UnknownMemberId)?;
+        if group.expire_members(Instant::now()) {
+            self.changed.notify_all();
+        }
+        validate_member(
+            group,
+            &sync.member_id,
+            sync.group_instance_id.as_deref(),
+            sync.generation_id,
+        )?;
+        let mismatch = |requested: &Option<String>, actual: &Option<String>| {
+            requested.is_some() && requested != actual
+        };
+        if mismatch(&sync.protocol_type, &group.protocol_type)
+            || mismatch(&sync.protocol_name, &group.protocol_name)
+        {
+            return Err(KafkaError::InconsistentGroupProtocol);
+        }
+
+        match group.state {
+            GroupState::Empty => return Err(KafkaError::UnknownMemberId),
+            GroupState::PreparingRebalance => return Err(KafkaError::RebalanceInProgress),
+            GroupState::CompletingRebalance if group.is_leader(&sync.member_id) => {
+                let mut assignments: HashMap<String, Vec<u8>> =
+                    sync.assignments.into_iter().collect();
+                for (member_id, member) in group.members.iter_mut() {
+                    member.assignment = assignments.remove(member_id).unwrap_or_default();
+                }
+                group.state = GroupState::Stable;
+                self.changed.notify_all();
+            }
+            GroupState::CompletingRebalance | GroupState::Stable => {}
+        }
+
+        loop {
+            let group = groups
+                .get_mut(&sync.group_id)
+                .expect("groups are never removed");
+            if group.generation_id != sync.generation_id {
+                return Err(KafkaError::RebalanceInProgress);
+            }
+            let Some(member) = group.members.get_mut(&sync.member_id) else {
+                return Err(KafkaError::RebalanceInProgress);
+            };
+            // a member blocked here cannot heartbeat on the same connection
+            member.last_heartbeat = Instant::now();
+            match group.state {
+                GroupState::Stable => {
+                    return Ok(SyncResult {
+                        protocol_type: group.protocol_type.clone(),
+                        protocol_name: group.protocol_name.clone(),
+                        assignment: member.assignment.clone(),
+                    })
+                }
+                GroupState::CompletingRebalance => {}
+                GroupState::Empty | GroupState::PreparingRebalance => {
+                    return Err(KafkaError::RebalanceInProgress)
+                }
+            }
+            if group.expire_members(Instant::now()) {
+                self.changed.notify_all();
+                continue;
+            }
+            groups = self
+                .changed
+                .wait_timeout(groups, EXPIRY_CHECK_INTERVAL)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Keep the member's session alive; `REBALANCE_IN_PROGRESS` tells it to
+    /// rejoin.
+    pub fn heartbeat(
+        &self,
+        group_id: &str,
+        generation_id: i32,
+        member_id: &str,
+        group_instance_id: Option<&str>,
+    ) -> Result<(), KafkaError> {
+        let now = Instant::now();
+        let mut groups = self.groups.lock().unwrap();
+        let group = groups
+            .get_mut(group_id)
+            .ok_or(KafkaError::UnknownMemberId)?;
+        if group.expire_members(now) {
+            self.changed.notify_all();
+        }
+        validate_member(group, member_id, group_instance_id, generation_id)?;
+        if let Some(member) = group.members.get_mut(member_id) {
+            member.last_heartbeat = now;
+        }
+        match group.state {
+            GroupState::PreparingRebalance => Err(KafkaError::RebalanceInProgress),
+            GroupState::Empty => Err(KafkaError::UnknownMemberId),
+            GroupState::CompletingRebalance | GroupState::Stable => Ok(()),
+        }
+    }
+
+    /// Remove members from the group, returning an error code per member.
+    /// The remaining members rebalance.
+    pub fn leave_group(&self, group_id: &str, leaving: &[LeavingMember]) -> Vec<KafkaError> {
+        let now = Instant::now();
+        let mut groups = self.groups.lock().unwrap();
+        let Some(group) = groups.get_mut(group_id) else {
+            return vec![KafkaError::UnknownMemberId; leaving.len()];
+        };
+        let mut removed = false;
+        let errors = leaving
+            .iter()
+            .map(|leaving| {
+                let member_id = match &leaving.group_instance_id {
+                    Some(instance_id) => match group.static_members.get(instance_id) {
+                        None => return KafkaError::UnknownMemberId,
+                        Some(member_id)
+                            if !leaving.member_id.is_empty() && *member_id != leaving.member_id =>
+                        {
+                            return KafkaError::FencedInstanceId
+                        }
+                        Some(member_id) => member_id.clone(),
+                    },
+                    None => leaving.member_id.clone(),
+                };
+                if group.pending_members.remove(&member_id).is_some() {
+                    KafkaError::None
+                } else if group.members.contains_key(&member_id) {
+                    group.remove_member(&member_id);
+                    removed = true;
+                    KafkaError::None
+                } else {
+                    KafkaError::UnknownMemberId
+                }
+            })
+            .collect();
+        if removed {
+            group.prepare_rebalance(now);
+            group.try_complete_join(now);
+            self.changed.notify_all();
+        }
+        errors
+    }
+
+    /// Store the offsets a member commits. Members of a group in a generation
+    /// are validated like for a heartbeat; a generation below zero with no
+    /// member id commits for a group that is not using the group protocol,
+    /// which is only allowed while the group has no members.
+    pub fn commit_offsets(
+        &self,
+        group_id: &str,
+        generation_id: i32,
+        member_id: &str,
+        group_instance_id: Option<&str>,
+        commits: Vec<PartitionCommit>,
+    ) -> Result<(), KafkaError> {
+        if group_id.is_empty() {
+            return Err(KafkaError::InvalidGroupId);
+        }
+        let mut groups = self.groups.lock().unwrap();
+        let standalone = generation_id < 0 && member_id.is_empty() && group_instance_id.is_none();
+        match groups.get_mut(group_id) {
+            None if standalone => {}
+            None => return Err(KafkaError::IllegalGeneration),
+            Some(group) => {
+                if group.expire_members(Instant::now()) {
+                    self.changed.notify_all();
+                }
+                if standalone {
+                    if group.state != GroupState::Empty {
+                        return Err(KafkaError::UnknownMemberId);
+                    }
+                } else {
+                    validate_member(group, member_id, group_instance_id, generation_id)?;
+                    match group.state {
+                        GroupState::CompletingRebalance => {
+                            return Err(KafkaError::RebalanceInProgress)
+                        }
+                        GroupState::Empty => return Err(KafkaError::UnknownMemberId),
+                        GroupState::PreparingRebalance | GroupState::Stable => {}
+                    }
+                    if let Some(member) = group.members.get_mut(member_id) {
+                        member.last_heartbeat = Instant::now();
+                    }
+                }
+            }
+        }
+
+        let commit_timestamp = now_ms();
+        let commits = commits
+            .into_iter()
+            .map(|commit| {
+                (
+                    OffsetKey {
+                        group_id: group_id.to_string(),
+                        topic: commit.topic,
+                        partition: commit.partition,
+                    },
+                    CommittedOffset {
+                        offset: commit.offset,
+                        leader_epoch: commit.leader_epoch,
+                        metadata: commit.metadata,
+                        commit_timestamp,
+                    },
+                )
+            })
+            .collect();
+        self.offsets
+            .lock()
+            .unwrap()
+            .commit(commits)
+            .map_err(|_| KafkaError::KafkaStorageError)
+    }
+
+    /// Offset the group committed for the partition, if any.
+    pub fn fetch_offset(
+        &self,
+        group_id: &str,
+        topic: &str,
+        partition: i32,
+    ) -> Option<CommittedOffset> {
+        let key = OffsetKey {
+            group_id: group_id.to_string(),
+            topic: topic.to_string(),
+            partition,
+        };
+        self.offsets.lock().unwrap().get(&key).cloned()
+    }
+
+    /// Every `(topic, partition)` the group committed an offset for.
+    pub fn committed_partitions(&self, group_id: &str) -> Vec<(String, i32)> {
+        self.offsets.lock().unwrap().partitions(group_id)
+    }
+}
+
+/// Check that `member_id` belongs to the group, is not fenced by a newer
+/// member with the same `group.instance.id`, and is in the current generation.
+fn validate_member(
+    group: &Group,
+    member_id: &str,
+    group_instance_id: Option<&str>,
+    generation_id: i32,
+) -> Result<(), KafkaError> {
+    if let Some(instance_id) = group_instance_id {
+        if group
+            .static_members
+            .get(instance_id)
+            .is_some_and(|static_member_id| static_member_id != member_id)
+        {
+            return Err(KafkaError::FencedInstanceId);
+        }
+    }
+    if !group.members.contains_key(member_id) {
+        return Err(KafkaError::UnknownMemberId);
+    }
+    if generation_id != group.generation_id {
+        return Err(KafkaError::IllegalGeneration);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+
+    fn coordinator() -> Arc<GroupCoordinator> {
+        Arc::new(GroupCoordinator::new(
+            Duration::from_millis(1),
+            Duration::from_secs(60),
+        ))
+    }
+
+    fn join(member_id: &str, group_instance_id: Option<&str>) -> JoinParams {
+        JoinParams {
+            group_id: "group".to_string(),
+            member_id: member_id.to_string(),
+            group_instance_id: group_instance_id.map(str::to_string),
+            client_id: "client".to_string(),
+            session_timeout: Duration::from_secs(10),
+            rebalance_timeout: Duration::from_secs(10),
+            protocol_type: "consumer".to_string(),
+            protocols: vec![Protocol {
+                name: "range".to_string(),
+                metadata: vec![1],
+            }],
+            require_known_member_id: true,
+        }
+    }
+
+    fn sync(member_id: &str, generation_id: i32, assignments: &[(&str, u8)]) -> SyncParams {
+        SyncParams {
+            group_id: "group".to_string(),
+            generation_id,
+            member_id: member_id.to_string(),
+            group_instance_id: None,
+            protocol_type: None,
+            protocol_name: None,
+            assignments: assignments
+                .iter()
+                .map(|(member_id, assignment)| (member_id.to_string(), vec![*assignment]))
+                .collect(),
+        }
+    }
+
+    /// Join a new dynamic member through the `MEMBER_ID_REQUIRED` round trip.
+    fn join_new_member(coordinator: &GroupCoordinator) -> JoinResult {
+        let first = coordinator.join_group(join("", None));
+        assert_eq!(first.error_code, KafkaError::MemberIdRequired);
+        assert!(first.member_id.starts_with("client-"));
+        coordinator.join_group(join(&first.member_id, None))
+    }
+
+    #[test]
+    fn test_single_member_rebalance() {
+        let coordinator = coordinator();
+        let joined = join_new_member(&coordinator);
+        assert_eq!(joined.error_code, KafkaError::None);
+        assert_eq!(joined.generation_id, 1);
+        assert_eq!(joined.leader, joined.member_id);
+        assert_eq!(joined.protocol_name.as_deref(), Some("range"));
+        assert_eq!(joined.members.len(), 1);
+        assert_eq!(
+            coordinator.group_state("group"),
+            Some(GroupState::CompletingRebalance)
+        );
+
+        let member_id = joined.member_id.as_str();
+        let synced = coordinator
+            .sync_group(sync(member_id, 1, &[(member_id, 7)]))
+            .unwrap();
+        assert_eq!(synced.assignment, [7]);
+        assert_eq!(coordinator.group_state("group"), Some(GroupState::Stable));
+        assert_eq!(coordinator.heartbeat("group", 1, member_id, None), Ok(()));
+        assert_eq!(
+            coordinator.heartbeat("group", 0, member_id, None),
+            Err(KafkaError::IllegalGeneration)
+        );
+        assert_eq!(
+            coordinator.heartbeat("group", 1, "stranger", None),
+            Err(KafkaError::UnknownMemberId)
+        );
+    }
+
+    #[test]
+    fn test_new_member_triggers_rebalance() {
+        let coordinator = coordinator();
+        let leader = join_new_member(&coordinator);
+        coordinator
+            .sync_group(sync(&leader.member_id, 1, &[]))
+            .unwrap();
+
+        let follower = {
+            let coordinator = coordinator.clone();
+            thread::spawn(move || join_new_member(&coordinator))
+        };
+        while coordinator.group_state("group") != Some(GroupState::PreparingRebalance) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(
+            coordinator.heartbeat("group", 1, &leader.member_id, None),
+            Err(KafkaError::RebalanceInProgress)
+        );
+
+        let rejoined = coordinator.join_group(join(&leader.member_id, None));
+        let follower = follower.join().unwrap();
+        assert_eq!(rejoined.generation_id, 2);
+        assert_eq!(follower.generation_id, 2);
+        assert_eq!(rejoined.leader, leader.member_id);
+        assert_eq!(follower.leader, leader.member_id);
+        assert_eq!(rejoined.members.len(), 2);
+        assert!(follower.members.is_empty());
+
+        // the follower waits for the leader's assignment
+        let follower_sync = {
+            let coordinator = coordinator.clone();
+            let member_id = follower.member_id.clone();
+            thread::spawn(move || coordinator.sync_group(sync(&member_id, 2, &[])))
+        };
+        coordinator
+            .sync_group(sync(
+                &leader.member_id,
+                2,
+                &[(&leader.member_id, 1), (&follower.member_id, 2)],
+            ))
+            .unwrap();
+        assert_eq!(follower_sync.join().unwrap().unwrap().assignment, [2]);
+    }
+
+    #[test]
+    fn test_static_member_rejoin_fences_old_member_id() {
+        let coordinator = coordinator();
+        let first = coordinator.join_group(join("", Some("instance-1")));
+        assert_eq!(first.error_code, KafkaError::None);
+        coordinator
+            .sync_group(sync(&first.member_id, 1, &[]))
+            .unwrap();
+
+        let second = coordinator.join_group(join("", Some("instance-1")));
+        assert_eq!(second.error_code, KafkaError::None);
+        assert_ne!(second.member_id, first.member_id);
+        assert_eq!(
+            coordinator.heartbeat(
+                "group",
+                second.generation_id,
+                &first.member_id,
+                Some("instance-1")
+            ),
+            Err(KafkaError::FencedInstanceId)
+        );
+        let rejoin = coordinator.join_group(join(&first.member_id, Some("instance-1")));
+        assert_eq!(rejoin.error_code, KafkaError::FencedInstanceId);
+    }
+
+    #[test]
+    fn test_leave_group_empties_group() {
+        let coordinator = coordinator();
+        let joined = join_new_member(&coordinator);
+        let errors = coordinator.leave_group(
+            "group",
+            &[
+                LeavingMember {
+                    member_id: joined.member_id.clone(),
+                    group_instance_id: None,
+                },
+                LeavingMember {
+                    member_id: "stranger".to_string(),
+                    group_instance_id: None,
+                },
+            ],
+        );
+        assert_eq!(errors, [KafkaError::None, KafkaError::UnknownMemberId]);
+        assert_eq!(coordinator.group_state("group"), Some(GroupState::Empty));
+        assert_eq!(
+            coordinator.heartbeat("group", 1, &joined.member_id, None),
+            Err(KafkaError::UnknownMemberId)
+        );
+    }
+
+    #[test]
+    fn test_expired_member_is_removed() {
+        let coordinator = coordinator();
+        let leader = join_new_member(&coordinator);
+        coordinator
+            .sync_group(sync(&leader.member_id, 1, &[]))
+            .unwrap();
+        let follower = {
+            let coordinator = coordinator.clone();
+            thread::spawn(move || {
+                let mut join = join("", None);
+                join.require_known_member_id = false;
+                join.session_timeout = Duration::from_millis(50);
+                coordinator.join_group(join)
+            })
+        };
+        while coordinator.group_state("group") != Some(GroupState::PreparingRebalance) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        let rejoined = coordinator.join_group(join(&leader.member_id, None));
+        let follower = follower.join().unwrap();
+        assert_eq!(rejoined.generation_id, 2);
+        coordinator
+            .sync_group(sync(&leader.member_id, 2, &[]))
+            .unwrap();
+
+        // the follower never heartbeats again
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(
+            coordinator.heartbeat("group", 2, &leader.member_id, None),
+            Err(KafkaError::RebalanceInProgress)
+        );
+        assert_eq!(
+            coordinator.heartbeat("group", 2, &follower.member_id, None),
+            Err(KafkaError::UnknownMemberId)
+        );
+    }
+
+    #[test]
+    fn test_commit_offsets_validates_member() {
+        let coordinator = coordinator();
+        let commit = |offset| {
+            vec![PartitionCommit {
+                topic: "foo".to_string(),
+                partition: 0,
+                offset,
+                leader_epoch: -1,
+                metadata: String::new(),
+            }]
+        };
+        // a group outside the group protocol commits with no member id
+        assert_eq!(
+            coordinator.commit_offsets("group", -1, "", None, commit(5)),
+            Ok(())
+        );
+        assert_eq!(
+            coordinator.fetch_offset("group", "foo", 0).unwrap().offset,
+            5
+        );
+
+        let joined = join_new_member(&coordinator);
+        let member_id = joined.member_id.as_str();
+        assert_eq!(
+            coordinator.commit_offsets("group", 1, member_id, None, commit(6)),
+            Err(KafkaError::RebalanceInProgress)
+        );
+        coordinator.sync_group(sync(member_id, 1, &[])).unwrap();
+        assert_eq!(
+            coordinator.commit_offsets("group", -1, "", None, commit(6)),
+            Err(KafkaError::UnknownMemberId)
+        );
+        assert_eq!(
+            coordinator.commit_offsets("group", 0, member_id, None, commit(6)),
+            Err(KafkaError::IllegalGeneration)
+        );
+        assert_eq!(
+            coordinator.commit_offsets("group", 1, member_id, None, commit(7)),
+            Ok(())
+        );
+        assert_eq!(
+            coordinator.fetch_offset("group", "foo", 0).unwrap().offset,
+            7
+        );
+        assert_eq!(
+            coordinator.committed_partitions("group"),
+            [("foo".to_string(), 0)]
+        );
+        assert_eq!(coordinator.fetch_offset("other", "foo", 0), None);
+    }
+
+    #[test]
+    fn test_invalid_join_requests() {
+        let coordinator = coordinator();
+        let mut invalid = join("", None);
+        invalid.group_id.clear();
+        assert_eq!(
+            coordinator.join_group(invalid).error_code,
+            KafkaError::InvalidGroupId
+        );
+        let mut invalid = join("", None);
+        invalid.session_timeout = Duration::from_secs(120);
+        assert_eq!(
+            coordinator.join_group(invalid).error_code,
+            KafkaError::InvalidSessionTimeout
+        );
+        assert_eq!(
+            coordinator.join_group(join("unknown", None)).error_code,
+            KafkaError::UnknownMemberId
+        );
+
+        join_new_member(&coordinator);
+        let mut other_protocol = join("", None);
+        other_protocol.protocols[0].name = "roundrobin".to_string();
+        assert_eq!(
+            coordinator.join_group(other_protocol).error_code,
+            KafkaError::InconsistentGroupProtocol
+        );
+    }
+}
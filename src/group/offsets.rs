@@ -0,0 +1,364 @@
+//! Committed offsets, cached in memory and persisted to `__consumer_offsets`.
+//!
+//! Every commit is appended as one record per partition, keyed by
+//! `(group, topic, partition)` in Kafka's own `OffsetCommitKey` (v1) and
+//! `OffsetCommitValue` (v3) formats, so the log can be read by Kafka tooling.
+//! On startup the log is replayed and the last value of every key wins; a
+//! null value is a tombstone that deletes the offset.
+//!
+//! The log is compacted down to those last values when it is loaded and
+//! whenever a commit rolls a new segment, so it does not grow with every
+//! commit forever.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Cursor, Read},
+};
+
+use binrw::{BinWrite, Endian};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    records::{Record, RecordBatch},
+    storage::{
+        partition_log::{end_offset, now_ms},
+        SharedPartitionLog,
+    },
+};
+
+/// Key version of an offset commit; versions 0 and 1 share the format, 2 is a
+/// group metadata record, which is skipped.
+const OFFSET_COMMIT_KEY_VERSION: i16 = 1;
+/// Value version that carries the leader epoch.
+const OFFSET_COMMIT_VALUE_VERSION: i16 = 3;
+
+/// Bytes of the offsets log read at a time while loading the cache.
+const LOAD_CHUNK_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OffsetKey {
+    pub group_id: String,
+    pub topic: String,
+    pub partition: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommittedOffset {
+    pub offset: i64,
+    /// `-1` when the consumer did not know the leader epoch.
+    pub leader_epoch: i32,
+    pub metadata: String,
+    pub commit_timestamp: i64,
+}
+
+impl OffsetKey {
+    fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        bytes.write_i16::<BigEndian>(OFFSET_COMMIT_KEY_VERSION)?;
+        write_string(&mut bytes, &self.group_id)?;
+        write_string(&mut bytes, &self.topic)?;
+        bytes.write_i32::<BigEndian>(self.partition)?;
+        Ok(bytes)
+    }
+
+    /// `None` for keys of other record kinds, such as group metadata.
+    fn from_bytes(bytes: &[u8]) -> io::Result<Option<Self>> {
+        let mut reader = Cursor::new(bytes);
+        let version = reader.read_i16::<BigEndian>()?;
+        if !matches!(version, 0 | 1) {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            group_id: read_string(&mut reader)?,
+            topic: read_string(&mut reader)?,
+            partition: reader.read_i32::<BigEndian>()?,
+        }))
+    }
+}
+
+impl CommittedOffset {
+    fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        bytes.write_i16::<BigEndian>(OFFSET_COMMIT_VALUE_VERSION)?;
+        bytes.write_i64::<BigEndian>(self.offset)?;
+        bytes.write_i32::<BigEndian>(self.leader_epoch)?;
+        write_string(&mut bytes, &self.metadata)?;
+        bytes.write_i64::<BigEndian>(self.commit_timestamp)?;
+        Ok(bytes)
+    }
+
+    /// Decode any value version from 0 to 3.
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Cursor::new(bytes);
+        let version = reader.read_i16::<BigEndian>()?;
+        if !(0..=OFFSET_COMMIT_VALUE_VERSION).contains(&version) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported offset commit value version {version}"),
+            ));
+        }
+        let offset = reader.read_i64::<BigEndian>()?;
+        let leader_epoch = if version >= 3 {
+            reader.read_i32::<BigEndian>()?
+        } else {
+            -1
+        };
+        let metadata = read_string(&mut reader)?;
+        let commit_timestamp = reader.read_i64::<BigEndian>()?;
+        Ok(Self {
+            offset,
+            leader_epoch,
+            metadata,
+            commit_timestamp,
+        })
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, s: &str) -> io::Result<()> {
+    bytes.write_i16::<BigEndian>(s.len() as i16)?;
+    bytes.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let length = reader.read_i16::<BigEndian>()?.max(0) as usize;
+    let mut buf = vec![0u8; length];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Committed offsets of every group, backed by the `__consumer_offsets` log.
+#[derive(Default)]
+pub struct OffsetStore {
+    /// Sorted so a group's offsets are listed by topic and partition.
+    offsets: BTreeMap<OffsetKey, CommittedOffset>,
+    /// `None` keeps the offsets in memory only.
+    log: Option<SharedPartitionLog>,
+}
+
+impl OffsetStore {
+    /// Compact `log`, then replay it from the start into a fresh cache that
+    /// writes through to it.
+    pub fn load(log: SharedPartitionLog) -> io::Result<Self> {
+        let mut offsets = BTreeMap::new();
+        {
+            let mut log = log.lock().unwrap();
+            log.compact()?;
+            let mut offset = log.log_start_offset();
+            while offset < log.high_watermark() {
+                let chunk = log.read(offset, LOAD_CHUNK_BYTES, true)?;
+                let Some(next_offset) = end_offset(&chunk)? else {
+                    break;
+                };
+                let batches = RecordBatch::read_batches_from(&mut Cursor::new(chunk))
+                    .map_err(io::Error::other)?;
+                for record in batches.iter().flat_map(|batch| &batch.records) {
+                    let Some(key) = record.key.as_deref() else {
+                        continue;
+                    };
+                    let Some(key) = OffsetKey::from_bytes(key)? else {
+                        continue;
+                    };
+                    match record.value.as_deref() {
+                        Some(value) => {
+                            offsets.insert(key, CommittedOffset::from_bytes(value)?);
+                        }
+                        None => {
+                            offsets.remove(&key);
+                        }
+                    }
+                }
+                if next_offset <= offset {
+                    break;
+                }
+                offset = next_offset;
+            }
+        }
+        Ok(Self {
+            offsets,
+            log: Some(log),
+        })
+    }
+
+    /// Number of committed offsets in the cache.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    pub fn get(&self, key: &OffsetKey) -> Option<&CommittedOffset> {
+        self.offsets.get(key)
+    }
+
+    /// Every committed `(topic, partition)` of the group.
+    pub fn partitions(&self, group_id: &str) -> Vec<(String, i32)> {
+        self.offsets
+            .keys()
+            .filter(|key| key.group_id == group_id)
+            .map(|key| (key.topic.clone(), key.partition))
+            .collect()
+    }
+
+    /// Persist the commits as a single batch, then update the cache, so a
+    /// failed write leaves the cache as it was.
+    pub fn commit(&mut self, commits: Vec<(OffsetKey, CommittedOffset)>) -> io::Result<()> {
+        if commits.is_empty() {
+            return Ok(());
+        }
+        if let Some(log) = &self.log {
+            let records = commits
+                .iter()
+                .enumerate()
+                .map(|(offset_delta, (key, value))| {
+                    Ok(Record {
+                        attributes: 0,
+                        timestamp_delta: 0,
+                        offset_delta: offset_delta as i32,
+                        key: Some(key.to_bytes()?),
+                        value: Some(value.to_bytes()?),
+                        headers: Vec::new(),
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            let now = now_ms();
+            let batch = RecordBatch {
+                base_offset: 0,
+                partition_leader_epoch: 0,
+                crc: 0,
+                attributes: 0,
+                base_timestamp: now,
+                max_timestamp: now,
+                producer_id: -1,
+                producer_epoch: -1,
+                base_sequence: -1,
+                records,
+            };
+            let mut bytes = Cursor::new(Vec::new());
+            batch
+                .write_options(&mut bytes, Endian::Big, ())
+                .map_err(io::Error::other)?;
+            let mut log = log.lock().unwrap();
+            let segments = log.segment_base_offsets().len();
+            log.append(&bytes.into_inner()).map_err(io::Error::other)?;
+            // only closed segments are compacted, so a roll gives it more to do
+            if log.segment_base_offsets().len() > segments {
+                if let Err(e) = log.compact() {
+                    println!("failed to compact the offsets log: {}", e);
+                }
+            }
+        }
+        self.offsets.extend(commits);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::storage::{LogConfig, PartitionLog};
+
+    fn key(partition: i32) -> OffsetKey {
+        OffsetKey {
+            group_id: "group".to_string(),
+            topic: "foo".to_string(),
+            partition,
+        }
+    }
+
+    fn committed(offset: i64) -> CommittedOffset {
+        CommittedOffset {
+            offset,
+            leader_epoch: 3,
+            metadata: "meta".to_string(),
+            commit_timestamp: 1000,
+        }
+    }
+
+    #[test]
+    fn test_key_and_value_round_trip() {
+        let key = key(7);
+        assert_eq!(
+            OffsetKey::from_bytes(&key.to_bytes().unwrap()).unwrap(),
+            Some(key)
+        );
+        let value = committed(42);
+        assert_eq!(
+            CommittedOffset::from_bytes(&value.to_bytes().unwrap()).unwrap(),
+            value
+        );
+        // group metadata keys are not offsets
+        assert_eq!(OffsetKey::from_bytes(&[0, 2, 0, 0]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_commits_survive_reload() {
+        let dir = std::env::temp_dir().join(format!("offsets-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let open = || {
+            let log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+            OffsetStore::load(Arc::new(Mutex::new(log))).unwrap()
+        };
+
+        let mut store = open();
+        store
+            .commit(vec![(key(0), committed(10)), (key(1), committed(20))])
+            .unwrap();
+        store.commit(vec![(key(0), committed(15))]).unwrap();
+        drop(store);
+
+        let store = open();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&key(0)), Some(&committed(15)));
+        assert_eq!(store.get(&key(1)), Some(&committed(20)));
+        assert_eq!(
+            store.partitions("group"),
+            [("foo".to_string(), 0), ("foo".to_string(), 1)]
+        );
+        assert!(store.partitions("other").is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_superseded_commits_are_compacted() {
+        let dir = std::env::temp_dir().join(format!("offsets-compact-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        // every commit rolls a segment
+        let config = LogConfig {
+            segment_bytes: 1,
+            ..LogConfig::default()
+        };
+        let open = || {
+            let log = PartitionLog::open(&dir, config.clone()).unwrap();
+            OffsetStore::load(Arc::new(Mutex::new(log))).unwrap()
+        };
+
+        let mut store = open();
+        for offset in 0..10 {
+            store
+                .commit(vec![
+                    (key(0), committed(offset)),
+                    (key(1), committed(offset)),
+                ])
+                .unwrap();
+        }
+        // only the first segment, kept for the log start offset, and the
+        // active one holding the last commit are left
+        let log = store.log.clone().unwrap();
+        assert_eq!(log.lock().unwrap().segment_base_offsets(), [0, 18]);
+        drop((store, log));
+
+        let store = open();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&key(0)), Some(&committed(9)));
+        assert_eq!(store.get(&key(1)), Some(&committed(9)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+//! Membership and rebalance state of a single consumer group.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use super::{JoinResult, JoinedMember, Protocol};
+use crate::response::error_code::KafkaError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupState {
+    /// No members; committed offsets may still exist.
+    Empty,
+    /// Waiting for every member to (re)join before a new generation starts.
+    PreparingRebalance,
+    /// A generation started and the members wait for the leader's assignment.
+    CompletingRebalance,
+    /// Every member has its assignment.
+    Stable,
+}
+
+#[derive(Debug)]
+pub(super) struct Member {
+    pub group_instance_id: Option<String>,
+    pub session_timeout: Duration,
+    pub rebalance_timeout: Duration,
+    pub protocols: Vec<Protocol>,
+    pub assignment: Vec<u8>,
+    pub last_heartbeat: Instant,
+    /// Joined the rebalance in progress, or the last completed one.
+    pub joined: bool,
+}
+
+impl Member {
+    fn metadata(&self, protocol_name: &str) -> Vec<u8> {
+        self.protocols
+            .iter()
+            .find(|protocol| protocol.name == protocol_name)
+            .map(|protocol| protocol.metadata.clone())
+            .unwrap_or_default()
+    }
+
+    fn supports(&self, protocol_name: &str) -> bool {
+        self.protocols
+            .iter()
+            .any(|protocol| protocol.name == protocol_name)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Group {
+    pub state: GroupState,
+    pub generation_id: i32,
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    pub leader_id: Option<String>,
+    /// Sorted so the member list handed to the leader is stable.
+    pub members: BTreeMap<String, Member>,
+    /// Member id currently held by each `group.instance.id`.
+    pub static_members: HashMap<String, String>,
+    /// Member ids handed out with `MEMBER_ID_REQUIRED`, until they expire.
+    pub pending_members: HashMap<String, Instant>,
+    /// When the rebalance in progress stops waiting for missing members.
+    pub rebalance_deadline: Option<Instant>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self {
+            state: GroupState::Empty,
+            generation_id: 0,
+            protocol_type: None,
+            protocol_name: None,
+            leader_id: None,
+            members: BTreeMap::new(),
+            static_members: HashMap::new(),
+            pending_members: HashMap::new(),
+            rebalance_deadline: None,
+        }
+    }
+
+    pub fn is_leader(&self, member_id: &str) -> bool {
+        self.leader_id.as_deref() == Some(member_id)
+    }
+
+    /// Whether a member offering `protocols` of `protocol_type` can join: an
+    /// empty group takes any, otherwise the type has to match and one of the
+    /// protocols has to be supported by every other member.
+    pub fn supports(&self, member_id: &str, protocol_type: &str, protocols: &[Protocol]) -> bool {
+        if protocol_type.is_empty() || protocols.is_empty() {
+            return false;
+        }
+        let others: Vec<&Member> = self
+            .members
+            .iter()
+            .filter(|(id, _)| id.as_str() != member_id)
+            .map(|(_, member)| member)
+            .collect();
+        if others.is_empty() {
+            return true;
+        }
+        self.protocol_type.as_deref() == Some(protocol_type)
+            && protocols
+                .iter()
+                .any(|protocol| others.iter().all(|member| member.supports(&protocol.name)))
+    }
+
+    pub fn remove_member(&mut self, member_id: &str) {
+        if let Some(member) = self.members.remove(member_id) {
+            if let Some(instance_id) = member.group_instance_id {
+                if self.static_members.get(&instance_id).map(String::as_str) == Some(member_id) {
+                    self.static_members.remove(&instance_id);
+                }
+            }
+        }
+        if self.is_leader(member_id) {
+            self.leader_id = None;
+        }
+    }
+
+    /// Move the member of a static `group.instance.id` to a fresh member id,
+    /// which fences the old one.
+    pub fn replace_static_member(&mut self, old_member_id: &str, new_member_id: &str) {
+        if let Some(member) = self.members.remove(old_member_id) {
+            if let Some(instance_id) = &member.group_instance_id {
+                self.static_members
+                    .insert(instance_id.clone(), new_member_id.to_string());
+            }
+            self.members.insert(new_member_id.to_string(), member);
+        }
+        if self.is_leader(old_member_id) {
+            self.leader_id = Some(new_member_id.to_string());
+        }
+    }
+
+    /// Start a rebalance unless one is already waiting for members.
+    pub fn prepare_rebalance(&mut self, now: Instant) {
+        if self.state == GroupState::PreparingRebalance {
+            return;
+        }
+        let timeout = self
+            .members
+            .values()
+            .map(|member| member.rebalance_timeout)
+            .max()
+            .unwrap_or_default();
+        for member in self.members.values_mut() {
+            member.joined = false;
+        }
+        self.state = GroupState::PreparingRebalance;
+        self.rebalance_deadline = Some(now + timeout);
+    }
+
+    /// Finish the rebalance in progress once every member rejoined or the
+    /// rebalance timeout passed. Returns whether a new generation started.
+    pub fn try_complete_join(&mut self, now: Instant) -> bool {
+        if self.state != GroupState::PreparingRebalance {
+            return false;
+        }
+        let all_joined = self.members.values().all(|member| member.joined);
+        if !all_joined
+            && self
+                .rebalance_deadline
+                .is_some_and(|deadline| now < deadline)
+        {
+            return false;
+        }
+
+        let missing: Vec<String> = self
+            .members
+            .iter()
+            .filter(|(_, member)| !member.joined)
+            .map(|(member_id, _)| member_id.clone())
+            .collect();
+        for member_id in missing {
+            self.remove_member(&member_id);
+        }
+
+        self.generation_id += 1;
+        self.rebalance_deadline = None;
+        if self.members.is_empty() {
+            self.state = GroupState::Empty;
+            self.protocol_name = None;
+            self.leader_id = None;
+            return true;
+        }
+        if !self
+            .leader_id
+            .as_ref()
+            .is_some_and(|leader_id| self.members.contains_key(leader_id))
+        {
+            self.leader_id = self.members.keys().next().cloned();
+        }
+        self.protocol_name = self.select_protocol();
+        for member in self.members.values_mut() {
+            member.assignment.clear();
+            member.last_heartbeat = now;
+        }
+        self.state = GroupState::CompletingRebalance;
+        true
+    }
+
+    /// The leader's most preferred protocol that every member supports.
+    fn select_protocol(&self) -> Option<String> {
+        let leader = self.members.get(self.leader_id.as_ref()?)?;
+        leader
+            .protocols
+            .iter()
+            .find(|protocol| {
+                self.members
+                    .values()
+                    .all(|member| member.supports(&protocol.name))
+            })
+            .map(|protocol| protocol.name.clone())
+    }
+
+    /// Drop members whose session timed out and pending member ids that were
+    /// never used, starting a rebalance if a member went away. Returns whether
+    /// the group changed.
+    pub fn expire_members(&mut self, now: Instant) -> bool {
+        self.pending_members.retain(|_, deadline| now < *deadline);
+        // members waiting in a rebalance are dropped by its deadline instead
+        if !matches!(
+            self.state,
+            GroupState::CompletingRebalance | GroupState::Stable
+        ) {
+            return false;
+        }
+        let expired: Vec<String> = self
+            .members
+            .iter()
+            .filter(|(_, member)| {
+                now.duration_since(member.last_heartbeat) > member.session_timeout
+            })
+            .map(|(member_id, _)| member_id.clone())
+            .collect();
+        if expired.is_empty() {
+            return false;
+        }
+        for member_id in expired {
+            self.remove_member(&member_id);
+        }
+        self.prepare_rebalance(now);
+        self.try_complete_join(now);
+        true
+    }
+
+    /// JoinGroup answer for `member_id` in the current generation. Only the
+    /// leader gets the member list it has to compute assignments from.
+    pub fn join_result(&self, member_id: &str) -> JoinResult {
+        let protocol_name = self.protocol_name.clone().unwrap_or_default();
+        let members = if self.is_leader(member_id) {
+            self.members
+                .iter()
+                .map(|(member_id, member)| JoinedMember {
+                    member_id: member_id.clone(),
+                    group_instance_id: member.group_instance_id.clone(),
+                    metadata: member.metadata(&protocol_name),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        JoinResult {
+            error_code: KafkaError::None,
+            generation_id: self.generation_id,
+            protocol_type: self.protocol_type.clone(),
+            protocol_name: self.protocol_name.clone(),
+            leader: self.leader_id.clone().unwrap_or_default(),
+            member_id: member_id.to_string(),
+            members,
+        }
+    }
+}
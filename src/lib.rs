@@ -4,4 +4,11 @@ pub mod consts;
 pub mod common_structs;
 pub mod traits;
 pub mod records;
-pub mod globals;
\ No newline at end of file
+pub mod globals;
+pub mod metadata;
+pub mod storage;
+pub mod network;
+pub mod config;
+pub mod group;
+pub mod fetch_session;
+pub mod producer_id;
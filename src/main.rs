@@ -1,73 +1,161 @@
-#![allow(unused_imports)]
-use std::{
-    io::{Read, Write},
-    net::{TcpListener, TcpStream},
-    sync::{Arc, OnceLock, RwLock},
-    thread::{self, JoinHandle},
-};
+use std::sync::{Arc, RwLock};
 
 use codecrafters_kafka::{
-    globals::RECORD_BATCHES,
-    records::RecordBatch,
-    request::{self, body::KafkaRequestBody, error::RequestError, KafkaRequest},
-    response::{KafkaResponse, KafkaResponseHeader},
-    traits::KafkaSeriarize,
+    config::BrokerConfig,
+    fetch_session::FetchSessionCache,
+    globals::{
+        FETCH_SESSION_CACHE, GROUP_COORDINATOR, LOG_MANAGER, METADATA_IMAGE, META_PROPERTIES,
+        PRODUCER_ID_MANAGER,
+    },
+    group::GroupCoordinator,
+    metadata, network,
+    producer_id::ProducerIdManager,
+    storage::{log_dir, LogManager},
+};
+use tokio::{
+    net::TcpListener,
+    signal::unix::{signal, SignalKind},
 };
 
-fn handle_stream(mut stream: TcpStream) {
-    println!("accepted new connection");
-    loop {
-        let request = KafkaRequest::try_from_reader(&mut stream);
-        let request = match request {
-            Ok(r) => dbg!(r),
-            Err(_e) => {
-                dbg!("stream is eof");
-                break;
-            }
-        };
-        println!("recieve new request");
-        // generate response
-        let response = KafkaResponse::from_request(&request);
-        response.serialize(&mut stream, ()).unwrap();
-        println!("response to new request");
+/// Resolves on the first SIGTERM or Ctrl-C.
+async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
     }
+}
 
-    println!("close the connection");
+/// `format --cluster-id <id> [server.properties] [--override key=value]...`:
+/// format the configured log dirs like `kafka-storage format` and exit.
+fn format_command(mut args: Vec<String>) -> ! {
+    let Some(position) = args
+        .iter()
+        .position(|arg| arg == "--cluster-id" || arg == "-t")
+    else {
+        eprintln!("format needs --cluster-id <id>");
+        std::process::exit(1);
+    };
+    args.remove(position);
+    if position == args.len() {
+        eprintln!("--cluster-id needs a value");
+        std::process::exit(1);
+    }
+    let cluster_id = args.remove(position);
+    if log_dir::parse_uuid(&cluster_id).is_none() {
+        eprintln!("cluster id {cluster_id:?} is not a base64-encoded UUID");
+        std::process::exit(1);
+    }
+    let config = match BrokerConfig::from_args(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    for dir in &config.log_dirs {
+        if let Err(e) = log_dir::format(dir, &cluster_id, config.node_id) {
+            eprintln!("failed to format {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+        println!("Formatted {} for cluster {cluster_id}", dir.display());
+    }
+    std::process::exit(0);
 }
 
-fn main() {
+#[tokio::main]
+async fn main() {
     println!("Logs from your program will appear here!");
 
-    let file_path = "/tmp/kraft-combined-logs/__cluster_metadata-0/00000000000000000000.log";
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("format") {
+        format_command(args[1..].to_vec());
+    }
+    let config = match BrokerConfig::from_args(args) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    match RecordBatch::read_batches_from_file(file_path) {
-        Ok(batches) => {
-            println!("Successfully read {} record", batches.len());
-            RECORD_BATCHES.get_or_init(|| Arc::new(RwLock::new(batches)));
+    match log_dir::load_or_format(&config.log_dirs, config.node_id) {
+        Ok(meta_properties) => {
+            println!(
+                "Node {} of cluster {}",
+                meta_properties.node_id, meta_properties.cluster_id
+            );
+            META_PROPERTIES.get_or_init(|| meta_properties);
         }
         Err(e) => {
-            println!("Unsuccessfully read with error: {}", e);
+            eprintln!("invalid log dir: {}", e);
+            std::process::exit(1);
         }
-    };
-    let listener = TcpListener::bind("127.0.0.1:9092").unwrap();
+    }
 
-    // 修改线程创建以使用全局变量
-    let mut threads = Vec::new();
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                // 每个线程都可以访问全局记录
-                threads.push(thread::spawn(|| {
-                    handle_stream(stream);
-                }));
+    let log_manager =
+        LOG_MANAGER.get_or_init(|| LogManager::new(config.log_dir(), config.log.clone()));
+    if let Err(e) = log_manager.load_logs() {
+        eprintln!(
+            "failed to recover logs in {}: {}",
+            config.log_dir().display(),
+            e
+        );
+        std::process::exit(1);
+    }
+    match metadata::load_image(log_manager) {
+        Ok(image) => {
+            println!("Loaded metadata image up to offset {}", image.last_offset());
+            if image.unknown_records() > 0 {
+                eprintln!(
+                    "warning: skipped {} metadata records of unknown types or versions",
+                    image.unknown_records()
+                );
             }
-            Err(e) => {
-                println!("error: {}", e);
+            METADATA_IMAGE.get_or_init(|| RwLock::new(image));
+            if let Err(e) = metadata::check_partition_metadata(log_manager) {
+                eprintln!("invalid partition directory: {}", e);
+                std::process::exit(1);
             }
         }
+        Err(e) => {
+            println!("Unsuccessfully read with error: {}", e);
+        }
+    };
+
+    let group_coordinator = GROUP_COORDINATOR.get_or_init(|| {
+        GroupCoordinator::new(
+            config.group_min_session_timeout,
+            config.group_max_session_timeout,
+        )
+    });
+    if let Err(e) = group_coordinator.load_offsets(log_manager) {
+        eprintln!("failed to load committed offsets: {}", e);
+        std::process::exit(1);
     }
 
-    for thread in threads {
-        thread.join().unwrap()
+    FETCH_SESSION_CACHE.get_or_init(|| FetchSessionCache::new(config.fetch_session_cache_slots));
+    PRODUCER_ID_MANAGER.get_or_init(|| ProducerIdManager::new(config.node_id));
+
+    let listener = &config.listener;
+    let host = if listener.host.is_empty() {
+        "0.0.0.0"
+    } else {
+        listener.host.as_str()
+    };
+    let tcp_listener = match TcpListener::bind((host, listener.port)).await {
+        Ok(tcp_listener) => tcp_listener,
+        Err(e) => {
+            eprintln!("failed to bind {}: {}", listener, e);
+            std::process::exit(1);
+        }
+    };
+    println!("listening on {}", listener);
+
+    network::serve(tcp_listener, config.clone(), shutdown_signal())
+        .await
+        .unwrap();
+    if let Err(e) = log_manager.write_producer_snapshots() {
+        eprintln!("failed to snapshot producer state: {}", e);
     }
 }
@@ -0,0 +1,717 @@
+//! Lookups against the cluster metadata image in `METADATA_IMAGE`.
+
+use std::{
+    io::{self, Cursor},
+    ops::Range,
+    sync::RwLock,
+};
+
+use binrw::{BinWrite, Endian};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    globals::{LOG_MANAGER, METADATA_IMAGE, META_PROPERTIES},
+    records::{
+        record_value::{
+            BrokerRegistrationRecord, ClusterMetadataRecord, ClusterMetadataValue, PartitionRecord,
+            ProducerIdsRecord, RemoveTopicRecord, TopicRecord,
+        },
+        Record, RecordBatch,
+    },
+    response::error_code::KafkaError,
+    storage::{
+        log_dir::{format_uuid, LogDirError, PartitionMetadata, PARTITION_METADATA_FILE},
+        partition_log::now_ms,
+        LogManager, PartitionLog, SharedPartitionLog, CLUSTER_METADATA_TOPIC,
+    },
+};
+
+mod image;
+mod snapshot;
+
+pub use image::{MetadataImage, TopicImage};
+pub use snapshot::SnapshotId;
+
+/// Producer ids reserved by one `ProducerIdsRecord`, like Kafka's controller
+/// hands them out.
+const PRODUCER_ID_BLOCK_SIZE: i64 = 1000;
+
+/// Topics the broker manages itself.
+const INTERNAL_TOPICS: [&str; 2] = ["__consumer_offsets", "__transaction_state"];
+
+/// The current image; an empty one if none was loaded.
+fn metadata_image() -> &'static RwLock<MetadataImage> {
+    METADATA_IMAGE.get_or_init(Default::default)
+}
+
+/// Run `f` against the current image.
+fn with_image<T>(f: impl FnOnce(&MetadataImage) -> T) -> T {
+    f(&metadata_image().read().unwrap())
+}
+
+pub fn topic_id_by_name(name: &str) -> Option<[u8; 16]> {
+    with_image(|image| image.topic_by_name(name).map(|topic| topic.id))
+}
+
+pub fn topic_name_by_id(topic_id: &[u8; 16]) -> Option<String> {
+    with_image(|image| image.topic_by_id(topic_id).map(|topic| topic.name.clone()))
+}
+
+pub fn topic_by_name(name: &str) -> Option<TopicImage> {
+    with_image(|image| image.topic_by_name(name).cloned())
+}
+
+pub fn partition_exists(topic_id: &[u8; 16], partition_index: i32) -> bool {
+    with_image(|image| image.partition(topic_id, partition_index).is_some())
+}
+
+/// Every topic, ordered by name.
+pub fn topics() -> Vec<TopicImage> {
+    with_image(|image| image.topics().cloned().collect())
+}
+
+/// Current state of one partition of the topic.
+pub fn partition(topic_id: &[u8; 16], partition_index: i32) -> Option<PartitionRecord> {
+    with_image(|image| image.partition(topic_id, partition_index).cloned())
+}
+
+/// Current state of every partition of the topic, ordered by partition id.
+pub fn partitions(topic_id: &[u8; 16]) -> Vec<PartitionRecord> {
+    with_image(|image| {
+        image
+            .topic_by_id(topic_id)
+            .map(|topic| topic.partitions.values().cloned().collect())
+            .unwrap_or_default()
+    })
+}
+
+/// Latest registration of every broker, ordered by broker id.
+pub fn brokers() -> Vec<BrokerRegistrationRecord> {
+    with_image(|image| image.brokers().cloned().collect())
+}
+
+pub fn is_internal_topic(name: &str) -> bool {
+    INTERNAL_TOPICS.contains(&name)
+}
+
+/// Same rules as Kafka's `Topic.validate`: 1-249 chars of `[a-zA-Z0-9._-]`,
+/// excluding `.` and `..`.
+pub fn is_valid_topic_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 249
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Cluster id from the log dir's `meta.properties`.
+pub fn cluster_id() -> Option<String> {
+    META_PROPERTIES
+        .get()
+        .map(|meta_properties| meta_properties.cluster_id.clone())
+}
+
+/// Check the `partition.metadata` of every partition directory against the
+/// image, writing it where it is missing. A directory holding another topic's
+/// id is left over from a deleted topic of the same name and is an error.
+pub fn check_partition_metadata(log_manager: &LogManager) -> Result<(), LogDirError> {
+    for topic in topics() {
+        for partition in topic.partitions.keys() {
+            let dir = log_manager.partition_dir(&topic.name, *partition);
+            if !dir.exists() {
+                continue;
+            }
+            match PartitionMetadata::read(&dir)? {
+                Some(metadata) if metadata.topic_id != topic.id => {
+                    return Err(LogDirError::Invalid {
+                        path: dir.join(PARTITION_METADATA_FILE),
+                        reason: format!(
+                            "topic id {} does not match the id {} of topic {}",
+                            format_uuid(&metadata.topic_id),
+                            format_uuid(&topic.id),
+                            topic.name
+                        ),
+                    })
+                }
+                Some(_) => {}
+                None => PartitionMetadata { topic_id: topic.id }.write(&dir)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Bytes of the metadata log read at a time while loading the image.
+const LOAD_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Load the latest snapshot of the `__cluster_metadata` log, if any, and
+/// replay the log records after it.
+pub fn load_image(log_manager: &LogManager) -> io::Result<MetadataImage> {
+    let log = log_manager.get_or_create(CLUSTER_METADATA_TOPIC, 0)?;
+    let log = log.lock().unwrap();
+    let mut image = match snapshot::latest_snapshot(log.dir())? {
+        Some(snapshot_id) => snapshot::read_snapshot(log.dir(), snapshot_id)?,
+        None => MetadataImage::new(),
+    };
+    let mut offset = log.log_start_offset().max(image.last_offset() + 1);
+    while offset < log.high_watermark() {
+        let chunk = log.read(offset, LOAD_CHUNK_BYTES, true)?;
+        let batches =
+            RecordBatch::read_batches_from(&mut Cursor::new(chunk)).map_err(io::Error::other)?;
+        let Some(next_offset) = batches.last().map(|batch| {
+            batch.base_offset + batch.records.last().map_or(0, |r| r.offset_delta as i64) + 1
+        }) else {
+            break;
+        };
+        for batch in &batches {
+            image.apply_batch(batch);
+        }
+        if next_offset <= offset {
+            break;
+        }
+        offset = next_offset;
+    }
+    Ok(image)
+}
+
+/// Why a topic could not be created, deleted or grown.
+#[derive(Debug, Error)]
+pub enum TopicError {
+    #[error("{0}")]
+    InvalidTopic(String),
+    #[error("Topic '{0}' already exists.")]
+    AlreadyExists(String),
+    #[error("This server does not host this topic.")]
+    UnknownTopic,
+    #[error("{0}")]
+    InvalidPartitions(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl TopicError {
+    pub fn error_code(&self) -> KafkaError {
+        match self {
+            TopicError::InvalidTopic(_) => KafkaError::InvalidTopicException,
+            TopicError::AlreadyExists(_) => KafkaError::TopicAlreadyExists,
+            TopicError::UnknownTopic => KafkaError::UnknownTopicOrPartition,
+            TopicError::InvalidPartitions(_) => KafkaError::InvalidPartitions,
+            TopicError::Io(_) => KafkaError::UnknownServerError,
+        }
+    }
+}
+
+/// Create a topic for a client that asked for it implicitly, e.g. through
+/// Metadata auto-creation.
+///
+/// Returns the id of the existing topic if it was created concurrently.
+pub fn create_topic(name: &str, num_partitions: i32, node_id: i32) -> io::Result<[u8; 16]> {
+    match create_new_topic(name, num_partitions, node_id, false) {
+        Ok(topic_id) => Ok(topic_id),
+        Err(TopicError::AlreadyExists(_)) => topic_id_by_name(name)
+            .ok_or_else(|| io::Error::other(format!("topic {name} was deleted concurrently"))),
+        Err(TopicError::Io(e)) => Err(e),
+        Err(e) => Err(io::Error::other(e)),
+    }
+}
+
+/// Create a topic by appending its `TopicRecord` and `PartitionRecord`s to the
+/// metadata log, with broker `node_id` as the only replica of every partition,
+/// and create the partition directories.
+///
+/// With `validate_only` nothing is written and the returned id is all zeros.
+pub fn create_new_topic(
+    name: &str,
+    num_partitions: i32,
+    node_id: i32,
+    validate_only: bool,
+) -> Result<[u8; 16], TopicError> {
+    create_new_topic_in(
+        log_manager()?,
+        metadata_image(),
+        name,
+        num_partitions,
+        node_id,
+        validate_only,
+    )
+}
+
+fn create_new_topic_in(
+    log_manager: &LogManager,
+    image: &RwLock<MetadataImage>,
+    name: &str,
+    num_partitions: i32,
+    node_id: i32,
+    validate_only: bool,
+) -> Result<[u8; 16], TopicError> {
+    if !is_valid_topic_name(name) {
+        return Err(TopicError::InvalidTopic(format!(
+            "Topic name '{name}' is illegal, it must be 1-249 characters of [a-zA-Z0-9._-]."
+        )));
+    }
+    if is_internal_topic(name) {
+        return Err(TopicError::InvalidTopic(format!(
+            "Topic name '{name}' is reserved for an internal topic."
+        )));
+    }
+    if num_partitions < 1 {
+        return Err(TopicError::InvalidPartitions(
+            "Number of partitions must be larger than 0.".to_string(),
+        ));
+    }
+    let log = metadata_log(log_manager)?;
+    // hold the metadata log for the whole check-then-append
+    let mut log = log.lock().unwrap();
+    if image.read().unwrap().topic_by_name(name).is_some() {
+        return Err(TopicError::AlreadyExists(name.to_string()));
+    }
+    if validate_only {
+        return Ok([0; 16]);
+    }
+
+    let topic_id = *Uuid::new_v4().as_bytes();
+    let topic = ClusterMetadataRecord {
+        frame_version: 1,
+        record_version: 0,
+        payload: ClusterMetadataValue::Topic(TopicRecord {
+            topic_name: name.to_string(),
+            uuid: topic_id,
+            tagged_fields: Vec::new(),
+        }),
+    };
+    let records = std::iter::once(topic)
+        .chain(partition_records(topic_id, 0..num_partitions, node_id))
+        .collect();
+    append_records(&mut log, image, records)?;
+    create_partition_dirs(log_manager, name, topic_id, 0..num_partitions)?;
+    Ok(topic_id)
+}
+
+/// Delete a topic by appending a `RemoveTopicRecord` to the metadata log, and
+/// remove its partition logs. Returns the deleted topic.
+pub fn delete_topic(topic_id: &[u8; 16]) -> Result<TopicImage, TopicError> {
+    delete_topic_in(log_manager()?, metadata_image(), topic_id)
+}
+
+fn delete_topic_in(
+    log_manager: &LogManager,
+    image: &RwLock<MetadataImage>,
+    topic_id: &[u8; 16],
+) -> Result<TopicImage, TopicError> {
+    let log = metadata_log(log_manager)?;
+    let mut log = log.lock().unwrap();
+    let topic = image.read().unwrap().topic_by_id(topic_id).cloned();
+    let topic = topic.ok_or(TopicError::UnknownTopic)?;
+    let remove = ClusterMetadataRecord {
+        frame_version: 1,
+        record_version: 0,
+        payload: ClusterMetadataValue::RemoveTopic(RemoveTopicRecord {
+            topic_id: *topic_id,
+            tagged_fields: Vec::new(),
+        }),
+    };
+    append_records(&mut log, image, vec![remove])?;
+    for partition in topic.partitions.keys() {
+        log_manager.remove(&topic.name, *partition)?;
+    }
+    Ok(topic)
+}
+
+/// Grow a topic to `count` partitions, with broker `node_id` as the only
+/// replica of every new partition.
+pub fn create_partitions(
+    name: &str,
+    count: i32,
+    node_id: i32,
+    validate_only: bool,
+) -> Result<(), TopicError> {
+    create_partitions_in(
+        log_manager()?,
+        metadata_image(),
+        name,
+        count,
+        node_id,
+        validate_only,
+    )
+}
+
+fn create_partitions_in(
+    log_manager: &LogManager,
+    image: &RwLock<MetadataImage>,
+    name: &str,
+    count: i32,
+    node_id: i32,
+    validate_only: bool,
+) -> Result<(), TopicError> {
+    let log = metadata_log(log_manager)?;
+    let mut log = log.lock().unwrap();
+    let topic = image.read().unwrap().topic_by_name(name).cloned();
+    let topic = topic.ok_or(TopicError::UnknownTopic)?;
+    let current = topic.partitions.len() as i32;
+    if count <= current {
+        return Err(TopicError::InvalidPartitions(format!(
+            "Topic currently has {current} partitions, which is higher than or equal to the \
+             requested {count}."
+        )));
+    }
+    if validate_only {
+        return Ok(());
+    }
+    append_records(
+        &mut log,
+        image,
+        partition_records(topic.id, current..count, node_id).collect(),
+    )?;
+    create_partition_dirs(log_manager, name, topic.id, current..count)?;
+    Ok(())
+}
+
+/// Reserve the next block of producer ids for broker `node_id` by appending a
+/// `ProducerIdsRecord` to the metadata log.
+pub fn allocate_producer_ids(node_id: i32) -> io::Result<Range<i64>> {
+    allocate_producer_ids_in(log_manager()?, metadata_image(), node_id)
+}
+
+pub(crate) fn allocate_producer_ids_in(
+    log_manager: &LogManager,
+    image: &RwLock<MetadataImage>,
+    node_id: i32,
+) -> io::Result<Range<i64>> {
+    let log = metadata_log(log_manager)?;
+    let mut log = log.lock().unwrap();
+    let (start, broker_epoch) = {
+        let image = image.read().unwrap();
+        let broker_epoch = image
+            .brokers()
+            .find(|broker| broker.broker_id == node_id)
+            .map_or(-1, |broker| broker.broker_epoch);
+        (image.next_producer_id(), broker_epoch)
+    };
+    let end = start + PRODUCER_ID_BLOCK_SIZE;
+    let producer_ids = ClusterMetadataRecord {
+        frame_version: 1,
+        record_version: 0,
+        payload: ClusterMetadataValue::ProducerIds(ProducerIdsRecord {
+            broker_id: node_id,
+            broker_epoch,
+            next_producer_id: end,
+            tagged_fields: Vec::new(),
+        }),
+    };
+    append_records(&mut log, image, vec![producer_ids])?;
+    Ok(start..end)
+}
+
+/// Write a snapshot of the current image next to the metadata log.
+pub fn write_snapshot() -> io::Result<SnapshotId> {
+    let log = metadata_log(log_manager()?)?;
+    // no record can be appended while the snapshot is taken
+    let log = log.lock().unwrap();
+    with_image(|image| snapshot::write_snapshot(log.dir(), image))
+}
+
+fn log_manager() -> io::Result<&'static LogManager> {
+    LOG_MANAGER
+        .get()
+        .ok_or_else(|| io::Error::other("log manager is not initialized"))
+}
+
+fn metadata_log(log_manager: &LogManager) -> io::Result<SharedPartitionLog> {
+    log_manager.get_or_create(CLUSTER_METADATA_TOPIC, 0)
+}
+
+/// Open the logs of new partitions and record their topic id next to them.
+fn create_partition_dirs(
+    log_manager: &LogManager,
+    name: &str,
+    topic_id: [u8; 16],
+    partitions: Range<i32>,
+) -> io::Result<()> {
+    for partition in partitions {
+        log_manager.get_or_create(name, partition)?;
+        PartitionMetadata { topic_id }
+            .write(&log_manager.partition_dir(name, partition))
+            .map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+fn partition_records(
+    topic_id: [u8; 16],
+    partition_ids: Range<i32>,
+    node_id: i32,
+) -> impl Iterator<Item = ClusterMetadataRecord> {
+    // new partitions live in the only log dir
+    let directory_id = META_PROPERTIES
+        .get()
+        .and_then(|meta_properties| meta_properties.directory_id)
+        .unwrap_or_default();
+    partition_ids.map(move |partition_id| ClusterMetadataRecord {
+        frame_version: 1,
+        record_version: 1,
+        payload: ClusterMetadataValue::Partition(PartitionRecord {
+            partition_id,
+            topic_id,
+            replicas: vec![node_id],
+            isr: vec![node_id],
+            rra: Vec::new(),
+            ara: Vec::new(),
+            leader_id: node_id,
+            leader_epoch: 0,
+            partition_epoch: 0,
+            directories: vec![directory_id],
+            tagged_fields: Vec::new(),
+        }),
+    })
+}
+
+/// Append `records` to the metadata log as a single batch and apply it to
+/// `image`.
+fn append_records(
+    log: &mut PartitionLog,
+    image: &RwLock<MetadataImage>,
+    records: Vec<ClusterMetadataRecord>,
+) -> io::Result<()> {
+    let records = records
+        .into_iter()
+        .enumerate()
+        .map(|(offset_delta, record)| {
+            Ok(Record {
+                attributes: 0,
+                timestamp_delta: 0,
+                offset_delta: offset_delta as i32,
+                key: None,
+                value: Some(record.to_bytes()?),
+                headers: Vec::new(),
+            })
+        })
+        .collect::<binrw::BinResult<Vec<_>>>()
+        .map_err(io::Error::other)?;
+
+    let now = now_ms();
+    let mut batch = RecordBatch {
+        base_offset: 0,
+        partition_leader_epoch: 0,
+        crc: 0,
+        attributes: 0,
+        base_timestamp: now,
+        max_timestamp: now,
+        producer_id: -1,
+        producer_epoch: -1,
+        base_sequence: -1,
+        records,
+    };
+    let mut bytes = Cursor::new(Vec::new());
+    batch
+        .write_options(&mut bytes, Endian::Big, ())
+        .map_err(io::Error::other)?;
+    let bytes = bytes.into_inner();
+    let info = log.append(&bytes).map_err(io::Error::other)?;
+    batch.base_offset = info.base_offset;
+
+    image.write().unwrap().apply_batch(&batch);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::storage::LogConfig;
+
+    fn topic(name: &str, id: u8) -> ClusterMetadataValue {
+        ClusterMetadataValue::Topic(TopicRecord {
+            topic_name: name.to_string(),
+            uuid: [id; 16],
+            tagged_fields: Vec::new(),
+        })
+    }
+
+    fn append(log: &SharedPartitionLog, payload: ClusterMetadataValue) {
+        let record = ClusterMetadataRecord {
+            frame_version: 1,
+            record_version: 0,
+            payload,
+        };
+        let batch = RecordBatch {
+            base_offset: 0,
+            partition_leader_epoch: 0,
+            crc: 0,
+            attributes: 0,
+            base_timestamp: 0,
+            max_timestamp: 0,
+            producer_id: -1,
+            producer_epoch: -1,
+            base_sequence: -1,
+            records: vec![Record {
+                attributes: 0,
+                timestamp_delta: 0,
+                offset_delta: 0,
+                key: None,
+                value: Some(record.to_bytes().unwrap()),
+                headers: Vec::new(),
+            }],
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        batch.write_options(&mut bytes, Endian::Big, ()).unwrap();
+        log.lock().unwrap().append(&bytes.into_inner()).unwrap();
+    }
+
+    #[test]
+    fn test_load_image_from_snapshot() {
+        let dir = std::env::temp_dir().join(format!("metadata-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log_manager = LogManager::new(&dir, LogConfig::default());
+        let log = log_manager
+            .get_or_create(CLUSTER_METADATA_TOPIC, 0)
+            .unwrap();
+        append(&log, topic("foo", 1));
+        append(&log, topic("bar", 2));
+
+        // the snapshot stands in for offset 0, so "foo" is never replayed
+        let snapshot_image = MetadataImage::from_snapshot(
+            [topic("baz", 3)],
+            SnapshotId {
+                end_offset: 1,
+                epoch: 0,
+            },
+            0,
+        );
+        snapshot::write_snapshot(log.lock().unwrap().dir(), &snapshot_image).unwrap();
+
+        let image = load_image(&log_manager).unwrap();
+        assert_eq!(image.last_offset(), 1);
+        let names: Vec<_> = image.topics().map(|topic| topic.name.as_str()).collect();
+        assert_eq!(names, ["bar", "baz"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_topic_lifecycle() {
+        let dir = std::env::temp_dir().join(format!("metadata-topics-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log_manager = LogManager::new(&dir, LogConfig::default());
+        let image = RwLock::new(MetadataImage::new());
+        let create = |name: &str, num_partitions, validate_only| {
+            create_new_topic_in(&log_manager, &image, name, num_partitions, 1, validate_only)
+        };
+        let grow = |name: &str, count, validate_only| {
+            create_partitions_in(&log_manager, &image, name, count, 1, validate_only)
+        };
+        let metadata_end = || {
+            let log = metadata_log(&log_manager).unwrap();
+            let high_watermark = log.lock().unwrap().high_watermark();
+            high_watermark
+        };
+
+        let long_name = "a".repeat(250);
+        for name in ["", ".", "..", "a/b", "a b", long_name.as_str()] {
+            assert!(
+                matches!(create(name, 1, false), Err(TopicError::InvalidTopic(_))),
+                "{name:?} is not a valid topic name"
+            );
+        }
+        assert!(matches!(
+            create("__consumer_offsets", 1, false),
+            Err(TopicError::InvalidTopic(_))
+        ));
+        assert!(matches!(
+            create("foo", 0, false),
+            Err(TopicError::InvalidPartitions(_))
+        ));
+
+        // validation only writes nothing
+        assert_eq!(create("foo", 2, true).unwrap(), [0; 16]);
+        assert_eq!(metadata_end(), 0);
+        assert!(image.read().unwrap().topic_by_name("foo").is_none());
+        assert!(!log_manager.partition_dir("foo", 0).exists());
+
+        let topic_id = create("foo", 2, false).unwrap();
+        assert_ne!(topic_id, [0; 16]);
+        assert_eq!(
+            image
+                .read()
+                .unwrap()
+                .topic_by_name("foo")
+                .unwrap()
+                .partitions
+                .len(),
+            2
+        );
+        for partition in 0..2 {
+            let dir = log_manager.partition_dir("foo", partition);
+            assert_eq!(
+                PartitionMetadata::read(&dir).unwrap().unwrap().topic_id,
+                topic_id
+            );
+        }
+        assert!(matches!(
+            create("foo", 1, false),
+            Err(TopicError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            create("foo", 1, true),
+            Err(TopicError::AlreadyExists(_))
+        ));
+
+        // partitions can only be added
+        for count in [1, 2] {
+            assert!(matches!(
+                grow("foo", count, false),
+                Err(TopicError::InvalidPartitions(_))
+            ));
+        }
+        assert!(matches!(
+            grow("bar", 3, false),
+            Err(TopicError::UnknownTopic)
+        ));
+        let end = metadata_end();
+        grow("foo", 3, true).unwrap();
+        assert_eq!(metadata_end(), end);
+        assert!(!log_manager.partition_dir("foo", 2).exists());
+        grow("foo", 3, false).unwrap();
+        assert_eq!(
+            image
+                .read()
+                .unwrap()
+                .topic_by_name("foo")
+                .unwrap()
+                .partitions
+                .len(),
+            3
+        );
+        assert!(log_manager.partition_dir("foo", 2).exists());
+
+        // the metadata log replays to the same topics
+        let names = |image: &MetadataImage| -> Vec<(String, usize)> {
+            image
+                .topics()
+                .map(|topic| (topic.name.clone(), topic.partitions.len()))
+                .collect()
+        };
+        assert_eq!(
+            names(&load_image(&log_manager).unwrap()),
+            names(&image.read().unwrap())
+        );
+
+        let deleted = delete_topic_in(&log_manager, &image, &topic_id).unwrap();
+        assert_eq!(deleted.name, "foo");
+        assert!(image.read().unwrap().topic_by_id(&topic_id).is_none());
+        for partition in 0..3 {
+            assert!(!log_manager.partition_dir("foo", partition).exists());
+        }
+        assert!(matches!(
+            delete_topic_in(&log_manager, &image, &topic_id),
+            Err(TopicError::UnknownTopic)
+        ));
+        assert!(load_image(&log_manager)
+            .unwrap()
+            .topic_by_name("foo")
+            .is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
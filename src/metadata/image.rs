@@ -0,0 +1,514 @@
+//! In-memory view of the cluster metadata, built by replaying the metadata log.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::records::{
+    record_value::{
+        BrokerRegistrationRecord, ClusterMetadataRecord, ClusterMetadataValue, FeatureLevelRecord,
+        PartitionChangeRecord, PartitionRecord, ProducerIdsRecord, TopicRecord, NO_LEADER_CHANGE,
+    },
+    RecordBatch,
+};
+
+use super::snapshot::SnapshotId;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicImage {
+    pub name: String,
+    pub id: [u8; 16],
+    /// Current state of every partition, ordered by partition id.
+    pub partitions: BTreeMap<i32, PartitionRecord>,
+}
+
+/// Topics, partitions, brokers, features and handed out producer ids as of
+/// `last_offset`.
+///
+/// Records are applied as deltas in offset order, so a later record always
+/// wins over an earlier one for the same entity.
+#[derive(Debug)]
+pub struct MetadataImage {
+    /// Offset of the last applied record, -1 when nothing was applied.
+    last_offset: i64,
+    /// Leader epoch of the batch holding `last_offset`.
+    last_epoch: i32,
+    /// Append time of the batch holding `last_offset`, -1 if unknown.
+    last_timestamp: i64,
+    topics: HashMap<[u8; 16], TopicImage>,
+    /// Sorted so listing all topics is stable.
+    topic_ids_by_name: BTreeMap<String, [u8; 16]>,
+    brokers: BTreeMap<i32, BrokerRegistrationRecord>,
+    features: BTreeMap<String, i16>,
+    /// Latest block of producer ids handed out; the next block starts at its
+    /// `next_producer_id`.
+    producer_ids: Option<ProducerIdsRecord>,
+    /// Records of types or versions this broker cannot decode, which were
+    /// skipped.
+    unknown_records: u64,
+}
+
+impl Default for MetadataImage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetadataImage {
+    pub fn new() -> Self {
+        Self {
+            last_offset: -1,
+            last_epoch: 0,
+            last_timestamp: -1,
+            topics: HashMap::new(),
+            topic_ids_by_name: BTreeMap::new(),
+            brokers: BTreeMap::new(),
+            features: BTreeMap::new(),
+            producer_ids: None,
+            unknown_records: 0,
+        }
+    }
+
+    /// Build an image from the batches of the metadata log, in log order.
+    pub fn replay<'a>(batches: impl IntoIterator<Item = &'a RecordBatch>) -> Self {
+        let mut image = Self::new();
+        for batch in batches {
+            image.apply_batch(batch);
+        }
+        image
+    }
+
+    /// Build an image from the records of a snapshot that ends before
+    /// `snapshot_id.end_offset`.
+    pub fn from_snapshot(
+        values: impl IntoIterator<Item = ClusterMetadataValue>,
+        snapshot_id: SnapshotId,
+        last_contained_log_timestamp: i64,
+    ) -> Self {
+        let mut image = Self::new();
+        for value in values {
+            image.apply(value);
+        }
+        image.last_offset = snapshot_id.end_offset - 1;
+        image.last_epoch = snapshot_id.epoch;
+        image.last_timestamp = last_contained_log_timestamp;
+        image
+    }
+
+    /// Apply every record of `batch` past `last_offset`, so replaying a batch
+    /// twice is harmless. Records that fail to decode and control records are
+    /// skipped.
+    pub fn apply_batch(&mut self, batch: &RecordBatch) {
+        for record in &batch.records {
+            let offset = batch.base_offset + record.offset_delta as i64;
+            if offset <= self.last_offset {
+                continue;
+            }
+            if !batch.is_control() {
+                if let Ok(record) = record.cluster_metadata_record() {
+                    self.apply(record.payload);
+                }
+            }
+            self.last_offset = offset;
+            self.last_epoch = batch.partition_leader_epoch;
+            self.last_timestamp = batch.max_timestamp;
+        }
+    }
+
+    pub fn apply(&mut self, value: ClusterMetadataValue) {
+        match value {
+            ClusterMetadataValue::Unknown { .. } => self.unknown_records += 1,
+            ClusterMetadataValue::BrokerRegistration(broker) => {
+                self.brokers.insert(broker.broker_id, broker);
+            }
+            ClusterMetadataValue::UnregisterBroker(unregister) => {
+                if self
+                    .broker_mut(unregister.broker_id, unregister.broker_epoch)
+                    .is_some()
+                {
+                    self.brokers.remove(&unregister.broker_id);
+                }
+            }
+            ClusterMetadataValue::FenceBroker(fence) => {
+                if let Some(broker) = self.broker_mut(fence.id, fence.epoch) {
+                    broker.fenced = true;
+                }
+            }
+            ClusterMetadataValue::UnfenceBroker(unfence) => {
+                if let Some(broker) = self.broker_mut(unfence.id, unfence.epoch) {
+                    broker.fenced = false;
+                }
+            }
+            ClusterMetadataValue::BrokerRegistrationChange(change) => {
+                if let Some(broker) = self.broker_mut(change.broker_id, change.broker_epoch) {
+                    if let Some(fenced) = change.fenced() {
+                        broker.fenced = fenced;
+                    }
+                    if let Some(in_controlled_shutdown) = change.in_controlled_shutdown() {
+                        broker.in_controlled_shutdown = in_controlled_shutdown;
+                    }
+                    if let Some(log_dirs) = change.log_dirs() {
+                        broker.log_dirs = log_dirs;
+                    }
+                }
+            }
+            ClusterMetadataValue::Topic(topic) => self.apply_topic(topic),
+            ClusterMetadataValue::FeatureLevel(feature) => {
+                // level 0 means the feature was disabled
+                if feature.level == 0 {
+                    self.features.remove(&feature.feature_name);
+                } else {
+                    self.features.insert(feature.feature_name, feature.level);
+                }
+            }
+            ClusterMetadataValue::Partition(partition) => {
+                if let Some(topic) = self.topics.get_mut(&partition.topic_id) {
+                    topic.partitions.insert(partition.partition_id, partition);
+                }
+            }
+            ClusterMetadataValue::PartitionChange(change) => self.apply_partition_change(change),
+            ClusterMetadataValue::RemoveTopic(remove) => {
+                if let Some(topic) = self.topics.remove(&remove.topic_id) {
+                    self.topic_ids_by_name.remove(&topic.name);
+                }
+            }
+            ClusterMetadataValue::ProducerIds(producer_ids) => {
+                self.producer_ids = Some(producer_ids);
+            }
+            // configs, ACLs, quotas, credentials and controller bookkeeping
+            // are not part of the image
+            ClusterMetadataValue::Config(_)
+            | ClusterMetadataValue::DelegationToken(_)
+            | ClusterMetadataValue::UserScramCredential(_)
+            | ClusterMetadataValue::ClientQuota(_)
+            | ClusterMetadataValue::AccessControlEntry(_)
+            | ClusterMetadataValue::RemoveAccessControlEntry(_)
+            | ClusterMetadataValue::NoOp(_)
+            | ClusterMetadataValue::ZkMigrationState(_)
+            | ClusterMetadataValue::RemoveUserScramCredential(_)
+            | ClusterMetadataValue::BeginTransaction(_)
+            | ClusterMetadataValue::EndTransaction(_)
+            | ClusterMetadataValue::AbortTransaction(_)
+            | ClusterMetadataValue::RemoveDelegationToken(_)
+            | ClusterMetadataValue::RegisterController(_) => {}
+        }
+    }
+
+    /// The registration of `broker_id`, unless it was registered again with a
+    /// newer epoch than the record refers to.
+    fn broker_mut(&mut self, broker_id: i32, epoch: i64) -> Option<&mut BrokerRegistrationRecord> {
+        self.brokers
+            .get_mut(&broker_id)
+            .filter(|broker| broker.broker_epoch == epoch)
+    }
+
+    fn apply_topic(&mut self, topic: TopicRecord) {
+        if let Some(old_id) = self
+            .topic_ids_by_name
+            .insert(topic.topic_name.clone(), topic.uuid)
+        {
+            if old_id != topic.uuid {
+                self.topics.remove(&old_id);
+            }
+        }
+        self.topics.insert(
+            topic.uuid,
+            TopicImage {
+                name: topic.topic_name,
+                id: topic.uuid,
+                partitions: BTreeMap::new(),
+            },
+        );
+    }
+
+    fn apply_partition_change(&mut self, change: PartitionChangeRecord) {
+        let Some(partition) = self
+            .topics
+            .get_mut(&change.topic_id)
+            .and_then(|topic| topic.partitions.get_mut(&change.partition_id))
+        else {
+            return;
+        };
+        if let Some(isr) = change.isr() {
+            partition.isr = isr;
+        }
+        if let Some(replicas) = change.replicas() {
+            partition.replicas = replicas;
+        }
+        if let Some(removing_replicas) = change.removing_replicas() {
+            partition.rra = removing_replicas;
+        }
+        if let Some(adding_replicas) = change.adding_replicas() {
+            partition.ara = adding_replicas;
+        }
+        if let Some(directories) = change.directories() {
+            partition.directories = directories;
+        }
+        let leader = change.leader();
+        if leader != NO_LEADER_CHANGE {
+            partition.leader_id = leader;
+            partition.leader_epoch += 1;
+        }
+        partition.partition_epoch += 1;
+    }
+
+    pub fn last_offset(&self) -> i64 {
+        self.last_offset
+    }
+
+    pub fn unknown_records(&self) -> u64 {
+        self.unknown_records
+    }
+
+    pub fn last_timestamp(&self) -> i64 {
+        self.last_timestamp
+    }
+
+    /// Id of a snapshot taken of this image.
+    pub fn snapshot_id(&self) -> SnapshotId {
+        SnapshotId {
+            end_offset: self.last_offset + 1,
+            epoch: self.last_epoch,
+        }
+    }
+
+    /// Records that rebuild this image from scratch: features first, then
+    /// brokers and the latest producer id block, then every topic followed by
+    /// its partitions.
+    pub fn snapshot_records(&self) -> Vec<ClusterMetadataRecord> {
+        let record = |record_version, payload| ClusterMetadataRecord {
+            frame_version: 1,
+            record_version,
+            payload,
+        };
+        let features = self.features.iter().map(|(name, level)| {
+            let feature = FeatureLevelRecord {
+                feature_name: name.clone(),
+                level: *level,
+                tagged_fields: Vec::new(),
+            };
+            record(0, ClusterMetadataValue::FeatureLevel(feature))
+        });
+        let brokers = self
+            .brokers
+            .values()
+            .map(|broker| record(3, ClusterMetadataValue::BrokerRegistration(broker.clone())));
+        let producer_ids = self
+            .producer_ids
+            .iter()
+            .map(|producer_ids| record(0, ClusterMetadataValue::ProducerIds(producer_ids.clone())));
+        let topics = self.topics().flat_map(|topic| {
+            let topic_record = TopicRecord {
+                topic_name: topic.name.clone(),
+                uuid: topic.id,
+                tagged_fields: Vec::new(),
+            };
+            std::iter::once(record(0, ClusterMetadataValue::Topic(topic_record))).chain(
+                topic.partitions.values().map(move |partition| {
+                    record(2, ClusterMetadataValue::Partition(partition.clone()))
+                }),
+            )
+        });
+        features
+            .chain(brokers)
+            .chain(producer_ids)
+            .chain(topics)
+            .collect()
+    }
+
+    pub fn topic_by_id(&self, topic_id: &[u8; 16]) -> Option<&TopicImage> {
+        self.topics.get(topic_id)
+    }
+
+    pub fn topic_by_name(&self, name: &str) -> Option<&TopicImage> {
+        self.topic_ids_by_name
+            .get(name)
+            .and_then(|topic_id| self.topics.get(topic_id))
+    }
+
+    /// Every topic, ordered by name.
+    pub fn topics(&self) -> impl Iterator<Item = &TopicImage> {
+        self.topic_ids_by_name
+            .values()
+            .filter_map(|topic_id| self.topics.get(topic_id))
+    }
+
+    pub fn partition(&self, topic_id: &[u8; 16], partition_index: i32) -> Option<&PartitionRecord> {
+        self.topic_by_id(topic_id)?.partitions.get(&partition_index)
+    }
+
+    /// Latest registration of every broker, ordered by broker id.
+    pub fn brokers(&self) -> impl Iterator<Item = &BrokerRegistrationRecord> {
+        self.brokers.values()
+    }
+
+    pub fn feature_level(&self, name: &str) -> Option<i16> {
+        self.features.get(name).copied()
+    }
+
+    /// First producer id that was never handed out.
+    pub fn next_producer_id(&self) -> i64 {
+        self.producer_ids
+            .as_ref()
+            .map_or(0, |producer_ids| producer_ids.next_producer_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common_structs::tagged_field::TaggedField,
+        records::record_value::{
+            BrokerRegistrationChangeRecord, FenceBrokerRecord, RemoveTopicRecord,
+            UnregisterBrokerRecord,
+        },
+    };
+
+    fn topic(name: &str, id: u8) -> ClusterMetadataValue {
+        ClusterMetadataValue::Topic(TopicRecord {
+            topic_name: name.to_string(),
+            uuid: [id; 16],
+            tagged_fields: Vec::new(),
+        })
+    }
+
+    fn partition(topic_id: u8, partition_id: i32) -> ClusterMetadataValue {
+        let ClusterMetadataValue::Partition(mut partition) =
+            ClusterMetadataRecord::mock_partition_record().payload
+        else {
+            unreachable!()
+        };
+        partition.topic_id = [topic_id; 16];
+        partition.partition_id = partition_id;
+        ClusterMetadataValue::Partition(partition)
+    }
+
+    #[test]
+    fn test_topics_and_partitions() {
+        let mut image = MetadataImage::new();
+        image.apply(topic("foo", 1));
+        image.apply(partition(1, 1));
+        image.apply(partition(1, 0));
+        // partitions of unknown topics are dropped
+        image.apply(partition(2, 0));
+
+        let foo = image.topic_by_name("foo").unwrap();
+        assert_eq!(foo.id, [1; 16]);
+        assert_eq!(foo.partitions.keys().copied().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(image.topic_by_id(&[1; 16]).unwrap().name, "foo");
+        assert!(image.topic_by_id(&[2; 16]).is_none());
+    }
+
+    #[test]
+    fn test_partition_change_and_topic_removal() {
+        let mut image = MetadataImage::new();
+        image.apply(topic("foo", 1));
+        image.apply(partition(1, 0));
+        let before = image.partition(&[1; 16], 0).unwrap().clone();
+
+        image.apply(ClusterMetadataValue::PartitionChange(
+            PartitionChangeRecord {
+                partition_id: 0,
+                topic_id: [1; 16],
+                tagged_fields: vec![
+                    TaggedField::new(0, vec![0x02, 0, 0, 0, 7]),
+                    TaggedField::new(1, 7i32.to_be_bytes().to_vec()),
+                ],
+            },
+        ));
+        let after = image.partition(&[1; 16], 0).unwrap();
+        assert_eq!(after.isr, [7]);
+        assert_eq!(after.leader_id, 7);
+        assert_eq!(after.leader_epoch, before.leader_epoch + 1);
+        assert_eq!(after.partition_epoch, before.partition_epoch + 1);
+        assert_eq!(after.replicas, before.replicas);
+
+        image.apply(ClusterMetadataValue::RemoveTopic(RemoveTopicRecord {
+            topic_id: [1; 16],
+            tagged_fields: Vec::new(),
+        }));
+        assert!(image.topic_by_name("foo").is_none());
+        assert_eq!(image.topics().count(), 0);
+    }
+
+    #[test]
+    fn test_broker_fencing() {
+        let ClusterMetadataValue::BrokerRegistration(broker) =
+            ClusterMetadataRecord::mock_broker_registration().payload
+        else {
+            unreachable!()
+        };
+        let (broker_id, epoch) = (broker.broker_id, broker.broker_epoch);
+        let mut image = MetadataImage::new();
+        image.apply(ClusterMetadataValue::BrokerRegistration(broker));
+
+        image.apply(ClusterMetadataValue::FenceBroker(FenceBrokerRecord {
+            id: broker_id,
+            epoch,
+            tagged_fields: Vec::new(),
+        }));
+        assert!(image.brokers().next().unwrap().fenced);
+
+        image.apply(ClusterMetadataValue::BrokerRegistrationChange(
+            BrokerRegistrationChangeRecord {
+                broker_id,
+                broker_epoch: epoch,
+                tagged_fields: vec![TaggedField::new(0, vec![0xff])],
+            },
+        ));
+        assert!(!image.brokers().next().unwrap().fenced);
+
+        // records about an older registration are ignored
+        image.apply(ClusterMetadataValue::UnregisterBroker(
+            UnregisterBrokerRecord {
+                broker_id,
+                broker_epoch: epoch - 1,
+                tagged_fields: Vec::new(),
+            },
+        ));
+        assert_eq!(image.brokers().count(), 1);
+        image.apply(ClusterMetadataValue::UnregisterBroker(
+            UnregisterBrokerRecord {
+                broker_id,
+                broker_epoch: epoch,
+                tagged_fields: Vec::new(),
+            },
+        ));
+        assert_eq!(image.brokers().count(), 0);
+    }
+
+    #[test]
+    fn test_unknown_records_are_counted() {
+        let mut image = MetadataImage::new();
+        image.apply(ClusterMetadataValue::Unknown {
+            record_type: 100,
+            record_version: 0,
+            raw: vec![0],
+        });
+        image.apply(topic("foo", 1));
+        assert_eq!(image.unknown_records(), 1);
+        assert!(image.topic_by_name("foo").is_some());
+    }
+
+    #[test]
+    fn test_producer_id_blocks() {
+        let mut image = MetadataImage::new();
+        assert_eq!(image.next_producer_id(), 0);
+        image.apply(ClusterMetadataValue::ProducerIds(ProducerIdsRecord {
+            broker_id: 1,
+            broker_epoch: -1,
+            next_producer_id: 1000,
+            tagged_fields: Vec::new(),
+        }));
+        assert_eq!(image.next_producer_id(), 1000);
+        assert_eq!(image.snapshot_records().len(), 1);
+    }
+
+    #[test]
+    fn test_recreated_topic_replaces_old_id() {
+        let mut image = MetadataImage::new();
+        image.apply(topic("foo", 1));
+        image.apply(topic("foo", 2));
+        assert_eq!(image.topic_by_name("foo").unwrap().id, [2; 16]);
+        assert!(image.topic_by_id(&[1; 16]).is_none());
+    }
+}
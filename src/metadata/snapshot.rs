@@ -0,0 +1,313 @@
+//! KRaft metadata snapshots: `<end offset>-<epoch>.checkpoint` files in the
+//! `__cluster_metadata-0` directory, holding every record needed to rebuild
+//! the image as of `end offset - 1`.
+//!
+//! A snapshot is a sequence of record batches that starts with a control batch
+//! holding a `SnapshotHeader` and ends with one holding a `SnapshotFooter`.
+
+use std::{
+    fs,
+    io::{self, Cursor, Write},
+    path::Path,
+};
+
+use binrw::{BinRead, BinResult, BinWrite, Endian};
+
+use crate::records::{
+    control_record::{
+        ControlRecordKey, ControlRecordType, SnapshotFooterRecord, SnapshotHeaderRecord,
+        CONTROL_BATCH_MASK,
+    },
+    Record, RecordBatch,
+};
+
+use super::MetadataImage;
+
+const SNAPSHOT_SUFFIX: &str = ".checkpoint";
+
+/// Suffix of a snapshot still being written; renamed once complete.
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// Records per data batch when writing a snapshot.
+const RECORDS_PER_BATCH: usize = 1000;
+
+/// Identifies a snapshot by the offset it ends before and the leader epoch of
+/// its last record. Ordered by offset, then epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SnapshotId {
+    pub end_offset: i64,
+    pub epoch: i32,
+}
+
+impl SnapshotId {
+    pub fn file_name(&self) -> String {
+        format!(
+            "{:020}-{:010}{}",
+            self.end_offset, self.epoch, SNAPSHOT_SUFFIX
+        )
+    }
+
+    /// Parse a snapshot file name, `None` for anything else.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let (end_offset, epoch) = name.strip_suffix(SNAPSHOT_SUFFIX)?.split_once('-')?;
+        Some(Self {
+            end_offset: end_offset.parse().ok()?,
+            epoch: epoch.parse().ok()?,
+        })
+    }
+}
+
+/// The newest snapshot in `dir`, if any.
+pub fn latest_snapshot(dir: &Path) -> io::Result<Option<SnapshotId>> {
+    let mut latest = None;
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let snapshot_id = name.to_str().and_then(SnapshotId::from_file_name);
+        latest = latest.max(snapshot_id);
+    }
+    Ok(latest)
+}
+
+/// Load the image stored in the snapshot `snapshot_id` of `dir`.
+pub fn read_snapshot(dir: &Path, snapshot_id: SnapshotId) -> io::Result<MetadataImage> {
+    let path = dir.join(snapshot_id.file_name());
+    let invalid = |message: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("snapshot {}: {message}", path.display()),
+        )
+    };
+    let bytes = fs::read(&path)?;
+    let batches = RecordBatch::read_batches_from(&mut Cursor::new(bytes))
+        .map_err(|e| invalid(&e.to_string()))?;
+
+    let header = batches
+        .first()
+        .filter(|batch| control_type(batch) == Some(ControlRecordType::SnapshotHeader))
+        .and_then(|batch| batch.records[0].value.as_deref())
+        .and_then(|value| SnapshotHeaderRecord::read_be(&mut Cursor::new(value)).ok())
+        .ok_or_else(|| invalid("does not start with a snapshot header"))?;
+    if batches.last().and_then(control_type) != Some(ControlRecordType::SnapshotFooter) {
+        return Err(invalid("does not end with a snapshot footer"));
+    }
+
+    let values = batches
+        .iter()
+        .filter(|batch| !batch.is_control())
+        .flat_map(|batch| &batch.records)
+        .filter_map(|record| record.cluster_metadata_record().ok())
+        .map(|record| record.payload);
+    Ok(MetadataImage::from_snapshot(
+        values,
+        snapshot_id,
+        header.last_contained_log_timestamp,
+    ))
+}
+
+/// Write a snapshot of `image` into `dir` and return its id.
+///
+/// The snapshot is written under a temporary name and renamed once synced,
+/// so a crash never leaves a partial `.checkpoint` behind.
+pub fn write_snapshot(dir: &Path, image: &MetadataImage) -> io::Result<SnapshotId> {
+    let snapshot_id = image.snapshot_id();
+    let timestamp = image.last_timestamp();
+
+    let header = SnapshotHeaderRecord {
+        version: 0,
+        last_contained_log_timestamp: timestamp,
+        tagged_fields: Vec::new(),
+    };
+    let footer = SnapshotFooterRecord {
+        version: 0,
+        tagged_fields: Vec::new(),
+    };
+    let mut batches = vec![control_batch(
+        ControlRecordType::SnapshotHeader,
+        to_bytes(&header)?,
+    )?];
+    for records in image.snapshot_records().chunks(RECORDS_PER_BATCH) {
+        let records = records
+            .iter()
+            .map(|record| record.to_bytes().map(|value| (None, Some(value))))
+            .collect::<BinResult<Vec<_>>>()
+            .map_err(io::Error::other)?;
+        batches.push(batch(0, records));
+    }
+    batches.push(control_batch(
+        ControlRecordType::SnapshotFooter,
+        to_bytes(&footer)?,
+    )?);
+
+    let mut bytes = Cursor::new(Vec::new());
+    let mut next_offset = 0;
+    for mut batch in batches {
+        batch.base_offset = next_offset;
+        batch.partition_leader_epoch = snapshot_id.epoch;
+        batch.base_timestamp = timestamp;
+        batch.max_timestamp = timestamp;
+        next_offset += batch.records.len() as i64;
+        batch
+            .write_options(&mut bytes, Endian::Big, ())
+            .map_err(io::Error::other)?;
+    }
+
+    let file_name = snapshot_id.file_name();
+    let partial = dir.join(format!("{file_name}{PARTIAL_SUFFIX}"));
+    let mut file = fs::File::create(&partial)?;
+    file.write_all(&bytes.into_inner())?;
+    file.sync_all()?;
+    fs::rename(&partial, dir.join(file_name))?;
+    Ok(snapshot_id)
+}
+
+/// Control type of the first record of `batch`, `None` for data batches.
+fn control_type(batch: &RecordBatch) -> Option<ControlRecordType> {
+    if !batch.is_control() {
+        return None;
+    }
+    let key = batch.records.first()?.key.as_deref()?;
+    ControlRecordKey::control_type(key)
+}
+
+fn control_batch(control_type: ControlRecordType, value: Vec<u8>) -> io::Result<RecordBatch> {
+    let key = to_bytes(&ControlRecordKey::new(control_type))?;
+    Ok(batch(CONTROL_BATCH_MASK, [(Some(key), Some(value))]))
+}
+
+/// A batch of `records` given as key and value; offsets and timestamps are
+/// filled in by the caller.
+fn batch(
+    attributes: i16,
+    records: impl IntoIterator<Item = (Option<Vec<u8>>, Option<Vec<u8>>)>,
+) -> RecordBatch {
+    let records = records
+        .into_iter()
+        .enumerate()
+        .map(|(offset_delta, (key, value))| Record {
+            attributes: 0,
+            timestamp_delta: 0,
+            offset_delta: offset_delta as i32,
+            key,
+            value,
+            headers: Vec::new(),
+        })
+        .collect();
+    RecordBatch {
+        base_offset: 0,
+        partition_leader_epoch: 0,
+        crc: 0,
+        attributes,
+        base_timestamp: 0,
+        max_timestamp: 0,
+        producer_id: -1,
+        producer_epoch: -1,
+        base_sequence: -1,
+        records,
+    }
+}
+
+fn to_bytes<T>(value: &T) -> io::Result<Vec<u8>>
+where
+    T: for<'a> BinWrite<Args<'a> = ()>,
+{
+    let mut cursor = Cursor::new(Vec::new());
+    value
+        .write_options(&mut cursor, Endian::Big, ())
+        .map_err(io::Error::other)?;
+    Ok(cursor.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::record_value::{
+        ClusterMetadataRecord, ClusterMetadataValue, FeatureLevelRecord, ProducerIdsRecord,
+        TopicRecord,
+    };
+
+    fn image() -> MetadataImage {
+        let feature = ClusterMetadataValue::FeatureLevel(FeatureLevelRecord {
+            feature_name: "metadata.version".to_string(),
+            level: 20,
+            tagged_fields: Vec::new(),
+        });
+        let topic = ClusterMetadataValue::Topic(TopicRecord {
+            topic_name: "foo".to_string(),
+            uuid: [10; 16],
+            tagged_fields: Vec::new(),
+        });
+        let producer_ids = ClusterMetadataValue::ProducerIds(ProducerIdsRecord {
+            broker_id: 1,
+            broker_epoch: -1,
+            next_producer_id: 2000,
+            tagged_fields: Vec::new(),
+        });
+        let partition = ClusterMetadataRecord::mock_partition_record().payload;
+        let records = [(0, feature), (0, producer_ids), (0, topic), (2, partition)]
+            .into_iter()
+            .map(|(record_version, payload)| {
+                let record = ClusterMetadataRecord {
+                    frame_version: 1,
+                    record_version,
+                    payload,
+                };
+                (None, Some(record.to_bytes().unwrap()))
+            });
+        let mut batch = batch(0, records);
+        batch.base_offset = 10;
+        batch.partition_leader_epoch = 3;
+        batch.max_timestamp = 1000;
+        MetadataImage::replay([&batch])
+    }
+
+    #[test]
+    fn test_file_names() {
+        let snapshot_id = SnapshotId {
+            end_offset: 13,
+            epoch: 3,
+        };
+        let file_name = snapshot_id.file_name();
+        assert_eq!(file_name, "00000000000000000013-0000000003.checkpoint");
+        assert_eq!(SnapshotId::from_file_name(&file_name), Some(snapshot_id));
+        assert_eq!(SnapshotId::from_file_name("00000000000000000000.log"), None);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = std::env::temp_dir().join(format!("snapshot-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let image = image();
+
+        let snapshot_id = write_snapshot(&dir, &image).unwrap();
+        assert_eq!(
+            snapshot_id,
+            SnapshotId {
+                end_offset: 14,
+                epoch: 3
+            }
+        );
+        assert_eq!(latest_snapshot(&dir).unwrap(), Some(snapshot_id));
+
+        let loaded = read_snapshot(&dir, snapshot_id).unwrap();
+        assert_eq!(loaded.last_offset(), 13);
+        assert_eq!(loaded.last_timestamp(), 1000);
+        assert_eq!(loaded.feature_level("metadata.version"), Some(20));
+        assert_eq!(loaded.next_producer_id(), 2000);
+        assert_eq!(loaded.topic_by_name("foo"), image.topic_by_name("foo"));
+        assert_eq!(loaded.snapshot_records(), image.snapshot_records());
+
+        // a snapshot without its footer was not completely written
+        let path = dir.join(snapshot_id.file_name());
+        let mut batches =
+            RecordBatch::read_batches_from(&mut Cursor::new(fs::read(&path).unwrap())).unwrap();
+        batches.pop();
+        let mut bytes = Cursor::new(Vec::new());
+        for batch in batches {
+            batch.write_options(&mut bytes, Endian::Big, ()).unwrap();
+        }
+        fs::write(&path, bytes.into_inner()).unwrap();
+        assert!(read_snapshot(&dir, snapshot_id).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,492 @@
+//! Async network layer: accepts connections and serves pipelined requests.
+//!
+//! Every connection has a reader task that frames requests, a handler task
+//! that handles them one at a time and a writer task that sends the
+//! responses, in the order the requests arrived even when a client pipelines
+//! several of them.
+//!
+//! A fetch that finds less than `min_bytes` is parked in its own task until
+//! an append to one of its partitions or `max_wait_ms`, while the requests
+//! behind it keep being handled; its response still goes out in order.
+
+use std::{future::Future, io, io::Cursor, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch, Notify, Semaphore},
+    task::{JoinHandle, JoinSet},
+    time::{self, Instant},
+};
+
+use crate::{
+    config::BrokerConfig,
+    request::KafkaRequest,
+    response::KafkaResponse,
+    traits::{KafkaDeseriarize, KafkaSeriarize},
+};
+
+/// Requests read ahead of the one being handled, per connection.
+const MAX_QUEUED_REQUESTS: usize = 16;
+
+/// Outcome of handling one request.
+enum Handled {
+    /// The encoded response, `None` if the request asked for none.
+    Response(Option<Vec<u8>>),
+    /// A fetch to read again on `appended`, for up to `max_wait` after it
+    /// arrived.
+    Delayed {
+        response: Box<KafkaResponse>,
+        appended: Arc<Notify>,
+        max_wait: Duration,
+    },
+}
+
+/// A response in the connection's send queue.
+enum PendingResponse {
+    Ready(Option<Vec<u8>>),
+    Delayed(JoinHandle<io::Result<Option<Vec<u8>>>>),
+}
+
+/// Serve connections from `listener` until `shutdown` resolves, then stop
+/// accepting and wait for every connection to answer what it already read.
+pub async fn serve(
+    listener: TcpListener,
+    config: Arc<BrokerConfig>,
+    shutdown: impl Future<Output = ()>,
+) -> io::Result<()> {
+    let permits = Arc::new(Semaphore::new(config.max_connections));
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut connections = JoinSet::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let permit = tokio::select! {
+            _ = &mut shutdown => break,
+            permit = permits.clone().acquire_owned() => permit.expect("semaphore is never closed"),
+        };
+        let (stream, peer) = tokio::select! {
+            _ = &mut shutdown => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    println!("error: {}", e);
+                    continue;
+                }
+            },
+            // reap finished connections so the set does not grow unbounded
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+        };
+        println!("accepted new connection from {}", peer);
+        let config = config.clone();
+        let shutdown_rx = shutdown_rx.clone();
+        connections.spawn(async move {
+            if let Err(e) = handle_connection(stream, &config, shutdown_rx).await {
+                println!("connection from {} failed: {}", peer, e);
+            }
+            drop(permit);
+        });
+    }
+
+    println!("shutting down, draining {} connections", connections.len());
+    drop(listener);
+    let _ = shutdown_tx.send(true);
+    while connections.join_next().await.is_some() {}
+    Ok(())
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    config: &Arc<BrokerConfig>,
+    mut shutdown: watch::Receiver<bool>,
+) -> io::Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let (frames_tx, mut frames_rx) = mpsc::channel::<Vec<u8>>(MAX_QUEUED_REQUESTS);
+    let (responses_tx, mut responses_rx) = mpsc::channel(MAX_QUEUED_REQUESTS);
+
+    let handler_config = config.clone();
+    let handler_shutdown = shutdown.clone();
+    let handler_task = tokio::spawn(async move {
+        while let Some(frame) = frames_rx.recv().await {
+            let arrived = Instant::now();
+            let config = handler_config.clone();
+            let handled = tokio::task::spawn_blocking(move || handle_frame(frame, &config))
+                .await
+                .map_err(io::Error::other)??;
+            let pending = match handled {
+                Handled::Response(response) => PendingResponse::Ready(response),
+                Handled::Delayed {
+                    response,
+                    appended,
+                    max_wait,
+                } => PendingResponse::Delayed(tokio::spawn(complete_delayed_fetch(
+                    response,
+                    appended,
+                    arrived + max_wait,
+                    handler_shutdown.clone(),
+                ))),
+            };
+            // the writer only stops early when the peer went away
+            if responses_tx.send(pending).await.is_err() {
+                break;
+            }
+        }
+        Ok::<_, io::Error>(())
+    });
+
+    let writer_task = tokio::spawn(async move {
+        while let Some(pending) = responses_rx.recv().await {
+            let response = match pending {
+                PendingResponse::Ready(response) => response,
+                PendingResponse::Delayed(fetch) => fetch.await.map_err(io::Error::other)??,
+            };
+            if let Some(response) = response {
+                writer.write_all(&response).await?;
+            }
+        }
+        writer.shutdown().await
+    });
+
+    let read_result = loop {
+        let frame = tokio::select! {
+            _ = shutdown.changed() => break Ok(()),
+            frame = time::timeout(
+                config.connections_max_idle,
+                read_frame(&mut reader, config.max_request_size),
+            ) => frame,
+        };
+        let frame = match frame {
+            Err(_) => {
+                println!("closing idle connection");
+                break Ok(());
+            }
+            Ok(Ok(Some(frame))) => frame,
+            Ok(Ok(None)) => break Ok(()),
+            Ok(Err(e)) => break Err(e),
+        };
+        if frames_tx.send(frame).await.is_err() {
+            break Ok(());
+        }
+    };
+    // let the handler and writer drain what was already read, then close
+    drop(frames_tx);
+    let handle_result = handler_task.await.map_err(io::Error::other)?;
+    let write_result = writer_task.await.map_err(io::Error::other)?;
+    println!("close the connection");
+    read_result.and(handle_result).and(write_result)
+}
+
+/// Read a parked fetch again on every append to its partitions until it has
+/// enough data, then answer with whatever it finds at `deadline` or when the
+/// broker shuts down.
+async fn complete_delayed_fetch(
+    mut response: Box<KafkaResponse>,
+    mut appended: Arc<Notify>,
+    deadline: Instant,
+    mut shutdown: watch::Receiver<bool>,
+) -> io::Result<Option<Vec<u8>>> {
+    loop {
+        let expired = tokio::select! {
+            _ = appended.notified() => false,
+            _ = time::sleep_until(deadline) => true,
+            _ = shutdown.changed() => true,
+        };
+        let handled = tokio::task::spawn_blocking(move || {
+            // the session was applied when the fetch arrived: only read again
+            response.refresh();
+            respond(true, response, expired)
+        })
+        .await
+        .map_err(io::Error::other)??;
+        match handled {
+            Handled::Response(response) => return Ok(response),
+            Handled::Delayed {
+                response: next_response,
+                appended: next_appended,
+                ..
+            } => {
+                response = next_response;
+                appended = next_appended;
+            }
+        }
+    }
+}
+
+/// Read one size-prefixed request, or `None` on a clean end of stream.
+async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_request_size: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    let message_size = match reader.read_i32().await {
+        Ok(message_size) => message_size,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if message_size < 0 || message_size as usize > max_request_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "request size {} is outside 0..={}",
+                message_size, max_request_size
+            ),
+        ));
+    }
+    let mut frame = vec![0u8; message_size as usize];
+    reader.read_exact(&mut frame).await?;
+    Ok(Some(frame))
+}
+
+/// Parse and answer one request.
+fn handle_frame(frame: Vec<u8>, config: &BrokerConfig) -> io::Result<Handled> {
+    let request = KafkaRequest::try_parse_from_reader(&mut Cursor::new(frame), ());
+    let response = KafkaResponse::from_request(&request, config);
+    match request {
+        Ok(request) => respond(request.expects_response(), Box::new(response), false),
+        Err(_) => encode(response).map(|bytes| Handled::Response(Some(bytes))),
+    }
+}
+
+/// Encode `response` unless the request asked for none, or park it if it is
+/// a fetch that may still wait for data.
+fn respond(
+    expects_response: bool,
+    mut response: Box<KafkaResponse>,
+    expired: bool,
+) -> io::Result<Handled> {
+    if !expired {
+        if let Some((appended, max_wait)) = response.delay() {
+            return Ok(Handled::Delayed {
+                response,
+                appended,
+                max_wait,
+            });
+        }
+    }
+    if !expects_response {
+        return Ok(Handled::Response(None));
+    }
+    response.complete();
+    encode(*response).map(|bytes| Handled::Response(Some(bytes)))
+}
+
+fn encode(response: KafkaResponse) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    response.serialize(&mut bytes, ())?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tokio::sync::oneshot;
+
+    use super::*;
+    use crate::{
+        globals::{LOG_MANAGER, METADATA_IMAGE},
+        records::record_value::{ClusterMetadataValue, PartitionRecord, TopicRecord},
+        storage::{LogConfig, LogManager},
+    };
+
+    /// Serve `config` on a loopback port and connect to it. The server stops
+    /// when the returned sender fires or is dropped.
+    async fn start(
+        config: BrokerConfig,
+    ) -> (TcpStream, oneshot::Sender<()>, JoinHandle<io::Result<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let server = tokio::spawn(serve(listener, Arc::new(config), async move {
+            let _ = shutdown_rx.await;
+        }));
+        let stream = TcpStream::connect(addr).await.unwrap();
+        (stream, shutdown_tx, server)
+    }
+
+    fn frame(request: &[u8]) -> Vec<u8> {
+        [&(request.len() as i32).to_be_bytes()[..], request].concat()
+    }
+
+    /// A request header v1, with a null client id.
+    fn header(api_key: i16, api_version: i16, correlation_id: i32) -> Vec<u8> {
+        [
+            &api_key.to_be_bytes()[..],
+            &api_version.to_be_bytes(),
+            &correlation_id.to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn api_versions(correlation_id: i32) -> Vec<u8> {
+        frame(&header(18, 0, correlation_id))
+    }
+
+    /// A v4 fetch of partition 0 of `topic` from offset 0 that waits up to
+    /// `max_wait_ms` for a byte.
+    fn fetch(correlation_id: i32, topic: &str, max_wait_ms: i32) -> Vec<u8> {
+        let body = [
+            &(-1i32).to_be_bytes()[..],
+            &max_wait_ms.to_be_bytes(),
+            &1i32.to_be_bytes(),
+            &(1i32 << 20).to_be_bytes(),
+            &[0],
+            &1i32.to_be_bytes(),
+            &(topic.len() as i16).to_be_bytes(),
+            topic.as_bytes(),
+            &1i32.to_be_bytes(),
+            &0i32.to_be_bytes(),
+            &0i64.to_be_bytes(),
+            &(1i32 << 20).to_be_bytes(),
+        ]
+        .concat();
+        frame(&[header(1, 4, correlation_id), body].concat())
+    }
+
+    /// The correlation id of the next response, `None` once the broker
+    /// closed the connection.
+    async fn next_correlation_id(stream: &mut TcpStream) -> Option<i32> {
+        let size = stream.read_i32().await.ok()?;
+        let mut response = vec![0; size as usize];
+        stream.read_exact(&mut response).await.unwrap();
+        Some(i32::from_be_bytes(response[..4].try_into().unwrap()))
+    }
+
+    /// Register partition 0 of `topic`, led by the default broker, with its
+    /// log in a directory shared by the tests of this module.
+    fn create_topic(topic: &str, topic_id: [u8; 16]) {
+        LOG_MANAGER.get_or_init(|| {
+            let dir = std::env::temp_dir().join(format!("network-test-{}", std::process::id()));
+            LogManager::new(dir, LogConfig::default())
+        });
+        let mut image = METADATA_IMAGE
+            .get_or_init(Default::default)
+            .write()
+            .unwrap();
+        image.apply(ClusterMetadataValue::Topic(TopicRecord {
+            topic_name: topic.to_string(),
+            uuid: topic_id,
+            tagged_fields: Vec::new(),
+        }));
+        let node_id = BrokerConfig::default().node_id;
+        image.apply(ClusterMetadataValue::Partition(PartitionRecord {
+            partition_id: 0,
+            topic_id,
+            replicas: vec![node_id],
+            isr: vec![node_id],
+            rra: Vec::new(),
+            ara: Vec::new(),
+            leader_id: node_id,
+            leader_epoch: 0,
+            partition_epoch: 0,
+            directories: vec![[0; 16]],
+            tagged_fields: Vec::new(),
+        }));
+    }
+
+    fn remove_topic(topic: &str) {
+        let log_manager = LOG_MANAGER.get().unwrap();
+        log_manager.remove(topic, 0).unwrap();
+        // the last test to finish takes the shared directory with it
+        let _ = fs::remove_dir(log_manager.log_dir());
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_rejects_oversized_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+
+        client.write_all(&frame(&[1, 2, 3, 4])).await.unwrap();
+        client.write_i32(17).await.unwrap();
+        let frame = read_frame(&mut server, 16).await.unwrap();
+        assert_eq!(frame, Some(vec![1, 2, 3, 4]));
+        let error = read_frame(&mut server, 16).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        client.write_i32(-1).await.unwrap();
+        let error = read_frame(&mut server, 16).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        drop(client);
+        assert_eq!(read_frame(&mut server, 16).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_closes_the_connection() {
+        let config = BrokerConfig {
+            max_request_size: 64,
+            ..BrokerConfig::default()
+        };
+        let (mut stream, _shutdown, _) = start(config).await;
+        stream.write_all(&api_versions(1)).await.unwrap();
+        stream.write_i32(65).await.unwrap();
+        assert_eq!(next_correlation_id(&mut stream).await, Some(1));
+        assert_eq!(next_correlation_id(&mut stream).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_responses_keep_request_order() {
+        create_topic("network-order", [21; 16]);
+        let (mut stream, _shutdown, _) = start(BrokerConfig::default()).await;
+
+        // the fetch is parked while the requests behind it are answered
+        let requests = [
+            api_versions(1),
+            fetch(2, "network-order", 200),
+            api_versions(3),
+            api_versions(4),
+        ]
+        .concat();
+        let sent = Instant::now();
+        stream.write_all(&requests).await.unwrap();
+        for correlation_id in 1..=4 {
+            assert_eq!(next_correlation_id(&mut stream).await, Some(correlation_id));
+        }
+        assert!(sent.elapsed() >= Duration::from_millis(200));
+        remove_topic("network-order");
+    }
+
+    #[tokio::test]
+    async fn test_idle_connections_are_closed() {
+        let config = BrokerConfig {
+            connections_max_idle: Duration::from_millis(50),
+            ..BrokerConfig::default()
+        };
+        let (mut stream, _shutdown, _) = start(config).await;
+        let closed = time::timeout(Duration::from_secs(5), next_correlation_id(&mut stream));
+        assert_eq!(closed.await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_answers_requests_already_read() {
+        create_topic("network-drain", [22; 16]);
+        let (mut stream, shutdown, server) = start(BrokerConfig::default()).await;
+
+        stream
+            .write_all(&[fetch(1, "network-drain", 60_000), api_versions(2)].concat())
+            .await
+            .unwrap();
+        // give the connection time to read both requests
+        time::sleep(Duration::from_millis(100)).await;
+        shutdown.send(()).unwrap();
+
+        // the parked fetch is answered early instead of being dropped
+        let drained = async {
+            let ids = [
+                next_correlation_id(&mut stream).await,
+                next_correlation_id(&mut stream).await,
+                next_correlation_id(&mut stream).await,
+            ];
+            server.await.unwrap().unwrap();
+            ids
+        };
+        let ids = time::timeout(Duration::from_secs(5), drained)
+            .await
+            .unwrap();
+        assert_eq!(ids, [Some(1), Some(2), None]);
+        remove_topic("network-drain");
+    }
+}
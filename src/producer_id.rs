@@ -0,0 +1,96 @@
+//! Producer ids for idempotent producers, handed out of blocks that are
+//! reserved in the metadata log so that no id is ever handed out twice, even
+//! across restarts.
+
+use std::{io, ops::Range, sync::Mutex};
+
+use crate::metadata;
+
+pub struct ProducerIdManager {
+    node_id: i32,
+    /// Ids left in the current block.
+    block: Mutex<Range<i64>>,
+}
+
+impl ProducerIdManager {
+    pub fn new(node_id: i32) -> Self {
+        Self {
+            node_id,
+            block: Mutex::new(0..0),
+        }
+    }
+
+    /// The next unused producer id, reserving a new block once the current
+    /// one runs out.
+    pub fn generate(&self) -> io::Result<i64> {
+        self.generate_with(metadata::allocate_producer_ids)
+    }
+
+    /// [`generate`](Self::generate), reserving blocks with `allocate`.
+    fn generate_with(
+        &self,
+        allocate: impl FnOnce(i32) -> io::Result<Range<i64>>,
+    ) -> io::Result<i64> {
+        let mut block = self.block.lock().unwrap();
+        if block.is_empty() {
+            *block = allocate(self.node_id)?;
+        }
+        Ok(block.next().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::RwLock};
+
+    use super::*;
+    use crate::{
+        metadata::MetadataImage,
+        storage::{LogConfig, LogManager, CLUSTER_METADATA_TOPIC},
+    };
+
+    #[test]
+    fn test_blocks_are_reserved_in_the_metadata_log() {
+        let dir = std::env::temp_dir().join(format!("producer-id-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log_manager = LogManager::new(&dir, LogConfig::default());
+        let image = RwLock::new(MetadataImage::new());
+        let manager = ProducerIdManager::new(1);
+        let generate = |manager: &ProducerIdManager| {
+            manager
+                .generate_with(|node_id| {
+                    metadata::allocate_producer_ids_in(&log_manager, &image, node_id)
+                })
+                .unwrap()
+        };
+        let metadata_end = || {
+            let log = log_manager
+                .get_or_create(CLUSTER_METADATA_TOPIC, 0)
+                .unwrap();
+            let high_watermark = log.lock().unwrap().high_watermark();
+            high_watermark
+        };
+
+        // the first block is handed out id by id, then the next one follows
+        // on without a gap
+        let ids: Vec<i64> = (0..1001).map(|_| generate(&manager)).collect();
+        assert_eq!(ids, (0..1001).collect::<Vec<_>>());
+        // one ProducerIdsRecord per block
+        assert_eq!(metadata_end(), 2);
+        assert_eq!(image.read().unwrap().next_producer_id(), 2000);
+
+        // after a restart, allocation resumes past the last reserved block
+        drop(log_manager);
+        let log_manager = LogManager::new(&dir, LogConfig::default());
+        let image = RwLock::new(metadata::load_image(&log_manager).unwrap());
+        assert_eq!(image.read().unwrap().next_producer_id(), 2000);
+        let manager = ProducerIdManager::new(1);
+        let producer_id = manager
+            .generate_with(|node_id| {
+                metadata::allocate_producer_ids_in(&log_manager, &image, node_id)
+            })
+            .unwrap();
+        assert_eq!(producer_id, 2000);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,12 +6,42 @@ use binrw::{
     BinRead, BinResult, BinWrite, Endian, Error,
 };
 
+use compression::{Compression, COMPRESSION_CODEC_MASK};
+use control_record::CONTROL_BATCH_MASK;
 use integer_encoding::VarIntReader;
 use integer_encoding::VarIntWriter;
 use record_header::RecordHeader;
 use record_value::ClusterMetadataRecord;
-use utils::{parse_vec_u8_with_signed_varint_length, write_vec_u8_with_signed_varint_length};
+use thiserror::Error;
+use utils::{
+    parse_nullable_vec_u8_with_signed_varint_length,
+    write_nullable_vec_u8_with_signed_varint_length,
+};
+
+/// Attribute bit set on batches whose timestamps are the broker's append time.
+const TIMESTAMP_TYPE_MASK: i16 = 0x08;
+
+/// Bytes before `attributes`, the first field covered by the CRC.
+const BATCH_CRC_START: usize = 21;
+
+#[derive(Debug, Error)]
+pub enum BatchReadError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("record batch at byte {position} is truncated")]
+    Truncated { position: usize },
+    #[error("record batch at byte {position} has crc {stored:08x}, expected {computed:08x}")]
+    CrcMismatch {
+        position: usize,
+        stored: u32,
+        computed: u32,
+    },
+    #[error("failed to parse record batch at byte {position}: {source}")]
+    Parse { position: usize, source: Error },
+}
 
+pub mod compression;
+pub mod control_record;
 pub mod record_header;
 pub mod record_value;
 mod utils;
@@ -43,7 +73,7 @@ pub struct RecordBatch {
     __records_length: i32,
 
     // 读取时，会用 __records_length 来决定要解析多少条 Record
-    #[br(count = __records_length)]
+    #[br(parse_with = parse_records, args(attributes, __batch_length, __records_length))]
     pub records: Vec<Record>,
 }
 
@@ -77,10 +107,14 @@ impl BinWrite for RecordBatch {
             .write_options(&mut batch_after_crc_cursor, endian, ())?;
         // 计算 records 长度
         (self.records.len() as i32).write_options(&mut batch_after_crc_cursor, endian, ())?;
-        // 写 records
+        // 写 records，按 attributes 中的压缩算法压缩
+        let mut records = Cursor::new(Vec::new());
         for record in &self.records {
-            record.write_options(&mut batch_after_crc_cursor, endian, ())?;
+            record.write_options(&mut records, endian, ())?;
         }
+        let compression = self.compression().ok_or_else(|| unknown_codec(self.attributes))?;
+        let records = compression.compress(&records.into_inner())?;
+        batch_after_crc_cursor.write_all(&records)?;
         // 计算 crc, The CRC32-C (Castagnoli) polynomial is used for the computation.
         // 使用crc库计算crc
         let crc = crc32c::crc32c(&batch_after_crc);
@@ -101,84 +135,67 @@ impl BinWrite for RecordBatch {
 }
 
 impl RecordBatch {
-    pub fn read_batches_from<R>(reader: &mut R) -> Result<Vec<Self>, Box<dyn std::error::Error>>
-    where
-        R: Read + Seek,
-    {
-        let mut batches = Vec::new();
-        let mut batch_count = 0;
-
-        // Try to read multiple RecordBatches until EOF
-        loop {
-            match Self::read(reader) {
-                Ok(batch) => {
-                    batch_count += 1;
-                    println!(
-                        "Successfully read RecordBatch #{} with CRC: {:08X}",
-                        batch_count, batch.crc
-                    );
-                    batch.print_summary();
-                    batches.push(batch);
-                }
-                Err(e) => {
-                    // Check if we've reached the end of the stream
-                    let reached_end = match reader.stream_position() {
-                        Ok(current_pos) => {
-                            match reader.seek(std::io::SeekFrom::End(0)) {
-                                Ok(total_size) => {
-                                    // Seek back to where we were
-                                    let _ = reader.seek(std::io::SeekFrom::Start(current_pos));
-
-                                    // If we're at or very near the end (within 4 bytes), consider it done
-                                    let remaining = total_size as i64 - current_pos as i64;
-                                    remaining <= 4
-                                }
-                                Err(_) => {
-                                    println!("Cannot determine total stream size");
-                                    false
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            println!("Cannot determine current stream position");
-                            false
-                        }
-                    };
-
-                    // If we've reached the end or if it's EOF error, exit normally
-                    if reached_end
-                        || matches!(e, Error::Io(ref io_err) if io_err.kind() == std::io::ErrorKind::UnexpectedEof)
-                    {
-                        println!("Reading complete, total {} batches", batch_count);
-                        break;
-                    }
-
-                    // Otherwise it's a genuine error
-                    println!("Parsing error: {:?}", e);
-
-                    // We might try to skip ahead a bit to recover, but for simplicity let's just break
-                    println!("Stopping due to parse error");
-                    break;
-                }
-            }
+    /// Timestamp of `record`: the broker's append time for `LogAppendTime`
+    /// batches, the producer's timestamp otherwise.
+    pub fn record_timestamp(&self, record: &Record) -> i64 {
+        if self.attributes & TIMESTAMP_TYPE_MASK != 0 {
+            self.max_timestamp
+        } else {
+            self.base_timestamp + record.timestamp_delta
         }
-
-        Ok(batches)
     }
 
-    /// Read multiple RecordBatches from a file at the specified path
-    pub fn read_batches_from_file(path: &str) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
-        use std::fs::File;
-        use std::io::BufReader;
+    /// Codec of the records section, `None` if the attributes name an
+    /// unknown one.
+    pub fn compression(&self) -> Option<Compression> {
+        Compression::from_attributes(self.attributes)
+    }
 
-        println!("Reading file: {}", path);
+    /// Whether the batch holds control records rather than data.
+    pub fn is_control(&self) -> bool {
+        self.attributes & CONTROL_BATCH_MASK != 0
+    }
 
-        // Open file
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
+    /// Read every batch up to the end of `reader`, checking each batch's CRC.
+    ///
+    /// Unlike the log recovery pass, nothing is skipped: a truncated, corrupt
+    /// or unparsable batch is an error.
+    pub fn read_batches_from<R>(reader: &mut R) -> Result<Vec<Self>, BatchReadError>
+    where
+        R: Read,
+    {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
 
-        // Call the generic method to read from the reader
-        Self::read_batches_from(&mut reader)
+        let mut batches = Vec::new();
+        let mut position = 0;
+        while position < buf.len() {
+            let rest = &buf[position..];
+            let truncated = BatchReadError::Truncated { position };
+            if rest.len() < BATCH_CRC_START {
+                return Err(truncated);
+            }
+            let batch_length = i32::from_be_bytes(rest[8..12].try_into().unwrap());
+            let size = 12 + batch_length.max(0) as usize;
+            if size < BATCH_CRC_START || rest.len() < size {
+                return Err(truncated);
+            }
+            let batch = &rest[..size];
+            let stored = u32::from_be_bytes(batch[17..21].try_into().unwrap());
+            let computed = crc32c::crc32c(&batch[BATCH_CRC_START..]);
+            if stored != computed {
+                return Err(BatchReadError::CrcMismatch {
+                    position,
+                    stored,
+                    computed,
+                });
+            }
+            let batch = Self::read(&mut Cursor::new(batch))
+                .map_err(|source| BatchReadError::Parse { position, source })?;
+            batches.push(batch);
+            position += size;
+        }
+        Ok(batches)
     }
 
     /// Print summary information for RecordBatch
@@ -193,49 +210,76 @@ impl RecordBatch {
             println!("  Record #{}", i);
             println!("    offset_delta: {}", record.offset_delta);
             println!("    timestamp_delta: {}", record.timestamp_delta);
-            println!("    key length: {}", record.key.len());
-
-            // Print content based on record type
-            match &record.value.payload {
-                record_value::ClusterMetadataValue::BrokerRegistration(_) => {
-                    println!("    type: BrokerRegistration")
-                }
-                record_value::ClusterMetadataValue::Topic(_) => println!("    type: Topic"),
-                record_value::ClusterMetadataValue::FeatureLevel(f) => {
-                    println!("    type: FeatureLevel");
-                    println!("    feature_level: {:?}", f);
-                    // Print value as BinWrite
-                    let mut f_cursor = Cursor::new(Vec::new());
-                    record.value.write_options(&mut f_cursor, Endian::Big, ()).unwrap();
-                    println!("    value as BinWrite: {:?}", f_cursor.get_ref());
-                }
-                record_value::ClusterMetadataValue::Partition(p) => {
-                    println!("    type: Partition");
-                    println!("    partition_id: {}", p.partition_id);
-                    println!("    topic_id: {:?}", p.topic_id);
-                    // Print value as BinWrite
-                    let mut f_cursor = Cursor::new(Vec::new());
-                    record.value.write_options(&mut f_cursor, Endian::Big, ()).unwrap();
-                    println!("    value as BinWrite: {:?}", f_cursor.get_ref());
-                }
-            }
+            println!("    key length: {:?}", record.key.as_ref().map(Vec::len));
+            println!(
+                "    value length: {:?}",
+                record.value.as_ref().map(Vec::len)
+            );
 
             println!("    headers count: {}", record.headers.len());
         }
     }
 }
 
+/// Bytes of the batch header counted in `batch_length`, before the records.
+const BATCH_HEADER_AFTER_LENGTH: i32 = 49;
+
+fn unknown_codec(attributes: i16) -> Error {
+    Error::AssertFail {
+        pos: 0,
+        message: format!(
+            "unknown compression codec {}",
+            attributes & COMPRESSION_CODEC_MASK
+        ),
+    }
+}
+
+/// Parse `count` records, first decompressing the records section when the
+/// batch attributes name a codec.
+fn parse_records<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+    (attributes, batch_length, count): (i16, i32, i32),
+) -> BinResult<Vec<Record>> {
+    let count = count.max(0) as usize;
+    let compression =
+        Compression::from_attributes(attributes).ok_or_else(|| unknown_codec(attributes))?;
+    if compression == Compression::None {
+        return (0..count)
+            .map(|_| Record::read_options(reader, endian, ()))
+            .collect();
+    }
+    let mut compressed = vec![0u8; (batch_length - BATCH_HEADER_AFTER_LENGTH).max(0) as usize];
+    reader.read_exact(&mut compressed)?;
+    let mut records = Cursor::new(compression.decompress(&compressed)?);
+    (0..count)
+        .map(|_| Record::read_options(&mut records, endian, ()))
+        .collect()
+}
+
+/// A single record, `key` and `value` are opaque and may be null.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Record {
     pub attributes: i8,
     pub timestamp_delta: i64,
     pub offset_delta: i32,
 
-    pub key: Vec<u8>,
-    pub value: ClusterMetadataRecord,
+    pub key: Option<Vec<u8>>,
+    pub value: Option<Vec<u8>>,
     pub headers: Vec<RecordHeader>,
 }
 
+impl Record {
+    /// Decode the value as a `__cluster_metadata` record.
+    pub fn cluster_metadata_record(&self) -> BinResult<ClusterMetadataRecord> {
+        let value = self.value.as_deref().ok_or_else(|| Error::AssertFail {
+            pos: 0,
+            message: "cluster metadata record has a null value".to_string(),
+        })?;
+        ClusterMetadataRecord::read_options(&mut Cursor::new(value), Endian::Big, ())
+    }
+}
+
 /// 自定义解析：先读 record_length，再基于它的大小来限制读取其余字段
 impl BinRead for Record {
     type Args<'a> = ();
@@ -254,13 +298,12 @@ impl BinRead for Record {
         let timestamp_delta: i64 = reader.read_varint()?;
         let offset_delta = reader.read_varint()?;
 
-        let key = parse_vec_u8_with_signed_varint_length(reader, endian, ())?;
-        let value = parse_vec_u8_with_signed_varint_length(reader, endian, ())?;
-        let mut value_cursor = Cursor::new(value);
-        let value = ClusterMetadataRecord::read_options(&mut value_cursor, endian, ())?;
+        let key = parse_nullable_vec_u8_with_signed_varint_length(reader, endian, ())?;
+        let value = parse_nullable_vec_u8_with_signed_varint_length(reader, endian, ())?;
 
         // 读取 headers
-        let header_count: usize = reader.read_varint()?;
+        let header_count: i64 = reader.read_varint()?;
+        let header_count = header_count.max(0) as usize;
         let mut headers = Vec::with_capacity(header_count);
         for _ in 0..header_count {
             headers.push(RecordHeader::read_options(reader, endian, ())?);
@@ -312,16 +355,23 @@ impl BinWrite for Record {
         record_cursor.write_varint(self.offset_delta)?;
 
         // 写 key
-        write_vec_u8_with_signed_varint_length(&self.key, &mut record_cursor, endian, ())?;
+        write_nullable_vec_u8_with_signed_varint_length(
+            self.key.as_deref(),
+            &mut record_cursor,
+            endian,
+            (),
+        )?;
 
         // 写 value
-        let mut value_vec: Vec<u8> = Vec::new();
-        let mut value_cursor = Cursor::new(&mut value_vec);
-        self.value.write_options(&mut value_cursor, endian, ())?;
-        write_vec_u8_with_signed_varint_length(&value_vec, &mut record_cursor, endian, ())?;
+        write_nullable_vec_u8_with_signed_varint_length(
+            self.value.as_deref(),
+            &mut record_cursor,
+            endian,
+            (),
+        )?;
 
         // 写 headers
-        record_cursor.write_varint(self.headers.len())?;
+        record_cursor.write_varint(self.headers.len() as i64)?;
         for h in &self.headers {
             h.write_options(&mut record_cursor, endian, ())?;
         }
@@ -348,7 +398,8 @@ mod tests {
         let original_batch = RecordBatch {
             base_offset: 1000,
             partition_leader_epoch: 0,
-            crc: 2216891472,
+            // codec bits 0b010: the records section is snappy compressed
+            crc: 3536014765,
             attributes: 0b0101_0010,
             base_timestamp: 1690000000,
             max_timestamp: 1690000050,
@@ -360,27 +411,35 @@ mod tests {
                     attributes: 0,
                     timestamp_delta: 3,
                     offset_delta: 0,
-                    key: b"key".to_vec(),
-                    value: ClusterMetadataRecord::mock_broker_registration(),
+                    key: Some(b"key".to_vec()),
+                    value: Some(
+                        ClusterMetadataRecord::mock_broker_registration()
+                            .to_bytes()
+                            .unwrap(),
+                    ),
                     headers: vec![RecordHeader {
                         key: b"k1".to_vec(),
-                        value: b"v1".to_vec(),
+                        value: Some(b"v1".to_vec()),
                     }],
                 },
                 Record {
                     attributes: 0,
                     timestamp_delta: 4,
                     offset_delta: 1,
-                    key: vec![],
-                    value: ClusterMetadataRecord::mock_feature_level_record(),
+                    key: None,
+                    value: Some(
+                        ClusterMetadataRecord::mock_feature_level_record()
+                            .to_bytes()
+                            .unwrap(),
+                    ),
                     headers: vec![
                         RecordHeader {
                             key: b"foo".to_vec(),
-                            value: b"bar".to_vec(),
+                            value: Some(b"bar".to_vec()),
                         },
                         RecordHeader {
                             key: b"baz".to_vec(),
-                            value: b"qux".to_vec(),
+                            value: Some(b"qux".to_vec()),
                         },
                     ],
                 },
@@ -409,33 +468,36 @@ mod tests {
             decoded_batch.records[1].value,
             original_batch.records[1].value
         );
+        assert_eq!(
+            decoded_batch.records[0].cluster_metadata_record().unwrap(),
+            ClusterMetadataRecord::mock_broker_registration()
+        );
 
         // Header 校验
         let first_headers = &decoded_batch.records[0].headers;
         assert_eq!(first_headers.len(), 1);
         assert_eq!(first_headers[0].key, b"k1");
-        assert_eq!(first_headers[0].value, b"v1");
+        assert_eq!(first_headers[0].value.as_deref(), Some(&b"v1"[..]));
 
         let second_headers = &decoded_batch.records[1].headers;
         assert_eq!(second_headers.len(), 2);
         assert_eq!(second_headers[0].key, b"foo");
-        assert_eq!(second_headers[0].value, b"bar");
+        assert_eq!(second_headers[0].value.as_deref(), Some(&b"bar"[..]));
         assert_eq!(second_headers[1].key, b"baz");
-        assert_eq!(second_headers[1].value, b"qux");
+        assert_eq!(second_headers[1].value.as_deref(), Some(&b"qux"[..]));
     }
 
     #[test]
     fn test_record_roundtrip() {
-        // 需要更新这个测试，因为 value 现在是 ClusterMetadataRecord 类型
         let record = Record {
             attributes: 1,
             timestamp_delta: 12345,
             offset_delta: 2,
-            key: b"sample_key".to_vec(),
-            value: ClusterMetadataRecord::mock_partition_record(), // 使用一个适当的mock值
+            key: Some(b"sample_key".to_vec()),
+            value: Some(b"arbitrary user payload".to_vec()),
             headers: vec![RecordHeader {
                 key: b"h1".to_vec(),
-                value: b"header1".to_vec(),
+                value: Some(b"header1".to_vec()),
             }],
         };
 
@@ -454,11 +516,11 @@ mod tests {
         assert_eq!(decoded.attributes, 1);
         assert_eq!(decoded.timestamp_delta, 12345);
         assert_eq!(decoded.offset_delta, 2);
-        assert_eq!(decoded.key, b"sample_key");
+        assert_eq!(decoded.key.as_deref(), Some(&b"sample_key"[..]));
         assert_eq!(decoded.value, record.value); // 比较实际对象
         assert_eq!(decoded.headers.len(), 1);
         assert_eq!(decoded.headers[0].key, b"h1");
-        assert_eq!(decoded.headers[0].value, b"header1");
+        assert_eq!(decoded.headers[0].value.as_deref(), Some(&b"header1"[..]));
     }
 
     #[test]
@@ -476,5 +538,121 @@ mod tests {
         let mut reader = BufReader::new(Cursor::new(real_data));
         let result = RecordBatch::read(&mut reader);
         assert!(result.is_ok(), "{:?}", result);
+        let batch = result.unwrap();
+        assert_eq!(batch.records[0].key, None);
+        assert!(matches!(
+            batch.records[0].cluster_metadata_record().unwrap().payload,
+            record_value::ClusterMetadataValue::FeatureLevel(_)
+        ));
+    }
+
+    #[test]
+    fn test_compressed_batches_roundtrip() {
+        let records: Vec<Record> = (0..50)
+            .map(|i| Record {
+                attributes: 0,
+                timestamp_delta: i,
+                offset_delta: i as i32,
+                key: Some(format!("key-{i}").into_bytes()),
+                value: Some(vec![i as u8; 100]),
+                headers: Vec::new(),
+            })
+            .collect();
+        for codec in [
+            Compression::Gzip,
+            Compression::Snappy,
+            Compression::Lz4,
+            Compression::Zstd,
+        ] {
+            let batch = RecordBatch {
+                base_offset: 0,
+                partition_leader_epoch: 0,
+                crc: 0,
+                attributes: codec.apply_to(0),
+                base_timestamp: 1,
+                max_timestamp: 50,
+                producer_id: -1,
+                producer_epoch: -1,
+                base_sequence: -1,
+                records: records.clone(),
+            };
+            let mut buffer = vec![];
+            batch
+                .write_options(&mut Cursor::new(&mut buffer), Endian::Big, ())
+                .unwrap();
+            assert!(buffer.len() < 50 * 100, "{codec} did not compress");
+            let decoded = RecordBatch::read_batches_from(&mut Cursor::new(&buffer)).unwrap();
+            assert_eq!(decoded[0].compression(), Some(codec));
+            assert_eq!(decoded[0].records, records, "{codec}");
+        }
+    }
+
+    #[test]
+    fn test_read_batches_checks_crc_and_length() {
+        let batch = RecordBatch {
+            base_offset: 0,
+            partition_leader_epoch: 0,
+            crc: 0,
+            attributes: 0,
+            base_timestamp: 1,
+            max_timestamp: 1,
+            producer_id: -1,
+            producer_epoch: -1,
+            base_sequence: -1,
+            records: vec![Record {
+                attributes: 0,
+                timestamp_delta: 0,
+                offset_delta: 0,
+                key: None,
+                value: Some(b"v".to_vec()),
+                headers: Vec::new(),
+            }],
+        };
+        let mut buffer = vec![];
+        batch
+            .write_options(&mut Cursor::new(&mut buffer), Endian::Big, ())
+            .unwrap();
+        buffer.extend(buffer.clone());
+        let batches = RecordBatch::read_batches_from(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].records, batch.records);
+
+        let size = buffer.len() / 2;
+        let mut corrupt = buffer.clone();
+        *corrupt.last_mut().unwrap() ^= 0xff;
+        assert!(matches!(
+            RecordBatch::read_batches_from(&mut Cursor::new(&corrupt)),
+            Err(BatchReadError::CrcMismatch { position, .. }) if position == size
+        ));
+        assert!(matches!(
+            RecordBatch::read_batches_from(&mut Cursor::new(&buffer[..size + 30])),
+            Err(BatchReadError::Truncated { position }) if position == size
+        ));
+    }
+
+    #[test]
+    fn test_null_key_and_value_roundtrip() {
+        let record = Record {
+            attributes: 0,
+            timestamp_delta: 0,
+            offset_delta: 0,
+            key: None,
+            value: None,
+            headers: vec![RecordHeader {
+                key: b"trace".to_vec(),
+                value: None,
+            }],
+        };
+
+        let mut buffer = vec![];
+        record
+            .write_options(&mut Cursor::new(&mut buffer), Endian::Big, ())
+            .unwrap();
+        // length, attributes, timestamp_delta, offset_delta, key -1, value -1, 1 header
+        assert_eq!(&buffer[..7], &[0x1a, 0x00, 0x00, 0x00, 0x01, 0x01, 0x02]);
+
+        let decoded = Record::read_options(&mut Cursor::new(&buffer), Endian::Big, ()).unwrap();
+        assert_eq!(decoded, record);
+        assert!(decoded.cluster_metadata_record().is_err());
     }
 }
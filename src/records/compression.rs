@@ -0,0 +1,180 @@
+//! Codecs of the records section of a record batch, selected by the low three
+//! bits of the batch `attributes`.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    str::FromStr,
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+/// Bits of the batch attributes that hold the compression codec.
+pub const COMPRESSION_CODEC_MASK: i16 = 0x07;
+
+/// Header written by snappy-java's `SnappyOutputStream`, which Java clients
+/// use: magic, version 1 and minimum compatible version 1.
+const XERIAL_HEADER: [u8; 16] = [
+    0x82, b'S', b'N', b'A', b'P', b'P', b'Y', 0, 0, 0, 0, 1, 0, 0, 0, 1,
+];
+const XERIAL_MAGIC_LEN: usize = 8;
+/// Uncompressed size of each xerial block, as in snappy-java.
+const XERIAL_BLOCK_SIZE: usize = 32 * 1024;
+/// Level Kafka uses when `compression.zstd.level` is not set.
+const ZSTD_DEFAULT_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, TryFromPrimitive, IntoPrimitive)]
+#[repr(i16)]
+pub enum Compression {
+    #[default]
+    None = 0,
+    Gzip = 1,
+    Snappy = 2,
+    Lz4 = 3,
+    Zstd = 4,
+}
+
+impl Compression {
+    /// The codec encoded in a batch's attributes; `None` for the reserved
+    /// values 5 to 7.
+    pub fn from_attributes(attributes: i16) -> Option<Self> {
+        Self::try_from(attributes & COMPRESSION_CODEC_MASK).ok()
+    }
+
+    /// `attributes` with the codec bits replaced by this codec.
+    pub fn apply_to(self, attributes: i16) -> i16 {
+        attributes & !COMPRESSION_CODEC_MASK | i16::from(self)
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Self::Snappy => {
+                let mut encoder = snap::raw::Encoder::new();
+                let mut compressed = XERIAL_HEADER.to_vec();
+                for block in data.chunks(XERIAL_BLOCK_SIZE) {
+                    let block = encoder.compress_vec(block).map_err(io::Error::other)?;
+                    compressed.extend_from_slice(&(block.len() as i32).to_be_bytes());
+                    compressed.extend_from_slice(&block);
+                }
+                Ok(compressed)
+            }
+            Self::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(data)?;
+                encoder.finish().map_err(io::Error::other)
+            }
+            Self::Zstd => zstd::encode_all(data, ZSTD_DEFAULT_LEVEL),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            Self::None => decompressed.extend_from_slice(data),
+            Self::Gzip => {
+                flate2::read::MultiGzDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Self::Snappy => decompressed = snappy_decompress(data)?,
+            Self::Lz4 => {
+                lz4_flex::frame::FrameDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Self::Zstd => decompressed = zstd::decode_all(data)?,
+        }
+        Ok(decompressed)
+    }
+}
+
+/// Decode either the xerial framing of Java clients or a raw snappy block,
+/// which librdkafka sends.
+fn snappy_decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = snap::raw::Decoder::new();
+    if !data.starts_with(&XERIAL_HEADER[..XERIAL_MAGIC_LEN]) {
+        return decoder.decompress_vec(data).map_err(io::Error::other);
+    }
+    let mut decompressed = Vec::new();
+    let mut rest = data.get(XERIAL_HEADER.len()..).unwrap_or_default();
+    while !rest.is_empty() {
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated snappy block");
+        let length = rest
+            .get(..4)
+            .map(|length| i32::from_be_bytes(length.try_into().unwrap()))
+            .ok_or_else(truncated)?;
+        let block = rest
+            .get(4..4 + length.max(0) as usize)
+            .ok_or_else(truncated)?;
+        decompressed.extend(decoder.decompress_vec(block).map_err(io::Error::other)?);
+        rest = &rest[4 + block.len()..];
+    }
+    Ok(decompressed)
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" | "uncompressed" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "snappy" => Ok(Self::Snappy),
+            "lz4" => Ok(Self::Lz4),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!("unknown compression type {s:?}")),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::Gzip => "gzip",
+            Self::Snappy => "snappy",
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codecs_roundtrip() {
+        let data: Vec<u8> = (0..100_000u32)
+            .flat_map(|i| (i % 251).to_be_bytes())
+            .collect();
+        for codec in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Snappy,
+            Compression::Lz4,
+            Compression::Zstd,
+        ] {
+            let compressed = codec.compress(&data).unwrap();
+            assert_eq!(codec.decompress(&compressed).unwrap(), data, "{codec}");
+        }
+    }
+
+    #[test]
+    fn test_snappy_accepts_raw_blocks() {
+        let raw = snap::raw::Encoder::new()
+            .compress_vec(b"librdkafka")
+            .unwrap();
+        assert_eq!(Compression::Snappy.decompress(&raw).unwrap(), b"librdkafka");
+    }
+
+    #[test]
+    fn test_codec_attribute_bits() {
+        assert_eq!(Compression::from_attributes(0x13), Some(Compression::Lz4));
+        assert_eq!(Compression::from_attributes(0x05), None);
+        assert_eq!(Compression::Zstd.apply_to(0x19), 0x1c);
+    }
+}
@@ -0,0 +1,71 @@
+//! Control records, written by the broker itself in control batches: their
+//! key names the control type and their value depends on it.
+
+use std::io::Cursor;
+
+use binrw::{binrw, BinRead};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::common_structs::tagged_field::TaggedField;
+
+use super::utils::{parse_tagged_fields, write_tagged_fields};
+
+/// Attribute bit set on batches holding control records.
+pub const CONTROL_BATCH_MASK: i16 = 0x20;
+
+#[derive(Debug, PartialEq, Copy, Clone, TryFromPrimitive, IntoPrimitive)]
+#[repr(i16)]
+pub enum ControlRecordType {
+    Abort = 0,
+    Commit = 1,
+    LeaderChange = 2,
+    SnapshotHeader = 3,
+    SnapshotFooter = 4,
+}
+
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct ControlRecordKey {
+    pub version: i16,
+    pub record_type: i16,
+}
+
+impl ControlRecordKey {
+    pub fn new(record_type: ControlRecordType) -> Self {
+        Self {
+            version: 0,
+            record_type: record_type.into(),
+        }
+    }
+
+    /// Decode a record key, `None` if it is not a known control type.
+    pub fn control_type(key: &[u8]) -> Option<ControlRecordType> {
+        let key = Self::read(&mut Cursor::new(key)).ok()?;
+        ControlRecordType::try_from(key.record_type).ok()
+    }
+}
+
+/// First record of a metadata snapshot.
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct SnapshotHeaderRecord {
+    pub version: i16,
+    /// Append time of the last log record contained in the snapshot.
+    pub last_contained_log_timestamp: i64,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// Last record of a metadata snapshot; a snapshot without one is incomplete.
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct SnapshotFooterRecord {
+    pub version: i16,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
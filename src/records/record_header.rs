@@ -1,11 +1,15 @@
 use binrw::{BinRead, BinWrite};
-use integer_encoding::VarIntReader;
-use integer_encoding::VarIntWriter;
 
+use super::utils::{
+    parse_nullable_vec_u8_with_signed_varint_length, parse_vec_u8_with_signed_varint_length,
+    write_nullable_vec_u8_with_signed_varint_length, write_vec_u8_with_signed_varint_length,
+};
+
+/// Record header, both lengths are zigzag encoded varints and the value may be null.
 #[derive(Debug, PartialEq, Clone)]
 pub struct RecordHeader {
     pub key: Vec<u8>,
-    pub value: Vec<u8>,
+    pub value: Option<Vec<u8>>,
 }
 
 impl BinRead for RecordHeader {
@@ -13,17 +17,11 @@ impl BinRead for RecordHeader {
 
     fn read_options<R: std::io::Read + std::io::Seek>(
         reader: &mut R,
-        _endian: binrw::Endian,
+        endian: binrw::Endian,
         _args: Self::Args<'_>,
     ) -> binrw::BinResult<Self> {
-        let key_length = reader.read_varint().map_err(binrw::Error::Io)?;
-        let mut key = vec![0u8; key_length];
-        reader.read_exact(&mut key).map_err(binrw::Error::Io)?;
-
-        let value_length = reader.read_varint().map_err(binrw::Error::Io)?;
-        let mut value = vec![0u8; value_length];
-        reader.read_exact(&mut value).map_err(binrw::Error::Io)?;
-
+        let key = parse_vec_u8_with_signed_varint_length(reader, endian, ())?;
+        let value = parse_nullable_vec_u8_with_signed_varint_length(reader, endian, ())?;
         Ok(Self { key, value })
     }
 }
@@ -34,17 +32,10 @@ impl BinWrite for RecordHeader {
     fn write_options<W: std::io::Write + std::io::Seek>(
         &self,
         writer: &mut W,
-        _endian: binrw::Endian,
+        endian: binrw::Endian,
         _args: Self::Args<'_>,
     ) -> binrw::BinResult<()> {
-        writer
-            .write_varint(self.key.len())
-            .map_err(binrw::Error::Io)?;
-        writer.write(&self.key).map_err(binrw::Error::Io)?;
-        writer
-            .write_varint(self.value.len())
-            .map_err(binrw::Error::Io)?;
-        writer.write(&self.value).map_err(binrw::Error::Io)?;
-        Ok(())
+        write_vec_u8_with_signed_varint_length(&self.key, writer, endian, ())?;
+        write_nullable_vec_u8_with_signed_varint_length(self.value.as_deref(), writer, endian, ())
     }
 }
@@ -1,21 +1,65 @@
+use std::io::Cursor;
+
 use crate::common_structs::tagged_field::TaggedField;
-use binrw::binrw;
+use binrw::{binrw, helpers::until_eof, BinRead, BinResult, BinWrite, Endian};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use super::utils::{
-    parse_compact_array, parse_compact_string, parse_tagged_fields, write_compact_array,
-    write_compact_string, write_tagged_fields,
+    parse_compact_array, parse_compact_string, parse_compact_string_array,
+    parse_nullable_compact_array, parse_nullable_compact_string, parse_tagged_fields,
+    write_compact_array, write_compact_string, write_compact_string_array,
+    write_nullable_compact_string, write_tagged_fields,
 };
 
-/// 表示不同类型的记录
+/// 表示不同类型的记录，取值即 KRaft 元数据记录的 api key
 #[derive(Debug, PartialEq, Copy, Clone, TryFromPrimitive, IntoPrimitive)]
 #[repr(i8)]
 pub enum RecordType {
     BrokerRegistration = 0,
+    UnregisterBroker = 1,
     Topic = 2,
-    FeatureLevel = 12,
     Partition = 3,
-    // 未来可以方便地添加更多类型...
+    Config = 4,
+    PartitionChange = 5,
+    FenceBroker = 7,
+    UnfenceBroker = 8,
+    RemoveTopic = 9,
+    DelegationToken = 10,
+    UserScramCredential = 11,
+    FeatureLevel = 12,
+    ClientQuota = 14,
+    ProducerIds = 15,
+    BrokerRegistrationChange = 17,
+    AccessControlEntry = 18,
+    RemoveAccessControlEntry = 19,
+    NoOp = 20,
+    ZkMigrationState = 21,
+    RemoveUserScramCredential = 22,
+    BeginTransaction = 23,
+    EndTransaction = 24,
+    AbortTransaction = 25,
+    RemoveDelegationToken = 26,
+    RegisterController = 27,
+}
+
+impl RecordType {
+    /// 能解析的最高 record_version，更高的版本按 `ClusterMetadataValue::Unknown` 保留
+    pub fn max_supported_version(self) -> i8 {
+        match self {
+            RecordType::BrokerRegistration => 3,
+            RecordType::Partition
+            | RecordType::PartitionChange
+            | RecordType::BrokerRegistrationChange => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// record_type 已知，且 record_version 不超过该类型支持的最高版本
+pub fn is_supported_record(record_type: i8, record_version: i8) -> bool {
+    RecordType::try_from(record_type).is_ok_and(|record_type| {
+        (0..=record_type.max_supported_version()).contains(&record_version)
+    })
 }
 
 #[binrw]
@@ -27,27 +71,32 @@ pub struct ClusterMetadataRecord {
 
     /// 临时字段，不保存在结构体；读时从文件获取，写时动态计算
     #[br(temp)]
-    #[bw(calc = compute_record_type(&payload).into())] // 使用 IntoPrimitive 自动转换
+    #[bw(calc = compute_record_type(payload))]
     record_type: i8,
 
     /// 同样共享的字段
     pub record_version: i8,
 
-    /// 剩余有效载荷，依赖 record_type 的值解析
-    #[br(args { record_type })]
+    /// 剩余有效载荷，依赖 record_type 和 record_version 的值解析
+    #[br(args { record_type, record_version })]
+    #[bw(args { record_version: *record_version })]
     pub payload: ClusterMetadataValue,
 }
 
 impl ClusterMetadataRecord {
+    /// 编码为 Record 的 value 字节
+    pub fn to_bytes(&self) -> BinResult<Vec<u8>> {
+        let mut cursor = Cursor::new(Vec::new());
+        self.write(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
     /// 创建一个 BrokerRegistration 类型的 ClusterMetadataRecord
     pub fn mock_broker_registration() -> Self {
         ClusterMetadataRecord {
-            frame_version: 10,
-            record_version: 7,
-            payload: ClusterMetadataValue::BrokerRegistration(BrokerRegistrationRecord {
-                broker_id: 123,
-                cluster_id: 456789,
-            }),
+            frame_version: 1,
+            record_version: 3,
+            payload: ClusterMetadataValue::BrokerRegistration(BrokerRegistrationRecord::mock()),
         }
     }
 
@@ -55,7 +104,7 @@ impl ClusterMetadataRecord {
     pub fn mock_topic_record() -> Self {
         ClusterMetadataRecord {
             frame_version: 99,
-            record_version: 3,
+            record_version: 0,
             payload: ClusterMetadataValue::Topic(TopicRecord {
                 topic_name: "example_topic".to_string(),
                 uuid: [1u8; 16],
@@ -68,7 +117,7 @@ impl ClusterMetadataRecord {
     pub fn mock_feature_level_record() -> Self {
         ClusterMetadataRecord {
             frame_version: 100,
-            record_version: 1,
+            record_version: 0,
             payload: ClusterMetadataValue::FeatureLevel(FeatureLevelRecord {
                 feature_name: "example_feature".to_string(),
                 level: 5,
@@ -99,42 +148,246 @@ impl ClusterMetadataRecord {
     }
 }
 
-/// 不同记录的枚举，根据 record_type 的值确定走哪个分支
+/// 不同记录的枚举，根据 record_type 的值确定走哪个分支，record_version 决定字段
 #[binrw]
-#[br(import { record_type: i8 })]
+#[br(import { record_type: i8, record_version: i8 })]
+#[bw(import { record_version: i8 })]
 #[derive(Debug, PartialEq, Clone)]
 #[brw(big)]
 pub enum ClusterMetadataValue {
+    /// 不认识的类型或版本，原样保留剩余字节，写回时不做改动
+    #[br(pre_assert(!is_supported_record(record_type, record_version)))]
+    Unknown {
+        #[br(calc = record_type)]
+        #[bw(ignore)]
+        record_type: i8,
+        #[br(calc = record_version)]
+        #[bw(ignore)]
+        record_version: i8,
+        #[br(parse_with = until_eof)]
+        raw: Vec<u8>,
+    },
+
     #[br(pre_assert(record_type == RecordType::BrokerRegistration.into()))]
-    BrokerRegistration(BrokerRegistrationRecord),
+    BrokerRegistration(#[brw(args { version: record_version })] BrokerRegistrationRecord),
+
+    #[br(pre_assert(record_type == RecordType::UnregisterBroker.into()))]
+    UnregisterBroker(UnregisterBrokerRecord),
 
     #[br(pre_assert(record_type == RecordType::Topic.into()))]
     Topic(TopicRecord),
 
+    #[br(pre_assert(record_type == RecordType::Partition.into()))]
+    Partition(#[brw(args { version: record_version })] PartitionRecord),
+
+    #[br(pre_assert(record_type == RecordType::Config.into()))]
+    Config(ConfigRecord),
+
+    #[br(pre_assert(record_type == RecordType::PartitionChange.into()))]
+    PartitionChange(PartitionChangeRecord),
+
+    #[br(pre_assert(record_type == RecordType::FenceBroker.into()))]
+    FenceBroker(FenceBrokerRecord),
+
+    #[br(pre_assert(record_type == RecordType::UnfenceBroker.into()))]
+    UnfenceBroker(UnfenceBrokerRecord),
+
+    #[br(pre_assert(record_type == RecordType::RemoveTopic.into()))]
+    RemoveTopic(RemoveTopicRecord),
+
+    #[br(pre_assert(record_type == RecordType::DelegationToken.into()))]
+    DelegationToken(DelegationTokenRecord),
+
+    #[br(pre_assert(record_type == RecordType::UserScramCredential.into()))]
+    UserScramCredential(UserScramCredentialRecord),
+
     #[br(pre_assert(record_type == RecordType::FeatureLevel.into()))]
     FeatureLevel(FeatureLevelRecord),
 
-    #[br(pre_assert(record_type == RecordType::Partition.into()))]
-    Partition(PartitionRecord),
+    #[br(pre_assert(record_type == RecordType::ClientQuota.into()))]
+    ClientQuota(ClientQuotaRecord),
+
+    #[br(pre_assert(record_type == RecordType::ProducerIds.into()))]
+    ProducerIds(ProducerIdsRecord),
+
+    #[br(pre_assert(record_type == RecordType::BrokerRegistrationChange.into()))]
+    BrokerRegistrationChange(BrokerRegistrationChangeRecord),
+
+    #[br(pre_assert(record_type == RecordType::AccessControlEntry.into()))]
+    AccessControlEntry(AccessControlEntryRecord),
+
+    #[br(pre_assert(record_type == RecordType::RemoveAccessControlEntry.into()))]
+    RemoveAccessControlEntry(RemoveAccessControlEntryRecord),
+
+    #[br(pre_assert(record_type == RecordType::NoOp.into()))]
+    NoOp(NoOpRecord),
+
+    #[br(pre_assert(record_type == RecordType::ZkMigrationState.into()))]
+    ZkMigrationState(ZkMigrationStateRecord),
+
+    #[br(pre_assert(record_type == RecordType::RemoveUserScramCredential.into()))]
+    RemoveUserScramCredential(RemoveUserScramCredentialRecord),
+
+    #[br(pre_assert(record_type == RecordType::BeginTransaction.into()))]
+    BeginTransaction(BeginTransactionRecord),
+
+    #[br(pre_assert(record_type == RecordType::EndTransaction.into()))]
+    EndTransaction(EndTransactionRecord),
+
+    #[br(pre_assert(record_type == RecordType::AbortTransaction.into()))]
+    AbortTransaction(AbortTransactionRecord),
+
+    #[br(pre_assert(record_type == RecordType::RemoveDelegationToken.into()))]
+    RemoveDelegationToken(RemoveDelegationTokenRecord),
+
+    #[br(pre_assert(record_type == RecordType::RegisterController.into()))]
+    RegisterController(RegisterControllerRecord),
 }
 
 /// 将 match 逻辑单独提取到函数
-fn compute_record_type(payload: &ClusterMetadataValue) -> RecordType {
-    match payload {
+fn compute_record_type(payload: &ClusterMetadataValue) -> i8 {
+    let record_type = match payload {
+        ClusterMetadataValue::Unknown { record_type, .. } => return *record_type,
         ClusterMetadataValue::BrokerRegistration(_) => RecordType::BrokerRegistration,
+        ClusterMetadataValue::UnregisterBroker(_) => RecordType::UnregisterBroker,
         ClusterMetadataValue::Topic(_) => RecordType::Topic,
-        ClusterMetadataValue::FeatureLevel(_) => RecordType::FeatureLevel,
         ClusterMetadataValue::Partition(_) => RecordType::Partition,
-    }
+        ClusterMetadataValue::Config(_) => RecordType::Config,
+        ClusterMetadataValue::PartitionChange(_) => RecordType::PartitionChange,
+        ClusterMetadataValue::FenceBroker(_) => RecordType::FenceBroker,
+        ClusterMetadataValue::UnfenceBroker(_) => RecordType::UnfenceBroker,
+        ClusterMetadataValue::RemoveTopic(_) => RecordType::RemoveTopic,
+        ClusterMetadataValue::DelegationToken(_) => RecordType::DelegationToken,
+        ClusterMetadataValue::UserScramCredential(_) => RecordType::UserScramCredential,
+        ClusterMetadataValue::FeatureLevel(_) => RecordType::FeatureLevel,
+        ClusterMetadataValue::ClientQuota(_) => RecordType::ClientQuota,
+        ClusterMetadataValue::ProducerIds(_) => RecordType::ProducerIds,
+        ClusterMetadataValue::BrokerRegistrationChange(_) => RecordType::BrokerRegistrationChange,
+        ClusterMetadataValue::AccessControlEntry(_) => RecordType::AccessControlEntry,
+        ClusterMetadataValue::RemoveAccessControlEntry(_) => RecordType::RemoveAccessControlEntry,
+        ClusterMetadataValue::NoOp(_) => RecordType::NoOp,
+        ClusterMetadataValue::ZkMigrationState(_) => RecordType::ZkMigrationState,
+        ClusterMetadataValue::RemoveUserScramCredential(_) => RecordType::RemoveUserScramCredential,
+        ClusterMetadataValue::BeginTransaction(_) => RecordType::BeginTransaction,
+        ClusterMetadataValue::EndTransaction(_) => RecordType::EndTransaction,
+        ClusterMetadataValue::AbortTransaction(_) => RecordType::AbortTransaction,
+        ClusterMetadataValue::RemoveDelegationToken(_) => RecordType::RemoveDelegationToken,
+        ClusterMetadataValue::RegisterController(_) => RecordType::RegisterController,
+    };
+    record_type.into()
 }
 
+/// RegisterBrokerRecord，版本 0-3
 #[binrw]
+#[brw(import { version: i8 })]
 #[derive(Debug, PartialEq, Clone)]
 #[brw(big)]
 pub struct BrokerRegistrationRecord {
     pub broker_id: i32,
-    pub cluster_id: i64,
-    // 可扩展更多字段
+
+    /// v2+
+    #[brw(if(version >= 2))]
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub is_migrating_zk_broker: bool,
+
+    pub incarnation_id: [u8; 16],
+
+    pub broker_epoch: i64,
+
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub end_points: Vec<BrokerEndpoint>,
+
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub features: Vec<BrokerFeature>,
+
+    #[br(parse_with=parse_nullable_compact_string)]
+    #[bw(write_with=write_nullable_compact_string)]
+    pub rack: Option<String>,
+
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub fenced: bool,
+
+    /// v1+
+    #[brw(if(version >= 1))]
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub in_controlled_shutdown: bool,
+
+    /// v3+
+    #[brw(if(version >= 3))]
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub log_dirs: Vec<[u8; 16]>,
+
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+impl BrokerRegistrationRecord {
+    fn mock() -> Self {
+        BrokerRegistrationRecord {
+            broker_id: 1,
+            is_migrating_zk_broker: false,
+            incarnation_id: [7; 16],
+            broker_epoch: 10,
+            end_points: vec![BrokerEndpoint {
+                name: "PLAINTEXT".to_string(),
+                host: "localhost".to_string(),
+                port: 9092,
+                security_protocol: 0,
+                tagged_fields: Vec::new(),
+            }],
+            features: vec![BrokerFeature {
+                name: "metadata.version".to_string(),
+                min_supported_version: 1,
+                max_supported_version: 20,
+                tagged_fields: Vec::new(),
+            }],
+            rack: None,
+            fenced: false,
+            in_controlled_shutdown: false,
+            log_dirs: vec![[9; 16]],
+            tagged_fields: Vec::new(),
+        }
+    }
+}
+
+/// broker 监听的一个 endpoint
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct BrokerEndpoint {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub name: String,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub host: String,
+    pub port: u16,
+    pub security_protocol: i16,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// broker 支持的 feature 版本范围
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct BrokerFeature {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub name: String,
+    pub min_supported_version: i16,
+    pub max_supported_version: i16,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
 }
 
 #[binrw]
@@ -163,8 +416,11 @@ pub struct FeatureLevelRecord {
     pub tagged_fields: Vec<TaggedField>,
 }
 
-/// Kafka 分区记录结构
+/// Kafka 分区记录结构，版本 0-2
+///
+/// LeaderRecoveryState 和 v2 的 ELR 字段都是 tagged fields，留在 `tagged_fields` 里
 #[binrw]
+#[brw(import { version: i8 })]
 #[derive(Debug, PartialEq, Clone)]
 #[brw(big)]
 pub struct PartitionRecord {
@@ -175,20 +431,20 @@ pub struct PartitionRecord {
     pub topic_id: [u8; 16],
 
     /// 副本列表（broker ID 数组）
-    #[br(parse_with=parse_compact_array::<_, _, ()>)]
+    #[br(parse_with=parse_compact_array::<_, _>)]
     #[bw(write_with=write_compact_array)]
     pub replicas: Vec<i32>,
 
     /// ISR列表（同步的副本 broker ID 数组）
-    #[br(parse_with=parse_compact_array::<_, _, ()>)]
+    #[br(parse_with=parse_compact_array::<_, _>)]
     #[bw(write_with=write_compact_array)]
     pub isr: Vec<i32>,
 
-    #[br(parse_with=parse_compact_array::<_, _, ()>)]
+    #[br(parse_with=parse_compact_array::<_, _>)]
     #[bw(write_with=write_compact_array)]
     pub rra: Vec<i32>,
 
-    #[br(parse_with=parse_compact_array::<_, _, ()>)]
+    #[br(parse_with=parse_compact_array::<_, _>)]
     #[bw(write_with=write_compact_array)]
     pub ara: Vec<i32>,
 
@@ -200,7 +456,9 @@ pub struct PartitionRecord {
 
     pub partition_epoch: i32,
 
-    #[br(parse_with=parse_compact_array::<_, _, ()>)]
+    /// v1+
+    #[brw(if(version >= 1))]
+    #[br(parse_with=parse_compact_array::<_, _>)]
     #[bw(write_with=write_compact_array)]
     pub directories: Vec<[u8; 16]>,
 
@@ -210,24 +468,448 @@ pub struct PartitionRecord {
     pub tagged_fields: Vec<TaggedField>,
 }
 
+/// `PartitionChangeRecord::leader` 的默认值，表示 leader 没有变化
+pub const NO_LEADER_CHANGE: i32 = -2;
+
+/// 分区变更记录，版本 0-2
+///
+/// 除了定位分区的两个字段，其余字段都是 tagged fields，只有发生变化的才会出现
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct PartitionChangeRecord {
+    pub partition_id: i32,
+    pub topic_id: [u8; 16],
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+impl PartitionChangeRecord {
+    /// 新的 ISR，`None` 表示不变
+    pub fn isr(&self) -> Option<Vec<i32>> {
+        self.tagged_array(0)
+    }
+
+    /// 新的 leader，`NO_LEADER_CHANGE` 表示不变，-1 表示没有 leader
+    pub fn leader(&self) -> i32 {
+        self.tagged_field(1)
+            .and_then(|data| i32::read_be(&mut Cursor::new(data)).ok())
+            .unwrap_or(NO_LEADER_CHANGE)
+    }
+
+    pub fn replicas(&self) -> Option<Vec<i32>> {
+        self.tagged_array(2)
+    }
+
+    pub fn removing_replicas(&self) -> Option<Vec<i32>> {
+        self.tagged_array(3)
+    }
+
+    pub fn adding_replicas(&self) -> Option<Vec<i32>> {
+        self.tagged_array(4)
+    }
+
+    /// 版本 2 起才有
+    pub fn directories(&self) -> Option<Vec<[u8; 16]>> {
+        self.tagged_array(8)
+    }
+
+    fn tagged_field(&self, tag: usize) -> Option<&[u8]> {
+        find_tagged_field(&self.tagged_fields, tag)
+    }
+
+    fn tagged_array<T>(&self, tag: usize) -> Option<Vec<T>>
+    where
+        T: for<'a> BinRead<Args<'a> = ()>,
+    {
+        let data = self.tagged_field(tag)?;
+        parse_nullable_compact_array(&mut Cursor::new(data), Endian::Big, ())
+            .ok()
+            .flatten()
+    }
+}
+
+fn find_tagged_field(tagged_fields: &[TaggedField], tag: usize) -> Option<&[u8]> {
+    tagged_fields
+        .iter()
+        .find(|field| field.field_tag() == tag)
+        .map(TaggedField::data)
+}
+
+/// 删除主题记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct RemoveTopicRecord {
+    pub topic_id: [u8; 16],
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// broker 下线记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct UnregisterBrokerRecord {
+    pub broker_id: i32,
+    pub broker_epoch: i64,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 配置变更记录，`value` 为 `None` 表示删除该配置
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct ConfigRecord {
+    pub resource_type: i8,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub resource_name: String,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub name: String,
+    #[br(parse_with=parse_nullable_compact_string)]
+    #[bw(write_with=write_nullable_compact_string)]
+    pub value: Option<String>,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 隔离 broker 记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct FenceBrokerRecord {
+    pub id: i32,
+    pub epoch: i64,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 解除 broker 隔离记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct UnfenceBrokerRecord {
+    pub id: i32,
+    pub epoch: i64,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// delegation token 记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct DelegationTokenRecord {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub owner: String,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub requester: String,
+    #[br(parse_with=parse_compact_string_array)]
+    #[bw(write_with=write_compact_string_array)]
+    pub renewers: Vec<String>,
+    pub issue_timestamp: i64,
+    pub max_timestamp: i64,
+    pub expiration_timestamp: i64,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub token_id: String,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// SCRAM 凭证记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct UserScramCredentialRecord {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub name: String,
+    pub mechanism: i8,
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub salt: Vec<u8>,
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub stored_key: Vec<u8>,
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub server_key: Vec<u8>,
+    pub iterations: i32,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 客户端配额记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct ClientQuotaRecord {
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub entity: Vec<ClientQuotaEntity>,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub key: String,
+    pub value: f64,
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub remove: bool,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 配额所属的实体，`entity_name` 为 `None` 表示默认实体
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct ClientQuotaEntity {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub entity_type: String,
+    #[br(parse_with=parse_nullable_compact_string)]
+    #[bw(write_with=write_nullable_compact_string)]
+    pub entity_name: Option<String>,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 分配出去的 producer id 区间，下一段从 `next_producer_id` 开始
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct ProducerIdsRecord {
+    pub broker_id: i32,
+    pub broker_epoch: i64,
+    pub next_producer_id: i64,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// broker 注册信息变更记录，版本 0-2
+///
+/// 除了定位 broker 的两个字段，其余字段都是 tagged fields，只有发生变化的才会出现
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct BrokerRegistrationChangeRecord {
+    pub broker_id: i32,
+    pub broker_epoch: i64,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+impl BrokerRegistrationChangeRecord {
+    /// 新的隔离状态，`None` 表示不变
+    pub fn fenced(&self) -> Option<bool> {
+        match self.tagged_i8(0)? {
+            1 => Some(true),
+            -1 => Some(false),
+            _ => None,
+        }
+    }
+
+    /// 版本 1 起才有，`None` 表示不变
+    pub fn in_controlled_shutdown(&self) -> Option<bool> {
+        match self.tagged_i8(1)? {
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// 版本 2 起才有
+    pub fn log_dirs(&self) -> Option<Vec<[u8; 16]>> {
+        let data = find_tagged_field(&self.tagged_fields, 2)?;
+        parse_nullable_compact_array(&mut Cursor::new(data), Endian::Big, ())
+            .ok()
+            .flatten()
+    }
+
+    fn tagged_i8(&self, tag: usize) -> Option<i8> {
+        find_tagged_field(&self.tagged_fields, tag)
+            .and_then(|data| i8::read_be(&mut Cursor::new(data)).ok())
+    }
+}
+
+/// ACL 记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct AccessControlEntryRecord {
+    pub id: [u8; 16],
+    pub resource_type: i8,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub resource_name: String,
+    pub pattern_type: i8,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub principal: String,
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub host: String,
+    pub operation: i8,
+    pub permission_type: i8,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 删除 ACL 记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct RemoveAccessControlEntryRecord {
+    pub id: [u8; 16],
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 空记录，controller 定期写入以推进元数据 offset
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct NoOpRecord {
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// ZooKeeper 迁移状态记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct ZkMigrationStateRecord {
+    pub zk_migration_state: i8,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 删除 SCRAM 凭证记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct RemoveUserScramCredentialRecord {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub name: String,
+    pub mechanism: i8,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 元数据事务开始记录，事务名是 tag 0 的 tagged field
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct BeginTransactionRecord {
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+impl BeginTransactionRecord {
+    pub fn name(&self) -> Option<String> {
+        let data = find_tagged_field(&self.tagged_fields, 0)?;
+        parse_nullable_compact_string(&mut Cursor::new(data), Endian::Big, ())
+            .ok()
+            .flatten()
+    }
+}
+
+/// 元数据事务提交记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct EndTransactionRecord {
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// 元数据事务中止记录，原因是 tag 0 的 tagged field
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct AbortTransactionRecord {
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+impl AbortTransactionRecord {
+    pub fn reason(&self) -> Option<String> {
+        let data = find_tagged_field(&self.tagged_fields, 0)?;
+        parse_nullable_compact_string(&mut Cursor::new(data), Endian::Big, ())
+            .ok()
+            .flatten()
+    }
+}
+
+/// 删除 delegation token 记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct RemoveDelegationTokenRecord {
+    #[br(parse_with=parse_compact_string)]
+    #[bw(write_with=write_compact_string::<String, _>)]
+    pub token_id: String,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
+/// controller 注册记录
+#[binrw]
+#[derive(Debug, PartialEq, Clone)]
+#[brw(big)]
+pub struct RegisterControllerRecord {
+    pub controller_id: i32,
+    pub incarnation_id: [u8; 16],
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub zk_migration_ready: bool,
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub end_points: Vec<BrokerEndpoint>,
+    #[br(parse_with=parse_compact_array::<_, _>)]
+    #[bw(write_with=write_compact_array)]
+    pub features: Vec<BrokerFeature>,
+    #[br(parse_with=parse_tagged_fields)]
+    #[bw(write_with=write_tagged_fields)]
+    pub tagged_fields: Vec<TaggedField>,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::records::record_value;
 
     use super::*;
-    use binrw::{BinRead, BinWrite};
-    use std::io::Cursor;
+    use binrw::BinRead;
 
     #[test]
     fn test_broker_registration() {
-        let original = ClusterMetadataRecord {
-            frame_version: 10,
-            record_version: 7,
-            payload: ClusterMetadataValue::BrokerRegistration(BrokerRegistrationRecord {
-                broker_id: 123,
-                cluster_id: 456789,
-            }),
-        };
+        let original = ClusterMetadataRecord::mock_broker_registration();
 
         let mut data = vec![];
         let mut cursor = Cursor::new(&mut data);
@@ -236,11 +918,32 @@ mod tests {
         assert_eq!(decoded, original);
     }
 
+    #[test]
+    fn test_broker_registration_v0_defaults() {
+        let mut original = ClusterMetadataRecord::mock_broker_registration();
+        original.record_version = 0;
+        if let ClusterMetadataValue::BrokerRegistration(broker) = &mut original.payload {
+            broker.in_controlled_shutdown = true;
+            broker.is_migrating_zk_broker = true;
+        }
+        let data = original.to_bytes().unwrap();
+        let decoded = ClusterMetadataRecord::read(&mut Cursor::new(&data)).unwrap();
+        let ClusterMetadataValue::BrokerRegistration(broker) = decoded.payload else {
+            panic!("expected a broker registration");
+        };
+        // fields added after v0 are neither written nor read
+        assert!(!broker.in_controlled_shutdown);
+        assert!(!broker.is_migrating_zk_broker);
+        assert!(broker.log_dirs.is_empty());
+        assert!(!broker.fenced);
+        assert_eq!(broker.end_points[0].port, 9092);
+    }
+
     #[test]
     fn test_topic_record() {
         let original = ClusterMetadataRecord {
             frame_version: 99,
-            record_version: 3,
+            record_version: 0,
             payload: ClusterMetadataValue::Topic(TopicRecord {
                 topic_name: "123".to_string(),
                 uuid: [1u8; 16],
@@ -259,7 +962,7 @@ mod tests {
     fn test_feature_level_record() {
         let original = ClusterMetadataRecord {
             frame_version: 100,
-            record_version: 1,
+            record_version: 0,
             payload: ClusterMetadataValue::FeatureLevel(FeatureLevelRecord {
                 feature_name: "999".to_string(),
                 level: 5,
@@ -301,12 +1004,210 @@ mod tests {
         assert_eq!(decoded, original);
     }
 
+    #[test]
+    fn test_partition_change_record() {
+        // isr = [1, 2]，leader = 2
+        let isr = [0x03, 0, 0, 0, 1, 0, 0, 0, 2];
+        let leader = 2i32.to_be_bytes();
+        let original = ClusterMetadataRecord {
+            frame_version: 1,
+            record_version: 0,
+            payload: ClusterMetadataValue::PartitionChange(PartitionChangeRecord {
+                partition_id: 0,
+                topic_id: [10; 16],
+                tagged_fields: vec![
+                    TaggedField::new(0, isr.to_vec()),
+                    TaggedField::new(1, leader.to_vec()),
+                ],
+            }),
+        };
+
+        let data = original.to_bytes().unwrap();
+        let decoded = ClusterMetadataRecord::read(&mut Cursor::new(&data)).unwrap();
+        assert_eq!(decoded, original);
+        let ClusterMetadataValue::PartitionChange(change) = decoded.payload else {
+            panic!("expected a partition change");
+        };
+        assert_eq!(change.isr(), Some(vec![1, 2]));
+        assert_eq!(change.leader(), 2);
+        assert_eq!(change.replicas(), None);
+    }
+
+    #[test]
+    fn test_partition_record_v0_has_no_directories() {
+        let mut original = ClusterMetadataRecord::mock_partition_record();
+        original.record_version = 0;
+        let data = original.to_bytes().unwrap();
+        let decoded = ClusterMetadataRecord::read(&mut Cursor::new(&data)).unwrap();
+        let ClusterMetadataValue::Partition(partition) = decoded.payload else {
+            panic!("expected a partition record");
+        };
+        assert!(partition.directories.is_empty());
+        assert_eq!(partition.partition_epoch, 2);
+    }
+
+    #[test]
+    fn test_record_catalog_roundtrip() {
+        let payloads = vec![
+            ClusterMetadataValue::UnregisterBroker(UnregisterBrokerRecord {
+                broker_id: 1,
+                broker_epoch: 10,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::Config(ConfigRecord {
+                resource_type: 2,
+                resource_name: "foo".to_string(),
+                name: "retention.ms".to_string(),
+                value: None,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::FenceBroker(FenceBrokerRecord {
+                id: 1,
+                epoch: 10,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::UnfenceBroker(UnfenceBrokerRecord {
+                id: 1,
+                epoch: 10,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::DelegationToken(DelegationTokenRecord {
+                owner: "User:alice".to_string(),
+                requester: "User:alice".to_string(),
+                renewers: vec!["User:bob".to_string()],
+                issue_timestamp: 1,
+                max_timestamp: 3,
+                expiration_timestamp: 2,
+                token_id: "token".to_string(),
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::UserScramCredential(UserScramCredentialRecord {
+                name: "alice".to_string(),
+                mechanism: 1,
+                salt: vec![1, 2],
+                stored_key: vec![3],
+                server_key: vec![4],
+                iterations: 4096,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::ClientQuota(ClientQuotaRecord {
+                entity: vec![ClientQuotaEntity {
+                    entity_type: "client-id".to_string(),
+                    entity_name: None,
+                    tagged_fields: Vec::new(),
+                }],
+                key: "producer_byte_rate".to_string(),
+                value: 1024.0,
+                remove: false,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::ProducerIds(ProducerIdsRecord {
+                broker_id: 1,
+                broker_epoch: 10,
+                next_producer_id: 1000,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::AccessControlEntry(AccessControlEntryRecord {
+                id: [3; 16],
+                resource_type: 2,
+                resource_name: "foo".to_string(),
+                pattern_type: 3,
+                principal: "User:alice".to_string(),
+                host: "*".to_string(),
+                operation: 3,
+                permission_type: 3,
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::RemoveAccessControlEntry(RemoveAccessControlEntryRecord {
+                id: [3; 16],
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::NoOp(NoOpRecord {
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::BeginTransaction(BeginTransactionRecord {
+                tagged_fields: vec![TaggedField::new(0, vec![0x04, b'b', b'o', b'b'])],
+            }),
+            ClusterMetadataValue::EndTransaction(EndTransactionRecord {
+                tagged_fields: Vec::new(),
+            }),
+            ClusterMetadataValue::RegisterController(RegisterControllerRecord {
+                controller_id: 3000,
+                incarnation_id: [5; 16],
+                zk_migration_ready: false,
+                end_points: vec![BrokerEndpoint {
+                    name: "CONTROLLER".to_string(),
+                    host: "localhost".to_string(),
+                    port: 9093,
+                    security_protocol: 0,
+                    tagged_fields: Vec::new(),
+                }],
+                features: Vec::new(),
+                tagged_fields: Vec::new(),
+            }),
+        ];
+        for payload in payloads {
+            let original = ClusterMetadataRecord {
+                frame_version: 1,
+                record_version: 0,
+                payload,
+            };
+            let data = original.to_bytes().unwrap();
+            let decoded = ClusterMetadataRecord::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(decoded, original);
+        }
+    }
+
+    #[test]
+    fn test_unknown_records_roundtrip() {
+        // 未知类型 200，以及 TopicRecord 尚不支持的版本 1
+        for (record_type, record_version) in [(-56i8, 0i8), (2, 1)] {
+            let data = [
+                1,
+                record_type as u8,
+                record_version as u8,
+                0x03,
+                b'a',
+                b'b',
+                0x00,
+            ];
+            let decoded = ClusterMetadataRecord::read(&mut Cursor::new(&data)).unwrap();
+            assert_eq!(
+                decoded.payload,
+                ClusterMetadataValue::Unknown {
+                    record_type,
+                    record_version,
+                    raw: data[3..].to_vec(),
+                }
+            );
+            assert_eq!(decoded.to_bytes().unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_begin_transaction_name() {
+        let record = BeginTransactionRecord {
+            tagged_fields: vec![TaggedField::new(0, vec![0x04, b'b', b'o', b'b'])],
+        };
+        assert_eq!(record.name().as_deref(), Some("bob"));
+    }
+
     #[test]
     fn test_real_data() {
-        let real_raw = [72, 101, 108, 108, 111, 32, 87, 111, 114, 108, 100, 33];
+        // PartitionRecord payload taken from a __cluster_metadata log
+        let real_raw = [
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x80, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x91, 0x02, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00,
+            0x00, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x02, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x80, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+        ];
         let cluster_metadata_record = ClusterMetadataValue::read_be_args(
             &mut Cursor::new(&real_raw),
-            record_value::ClusterMetadataValueBinReadArgs { record_type: 3 },
+            record_value::ClusterMetadataValueBinReadArgs {
+                record_type: 3,
+                record_version: 1,
+            },
         )
         .unwrap();
         // test时候打印一下
@@ -13,7 +13,7 @@ use crate::common_structs::tagged_field::TaggedField;
 use crate::traits::KafkaDeseriarize;
 use crate::traits::KafkaSeriarize;
 
-pub fn parse_compact_array<'a, R: Read + Seek, T, Args>(
+pub fn parse_compact_array<'a, R: Read + Seek, T>(
     reader: &mut R,
     endian: Endian,
     args: T::Args<'a>,
@@ -32,6 +32,27 @@ where
     Ok(results)
 }
 
+/// Like [`parse_compact_array`], but a length of `0` decodes as `None`.
+pub fn parse_nullable_compact_array<'a, R: Read + Seek, T>(
+    reader: &mut R,
+    endian: Endian,
+    args: T::Args<'a>,
+) -> BinResult<Option<Vec<T>>>
+where
+    T: BinRead,
+    T::Args<'a>: Copy,
+{
+    let i: usize = reader.read_varint()?;
+    if i == 0 {
+        return Ok(None);
+    }
+    let mut results = Vec::with_capacity(i - 1);
+    for _ in 0..i - 1 {
+        results.push(BinRead::read_options(reader, endian, args)?);
+    }
+    Ok(Some(results))
+}
+
 pub fn write_compact_array<'a, W: Write + Seek, T>(
     array: &impl AsRef<[T]>,
     writer: &mut W,
@@ -43,9 +64,8 @@ where
     T::Args<'a>: Copy,
 {
     let array = array.as_ref();
-    let length = array.len();
-    let write_len = if length == 0 { 0 } else { length + 1 };
-    writer.write_varint(write_len)?;
+    // a compact length of 0 means null, so an empty array is written as 1
+    writer.write_varint(array.len() + 1)?;
     for element in array {
         BinWrite::write_options(element, writer, endian, args)?;
     }
@@ -85,12 +105,36 @@ where
 
 pub fn parse_compact_string<R: Read + Seek>(
     reader: &mut R,
-    _endian: Endian,
+    endian: Endian,
     _: (),
 ) -> BinResult<String> {
+    parse_nullable_compact_string(reader, endian, ()).map(Option::unwrap_or_default)
+}
+
+pub fn write_compact_string<S, R: Write + Seek>(
+    s: &S,
+    writer: &mut R,
+    _endian: Endian,
+    _: (),
+) -> BinResult<()>
+where
+    S: AsRef<str>,
+{
+    let vec = s.as_ref().as_bytes();
+    writer.write_varint(vec.len() + 1)?;
+    writer.write_all(vec)?;
+    Ok(())
+}
+
+/// Like [`parse_compact_string`], but a length of `0` decodes as `None`.
+pub fn parse_nullable_compact_string<R: Read + Seek>(
+    reader: &mut R,
+    _endian: Endian,
+    _: (),
+) -> BinResult<Option<String>> {
     let length: usize = reader.read_varint()?;
     if length == 0 {
-        return Ok(String::new());
+        return Ok(None);
     }
     let length = length - 1;
     let mut buf = vec![0u8; length];
@@ -104,23 +148,49 @@ pub fn parse_compact_string<R: Read + Seek>(
         message: format!("UTF-8 parse error: {utf8_err}"),
     })?;
 
-    Ok(result)
+    Ok(Some(result))
 }
 
-pub fn write_compact_string<S, R: Write + Seek>(
-    s: &S,
+pub fn write_nullable_compact_string<R: Write + Seek>(
+    s: &Option<String>,
     writer: &mut R,
-    _endian: Endian,
+    endian: Endian,
     _: (),
-) -> BinResult<()>
-where
-    S: AsRef<str>,
-{
-    let vec = s.as_ref().as_bytes();
-    let length = vec.len();
-    let length = if length == 0 { length } else { length + 1 };
-    writer.write_varint(length)?;
-    writer.write_all(vec)?;
+) -> BinResult<()> {
+    match s {
+        Some(s) => write_compact_string(s, writer, endian, ()),
+        None => {
+            writer.write_varint(0usize)?;
+            Ok(())
+        }
+    }
+}
+
+pub fn parse_compact_string_array<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+    _: (),
+) -> BinResult<Vec<String>> {
+    let i: usize = reader.read_varint()?;
+    let length = if i == 0 { 0 } else { i - 1 };
+    let mut results = Vec::with_capacity(length);
+    for _ in 0..length {
+        results.push(parse_compact_string(reader, endian, ())?);
+    }
+    Ok(results)
+}
+
+pub fn write_compact_string_array<R: Write + Seek>(
+    array: &impl AsRef<[String]>,
+    writer: &mut R,
+    endian: Endian,
+    _: (),
+) -> BinResult<()> {
+    let array = array.as_ref();
+    writer.write_varint(array.len() + 1)?;
+    for s in array {
+        write_compact_string(s, writer, endian, ())?;
+    }
     Ok(())
 }
 
@@ -141,6 +211,36 @@ pub fn parse_vec_u8_with_signed_varint_length<R: Read + Seek>(
     Ok(vec)
 }
 
+/// Like [`parse_vec_u8_with_signed_varint_length`], but a length of `-1` decodes as `None`.
+pub fn parse_nullable_vec_u8_with_signed_varint_length<R: Read + Seek>(
+    reader: &mut R,
+    _endian: Endian,
+    _: (),
+) -> BinResult<Option<Vec<u8>>> {
+    let len: i64 = reader.read_varint()?;
+    if len < 0 {
+        return Ok(None);
+    }
+    let mut vec = vec![0u8; len as usize];
+    reader.read_exact(&mut vec)?;
+    Ok(Some(vec))
+}
+
+pub fn write_nullable_vec_u8_with_signed_varint_length<W: Write + Seek>(
+    slice: Option<&[u8]>,
+    writer: &mut W,
+    endian: Endian,
+    _: (),
+) -> BinResult<()> {
+    match slice {
+        Some(slice) => write_vec_u8_with_signed_varint_length(slice, writer, endian, ()),
+        None => {
+            writer.write_varint(-1i64)?;
+            Ok(())
+        }
+    }
+}
+
 pub fn write_vec_u8_with_signed_varint_length<W: Write + Seek>(
     slice: &[u8],
     writer: &mut W,
@@ -1,8 +1,7 @@
-use std::io::{self, Cursor, Read};
+use std::io::Read;
 
 use api_key::RequestApiKey;
 use body::KafkaRequestBody;
-use byteorder::{BigEndian, ReadBytesExt};
 use error::RequestError;
 use header::KafkaRequestHeader;
 
@@ -23,20 +22,6 @@ pub struct KafkaRequest {
     body: KafkaRequestBody,
 }
 
-// public function
-impl KafkaRequest {
-    pub fn try_from_reader<R: Read>(
-        reader: &mut R,
-    ) -> Result<Result<Self, RequestError>, io::Error> {
-        let message_size = reader.read_i32::<BigEndian>()?;
-        let mut buf = vec![0u8; message_size as usize];
-        reader.read_exact(&mut buf)?;
-
-        let mut reader = Cursor::new(buf);
-        Ok(Self::try_parse_from_reader(&mut reader, ()))
-    }
-}
-
 impl KafkaDeseriarize for KafkaRequest {
     type Error = RequestError;
 
@@ -46,14 +31,10 @@ impl KafkaDeseriarize for KafkaRequest {
         reader: &mut R,
         _: Self::DependentData<'_>,
     ) -> Result<Self, Self::Error> {
-        let header = KafkaRequestHeader::try_parse_from_reader(reader, ()).map_err(|e| {
-            dbg!("header is invalid! {e}");
-            e
-        })?;
-        let body = KafkaRequestBody::try_parse_from_reader(reader, &header).map_err(|e| {
-            dbg!("body is invalid! {:?}", &e);
-            e
-        })?;
+        let header = KafkaRequestHeader::try_parse_from_reader(reader, ())
+            .inspect_err(|e| eprintln!("invalid request header: {e}"))?;
+        let body = KafkaRequestBody::try_parse_from_reader(reader, &header)
+            .inspect_err(|e| eprintln!("invalid request body: {e}"))?;
         Ok(KafkaRequest { header, body })
     }
 }
@@ -61,7 +42,7 @@ impl KafkaDeseriarize for KafkaRequest {
 impl KafkaRequest {
     #[inline]
     pub fn request_api_key(&self) -> &RequestApiKey {
-        &self.header.request_api_key()
+        self.header.request_api_key()
     }
 
     #[inline]
@@ -78,4 +59,9 @@ impl KafkaRequest {
     pub fn request_body(&self) -> &KafkaRequestBody {
         &self.body
     }
+
+    /// Produce requests with `acks = 0` must not be answered at all.
+    pub fn expects_response(&self) -> bool {
+        !matches!(&self.body, KafkaRequestBody::Produce(body) if body.acks == 0)
+    }
 }
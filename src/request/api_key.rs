@@ -1,15 +1,34 @@
 use num_enum::TryFromPrimitive;
 
 use crate::consts::{
-    api_versions::API_VERSIONS_API_KEY,
+    api_versions::API_VERSIONS_API_KEY, create_partitions::CREATE_PARTITIONS_API_KEY,
+    create_topics::CREATE_TOPICS_API_KEY, delete_topics::DELETE_TOPICS_API_KEY,
     describe_topic_partitions::DESCRIBE_TOPIC_PARTITIONS_API_KEY, fetch::FETCH_API_KEY,
+    find_coordinator::FIND_COORDINATOR_API_KEY, heartbeat::HEARTBEAT_API_KEY,
+    init_producer_id::INIT_PRODUCER_ID_API_KEY, join_group::JOIN_GROUP_API_KEY,
+    leave_group::LEAVE_GROUP_API_KEY, list_offsets::LIST_OFFSETS_API_KEY,
+    metadata::METADATA_API_KEY, offset_commit::OFFSET_COMMIT_API_KEY,
+    offset_fetch::OFFSET_FETCH_API_KEY, produce::PRODUCE_API_KEY, sync_group::SYNC_GROUP_API_KEY,
 };
 
 #[repr(i16)]
 #[derive(Debug, TryFromPrimitive, Clone, Copy)]
 pub enum RequestApiKey {
-    Produce = 0,
+    Produce = PRODUCE_API_KEY,
     Fetch = FETCH_API_KEY,
+    ListOffsets = LIST_OFFSETS_API_KEY,
+    Metadata = METADATA_API_KEY,
+    OffsetCommit = OFFSET_COMMIT_API_KEY,
+    OffsetFetch = OFFSET_FETCH_API_KEY,
+    FindCoordinator = FIND_COORDINATOR_API_KEY,
+    JoinGroup = JOIN_GROUP_API_KEY,
+    Heartbeat = HEARTBEAT_API_KEY,
+    LeaveGroup = LEAVE_GROUP_API_KEY,
+    SyncGroup = SYNC_GROUP_API_KEY,
     ApiVersions = API_VERSIONS_API_KEY,
+    CreateTopics = CREATE_TOPICS_API_KEY,
+    DeleteTopics = DELETE_TOPICS_API_KEY,
+    InitProducerId = INIT_PRODUCER_ID_API_KEY,
+    CreatePartitions = CREATE_PARTITIONS_API_KEY,
     DescribeTopicPartitions = DESCRIBE_TOPIC_PARTITIONS_API_KEY,
 }
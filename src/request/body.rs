@@ -1,24 +1,64 @@
 use std::io::Read;
 
 use api_versions::ApiVersionsRequestBody;
+use create_partitions::CreatePartitionsRequestBody;
+use create_topics::CreateTopicsRequestBody;
+use delete_topics::DeleteTopicsRequestBody;
 use describe_topic_partitions::DescribeTopicPartitionsRequestBody;
 use fetch::FetchRequestBody;
+use find_coordinator::FindCoordinatorRequestBody;
+use heartbeat::HeartbeatRequestBody;
+use init_producer_id::InitProducerIdRequestBody;
+use join_group::JoinGroupRequestBody;
+use leave_group::LeaveGroupRequestBody;
+use list_offsets::ListOffsetsRequestBody;
+use metadata::MetadataRequestBody;
+use offset_commit::OffsetCommitRequestBody;
+use offset_fetch::OffsetFetchRequestBody;
+use produce::ProduceRequestBody;
+use sync_group::SyncGroupRequestBody;
 
 use crate::{request::api_key::RequestApiKey, traits::KafkaDeseriarize};
 
 use super::{error::RequestError, KafkaRequestHeader};
 
 pub mod api_versions;
+pub mod create_partitions;
+pub mod create_topics;
+pub mod delete_topics;
 pub mod describe_topic_partitions;
 pub mod fetch;
+pub mod find_coordinator;
+pub mod heartbeat;
+pub mod init_producer_id;
+pub mod join_group;
+pub mod leave_group;
+pub mod list_offsets;
+pub mod metadata;
+pub mod offset_commit;
+pub mod offset_fetch;
+pub mod produce;
+pub mod sync_group;
 
 #[allow(unused)]
 #[derive(Debug)]
 pub enum KafkaRequestBody {
-    Empty,
-    Produce,
+    Produce(ProduceRequestBody),
     Fetch(FetchRequestBody),
+    ListOffsets(ListOffsetsRequestBody),
+    Metadata(MetadataRequestBody),
+    OffsetCommit(OffsetCommitRequestBody),
+    OffsetFetch(OffsetFetchRequestBody),
+    FindCoordinator(FindCoordinatorRequestBody),
+    JoinGroup(JoinGroupRequestBody),
+    Heartbeat(HeartbeatRequestBody),
+    LeaveGroup(LeaveGroupRequestBody),
+    SyncGroup(SyncGroupRequestBody),
     ApiVersions(ApiVersionsRequestBody),
+    CreateTopics(CreateTopicsRequestBody),
+    DeleteTopics(DeleteTopicsRequestBody),
+    InitProducerId(InitProducerIdRequestBody),
+    CreatePartitions(CreatePartitionsRequestBody),
     DescribeTopicPartitions(DescribeTopicPartitionsRequestBody),
 }
 
@@ -38,13 +78,54 @@ impl KafkaDeseriarize for KafkaRequestBody {
             RequestApiKey::ApiVersions => KafkaRequestBody::ApiVersions(
                 ApiVersionsRequestBody::try_parse_from_reader(reader, header)?,
             ),
+            RequestApiKey::CreatePartitions => KafkaRequestBody::CreatePartitions(
+                CreatePartitionsRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::CreateTopics => KafkaRequestBody::CreateTopics(
+                CreateTopicsRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::DeleteTopics => KafkaRequestBody::DeleteTopics(
+                DeleteTopicsRequestBody::try_parse_from_reader(reader, header)?,
+            ),
             RequestApiKey::DescribeTopicPartitions => KafkaRequestBody::DescribeTopicPartitions(
                 DescribeTopicPartitionsRequestBody::try_parse_from_reader(reader, header)?,
             ),
-            RequestApiKey::Fetch => KafkaRequestBody::Fetch(
-                FetchRequestBody::try_parse_from_reader(reader, header)?,
+            RequestApiKey::Fetch => {
+                KafkaRequestBody::Fetch(FetchRequestBody::try_parse_from_reader(reader, header)?)
+            }
+            RequestApiKey::FindCoordinator => KafkaRequestBody::FindCoordinator(
+                FindCoordinatorRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::Heartbeat => KafkaRequestBody::Heartbeat(
+                HeartbeatRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::InitProducerId => KafkaRequestBody::InitProducerId(
+                InitProducerIdRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::JoinGroup => KafkaRequestBody::JoinGroup(
+                JoinGroupRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::LeaveGroup => KafkaRequestBody::LeaveGroup(
+                LeaveGroupRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::ListOffsets => KafkaRequestBody::ListOffsets(
+                ListOffsetsRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::Metadata => KafkaRequestBody::Metadata(
+                MetadataRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::OffsetCommit => KafkaRequestBody::OffsetCommit(
+                OffsetCommitRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::OffsetFetch => KafkaRequestBody::OffsetFetch(
+                OffsetFetchRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::Produce => KafkaRequestBody::Produce(
+                ProduceRequestBody::try_parse_from_reader(reader, header)?,
+            ),
+            RequestApiKey::SyncGroup => KafkaRequestBody::SyncGroup(
+                SyncGroupRequestBody::try_parse_from_reader(reader, header)?,
             ),
-            _ => KafkaRequestBody::Empty,
         };
         Ok(body)
     }
@@ -50,10 +50,10 @@ impl KafkaDeseriarize for ApiVersionsRequestBody {
             SupportApiVersionsRequestVersion::V1 => ApiVersionsRequestBody::V1,
             SupportApiVersionsRequestVersion::V2 => ApiVersionsRequestBody::V2,
             SupportApiVersionsRequestVersion::V3 => ApiVersionsRequestBody::V3(
-                ApiVersionsRequestBodyV3::try_parse_from_reader(reader, &header)?,
+                ApiVersionsRequestBodyV3::try_parse_from_reader(reader, header)?,
             ),
             SupportApiVersionsRequestVersion::V4 => ApiVersionsRequestBody::V4(
-                ApiVersionsRequestBodyV4::try_parse_from_reader(reader, &header)?,
+                ApiVersionsRequestBodyV4::try_parse_from_reader(reader, header)?,
             ),
         };
         Ok(body)
@@ -0,0 +1,280 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::create_partitions::SupportCreatePartitionsRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_nullable_vec_from_array,
+            try_read_nullable_vec_from_compact_array, try_read_tagged_fields,
+            try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// CreatePartitions request, versions 0 through 3.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct CreatePartitionsRequestBody {
+    version: SupportCreatePartitionsRequestVersion,
+    pub topics: Vec<CreatePartitionsTopic>,
+    timeout_ms: i32,
+    /// Check the request without creating anything.
+    pub validate_only: bool,
+}
+
+impl CreatePartitionsRequestBody {
+    pub fn get_api_version(&self) -> SupportCreatePartitionsRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for CreatePartitionsRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportCreatePartitionsRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let read_topic =
+            |r: &mut R| CreatePartitionsTopic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        let timeout_ms = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("create_partitions timeout_ms", correlation_id)
+        })?;
+        let validate_only = reader.read_u8().map_err(|_| {
+            RequestError::invalid_format("create_partitions validate_only", correlation_id)
+        })? != 0;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("create_partitions tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            topics,
+            timeout_ms,
+            validate_only,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct CreatePartitionsTopic {
+    pub name: String,
+    /// New total partition count.
+    pub count: i32,
+    /// Replicas of every new partition, or `None` to let the broker choose.
+    pub assignments: Option<Vec<Vec<i32>>>,
+}
+
+impl KafkaDeseriarize for CreatePartitionsTopic {
+    type Error = RequestError;
+
+    type DependentData<'a> = (
+        &'a KafkaRequestHeader,
+        SupportCreatePartitionsRequestVersion,
+    );
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        let count = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("topic count", correlation_id))?;
+
+        let read_assignment = |r: &mut R| {
+            let read_broker_id = |r: &mut R| {
+                r.read_i32::<BigEndian>().map_err(|_| {
+                    RequestError::invalid_format("assignment broker_ids", correlation_id)
+                })
+            };
+            let broker_ids = if flexible {
+                try_read_vec_from_compact_array(r, read_broker_id)
+            } else {
+                try_read_vec_from_array(r, read_broker_id)
+            }
+            .map_err(|e| e.into_request_error("broker_ids length", correlation_id))?;
+            if flexible {
+                let _ = try_read_tagged_fields(r).map_err(|_| {
+                    RequestError::invalid_format("assignment tagged_fields", correlation_id)
+                })?;
+            }
+            Ok::<_, RequestError>(broker_ids)
+        };
+        let assignments = if flexible {
+            try_read_nullable_vec_from_compact_array(reader, read_assignment)
+        } else {
+            try_read_nullable_vec_from_array(reader, read_assignment)
+        }
+        .map_err(|e| e.into_request_error("assignments length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self {
+            name,
+            count,
+            assignments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 2 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::CreatePartitions,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::CreatePartitions,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// A request growing "foo" to 3 partitions with the broker choosing
+    /// replicas, and "bar" to 2 with the new partition on brokers 1 and 2,
+    /// laid out as `version` sends it.
+    fn encode(version: i16, validate_only: bool) -> Vec<u8> {
+        let flexible = version >= 2;
+        let mut buf = Vec::new();
+        let write_len = |buf: &mut Vec<u8>, len: i32| {
+            if flexible {
+                buf.push((len + 1) as u8);
+            } else {
+                buf.write_i32::<BigEndian>(len).unwrap();
+            }
+        };
+        let write_string = |buf: &mut Vec<u8>, s: &str| {
+            if flexible {
+                buf.push(s.len() as u8 + 1);
+            } else {
+                buf.write_i16::<BigEndian>(s.len() as i16).unwrap();
+            }
+            buf.extend_from_slice(s.as_bytes());
+        };
+
+        write_len(&mut buf, 2);
+        write_string(&mut buf, "foo");
+        buf.write_i32::<BigEndian>(3).unwrap();
+        write_len(&mut buf, -1);
+        if flexible {
+            buf.push(0);
+        }
+        write_string(&mut buf, "bar");
+        buf.write_i32::<BigEndian>(2).unwrap();
+        write_len(&mut buf, 1);
+        write_len(&mut buf, 2);
+        buf.write_i32::<BigEndian>(1).unwrap();
+        buf.write_i32::<BigEndian>(2).unwrap();
+        if flexible {
+            buf.push(0); // assignment tagged fields
+            buf.push(0);
+        }
+        buf.write_i32::<BigEndian>(30_000).unwrap();
+        buf.push(validate_only as u8);
+        if flexible {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16, validate_only: bool) -> CreatePartitionsRequestBody {
+        let buf = encode(version, validate_only);
+        let mut reader = Cursor::new(&buf[..]);
+        let body =
+            CreatePartitionsRequestBody::try_parse_from_reader(&mut reader, &header(version))
+                .unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        for version in 0..=3 {
+            let body = parse(version, false);
+            assert_eq!(body.topics.len(), 2, "v{version}");
+            assert_eq!(body.topics[0].name, "foo");
+            assert_eq!(body.topics[0].count, 3);
+            assert_eq!(body.topics[0].assignments, None);
+            assert_eq!(body.topics[1].name, "bar");
+            assert_eq!(body.topics[1].count, 2);
+            assert_eq!(body.topics[1].assignments, Some(vec![vec![1, 2]]));
+            assert_eq!(body.timeout_ms, 30_000);
+            assert!(!body.validate_only);
+        }
+        // v2: compact encoding and tagged fields
+        assert_eq!(
+            parse(2, false).get_api_version(),
+            SupportCreatePartitionsRequestVersion::V2
+        );
+    }
+
+    #[test]
+    fn test_parse_validate_only() {
+        assert!(parse(0, true).validate_only);
+        assert!(parse(3, true).validate_only);
+    }
+}
@@ -0,0 +1,243 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::create_topics::SupportCreateTopicsRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_optional_compact_string,
+            try_read_optional_string, try_read_tagged_fields, try_read_vec_from_array,
+            try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// CreateTopics request, versions 2 through 7.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct CreateTopicsRequestBody {
+    version: SupportCreateTopicsRequestVersion,
+    pub topics: Vec<CreatableTopic>,
+    timeout_ms: i32,
+    /// Check the request without creating anything.
+    pub validate_only: bool,
+}
+
+impl CreateTopicsRequestBody {
+    pub fn get_api_version(&self) -> SupportCreateTopicsRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for CreateTopicsRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportCreateTopicsRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let read_topic = |r: &mut R| CreatableTopic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        let timeout_ms = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("create_topics timeout_ms", correlation_id)
+        })?;
+        let validate_only = reader.read_u8().map_err(|_| {
+            RequestError::invalid_format("create_topics validate_only", correlation_id)
+        })? != 0;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("create_topics tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            topics,
+            timeout_ms,
+            validate_only,
+        })
+    }
+}
+
+#[allow(unused)]
+#[derive(Debug)]
+pub struct CreatableTopic {
+    pub name: String,
+    /// `-1` for the broker's `num.partitions`, or when `assignments` is set.
+    pub num_partitions: i32,
+    /// `-1` for the broker's default, or when `assignments` is set.
+    pub replication_factor: i16,
+    pub assignments: Vec<CreatableReplicaAssignment>,
+    /// Topic configs; accepted and ignored.
+    configs: Vec<(String, Option<String>)>,
+}
+
+impl CreatableTopic {
+    #[cfg(test)]
+    pub fn new(
+        name: &str,
+        num_partitions: i32,
+        replication_factor: i16,
+        assignments: Vec<CreatableReplicaAssignment>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            num_partitions,
+            replication_factor,
+            assignments,
+            configs: Vec::new(),
+        }
+    }
+}
+
+impl KafkaDeseriarize for CreatableTopic {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportCreateTopicsRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        let num_partitions = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("topic num_partitions", correlation_id))?;
+        let replication_factor = reader.read_i16::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("topic replication_factor", correlation_id)
+        })?;
+
+        let read_assignment =
+            |r: &mut R| CreatableReplicaAssignment::try_parse_from_reader(r, (header, version));
+        let assignments = if flexible {
+            try_read_vec_from_compact_array(reader, read_assignment)
+        } else {
+            try_read_vec_from_array(reader, read_assignment)
+        }
+        .map_err(|e| e.into_request_error("assignments length", correlation_id))?;
+
+        let read_config = |r: &mut R| {
+            let name = if flexible {
+                try_read_compact_string(r)
+            } else {
+                try_read_nullable_string(r)
+            }
+            .map_err(|_| RequestError::invalid_format("config name", correlation_id))?;
+            let value = if flexible {
+                try_read_optional_compact_string(r)
+            } else {
+                try_read_optional_string(r)
+            }
+            .map_err(|_| RequestError::invalid_format("config value", correlation_id))?;
+            if flexible {
+                let _ = try_read_tagged_fields(r).map_err(|_| {
+                    RequestError::invalid_format("config tagged_fields", correlation_id)
+                })?;
+            }
+            Ok::<_, RequestError>((name, value))
+        };
+        let configs = if flexible {
+            try_read_vec_from_compact_array(reader, read_config)
+        } else {
+            try_read_vec_from_array(reader, read_config)
+        }
+        .map_err(|e| e.into_request_error("configs length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self {
+            name,
+            num_partitions,
+            replication_factor,
+            assignments,
+            configs,
+        })
+    }
+}
+
+/// Replicas of one partition, chosen by the client.
+#[derive(Debug)]
+pub struct CreatableReplicaAssignment {
+    pub partition_index: i32,
+    pub broker_ids: Vec<i32>,
+}
+
+impl KafkaDeseriarize for CreatableReplicaAssignment {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportCreateTopicsRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let partition_index = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("assignment partition_index", correlation_id)
+        })?;
+        let read_broker_id = |r: &mut R| {
+            r.read_i32::<BigEndian>()
+                .map_err(|_| RequestError::invalid_format("assignment broker_ids", correlation_id))
+        };
+        let broker_ids = if flexible {
+            try_read_vec_from_compact_array(reader, read_broker_id)
+        } else {
+            try_read_vec_from_array(reader, read_broker_id)
+        }
+        .map_err(|e| e.into_request_error("broker_ids length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("assignment tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            partition_index,
+            broker_ids,
+        })
+    }
+}
@@ -0,0 +1,240 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::delete_topics::SupportDeleteTopicsRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_optional_compact_string,
+            try_read_tagged_fields, try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// DeleteTopics request, versions 1 through 6.
+///
+/// Versions 1-5 name topics by name only; version 6 by name or by id. Both
+/// are kept in `topics`.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct DeleteTopicsRequestBody {
+    version: SupportDeleteTopicsRequestVersion,
+    pub topics: Vec<DeleteTopicState>,
+    timeout_ms: i32,
+}
+
+impl DeleteTopicsRequestBody {
+    pub fn get_api_version(&self) -> SupportDeleteTopicsRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for DeleteTopicsRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportDeleteTopicsRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let topics = if version.uses_topic_ids() {
+            let read_topic =
+                |r: &mut R| DeleteTopicState::try_parse_from_reader(r, (header, version));
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            let read_name = |r: &mut R| {
+                if flexible {
+                    try_read_compact_string(r)
+                } else {
+                    try_read_nullable_string(r)
+                }
+                .map(|name| DeleteTopicState {
+                    name: Some(name),
+                    topic_id: [0; 16],
+                })
+                .map_err(|_| RequestError::invalid_format("topic_names", correlation_id))
+            };
+            if flexible {
+                try_read_vec_from_compact_array(reader, read_name)
+            } else {
+                try_read_vec_from_array(reader, read_name)
+            }
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        let timeout_ms = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("delete_topics timeout_ms", correlation_id)
+        })?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("delete_topics tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            topics,
+            timeout_ms,
+        })
+    }
+}
+
+/// A topic to delete, by name or, from version 6, by id.
+#[derive(Debug)]
+pub struct DeleteTopicState {
+    pub name: Option<String>,
+    /// All zeros when the topic is named.
+    pub topic_id: [u8; 16],
+}
+
+impl KafkaDeseriarize for DeleteTopicState {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportDeleteTopicsRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, _version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+
+        let name = try_read_optional_compact_string(reader)
+            .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+        let mut topic_id = [0u8; 16];
+        reader
+            .read_exact(&mut topic_id)
+            .map_err(|_| RequestError::invalid_format("topic topic_id", correlation_id))?;
+        let _ = try_read_tagged_fields(reader)
+            .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+
+        Ok(Self { name, topic_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    const TOPIC_ID: [u8; 16] = [7; 16];
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 4 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::DeleteTopics,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::DeleteTopics,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// A request deleting "foo" and, from v6, a second topic by id only,
+    /// laid out as `version` sends it.
+    fn encode(version: i16) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if version >= 6 {
+            buf.push(3);
+            buf.push(4);
+            buf.extend_from_slice(b"foo");
+            buf.extend_from_slice(&[0; 16]);
+            buf.push(0);
+            buf.push(0); // null name
+            buf.extend_from_slice(&TOPIC_ID);
+            buf.push(0);
+        } else if version >= 4 {
+            buf.push(2);
+            buf.push(4);
+            buf.extend_from_slice(b"foo");
+        } else {
+            buf.write_i32::<BigEndian>(1).unwrap();
+            buf.write_i16::<BigEndian>(3).unwrap();
+            buf.extend_from_slice(b"foo");
+        }
+        buf.write_i32::<BigEndian>(30_000).unwrap();
+        if version >= 4 {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16) -> DeleteTopicsRequestBody {
+        let buf = encode(version);
+        let mut reader = Cursor::new(&buf[..]);
+        let body =
+            DeleteTopicsRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v1: an array of names
+        let body = parse(1);
+        assert_eq!(body.topics.len(), 1);
+        assert_eq!(body.topics[0].name.as_deref(), Some("foo"));
+        assert_eq!(body.topics[0].topic_id, [0; 16]);
+        assert_eq!(body.timeout_ms, 30_000);
+
+        // v4: compact encoding and tagged fields
+        let body = parse(4);
+        assert_eq!(
+            body.get_api_version(),
+            SupportDeleteTopicsRequestVersion::V4
+        );
+        assert_eq!(body.topics[0].name.as_deref(), Some("foo"));
+        assert_eq!(body.timeout_ms, 30_000);
+
+        // v6 names topics by name or by id
+        let body = parse(6);
+        assert_eq!(body.topics.len(), 2);
+        assert_eq!(body.topics[0].name.as_deref(), Some("foo"));
+        assert_eq!(body.topics[0].topic_id, [0; 16]);
+        assert_eq!(body.topics[1].name, None);
+        assert_eq!(body.topics[1].topic_id, TOPIC_ID);
+    }
+
+    #[test]
+    fn test_unsupported_version_is_rejected() {
+        let buf = encode(1);
+        let mut reader = Cursor::new(&buf[..]);
+        assert!(DeleteTopicsRequestBody::try_parse_from_reader(&mut reader, &header(0)).is_err());
+    }
+}
@@ -13,17 +13,22 @@ pub enum DescribeTopicPartitionsRequestBody {
     V0(DescribeTopicPartitionsRequestBodyV0),
 }
 
-#[allow(unused)]
 #[derive(Debug)]
 pub struct DescribeTopicPartitionsRequestBodyV0 {
+    /// Empty to describe every topic.
     pub topics: Vec<String>,
+    /// Most partitions to return, across all topics.
     pub response_partition_limit: i32,
-    cursor: Cursor,
+    /// Where to resume a previous, truncated response.
+    pub cursor: Option<Cursor>,
 }
 
-#[allow(unused)]
-#[derive(Debug)]
-struct Cursor {}
+/// First partition to describe: the `next_cursor` of the previous response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub topic_name: String,
+    pub partition_index: i32,
+}
 
 impl KafkaDeseriarize for DescribeTopicPartitionsRequestBody {
     type Error = RequestError;
@@ -76,15 +81,31 @@ impl KafkaDeseriarize for DescribeTopicPartitionsRequestBodyV0 {
         let response_partition_limit = reader
             .read_i32::<BigEndian>()
             .map_err(|_| build_ill_format_error_helper("response_partition_limit"))?;
-        let _ = reader
+        // a nullable struct is prefixed with -1 when null, 1 otherwise
+        let cursor_present = reader
             .read_i8()
             .map_err(|_| build_ill_format_error_helper("cursor"))?;
+        let cursor = if cursor_present < 0 {
+            None
+        } else {
+            let topic_name = try_read_compact_string(reader)
+                .map_err(|_| build_ill_format_error_helper("cursor topic_name"))?;
+            let partition_index = reader
+                .read_i32::<BigEndian>()
+                .map_err(|_| build_ill_format_error_helper("cursor partition_index"))?;
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| build_ill_format_error_helper("cursor tagged fields"))?;
+            Some(Cursor {
+                topic_name,
+                partition_index,
+            })
+        };
         let _ = try_read_tagged_fields(reader)
             .map_err(|_| build_ill_format_error_helper("tagged fields"))?;
         Ok(Self {
             topics,
             response_partition_limit,
-            cursor: Cursor {},
+            cursor,
         })
     }
 }
@@ -7,14 +7,40 @@ use crate::{
     request::{
         error::RequestError,
         header::KafkaRequestHeader,
-        utils::{try_read_compact_string, try_read_tagged_fields, try_read_vec_from_compact_array},
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_tagged_fields,
+            try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
     },
     traits::KafkaDeseriarize,
 };
 
+/// Fetch request, versions 4 through 16.
+///
+/// Fields only grow across versions, so absent fields are filled with the
+/// defaults the broker assumes for older clients. Versions 12 and above use
+/// the flexible encoding; from version 13 topics are addressed by id.
+#[allow(unused)]
 #[derive(Debug)]
-pub enum FetchRequestBody {
-    V16(FetchRequestBodyV16),
+pub struct FetchRequestBody {
+    version: SupportFetchRequestVersion,
+    /// Removed in version 15, where it moved into the header's tagged fields.
+    replica_id: i32,
+    pub max_wait_ms: i32,
+    pub min_bytes: i32,
+    pub max_bytes: i32,
+    pub isolation_level: i8,
+    pub session_id: i32,
+    pub session_epoch: i32,
+    pub topics: Vec<Topic>,
+    pub forgotten_topics: Vec<ForgettenTopic>,
+    rack_id: String,
+}
+
+impl FetchRequestBody {
+    pub fn get_api_version(&self) -> SupportFetchRequestVersion {
+        self.version
+    }
 }
 
 impl KafkaDeseriarize for FetchRequestBody {
@@ -29,56 +55,24 @@ impl KafkaDeseriarize for FetchRequestBody {
     where
         Self: Sized,
     {
-        let version = header.request_api_version();
-        let version: SupportFetchRequestVersion = header
-            .request_api_version()
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportFetchRequestVersion = api_version
             .try_into()
-            .map_err(|_| RequestError::unsupported_version(version, header.correlation_id()))?;
-        let body = match version {
-            SupportFetchRequestVersion::V16 => {
-                FetchRequestBody::V16(FetchRequestBodyV16::try_parse_from_reader(reader, header)?)
-            }
-            _ => {
-                return Err(RequestError::unsupported_version(
-                    version.into(),
-                    header.correlation_id(),
-                ))
-            }
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let replica_id = if version < SupportFetchRequestVersion::V15 {
+            reader
+                .read_i32::<BigEndian>()
+                .map_err(|_| RequestError::invalid_format("fetch replica_id", correlation_id))?
+        } else {
+            -1
         };
-        Ok(body)
-    }
-}
-
-#[allow(unused)]
-#[derive(Debug)]
-pub struct FetchRequestBodyV16 {
-    ax_wait_ms: i32,
-    min_bytes: i32,
-    max_bytes: i32,
-    isolation_level: i8,
-    pub session_id: i32,
-    session_epoch: i32,
-    pub topics: Vec<Topic>,
-    forgotten_topics: Vec<ForgettenTopic>,
-    rack_id: String,
-}
-
-impl KafkaDeseriarize for FetchRequestBodyV16 {
-    type Error = RequestError;
-
-    type DependentData<'a> = &'a KafkaRequestHeader;
 
-    fn try_parse_from_reader<R: io::Read>(
-        reader: &mut R,
-        data: Self::DependentData<'_>,
-    ) -> Result<Self, Self::Error>
-    where
-        Self: Sized,
-    {
-        let correlation_id = data.correlation_id();
-        let ax_wait_ms = reader
+        let max_wait_ms = reader
             .read_i32::<BigEndian>()
-            .map_err(|_| RequestError::invalid_format("fetch ax_wait_ms", correlation_id))?;
+            .map_err(|_| RequestError::invalid_format("fetch max_wait_ms", correlation_id))?;
 
         let min_bytes = reader
             .read_i32::<BigEndian>()
@@ -92,30 +86,55 @@ impl KafkaDeseriarize for FetchRequestBodyV16 {
             .read_i8()
             .map_err(|_| RequestError::invalid_format("fetch isolation_level", correlation_id))?;
 
-        let session_id = reader
-            .read_i32::<BigEndian>()
-            .map_err(|_| RequestError::invalid_format("fetch session_id", correlation_id))?;
-
-        let session_epoch = reader
-            .read_i32::<BigEndian>()
-            .map_err(|_| RequestError::invalid_format("fetch session_epoch", correlation_id))?;
+        let (session_id, session_epoch) = if version >= SupportFetchRequestVersion::V7 {
+            let session_id = reader
+                .read_i32::<BigEndian>()
+                .map_err(|_| RequestError::invalid_format("fetch session_id", correlation_id))?;
+            let session_epoch = reader
+                .read_i32::<BigEndian>()
+                .map_err(|_| RequestError::invalid_format("fetch session_epoch", correlation_id))?;
+            (session_id, session_epoch)
+        } else {
+            (0, -1)
+        };
 
-        let topics =
-            try_read_vec_from_compact_array(reader, |r| Topic::try_parse_from_reader(r, data))
-                .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+        let read_topic = |r: &mut R| Topic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        let forgotten_topics = if version >= SupportFetchRequestVersion::V7 {
+            let read_forgotten =
+                |r: &mut R| ForgettenTopic::try_parse_from_reader(r, (header, version));
+            if flexible {
+                try_read_vec_from_compact_array(reader, read_forgotten)
+            } else {
+                try_read_vec_from_array(reader, read_forgotten)
+            }
+            .map_err(|e| e.into_request_error("forgotten_topics length", correlation_id))?
+        } else {
+            Vec::new()
+        };
 
-        let forgotten_topics = try_read_vec_from_compact_array(reader, |r| {
-            ForgettenTopic::try_parse_from_reader(r, data)
-        })
-        .map_err(|e| e.into_request_error("forgotten_topics length", correlation_id))?;
+        let rack_id = match version {
+            _ if flexible => try_read_compact_string(reader),
+            _ if version >= SupportFetchRequestVersion::V11 => try_read_nullable_string(reader),
+            _ => Ok(String::new()),
+        }
+        .map_err(|_| RequestError::invalid_format("rack_id", correlation_id))?;
 
-        let rack_id = try_read_compact_string(reader)
-            .map_err(|_| RequestError::invalid_format("rack_id", correlation_id))?;
-        let _ = try_read_tagged_fields(reader)
-            .map_err(|_| RequestError::invalid_format("fetch tagged_fields", correlation_id))?;
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("fetch tagged_fields", correlation_id))?;
+        }
 
         Ok(Self {
-            ax_wait_ms,
+            version,
+            replica_id,
+            max_wait_ms,
             min_bytes,
             max_bytes,
             isolation_level,
@@ -128,9 +147,29 @@ impl KafkaDeseriarize for FetchRequestBodyV16 {
     }
 }
 
+/// Read a topic reference: a name before version 13, an id from then on.
+fn try_read_topic<R: io::Read>(
+    reader: &mut R,
+    version: SupportFetchRequestVersion,
+) -> Result<(String, [u8; 16]), Box<dyn std::error::Error>> {
+    let mut topic_id = [0u8; 16];
+    let name = if version.uses_topic_ids() {
+        reader.read_exact(&mut topic_id)?;
+        String::new()
+    } else if version.is_flexible() {
+        try_read_compact_string(reader)?
+    } else {
+        try_read_nullable_string(reader)?
+    };
+    Ok((name, topic_id))
+}
+
 #[allow(unused)]
 #[derive(Debug)]
 pub struct Topic {
+    /// Topic name, only sent before version 13.
+    pub name: String,
+    /// Topic id, only sent from version 13.
     pub topic_id: [u8; 16],
     pub partitions: Vec<Partition>,
 }
@@ -138,29 +177,36 @@ pub struct Topic {
 impl KafkaDeseriarize for Topic {
     type Error = RequestError;
 
-    type DependentData<'a> = &'a KafkaRequestHeader;
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportFetchRequestVersion);
 
     fn try_parse_from_reader<R: io::Read>(
         reader: &mut R,
-        data: Self::DependentData<'_>,
+        (header, version): Self::DependentData<'_>,
     ) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
-        let correlation_id = data.correlation_id();
-        let mut topic_id = [0u8; 16];
-        reader
-            .read_exact(&mut topic_id)
-            .map_err(|_| RequestError::invalid_format("topic topic_id", correlation_id))?;
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let (name, topic_id) = try_read_topic(reader, version)
+            .map_err(|_| RequestError::invalid_format("topic", correlation_id))?;
 
-        let partitions =
-            try_read_vec_from_compact_array(reader, |r| Partition::try_parse_from_reader(r, data))
-                .map_err(|e| e.into_request_error("parition length", correlation_id))?;
+        let read_partition = |r: &mut R| Partition::try_parse_from_reader(r, (header, version));
+        let partitions = if flexible {
+            try_read_vec_from_compact_array(reader, read_partition)
+        } else {
+            try_read_vec_from_array(reader, read_partition)
+        }
+        .map_err(|e| e.into_request_error("parition length", correlation_id))?;
 
-        let _ = try_read_tagged_fields(reader)
-            .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
 
         Ok(Self {
+            name,
             topic_id,
             partitions,
         })
@@ -171,20 +217,20 @@ impl KafkaDeseriarize for Topic {
 #[derive(Debug)]
 pub struct Partition {
     pub index: i32,
-    current_leader_epoch: i32,
-    fetch_offset: i64,
-    last_fetched_epoch: i32,
+    pub current_leader_epoch: i32,
+    pub fetch_offset: i64,
+    pub last_fetched_epoch: i32,
     log_start_offset: i64,
-    partition_max_bytes: i32,
+    pub partition_max_bytes: i32,
 }
 
 impl KafkaDeseriarize for Partition {
     type Error = RequestError;
-    type DependentData<'a> = &'a KafkaRequestHeader;
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportFetchRequestVersion);
 
     fn try_parse_from_reader<R: std::io::Read>(
         reader: &mut R,
-        header: Self::DependentData<'_>,
+        (header, version): Self::DependentData<'_>,
     ) -> Result<Self, Self::Error>
     where
         Self: Sized,
@@ -193,23 +239,38 @@ impl KafkaDeseriarize for Partition {
         let index = reader
             .read_i32::<BigEndian>()
             .map_err(|_| RequestError::invalid_format("partition_id", correlation_id))?;
-        let current_leader_epoch = reader.read_i32::<BigEndian>().map_err(|_| {
-            RequestError::invalid_format("partition current leader epoch", correlation_id)
-        })?;
+        let current_leader_epoch = if version >= SupportFetchRequestVersion::V9 {
+            reader.read_i32::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("partition current leader epoch", correlation_id)
+            })?
+        } else {
+            -1
+        };
         let fetch_offset = reader
             .read_i64::<BigEndian>()
             .map_err(|_| RequestError::invalid_format("partition fetch_offset", correlation_id))?;
-        let last_fetched_epoch = reader.read_i32::<BigEndian>().map_err(|_| {
-            RequestError::invalid_format("partition last_fetched_epoch", correlation_id)
-        })?;
-        let log_start_offset = reader.read_i64::<BigEndian>().map_err(|_| {
-            RequestError::invalid_format("partition log_start_offset", correlation_id)
-        })?;
+        let last_fetched_epoch = if version >= SupportFetchRequestVersion::V12 {
+            reader.read_i32::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("partition last_fetched_epoch", correlation_id)
+            })?
+        } else {
+            -1
+        };
+        let log_start_offset = if version >= SupportFetchRequestVersion::V5 {
+            reader.read_i64::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("partition log_start_offset", correlation_id)
+            })?
+        } else {
+            -1
+        };
         let partition_max_bytes = reader.read_i32::<BigEndian>().map_err(|_| {
             RequestError::invalid_format("partition partition_max_bytes", correlation_id)
         })?;
-        let _ = try_read_tagged_fields(reader)
-            .map_err(|_| RequestError::invalid_format("partition tagged_fields", correlation_id))?;
+        if version.is_flexible() {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("partition tagged_fields", correlation_id)
+            })?;
+        }
         Ok(Self {
             index,
             current_leader_epoch,
@@ -224,37 +285,208 @@ impl KafkaDeseriarize for Partition {
 #[allow(unused)]
 #[derive(Debug)]
 pub struct ForgettenTopic {
-    topic_id: [u8; 16],
-    partitions: Vec<i32>,
+    pub name: String,
+    pub topic_id: [u8; 16],
+    pub partitions: Vec<i32>,
 }
 
 impl KafkaDeseriarize for ForgettenTopic {
     type Error = RequestError;
 
-    type DependentData<'a> = &'a KafkaRequestHeader;
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportFetchRequestVersion);
 
     fn try_parse_from_reader<R: io::Read>(
         reader: &mut R,
-        data: Self::DependentData<'_>,
+        (header, version): Self::DependentData<'_>,
     ) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
-        let correlation_id = data.correlation_id();
-        let mut topic_id = [0u8; 16];
-        reader.read_exact(&mut topic_id).map_err(|_| {
-            RequestError::invalid_format("forgotten topic topic_id", correlation_id)
-        })?;
-        let partitions = try_read_vec_from_compact_array(reader, |r| r.read_i32::<BigEndian>())
-            .map_err(|_| {
-                RequestError::invalid_format("forgotten topic topic_id", correlation_id)
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+        let (name, topic_id) = try_read_topic(reader, version)
+            .map_err(|_| RequestError::invalid_format("forgotten topic", correlation_id))?;
+        let read_partition = |r: &mut R| r.read_i32::<BigEndian>();
+        let partitions = if flexible {
+            try_read_vec_from_compact_array(reader, read_partition)
+        } else {
+            try_read_vec_from_array(reader, read_partition)
+        }
+        .map_err(|_| RequestError::invalid_format("forgotten topic partitions", correlation_id))?;
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("forgotten topic tagged_fields", correlation_id)
             })?;
-        let _ = try_read_tagged_fields(reader).map_err(|_| {
-            RequestError::invalid_format("forgotten topic tagged_fields", correlation_id)
-        })?;
+        }
         Ok(Self {
+            name,
             topic_id,
             partitions,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    const TOPIC_ID: [u8; 16] = [7; 16];
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 12 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::Fetch,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::Fetch,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// A fetch of partition 0 of topic "foo", laid out as `version` sends it.
+    fn encode(version: i16) -> Vec<u8> {
+        let flexible = version >= 12;
+        let mut buf = Vec::new();
+        let write_len = |buf: &mut Vec<u8>, len: usize| {
+            if flexible {
+                buf.push(len as u8 + 1);
+            } else {
+                buf.write_i32::<BigEndian>(len as i32).unwrap();
+            }
+        };
+        let write_string = |buf: &mut Vec<u8>, s: &str| {
+            if flexible {
+                buf.push(s.len() as u8 + 1);
+            } else {
+                buf.write_i16::<BigEndian>(s.len() as i16).unwrap();
+            }
+            buf.extend_from_slice(s.as_bytes());
+        };
+
+        if version < 15 {
+            buf.write_i32::<BigEndian>(5).unwrap();
+        }
+        buf.write_i32::<BigEndian>(500).unwrap();
+        buf.write_i32::<BigEndian>(1).unwrap();
+        buf.write_i32::<BigEndian>(1024).unwrap();
+        buf.write_i8(1).unwrap();
+        if version >= 7 {
+            buf.write_i32::<BigEndian>(9).unwrap();
+            buf.write_i32::<BigEndian>(2).unwrap();
+        }
+
+        write_len(&mut buf, 1);
+        if version >= 13 {
+            buf.extend_from_slice(&TOPIC_ID);
+        } else {
+            write_string(&mut buf, "foo");
+        }
+        write_len(&mut buf, 1);
+        buf.write_i32::<BigEndian>(0).unwrap();
+        if version >= 9 {
+            buf.write_i32::<BigEndian>(4).unwrap();
+        }
+        buf.write_i64::<BigEndian>(42).unwrap();
+        if version >= 12 {
+            buf.write_i32::<BigEndian>(3).unwrap();
+        }
+        if version >= 5 {
+            buf.write_i64::<BigEndian>(10).unwrap();
+        }
+        buf.write_i32::<BigEndian>(2048).unwrap();
+        if flexible {
+            buf.push(0); // partition tagged fields
+            buf.push(0); // topic tagged fields
+        }
+
+        if version >= 7 {
+            write_len(&mut buf, 0);
+        }
+        if version >= 11 {
+            write_string(&mut buf, "");
+        }
+        if flexible {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16) -> FetchRequestBody {
+        let buf = encode(version);
+        let mut reader = Cursor::new(&buf[..]);
+        let body = FetchRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v4: no sessions, leader epochs or log start offset
+        let body = parse(4);
+        assert_eq!(body.replica_id, 5);
+        assert_eq!((body.session_id, body.session_epoch), (0, -1));
+        assert_eq!(body.topics[0].name, "foo");
+        let partition = &body.topics[0].partitions[0];
+        assert_eq!(partition.current_leader_epoch, -1);
+        assert_eq!(partition.fetch_offset, 42);
+        assert_eq!(partition.last_fetched_epoch, -1);
+        assert_eq!(partition.log_start_offset, -1);
+        assert_eq!(partition.partition_max_bytes, 2048);
+
+        // v5 adds the log start offset
+        assert_eq!(parse(5).topics[0].partitions[0].log_start_offset, 10);
+
+        // v12: flexible, still addressed by name
+        let body = parse(12);
+        assert_eq!(body.replica_id, 5);
+        assert_eq!((body.session_id, body.session_epoch), (9, 2));
+        assert_eq!(body.topics[0].name, "foo");
+        assert_eq!(body.topics[0].topic_id, [0; 16]);
+        let partition = &body.topics[0].partitions[0];
+        assert_eq!(partition.current_leader_epoch, 4);
+        assert_eq!(partition.last_fetched_epoch, 3);
+        assert_eq!(partition.log_start_offset, 10);
+
+        // v13: addressed by id
+        let body = parse(13);
+        assert_eq!(body.replica_id, 5);
+        assert_eq!(body.topics[0].name, "");
+        assert_eq!(body.topics[0].topic_id, TOPIC_ID);
+        assert_eq!(body.topics[0].partitions[0].fetch_offset, 42);
+
+        // v15: replica_id moved out of the body
+        let body = parse(15);
+        assert_eq!(body.replica_id, -1);
+        assert_eq!(body.max_wait_ms, 500);
+        assert_eq!(body.isolation_level, 1);
+        assert_eq!(body.topics[0].topic_id, TOPIC_ID);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_partition() {
+        let mut buf = encode(12);
+        buf.truncate(buf.len() - 10);
+        let result =
+            FetchRequestBody::try_parse_from_reader(&mut Cursor::new(&buf[..]), &header(12));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::io;
+
+use byteorder::ReadBytesExt;
+
+use crate::{
+    consts::find_coordinator::{SupportFindCoordinatorRequestVersion, GROUP_KEY_TYPE},
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_tagged_fields,
+            try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// FindCoordinator request, versions 0 through 4.
+///
+/// Versions 0-3 look up a single `key`, version 4 a batch of
+/// `coordinator_keys`; both are kept in `keys`. `key_type` arrives in
+/// version 1, older clients only look up groups.
+#[derive(Debug)]
+pub struct FindCoordinatorRequestBody {
+    version: SupportFindCoordinatorRequestVersion,
+    pub key_type: i8,
+    pub keys: Vec<String>,
+}
+
+impl FindCoordinatorRequestBody {
+    pub fn get_api_version(&self) -> SupportFindCoordinatorRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for FindCoordinatorRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportFindCoordinatorRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let key = if version.is_batched() {
+            None
+        } else if flexible {
+            Some(try_read_compact_string(reader))
+        } else {
+            Some(try_read_nullable_string(reader))
+        }
+        .transpose()
+        .map_err(|_| RequestError::invalid_format("find_coordinator key", correlation_id))?;
+
+        let key_type = if version >= SupportFindCoordinatorRequestVersion::V1 {
+            reader.read_i8().map_err(|_| {
+                RequestError::invalid_format("find_coordinator key_type", correlation_id)
+            })?
+        } else {
+            GROUP_KEY_TYPE
+        };
+
+        let keys = match key {
+            Some(key) => vec![key],
+            None => try_read_vec_from_compact_array(reader, |r| {
+                try_read_compact_string(r).map_err(|_| {
+                    RequestError::invalid_format(
+                        "find_coordinator coordinator_keys",
+                        correlation_id,
+                    )
+                })
+            })
+            .map_err(|e| e.into_request_error("coordinator_keys length", correlation_id))?,
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("find_coordinator tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            key_type,
+            keys,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        consts::find_coordinator::TRANSACTION_KEY_TYPE,
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+        },
+    };
+
+    fn parse(version: i16, buf: &[u8]) -> FindCoordinatorRequestBody {
+        let header = if version >= 3 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::FindCoordinator,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::FindCoordinator,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        };
+        let mut reader = Cursor::new(buf);
+        let body = FindCoordinatorRequestBody::try_parse_from_reader(&mut reader, &header).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v0: a group key only
+        let body = parse(0, &[&1i16.to_be_bytes()[..], b"g"].concat());
+        assert_eq!(body.key_type, GROUP_KEY_TYPE);
+        assert_eq!(body.keys, ["g"]);
+
+        // v1 adds the key type
+        let body = parse(1, &[&1i16.to_be_bytes()[..], b"t", &[1]].concat());
+        assert_eq!(body.key_type, TRANSACTION_KEY_TYPE);
+        assert_eq!(body.keys, ["t"]);
+
+        // v3: compact encoding and tagged fields
+        let body = parse(3, &[&[2][..], b"g", &[0, 0]].concat());
+        assert_eq!(
+            body.get_api_version(),
+            SupportFindCoordinatorRequestVersion::V3
+        );
+        assert_eq!(body.keys, ["g"]);
+
+        // v4: a batch of keys after the key type
+        let body = parse(4, &[&[0][..], &[3, 2], b"a", &[2], b"b", &[0]].concat());
+        assert_eq!(body.key_type, GROUP_KEY_TYPE);
+        assert_eq!(body.keys, ["a", "b"]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::heartbeat::SupportHeartbeatRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_optional_compact_string,
+            try_read_optional_string, try_read_tagged_fields,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// Heartbeat request, versions 0 through 4. `group_instance_id` arrives in
+/// version 3.
+#[derive(Debug)]
+pub struct HeartbeatRequestBody {
+    version: SupportHeartbeatRequestVersion,
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+}
+
+impl HeartbeatRequestBody {
+    pub fn get_api_version(&self) -> SupportHeartbeatRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for HeartbeatRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportHeartbeatRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let group_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("heartbeat group_id", correlation_id))?;
+
+        let generation_id = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("heartbeat generation_id", correlation_id))?;
+
+        let member_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("heartbeat member_id", correlation_id))?;
+
+        let group_instance_id = if version < SupportHeartbeatRequestVersion::V3 {
+            Ok(None)
+        } else if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("heartbeat group_instance_id", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("heartbeat tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            group_id,
+            generation_id,
+            member_id,
+            group_instance_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn parse(version: i16, buf: &[u8]) -> HeartbeatRequestBody {
+        let header = if version >= 4 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::Heartbeat,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::Heartbeat,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        };
+        let mut reader = Cursor::new(buf);
+        let body = HeartbeatRequestBody::try_parse_from_reader(&mut reader, &header).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v0: group, generation and member
+        let classic = [
+            &1i16.to_be_bytes()[..],
+            b"g",
+            &3i32.to_be_bytes(),
+            &1i16.to_be_bytes(),
+            b"m",
+        ]
+        .concat();
+        let body = parse(0, &classic);
+        assert_eq!(body.group_id, "g");
+        assert_eq!(body.generation_id, 3);
+        assert_eq!(body.member_id, "m");
+        assert_eq!(body.group_instance_id, None);
+
+        // v3 adds the group instance id
+        let body = parse(3, &[&classic[..], &1i16.to_be_bytes(), b"i"].concat());
+        assert_eq!(body.group_instance_id.as_deref(), Some("i"));
+
+        // v4: compact encoding and tagged fields
+        let flexible = [&[2][..], b"g", &3i32.to_be_bytes(), &[2], b"m", &[0, 0]].concat();
+        let body = parse(4, &flexible);
+        assert_eq!(body.get_api_version(), SupportHeartbeatRequestVersion::V4);
+        assert_eq!(body.member_id, "m");
+        assert_eq!(body.group_instance_id, None);
+    }
+}
@@ -0,0 +1,183 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::init_producer_id::SupportInitProducerIdRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_optional_compact_string, try_read_optional_string, try_read_tagged_fields,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// InitProducerId request, versions 0 through 5. The `producer_id` and
+/// `producer_epoch` of a producer bumping its epoch arrive in version 3.
+#[derive(Debug)]
+pub struct InitProducerIdRequestBody {
+    version: SupportInitProducerIdRequestVersion,
+    pub transactional_id: Option<String>,
+    pub transaction_timeout_ms: i32,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+}
+
+impl InitProducerIdRequestBody {
+    pub fn get_api_version(&self) -> SupportInitProducerIdRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for InitProducerIdRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportInitProducerIdRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let transactional_id = if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| {
+            RequestError::invalid_format("init_producer_id transactional_id", correlation_id)
+        })?;
+
+        let transaction_timeout_ms = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("init_producer_id transaction_timeout_ms", correlation_id)
+        })?;
+
+        let (producer_id, producer_epoch) = if version >= SupportInitProducerIdRequestVersion::V3 {
+            let producer_id = reader.read_i64::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("init_producer_id producer_id", correlation_id)
+            })?;
+            let producer_epoch = reader.read_i16::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("init_producer_id producer_epoch", correlation_id)
+            })?;
+            (producer_id, producer_epoch)
+        } else {
+            (-1, -1)
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("init_producer_id tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            transactional_id,
+            transaction_timeout_ms,
+            producer_id,
+            producer_epoch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 2 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::InitProducerId,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::InitProducerId,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// A request for `transactional_id` from producer 42 at epoch 3, laid out
+    /// as `version` sends it.
+    fn encode(version: i16, transactional_id: Option<&str>) -> Vec<u8> {
+        let flexible = version >= 2;
+        let mut buf = Vec::new();
+        match (transactional_id, flexible) {
+            (None, true) => buf.push(0),
+            (None, false) => buf.write_i16::<BigEndian>(-1).unwrap(),
+            (Some(id), true) => {
+                buf.push(id.len() as u8 + 1);
+                buf.extend_from_slice(id.as_bytes());
+            }
+            (Some(id), false) => {
+                buf.write_i16::<BigEndian>(id.len() as i16).unwrap();
+                buf.extend_from_slice(id.as_bytes());
+            }
+        }
+        buf.write_i32::<BigEndian>(60_000).unwrap();
+        if version >= 3 {
+            buf.write_i64::<BigEndian>(42).unwrap();
+            buf.write_i16::<BigEndian>(3).unwrap();
+        }
+        if flexible {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16, transactional_id: Option<&str>) -> InitProducerIdRequestBody {
+        let buf = encode(version, transactional_id);
+        let mut reader = Cursor::new(&buf[..]);
+        let body = InitProducerIdRequestBody::try_parse_from_reader(&mut reader, &header(version))
+            .unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        for version in 0..=5 {
+            let body = parse(version, None);
+            assert_eq!(body.get_api_version() as i16, version);
+            assert_eq!(body.transactional_id, None, "v{version}");
+            assert_eq!(body.transaction_timeout_ms, 60_000);
+            if version >= 3 {
+                // v3 adds the producer id and epoch to bump
+                assert_eq!((body.producer_id, body.producer_epoch), (42, 3));
+            } else {
+                assert_eq!((body.producer_id, body.producer_epoch), (-1, -1));
+            }
+
+            let body = parse(version, Some("txn"));
+            assert_eq!(body.transactional_id.as_deref(), Some("txn"), "v{version}");
+        }
+    }
+}
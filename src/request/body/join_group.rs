@@ -0,0 +1,309 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::join_group::SupportJoinGroupRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_nullable_bytes, try_read_compact_string, try_read_nullable_bytes,
+            try_read_nullable_string, try_read_optional_compact_string, try_read_optional_string,
+            try_read_tagged_fields, try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// JoinGroup request, versions 0 through 9.
+///
+/// `rebalance_timeout_ms` arrives in version 1 (older clients use the session
+/// timeout for both), `group_instance_id` in version 5 and `reason` in
+/// version 8.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct JoinGroupRequestBody {
+    version: SupportJoinGroupRequestVersion,
+    /// From the request header; prefixes the member ids handed out.
+    pub client_id: String,
+    pub group_id: String,
+    pub session_timeout_ms: i32,
+    pub rebalance_timeout_ms: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub protocol_type: String,
+    pub protocols: Vec<JoinGroupRequestProtocol>,
+    reason: Option<String>,
+}
+
+impl JoinGroupRequestBody {
+    pub fn get_api_version(&self) -> SupportJoinGroupRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for JoinGroupRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportJoinGroupRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let group_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("join_group group_id", correlation_id))?;
+
+        let session_timeout_ms = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("join_group session_timeout_ms", correlation_id)
+        })?;
+        let rebalance_timeout_ms = if version >= SupportJoinGroupRequestVersion::V1 {
+            reader.read_i32::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("join_group rebalance_timeout_ms", correlation_id)
+            })?
+        } else {
+            session_timeout_ms
+        };
+
+        let member_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("join_group member_id", correlation_id))?;
+
+        let group_instance_id = if version < SupportJoinGroupRequestVersion::V5 {
+            Ok(None)
+        } else if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| {
+            RequestError::invalid_format("join_group group_instance_id", correlation_id)
+        })?;
+
+        let protocol_type = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("join_group protocol_type", correlation_id))?;
+
+        let read_protocol =
+            |r: &mut R| JoinGroupRequestProtocol::try_parse_from_reader(r, (header, version));
+        let protocols = if flexible {
+            try_read_vec_from_compact_array(reader, read_protocol)
+        } else {
+            try_read_vec_from_array(reader, read_protocol)
+        }
+        .map_err(|e| e.into_request_error("protocols length", correlation_id))?;
+
+        let reason = if version >= SupportJoinGroupRequestVersion::V8 {
+            try_read_optional_compact_string(reader)
+                .map_err(|_| RequestError::invalid_format("join_group reason", correlation_id))?
+        } else {
+            None
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("join_group tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            client_id: header.client_id().unwrap_or_default().to_string(),
+            group_id,
+            session_timeout_ms,
+            rebalance_timeout_ms,
+            member_id,
+            group_instance_id,
+            protocol_type,
+            protocols,
+            reason,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct JoinGroupRequestProtocol {
+    pub name: String,
+    pub metadata: Vec<u8>,
+}
+
+impl KafkaDeseriarize for JoinGroupRequestProtocol {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportJoinGroupRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("protocol name", correlation_id))?;
+
+        let metadata = if flexible {
+            try_read_compact_nullable_bytes(reader)
+        } else {
+            try_read_nullable_bytes(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("protocol metadata", correlation_id))?
+        .unwrap_or_default();
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("protocol tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self { name, metadata })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 6 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::JoinGroup,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: "client".to_string(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::JoinGroup,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: "client".to_string(),
+            })
+        }
+    }
+
+    /// A first join of group "g" with the range protocol, laid out as
+    /// `version` sends it.
+    fn encode(version: i16) -> Vec<u8> {
+        let flexible = version >= 6;
+        let mut buf = Vec::new();
+        let write_string = |buf: &mut Vec<u8>, s: &str| {
+            if flexible {
+                buf.push(s.len() as u8 + 1);
+            } else {
+                buf.write_i16::<BigEndian>(s.len() as i16).unwrap();
+            }
+            buf.extend_from_slice(s.as_bytes());
+        };
+
+        write_string(&mut buf, "g");
+        buf.write_i32::<BigEndian>(3000).unwrap();
+        if version >= 1 {
+            buf.write_i32::<BigEndian>(6000).unwrap();
+        }
+        write_string(&mut buf, "");
+        if version >= 5 {
+            write_string(&mut buf, "i");
+        }
+        write_string(&mut buf, "consumer");
+        if flexible {
+            buf.push(2);
+        } else {
+            buf.write_i32::<BigEndian>(1).unwrap();
+        }
+        write_string(&mut buf, "range");
+        if flexible {
+            buf.extend_from_slice(&[3, 1, 2, 0]);
+        } else {
+            buf.write_i32::<BigEndian>(2).unwrap();
+            buf.extend_from_slice(&[1, 2]);
+        }
+        if version >= 8 {
+            write_string(&mut buf, "r");
+        }
+        if flexible {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16) -> JoinGroupRequestBody {
+        let buf = encode(version);
+        let mut reader = Cursor::new(&buf[..]);
+        let body =
+            JoinGroupRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v0: the session timeout doubles as the rebalance timeout
+        let body = parse(0);
+        assert_eq!(body.client_id, "client");
+        assert_eq!(body.group_id, "g");
+        assert_eq!(body.session_timeout_ms, 3000);
+        assert_eq!(body.rebalance_timeout_ms, 3000);
+        assert_eq!(body.member_id, "");
+        assert_eq!(body.group_instance_id, None);
+        assert_eq!(body.protocol_type, "consumer");
+        assert_eq!(body.protocols[0].name, "range");
+        assert_eq!(body.protocols[0].metadata, [1, 2]);
+
+        // v1 adds the rebalance timeout
+        assert_eq!(parse(1).rebalance_timeout_ms, 6000);
+
+        // v5 adds the group instance id
+        assert_eq!(parse(5).group_instance_id.as_deref(), Some("i"));
+
+        // v6: compact encoding and tagged fields
+        let body = parse(6);
+        assert_eq!(body.get_api_version(), SupportJoinGroupRequestVersion::V6);
+        assert_eq!(body.group_instance_id.as_deref(), Some("i"));
+        assert_eq!(body.protocols[0].metadata, [1, 2]);
+        assert_eq!(body.reason, None);
+
+        // v8 adds the reason
+        assert_eq!(parse(8).reason.as_deref(), Some("r"));
+    }
+}
@@ -0,0 +1,230 @@
+use std::io;
+
+use crate::{
+    consts::leave_group::SupportLeaveGroupRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_optional_compact_string,
+            try_read_optional_string, try_read_tagged_fields, try_read_vec_from_array,
+            try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// LeaveGroup request, versions 0 through 5.
+///
+/// Versions 0-2 carry a single `member_id`, versions 3 and above a batch of
+/// members; both are kept in `members`.
+#[derive(Debug)]
+pub struct LeaveGroupRequestBody {
+    version: SupportLeaveGroupRequestVersion,
+    pub group_id: String,
+    pub members: Vec<LeaveGroupRequestMember>,
+}
+
+impl LeaveGroupRequestBody {
+    pub fn get_api_version(&self) -> SupportLeaveGroupRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for LeaveGroupRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportLeaveGroupRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let group_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("leave_group group_id", correlation_id))?;
+
+        let members = if version.is_batched() {
+            let read_member =
+                |r: &mut R| LeaveGroupRequestMember::try_parse_from_reader(r, (header, version));
+            if flexible {
+                try_read_vec_from_compact_array(reader, read_member)
+            } else {
+                try_read_vec_from_array(reader, read_member)
+            }
+            .map_err(|e| e.into_request_error("members length", correlation_id))?
+        } else {
+            let member_id = try_read_nullable_string(reader).map_err(|_| {
+                RequestError::invalid_format("leave_group member_id", correlation_id)
+            })?;
+            vec![LeaveGroupRequestMember {
+                member_id,
+                group_instance_id: None,
+                reason: None,
+            }]
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("leave_group tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            group_id,
+            members,
+        })
+    }
+}
+
+#[allow(unused)]
+#[derive(Debug)]
+pub struct LeaveGroupRequestMember {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    reason: Option<String>,
+}
+
+impl KafkaDeseriarize for LeaveGroupRequestMember {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportLeaveGroupRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let member_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("member member_id", correlation_id))?;
+
+        let group_instance_id = if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("member group_instance_id", correlation_id))?;
+
+        let reason = if version >= SupportLeaveGroupRequestVersion::V5 {
+            try_read_optional_compact_string(reader)
+                .map_err(|_| RequestError::invalid_format("member reason", correlation_id))?
+        } else {
+            None
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("member tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            member_id,
+            group_instance_id,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn parse(version: i16, buf: &[u8]) -> LeaveGroupRequestBody {
+        let header = if version >= 4 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::LeaveGroup,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::LeaveGroup,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        };
+        let mut reader = Cursor::new(buf);
+        let body = LeaveGroupRequestBody::try_parse_from_reader(&mut reader, &header).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v0: a single member id
+        let buf = [&1i16.to_be_bytes()[..], b"g", &1i16.to_be_bytes(), b"m"].concat();
+        let body = parse(0, &buf);
+        assert_eq!(body.group_id, "g");
+        assert_eq!(body.members.len(), 1);
+        assert_eq!(body.members[0].member_id, "m");
+        assert_eq!(body.members[0].group_instance_id, None);
+
+        // v3: a batch of members, each with a group instance id
+        let buf = [
+            &1i16.to_be_bytes()[..],
+            b"g",
+            &2i32.to_be_bytes(),
+            &1i16.to_be_bytes(),
+            b"m",
+            &(-1i16).to_be_bytes(),
+            &0i16.to_be_bytes(),
+            &1i16.to_be_bytes(),
+            b"i",
+        ]
+        .concat();
+        let body = parse(3, &buf);
+        assert_eq!(body.members.len(), 2);
+        assert_eq!(body.members[0].member_id, "m");
+        assert_eq!(body.members[0].group_instance_id, None);
+        assert_eq!(body.members[1].member_id, "");
+        assert_eq!(body.members[1].group_instance_id.as_deref(), Some("i"));
+
+        // v4: compact encoding and tagged fields
+        let buf = [&[2][..], b"g", &[2, 2], b"m", &[0, 0, 0]].concat();
+        let body = parse(4, &buf);
+        assert_eq!(body.get_api_version(), SupportLeaveGroupRequestVersion::V4);
+        assert_eq!(body.members[0].member_id, "m");
+        assert_eq!(body.members[0].reason, None);
+
+        // v5 adds a reason per member
+        let buf = [&[2][..], b"g", &[2, 2], b"m", &[0, 2], b"r", &[0, 0]].concat();
+        let body = parse(5, &buf);
+        assert_eq!(body.members[0].reason.as_deref(), Some("r"));
+    }
+}
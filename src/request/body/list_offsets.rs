@@ -0,0 +1,305 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::list_offsets::SupportListOffsetsRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_tagged_fields,
+            try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// ListOffsets request, versions 1 through 8.
+///
+/// `isolation_level` arrives in version 2 and `current_leader_epoch` in
+/// version 4; older clients get `read_uncommitted` and no epoch check.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct ListOffsetsRequestBody {
+    version: SupportListOffsetsRequestVersion,
+    replica_id: i32,
+    pub isolation_level: i8,
+    pub topics: Vec<ListOffsetsTopic>,
+}
+
+impl ListOffsetsRequestBody {
+    pub fn get_api_version(&self) -> SupportListOffsetsRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for ListOffsetsRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportListOffsetsRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let replica_id = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("list_offsets replica_id", correlation_id))?;
+
+        let isolation_level = if version >= SupportListOffsetsRequestVersion::V2 {
+            reader.read_i8().map_err(|_| {
+                RequestError::invalid_format("list_offsets isolation_level", correlation_id)
+            })?
+        } else {
+            0
+        };
+
+        let read_topic = |r: &mut R| ListOffsetsTopic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("list_offsets tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            replica_id,
+            isolation_level,
+            topics,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ListOffsetsTopic {
+    pub name: String,
+    pub partitions: Vec<ListOffsetsPartition>,
+}
+
+impl KafkaDeseriarize for ListOffsetsTopic {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportListOffsetsRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        let read_partition =
+            |r: &mut R| ListOffsetsPartition::try_parse_from_reader(r, (header, version));
+        let partitions = if flexible {
+            try_read_vec_from_compact_array(reader, read_partition)
+        } else {
+            try_read_vec_from_array(reader, read_partition)
+        }
+        .map_err(|e| e.into_request_error("partitions length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self { name, partitions })
+    }
+}
+
+#[derive(Debug)]
+pub struct ListOffsetsPartition {
+    pub partition_index: i32,
+    /// `-1` when the client does not know the leader epoch.
+    pub current_leader_epoch: i32,
+    /// A timestamp in milliseconds, or one of the special values in
+    /// [`crate::consts::list_offsets`].
+    pub timestamp: i64,
+}
+
+impl KafkaDeseriarize for ListOffsetsPartition {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportListOffsetsRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+
+        let partition_index = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("partition_index", correlation_id))?;
+        let current_leader_epoch = if version >= SupportListOffsetsRequestVersion::V4 {
+            reader.read_i32::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("partition current_leader_epoch", correlation_id)
+            })?
+        } else {
+            -1
+        };
+        let timestamp = reader
+            .read_i64::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("partition timestamp", correlation_id))?;
+
+        if version.is_flexible() {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("partition tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            partition_index,
+            current_leader_epoch,
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 6 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::ListOffsets,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::ListOffsets,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// A lookup of partition 3 of topic "foo", laid out as `version` sends it.
+    fn encode(version: i16) -> Vec<u8> {
+        let flexible = version >= 6;
+        let mut buf = Vec::new();
+        buf.write_i32::<BigEndian>(-1).unwrap();
+        if version >= 2 {
+            buf.write_i8(1).unwrap();
+        }
+        if flexible {
+            buf.extend_from_slice(&[2, 4]);
+        } else {
+            buf.write_i32::<BigEndian>(1).unwrap();
+            buf.write_i16::<BigEndian>(3).unwrap();
+        }
+        buf.extend_from_slice(b"foo");
+        if flexible {
+            buf.push(2);
+        } else {
+            buf.write_i32::<BigEndian>(1).unwrap();
+        }
+        buf.write_i32::<BigEndian>(3).unwrap();
+        if version >= 4 {
+            buf.write_i32::<BigEndian>(5).unwrap();
+        }
+        buf.write_i64::<BigEndian>(-2).unwrap();
+        if flexible {
+            buf.push(0); // partition tagged fields
+            buf.push(0); // topic tagged fields
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16) -> ListOffsetsRequestBody {
+        let buf = encode(version);
+        let mut reader = Cursor::new(&buf[..]);
+        let body =
+            ListOffsetsRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v1: no isolation level or leader epoch
+        let body = parse(1);
+        assert_eq!(body.replica_id, -1);
+        assert_eq!(body.isolation_level, 0);
+        assert_eq!(body.topics[0].name, "foo");
+        let partition = &body.topics[0].partitions[0];
+        assert_eq!(partition.partition_index, 3);
+        assert_eq!(partition.current_leader_epoch, -1);
+        assert_eq!(partition.timestamp, -2);
+
+        // v2 adds the isolation level
+        let body = parse(2);
+        assert_eq!(body.isolation_level, 1);
+        assert_eq!(body.topics[0].partitions[0].current_leader_epoch, -1);
+
+        // v4 adds the current leader epoch
+        let body = parse(4);
+        assert_eq!(body.topics[0].partitions[0].current_leader_epoch, 5);
+        assert_eq!(body.topics[0].partitions[0].timestamp, -2);
+
+        // v6: compact encoding and tagged fields
+        let body = parse(6);
+        assert_eq!(body.get_api_version(), SupportListOffsetsRequestVersion::V6);
+        assert_eq!(body.isolation_level, 1);
+        assert_eq!(body.topics[0].name, "foo");
+        let partition = &body.topics[0].partitions[0];
+        assert_eq!(partition.partition_index, 3);
+        assert_eq!(partition.current_leader_epoch, 5);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_partition() {
+        let mut buf = encode(6);
+        buf.truncate(buf.len() - 6);
+        let result =
+            ListOffsetsRequestBody::try_parse_from_reader(&mut Cursor::new(&buf[..]), &header(6));
+        assert!(result.is_err());
+    }
+}
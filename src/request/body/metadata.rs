@@ -0,0 +1,263 @@
+use std::io;
+
+use byteorder::ReadBytesExt;
+
+use crate::{
+    consts::metadata::SupportMetadataRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_nullable_vec_from_array, try_read_nullable_vec_from_compact_array,
+            try_read_optional_compact_string, try_read_optional_string, try_read_tagged_fields,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// Metadata request, versions 0 through 12.
+///
+/// Versions 9 and above use the flexible encoding; from version 10 topics may
+/// also be looked up by id.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct MetadataRequestBody {
+    version: SupportMetadataRequestVersion,
+    /// `None` asks for every topic. Version 0 has no null array and sends an
+    /// empty one instead.
+    pub topics: Option<Vec<MetadataRequestTopic>>,
+    /// Absent before version 4, where it was implicitly true.
+    pub allow_auto_topic_creation: bool,
+    include_cluster_authorized_operations: bool,
+    pub include_topic_authorized_operations: bool,
+}
+
+impl MetadataRequestBody {
+    pub fn get_api_version(&self) -> SupportMetadataRequestVersion {
+        self.version
+    }
+
+    pub fn include_cluster_authorized_operations(&self) -> bool {
+        self.include_cluster_authorized_operations
+    }
+}
+
+impl KafkaDeseriarize for MetadataRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportMetadataRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let read_topic =
+            |r: &mut R| MetadataRequestTopic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_nullable_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_nullable_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("metadata topics length", correlation_id))?;
+        let topics = match topics {
+            Some(topics) if topics.is_empty() && version == SupportMetadataRequestVersion::V0 => {
+                None
+            }
+            topics => topics,
+        };
+
+        let read_bool = |reader: &mut R, field: &'static str| {
+            reader
+                .read_u8()
+                .map(|b| b != 0)
+                .map_err(|_| RequestError::invalid_format(field, correlation_id))
+        };
+        let allow_auto_topic_creation = if version >= SupportMetadataRequestVersion::V4 {
+            read_bool(reader, "metadata allow_auto_topic_creation")?
+        } else {
+            true
+        };
+        let include_cluster_authorized_operations = if (SupportMetadataRequestVersion::V8
+            ..=SupportMetadataRequestVersion::V10)
+            .contains(&version)
+        {
+            read_bool(reader, "metadata include_cluster_authorized_operations")?
+        } else {
+            false
+        };
+        let include_topic_authorized_operations = if version >= SupportMetadataRequestVersion::V8 {
+            read_bool(reader, "metadata include_topic_authorized_operations")?
+        } else {
+            false
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("metadata tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            topics,
+            allow_auto_topic_creation,
+            include_cluster_authorized_operations,
+            include_topic_authorized_operations,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct MetadataRequestTopic {
+    /// Only sent from version 10, all zeros when the topic is named instead.
+    pub topic_id: [u8; 16],
+    /// Nullable from version 10, when the topic is looked up by id.
+    pub name: Option<String>,
+}
+
+impl KafkaDeseriarize for MetadataRequestTopic {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportMetadataRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let mut topic_id = [0u8; 16];
+        if version >= SupportMetadataRequestVersion::V10 {
+            reader
+                .read_exact(&mut topic_id)
+                .map_err(|_| RequestError::invalid_format("topic topic_id", correlation_id))?;
+        }
+
+        let name = if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self { topic_id, name })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    const TOPIC_ID: [u8; 16] = [7; 16];
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 9 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::Metadata,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::Metadata,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    fn parse(version: i16, buf: &[u8]) -> MetadataRequestBody {
+        let mut reader = Cursor::new(buf);
+        let body =
+            MetadataRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_v0_empty_array_means_all_topics() {
+        let body = parse(0, &0i32.to_be_bytes());
+        assert!(body.topics.is_none());
+        assert!(body.allow_auto_topic_creation);
+
+        // from v1 the same array asks for no topic at all
+        let body = parse(1, &0i32.to_be_bytes());
+        assert_eq!(body.topics.map(|topics| topics.len()), Some(0));
+        assert!(parse(1, &(-1i32).to_be_bytes()).topics.is_none());
+    }
+
+    #[test]
+    fn test_parse_classic_version_boundaries() {
+        let topics = [&1i32.to_be_bytes()[..], &3i16.to_be_bytes(), b"foo"].concat();
+        let body = parse(3, &topics);
+        assert_eq!(body.topics.unwrap()[0].name.as_deref(), Some("foo"));
+        assert!(body.allow_auto_topic_creation);
+
+        // v4 lets the client refuse auto creation
+        let body = parse(4, &[&topics[..], &[0]].concat());
+        assert!(!body.allow_auto_topic_creation);
+
+        // v8 adds both authorized operations flags
+        let body = parse(8, &[&topics[..], &[1, 1, 0]].concat());
+        assert!(body.allow_auto_topic_creation);
+        assert!(body.include_cluster_authorized_operations());
+        assert!(!body.include_topic_authorized_operations);
+    }
+
+    #[test]
+    fn test_parse_flexible_version_boundaries() {
+        // v9: compact arrays and strings, tagged fields
+        let buf = [&[2, 4][..], b"foo", &[0, 1, 0, 1, 0]].concat();
+        let body = parse(9, &buf);
+        let topics = body.topics.as_ref().unwrap();
+        assert_eq!(topics[0].name.as_deref(), Some("foo"));
+        assert_eq!(topics[0].topic_id, [0; 16]);
+        assert!(body.include_topic_authorized_operations);
+
+        // v10: topics by id, with a null name
+        let buf = [&[2][..], &TOPIC_ID, &[0, 0, 1, 1, 0, 0]].concat();
+        let body = parse(10, &buf);
+        let topics = body.topics.as_ref().unwrap();
+        assert_eq!(topics[0].name, None);
+        assert_eq!(topics[0].topic_id, TOPIC_ID);
+        assert!(body.include_cluster_authorized_operations());
+
+        // v11 drops include_cluster_authorized_operations
+        let buf = [&[0][..], &[1, 1, 0]].concat();
+        let body = parse(11, &buf);
+        assert!(body.topics.is_none());
+        assert!(!body.include_cluster_authorized_operations());
+        assert!(body.include_topic_authorized_operations);
+    }
+}
@@ -0,0 +1,368 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::offset_commit::SupportOffsetCommitRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_optional_compact_string,
+            try_read_optional_string, try_read_tagged_fields, try_read_vec_from_array,
+            try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// OffsetCommit request, versions 2 through 8.
+///
+/// `retention_time_ms` is only sent by versions 2-4 and is ignored; committed
+/// offsets are kept until they are overwritten. `committed_leader_epoch`
+/// arrives in version 6 and `group_instance_id` in version 7.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct OffsetCommitRequestBody {
+    version: SupportOffsetCommitRequestVersion,
+    pub group_id: String,
+    /// `-1` for a commit from outside the group protocol.
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    retention_time_ms: i64,
+    pub topics: Vec<OffsetCommitTopic>,
+}
+
+impl OffsetCommitRequestBody {
+    pub fn get_api_version(&self) -> SupportOffsetCommitRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for OffsetCommitRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportOffsetCommitRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let group_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("offset_commit group_id", correlation_id))?;
+
+        let generation_id = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("offset_commit generation_id", correlation_id)
+        })?;
+
+        let member_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("offset_commit member_id", correlation_id))?;
+
+        let group_instance_id = if version < SupportOffsetCommitRequestVersion::V7 {
+            Ok(None)
+        } else if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| {
+            RequestError::invalid_format("offset_commit group_instance_id", correlation_id)
+        })?;
+
+        let retention_time_ms = if version <= SupportOffsetCommitRequestVersion::V4 {
+            reader.read_i64::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("offset_commit retention_time_ms", correlation_id)
+            })?
+        } else {
+            -1
+        };
+
+        let read_topic = |r: &mut R| OffsetCommitTopic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("offset_commit tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            group_id,
+            generation_id,
+            member_id,
+            group_instance_id,
+            retention_time_ms,
+            topics,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetCommitTopic {
+    pub name: String,
+    pub partitions: Vec<OffsetCommitPartition>,
+}
+
+impl KafkaDeseriarize for OffsetCommitTopic {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportOffsetCommitRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        let read_partition =
+            |r: &mut R| OffsetCommitPartition::try_parse_from_reader(r, (header, version));
+        let partitions = if flexible {
+            try_read_vec_from_compact_array(reader, read_partition)
+        } else {
+            try_read_vec_from_array(reader, read_partition)
+        }
+        .map_err(|e| e.into_request_error("partitions length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self { name, partitions })
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetCommitPartition {
+    pub partition_index: i32,
+    pub committed_offset: i64,
+    /// `-1` when the client does not know the leader epoch.
+    pub committed_leader_epoch: i32,
+    pub committed_metadata: Option<String>,
+}
+
+impl KafkaDeseriarize for OffsetCommitPartition {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportOffsetCommitRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let partition_index = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("partition_index", correlation_id))?;
+        let committed_offset = reader.read_i64::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("partition committed_offset", correlation_id)
+        })?;
+        let committed_leader_epoch = if version >= SupportOffsetCommitRequestVersion::V6 {
+            reader.read_i32::<BigEndian>().map_err(|_| {
+                RequestError::invalid_format("partition committed_leader_epoch", correlation_id)
+            })?
+        } else {
+            -1
+        };
+        let committed_metadata = if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| {
+            RequestError::invalid_format("partition committed_metadata", correlation_id)
+        })?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("partition tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            partition_index,
+            committed_offset,
+            committed_leader_epoch,
+            committed_metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 8 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::OffsetCommit,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::OffsetCommit,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// A commit of offset 42 on partitions 0 and 1 of topic "foo", the
+    /// second without metadata, laid out as `version` sends it.
+    fn encode(version: i16) -> Vec<u8> {
+        let flexible = version >= 8;
+        let mut buf = Vec::new();
+        let write_len = |buf: &mut Vec<u8>, len: usize| {
+            if flexible {
+                buf.push(len as u8 + 1);
+            } else {
+                buf.write_i32::<BigEndian>(len as i32).unwrap();
+            }
+        };
+        let write_string = |buf: &mut Vec<u8>, s: Option<&str>| match (s, flexible) {
+            (None, true) => buf.push(0),
+            (None, false) => buf.write_i16::<BigEndian>(-1).unwrap(),
+            (Some(s), true) => {
+                buf.push(s.len() as u8 + 1);
+                buf.extend_from_slice(s.as_bytes());
+            }
+            (Some(s), false) => {
+                buf.write_i16::<BigEndian>(s.len() as i16).unwrap();
+                buf.extend_from_slice(s.as_bytes());
+            }
+        };
+
+        write_string(&mut buf, Some("g"));
+        buf.write_i32::<BigEndian>(3).unwrap();
+        write_string(&mut buf, Some("m"));
+        if version >= 7 {
+            write_string(&mut buf, Some("i"));
+        }
+        if version <= 4 {
+            buf.write_i64::<BigEndian>(60_000).unwrap();
+        }
+        write_len(&mut buf, 1);
+        write_string(&mut buf, Some("foo"));
+        write_len(&mut buf, 2);
+        for (partition, metadata) in [(0, Some("meta")), (1, None)] {
+            buf.write_i32::<BigEndian>(partition).unwrap();
+            buf.write_i64::<BigEndian>(42).unwrap();
+            if version >= 6 {
+                buf.write_i32::<BigEndian>(5).unwrap();
+            }
+            write_string(&mut buf, metadata);
+            if flexible {
+                buf.push(0);
+            }
+        }
+        if flexible {
+            buf.push(0); // topic tagged fields
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(version: i16) -> OffsetCommitRequestBody {
+        let buf = encode(version);
+        let mut reader = Cursor::new(&buf[..]);
+        let body =
+            OffsetCommitRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v2: with a retention time, no leader epoch or group instance id
+        let body = parse(2);
+        assert_eq!(body.group_id, "g");
+        assert_eq!(body.generation_id, 3);
+        assert_eq!(body.member_id, "m");
+        assert_eq!(body.group_instance_id, None);
+        assert_eq!(body.retention_time_ms, 60_000);
+        assert_eq!(body.topics[0].name, "foo");
+        let partitions = &body.topics[0].partitions;
+        assert_eq!(partitions[0].partition_index, 0);
+        assert_eq!(partitions[0].committed_offset, 42);
+        assert_eq!(partitions[0].committed_leader_epoch, -1);
+        assert_eq!(partitions[0].committed_metadata.as_deref(), Some("meta"));
+        assert_eq!(partitions[1].committed_metadata, None);
+
+        // v5 drops the retention time
+        assert_eq!(parse(5).retention_time_ms, -1);
+
+        // v6 adds the committed leader epoch
+        let body = parse(6);
+        assert_eq!(body.topics[0].partitions[0].committed_leader_epoch, 5);
+
+        // v7 adds the group instance id
+        assert_eq!(parse(7).group_instance_id.as_deref(), Some("i"));
+
+        // v8: compact encoding and tagged fields
+        let body = parse(8);
+        assert_eq!(
+            body.get_api_version(),
+            SupportOffsetCommitRequestVersion::V8
+        );
+        let partitions = &body.topics[0].partitions;
+        assert_eq!(partitions[0].committed_leader_epoch, 5);
+        assert_eq!(partitions[0].committed_metadata.as_deref(), Some("meta"));
+        assert_eq!(partitions[1].committed_metadata, None);
+    }
+}
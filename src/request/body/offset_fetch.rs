@@ -0,0 +1,286 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::offset_fetch::SupportOffsetFetchRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_string, try_read_nullable_string, try_read_nullable_vec_from_array,
+            try_read_nullable_vec_from_compact_array, try_read_tagged_fields,
+            try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// OffsetFetch request, versions 1 through 8.
+///
+/// Versions 1-7 ask for a single group, version 8 for a batch of groups; both
+/// are kept in `groups`. `require_stable` arrives in version 7.
+#[allow(unused)]
+#[derive(Debug)]
+pub struct OffsetFetchRequestBody {
+    version: SupportOffsetFetchRequestVersion,
+    pub groups: Vec<OffsetFetchGroup>,
+    require_stable: bool,
+}
+
+impl OffsetFetchRequestBody {
+    pub fn get_api_version(&self) -> SupportOffsetFetchRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for OffsetFetchRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportOffsetFetchRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let groups = if version.is_batched() {
+            let read_group =
+                |r: &mut R| OffsetFetchGroup::try_parse_from_reader(r, (header, version));
+            try_read_vec_from_compact_array(reader, read_group)
+                .map_err(|e| e.into_request_error("groups length", correlation_id))?
+        } else {
+            vec![OffsetFetchGroup::try_parse_from_reader(
+                reader,
+                (header, version),
+            )?]
+        };
+
+        let require_stable = if version >= SupportOffsetFetchRequestVersion::V7 {
+            reader.read_u8().map_err(|_| {
+                RequestError::invalid_format("offset_fetch require_stable", correlation_id)
+            })? != 0
+        } else {
+            false
+        };
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("offset_fetch tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            groups,
+            require_stable,
+        })
+    }
+}
+
+/// A group and the partitions whose offsets are asked for.
+#[derive(Debug)]
+pub struct OffsetFetchGroup {
+    pub group_id: String,
+    /// `None` for every partition the group committed an offset for.
+    pub topics: Option<Vec<OffsetFetchTopic>>,
+}
+
+impl KafkaDeseriarize for OffsetFetchGroup {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportOffsetFetchRequestVersion);
+
+    /// Read the group id and topics; in versions 1-7 these are top-level
+    /// fields of the request, so the tagged fields are only read in batches.
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let group_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("group group_id", correlation_id))?;
+
+        let read_topic = |r: &mut R| OffsetFetchTopic::try_parse_from_reader(r, (header, version));
+        let topics = if flexible {
+            try_read_nullable_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_nullable_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topics length", correlation_id))?;
+
+        if version.is_batched() {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("group tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self { group_id, topics })
+    }
+}
+
+#[derive(Debug)]
+pub struct OffsetFetchTopic {
+    pub name: String,
+    pub partition_indexes: Vec<i32>,
+}
+
+impl KafkaDeseriarize for OffsetFetchTopic {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportOffsetFetchRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        let read_partition = |r: &mut R| {
+            r.read_i32::<BigEndian>()
+                .map_err(|_| RequestError::invalid_format("partition_indexes", correlation_id))
+        };
+        let partition_indexes = if flexible {
+            try_read_vec_from_compact_array(reader, read_partition)
+        } else {
+            try_read_vec_from_array(reader, read_partition)
+        }
+        .map_err(|e| e.into_request_error("partition_indexes length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self {
+            name,
+            partition_indexes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn parse(version: i16, buf: &[u8]) -> OffsetFetchRequestBody {
+        let header = if version >= 6 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::OffsetFetch,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::OffsetFetch,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        };
+        let mut reader = Cursor::new(buf);
+        let body = OffsetFetchRequestBody::try_parse_from_reader(&mut reader, &header).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v1: a single group with its topics
+        let classic = [
+            &1i16.to_be_bytes()[..],
+            b"g",
+            &1i32.to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"foo",
+            &2i32.to_be_bytes(),
+            &0i32.to_be_bytes(),
+            &1i32.to_be_bytes(),
+        ]
+        .concat();
+        let body = parse(1, &classic);
+        assert_eq!(body.groups.len(), 1);
+        assert_eq!(body.groups[0].group_id, "g");
+        let topics = body.groups[0].topics.as_ref().unwrap();
+        assert_eq!(topics[0].name, "foo");
+        assert_eq!(topics[0].partition_indexes, [0, 1]);
+
+        // v2 accepts a null array for every committed partition
+        let body = parse(
+            2,
+            &[&1i16.to_be_bytes()[..], b"g", &(-1i32).to_be_bytes()].concat(),
+        );
+        assert!(body.groups[0].topics.is_none());
+
+        // v6: compact encoding, the group still inline
+        let flexible = [
+            &[2][..],
+            b"g",
+            &[2, 4],
+            b"foo",
+            &[2],
+            &0i32.to_be_bytes(),
+            &[0],
+        ]
+        .concat();
+        let body = parse(6, &[&flexible[..], &[0]].concat());
+        assert_eq!(
+            body.groups[0].topics.as_ref().unwrap()[0].partition_indexes,
+            [0]
+        );
+        assert!(!body.require_stable);
+
+        // v7 adds require_stable
+        let body = parse(7, &[&flexible[..], &[1, 0]].concat());
+        assert!(body.require_stable);
+
+        // v8: a batch of groups, each with tagged fields
+        let buf = [&[3][..], &flexible, &[0], &[2], b"h", &[0, 0], &[0, 0]].concat();
+        let body = parse(8, &buf);
+        assert_eq!(body.get_api_version(), SupportOffsetFetchRequestVersion::V8);
+        assert_eq!(body.groups.len(), 2);
+        assert_eq!(body.groups[0].group_id, "g");
+        assert_eq!(body.groups[0].topics.as_ref().unwrap()[0].name, "foo");
+        assert_eq!(body.groups[1].group_id, "h");
+        assert!(body.groups[1].topics.is_none());
+    }
+}
@@ -0,0 +1,291 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::produce::SupportProduceRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_nullable_bytes, try_read_compact_string, try_read_nullable_bytes,
+            try_read_nullable_string, try_read_optional_compact_string, try_read_optional_string,
+            try_read_tagged_fields, try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// Produce request, versions 3 through 11.
+///
+/// Versions 3-8 share the classic encoding, 9-11 the flexible one; the fields
+/// are identical across the whole range so a single body type covers them.
+#[derive(Debug)]
+pub struct ProduceRequestBody {
+    version: SupportProduceRequestVersion,
+    pub transactional_id: Option<String>,
+    pub acks: i16,
+    pub timeout_ms: i32,
+    pub topic_data: Vec<TopicProduceData>,
+}
+
+impl ProduceRequestBody {
+    pub fn get_api_version(&self) -> SupportProduceRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for ProduceRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportProduceRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let transactional_id = if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("produce transactional_id", correlation_id))?;
+
+        let acks = reader
+            .read_i16::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("produce acks", correlation_id))?;
+
+        let timeout_ms = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("produce timeout_ms", correlation_id))?;
+
+        let read_topic = |r: &mut R| TopicProduceData::try_parse_from_reader(r, (header, version));
+        let topic_data = if flexible {
+            try_read_vec_from_compact_array(reader, read_topic)
+        } else {
+            try_read_vec_from_array(reader, read_topic)
+        }
+        .map_err(|e| e.into_request_error("topic_data length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("produce tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            transactional_id,
+            acks,
+            timeout_ms,
+            topic_data,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TopicProduceData {
+    pub name: String,
+    pub partition_data: Vec<PartitionProduceData>,
+}
+
+impl KafkaDeseriarize for TopicProduceData {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportProduceRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let name = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("topic name", correlation_id))?;
+
+        let read_partition =
+            |r: &mut R| PartitionProduceData::try_parse_from_reader(r, (header, version));
+        let partition_data = if flexible {
+            try_read_vec_from_compact_array(reader, read_partition)
+        } else {
+            try_read_vec_from_array(reader, read_partition)
+        }
+        .map_err(|e| e.into_request_error("partition_data length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader)
+                .map_err(|_| RequestError::invalid_format("topic tagged_fields", correlation_id))?;
+        }
+
+        Ok(Self {
+            name,
+            partition_data,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PartitionProduceData {
+    pub index: i32,
+    /// Raw record batches as sent by the producer, `None` for a null record set.
+    pub records: Option<Vec<u8>>,
+}
+
+impl KafkaDeseriarize for PartitionProduceData {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportProduceRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let index = reader
+            .read_i32::<BigEndian>()
+            .map_err(|_| RequestError::invalid_format("partition index", correlation_id))?;
+
+        let records = if flexible {
+            try_read_compact_nullable_bytes(reader)
+        } else {
+            try_read_nullable_bytes(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("partition records", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("partition tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self { index, records })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        consts::produce::SupportProduceRequestVersion,
+        request::{body::KafkaRequestBody, KafkaRequest},
+        traits::KafkaDeseriarize,
+    };
+
+    fn header(version: i16, flexible: bool) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&0i16.to_be_bytes());
+        buf.extend_from_slice(&version.to_be_bytes());
+        buf.extend_from_slice(&7i32.to_be_bytes());
+        buf.extend_from_slice(&(-1i16).to_be_bytes());
+        if flexible {
+            buf.push(0);
+        }
+        buf
+    }
+
+    fn parse(buf: Vec<u8>) -> KafkaRequest {
+        KafkaRequest::try_parse_from_reader(&mut Cursor::new(buf), ()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_v3() {
+        let mut buf = header(3, false);
+        buf.extend_from_slice(&(-1i16).to_be_bytes());
+        buf.extend_from_slice(&1i16.to_be_bytes());
+        buf.extend_from_slice(&1500i32.to_be_bytes());
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        buf.extend_from_slice(&3i16.to_be_bytes());
+        buf.extend_from_slice(b"foo");
+        buf.extend_from_slice(&2i32.to_be_bytes());
+        buf.extend_from_slice(&0i32.to_be_bytes());
+        buf.extend_from_slice(&3i32.to_be_bytes());
+        buf.extend_from_slice(&[1, 2, 3]);
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        buf.extend_from_slice(&(-1i32).to_be_bytes());
+
+        let request = parse(buf);
+        let KafkaRequestBody::Produce(body) = request.request_body() else {
+            panic!("not a produce request");
+        };
+        assert_eq!(body.get_api_version(), SupportProduceRequestVersion::V3);
+        assert_eq!(body.transactional_id, None);
+        assert_eq!(body.acks, 1);
+        assert_eq!(body.timeout_ms, 1500);
+        assert_eq!(body.topic_data.len(), 1);
+        assert_eq!(body.topic_data[0].name, "foo");
+        let partitions = &body.topic_data[0].partition_data;
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].index, 0);
+        assert_eq!(partitions[0].records.as_deref(), Some(&[1u8, 2, 3][..]));
+        assert_eq!(partitions[1].index, 1);
+        assert_eq!(partitions[1].records, None);
+        assert!(request.expects_response());
+    }
+
+    #[test]
+    fn test_parse_v9_flexible() {
+        let mut buf = header(9, true);
+        buf.push(4);
+        buf.extend_from_slice(b"txn");
+        buf.extend_from_slice(&(-1i16).to_be_bytes());
+        buf.extend_from_slice(&1500i32.to_be_bytes());
+        buf.push(2);
+        buf.push(4);
+        buf.extend_from_slice(b"foo");
+        buf.push(2);
+        buf.extend_from_slice(&5i32.to_be_bytes());
+        buf.push(4);
+        buf.extend_from_slice(&[1, 2, 3]);
+        buf.push(0);
+        buf.push(0);
+        buf.push(0);
+
+        let request = parse(buf);
+        let KafkaRequestBody::Produce(body) = request.request_body() else {
+            panic!("not a produce request");
+        };
+        assert_eq!(body.get_api_version(), SupportProduceRequestVersion::V9);
+        assert_eq!(body.transactional_id.as_deref(), Some("txn"));
+        assert_eq!(body.acks, -1);
+        assert_eq!(body.topic_data[0].name, "foo");
+        let partitions = &body.topic_data[0].partition_data;
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].index, 5);
+        assert_eq!(partitions[0].records.as_deref(), Some(&[1u8, 2, 3][..]));
+    }
+
+    #[test]
+    fn test_acks_zero_expects_no_response() {
+        let mut buf = header(3, false);
+        buf.extend_from_slice(&(-1i16).to_be_bytes());
+        buf.extend_from_slice(&0i16.to_be_bytes());
+        buf.extend_from_slice(&1500i32.to_be_bytes());
+        buf.extend_from_slice(&0i32.to_be_bytes());
+
+        assert!(!parse(buf).expects_response());
+    }
+}
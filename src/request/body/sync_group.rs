@@ -0,0 +1,286 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    consts::sync_group::SupportSyncGroupRequestVersion,
+    request::{
+        error::RequestError,
+        header::KafkaRequestHeader,
+        utils::{
+            try_read_compact_nullable_bytes, try_read_compact_string, try_read_nullable_bytes,
+            try_read_nullable_string, try_read_optional_compact_string, try_read_optional_string,
+            try_read_tagged_fields, try_read_vec_from_array, try_read_vec_from_compact_array,
+        },
+    },
+    traits::KafkaDeseriarize,
+};
+
+/// SyncGroup request, versions 0 through 5.
+///
+/// `group_instance_id` arrives in version 3, `protocol_type` and
+/// `protocol_name` in version 5.
+#[derive(Debug)]
+pub struct SyncGroupRequestBody {
+    version: SupportSyncGroupRequestVersion,
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    pub assignments: Vec<SyncGroupRequestAssignment>,
+}
+
+impl SyncGroupRequestBody {
+    pub fn get_api_version(&self) -> SupportSyncGroupRequestVersion {
+        self.version
+    }
+}
+
+impl KafkaDeseriarize for SyncGroupRequestBody {
+    type Error = RequestError;
+
+    type DependentData<'a> = &'a KafkaRequestHeader;
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        header: Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let api_version = header.request_api_version();
+        let correlation_id = header.correlation_id();
+        let version: SupportSyncGroupRequestVersion = api_version
+            .try_into()
+            .map_err(|_| RequestError::unsupported_version(api_version, correlation_id))?;
+        let flexible = version.is_flexible();
+
+        let group_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("sync_group group_id", correlation_id))?;
+
+        let generation_id = reader.read_i32::<BigEndian>().map_err(|_| {
+            RequestError::invalid_format("sync_group generation_id", correlation_id)
+        })?;
+
+        let member_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("sync_group member_id", correlation_id))?;
+
+        let group_instance_id = if version < SupportSyncGroupRequestVersion::V3 {
+            Ok(None)
+        } else if flexible {
+            try_read_optional_compact_string(reader)
+        } else {
+            try_read_optional_string(reader)
+        }
+        .map_err(|_| {
+            RequestError::invalid_format("sync_group group_instance_id", correlation_id)
+        })?;
+
+        let (protocol_type, protocol_name) = if version >= SupportSyncGroupRequestVersion::V5 {
+            let protocol_type = try_read_optional_compact_string(reader).map_err(|_| {
+                RequestError::invalid_format("sync_group protocol_type", correlation_id)
+            })?;
+            let protocol_name = try_read_optional_compact_string(reader).map_err(|_| {
+                RequestError::invalid_format("sync_group protocol_name", correlation_id)
+            })?;
+            (protocol_type, protocol_name)
+        } else {
+            (None, None)
+        };
+
+        let read_assignment =
+            |r: &mut R| SyncGroupRequestAssignment::try_parse_from_reader(r, (header, version));
+        let assignments = if flexible {
+            try_read_vec_from_compact_array(reader, read_assignment)
+        } else {
+            try_read_vec_from_array(reader, read_assignment)
+        }
+        .map_err(|e| e.into_request_error("assignments length", correlation_id))?;
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("sync_group tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            version,
+            group_id,
+            generation_id,
+            member_id,
+            group_instance_id,
+            protocol_type,
+            protocol_name,
+            assignments,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SyncGroupRequestAssignment {
+    pub member_id: String,
+    pub assignment: Vec<u8>,
+}
+
+impl KafkaDeseriarize for SyncGroupRequestAssignment {
+    type Error = RequestError;
+
+    type DependentData<'a> = (&'a KafkaRequestHeader, SupportSyncGroupRequestVersion);
+
+    fn try_parse_from_reader<R: io::Read>(
+        reader: &mut R,
+        (header, version): Self::DependentData<'_>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let correlation_id = header.correlation_id();
+        let flexible = version.is_flexible();
+
+        let member_id = if flexible {
+            try_read_compact_string(reader)
+        } else {
+            try_read_nullable_string(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("assignment member_id", correlation_id))?;
+
+        let assignment = if flexible {
+            try_read_compact_nullable_bytes(reader)
+        } else {
+            try_read_nullable_bytes(reader)
+        }
+        .map_err(|_| RequestError::invalid_format("assignment assignment", correlation_id))?
+        .unwrap_or_default();
+
+        if flexible {
+            let _ = try_read_tagged_fields(reader).map_err(|_| {
+                RequestError::invalid_format("assignment tagged_fields", correlation_id)
+            })?;
+        }
+
+        Ok(Self {
+            member_id,
+            assignment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+    use crate::request::{
+        api_key::RequestApiKey,
+        header::{KafkaRequestHeaderV1, KafkaRequestHeaderV2},
+    };
+
+    fn header(version: i16) -> KafkaRequestHeader {
+        if version >= 4 {
+            KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+                request_api_key: RequestApiKey::SyncGroup,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        } else {
+            KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+                request_api_key: RequestApiKey::SyncGroup,
+                request_api_version: version,
+                correlation_id: 1,
+                client_id: String::new(),
+            })
+        }
+    }
+
+    /// The leader "m" of group "g" assigning itself `[9]`, laid out as
+    /// `version` sends it.
+    fn encode(version: i16) -> Vec<u8> {
+        let flexible = version >= 4;
+        let mut buf = Vec::new();
+        let write_string = |buf: &mut Vec<u8>, s: &str| {
+            if flexible {
+                buf.push(s.len() as u8 + 1);
+            } else {
+                buf.write_i16::<BigEndian>(s.len() as i16).unwrap();
+            }
+            buf.extend_from_slice(s.as_bytes());
+        };
+
+        write_string(&mut buf, "g");
+        buf.write_i32::<BigEndian>(3).unwrap();
+        write_string(&mut buf, "m");
+        if version >= 3 {
+            write_string(&mut buf, "i");
+        }
+        if version >= 5 {
+            write_string(&mut buf, "consumer");
+            write_string(&mut buf, "range");
+        }
+        if flexible {
+            buf.push(2);
+        } else {
+            buf.write_i32::<BigEndian>(1).unwrap();
+        }
+        write_string(&mut buf, "m");
+        if flexible {
+            buf.extend_from_slice(&[2, 9, 0, 0]);
+        } else {
+            buf.write_i32::<BigEndian>(1).unwrap();
+            buf.push(9);
+        }
+        buf
+    }
+
+    fn parse(version: i16) -> SyncGroupRequestBody {
+        let buf = encode(version);
+        let mut reader = Cursor::new(&buf[..]);
+        let body =
+            SyncGroupRequestBody::try_parse_from_reader(&mut reader, &header(version)).unwrap();
+        assert_eq!(
+            reader.position() as usize,
+            buf.len(),
+            "v{version} left bytes unread"
+        );
+        body
+    }
+
+    #[test]
+    fn test_parse_version_boundaries() {
+        // v0: no group instance id or protocol
+        let body = parse(0);
+        assert_eq!(body.group_id, "g");
+        assert_eq!(body.generation_id, 3);
+        assert_eq!(body.member_id, "m");
+        assert_eq!(body.group_instance_id, None);
+        assert_eq!(body.assignments[0].member_id, "m");
+        assert_eq!(body.assignments[0].assignment, [9]);
+
+        // v3 adds the group instance id
+        assert_eq!(parse(3).group_instance_id.as_deref(), Some("i"));
+
+        // v4: compact encoding and tagged fields
+        let body = parse(4);
+        assert_eq!(body.get_api_version(), SupportSyncGroupRequestVersion::V4);
+        assert_eq!(body.group_instance_id.as_deref(), Some("i"));
+        assert_eq!(body.protocol_type, None);
+        assert_eq!(body.assignments[0].assignment, [9]);
+
+        // v5 adds the protocol type and name
+        let body = parse(5);
+        assert_eq!(body.protocol_type.as_deref(), Some("consumer"));
+        assert_eq!(body.protocol_name.as_deref(), Some("range"));
+    }
+}
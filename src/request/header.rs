@@ -2,6 +2,21 @@ use byteorder::ReadBytesExt;
 
 use byteorder::BigEndian;
 
+use crate::consts::create_partitions::SupportCreatePartitionsRequestVersion;
+use crate::consts::create_topics::SupportCreateTopicsRequestVersion;
+use crate::consts::delete_topics::SupportDeleteTopicsRequestVersion;
+use crate::consts::fetch::SupportFetchRequestVersion;
+use crate::consts::find_coordinator::SupportFindCoordinatorRequestVersion;
+use crate::consts::heartbeat::SupportHeartbeatRequestVersion;
+use crate::consts::init_producer_id::SupportInitProducerIdRequestVersion;
+use crate::consts::join_group::SupportJoinGroupRequestVersion;
+use crate::consts::leave_group::SupportLeaveGroupRequestVersion;
+use crate::consts::list_offsets::SupportListOffsetsRequestVersion;
+use crate::consts::metadata::SupportMetadataRequestVersion;
+use crate::consts::offset_commit::SupportOffsetCommitRequestVersion;
+use crate::consts::offset_fetch::SupportOffsetFetchRequestVersion;
+use crate::consts::produce::SupportProduceRequestVersion;
+use crate::consts::sync_group::SupportSyncGroupRequestVersion;
 use crate::traits::KafkaDeseriarize;
 
 use super::{
@@ -108,7 +123,8 @@ impl KafkaDeseriarize for KafkaRequestHeader {
                     api_key: request_api_key,
                     correlation_id,
                 })?;
-        let header_version = header_version_from_request_api_key(request_api_key);
+        let header_version =
+            header_version_from_request_api_key(request_api_key, request_api_version);
         match header_version {
             KafkaRequestHeaderVersion::V0 => Ok(KafkaRequestHeader::V0(KafkaRequestHeaderV0 {
                 request_api_key,
@@ -152,11 +168,89 @@ impl KafkaDeseriarize for KafkaRequestHeader {
     }
 }
 
-fn header_version_from_request_api_key(api_key: RequestApiKey) -> KafkaRequestHeaderVersion {
+fn header_version_from_request_api_key(
+    api_key: RequestApiKey,
+    api_version: i16,
+) -> KafkaRequestHeaderVersion {
     match api_key {
-        RequestApiKey::Produce => KafkaRequestHeaderVersion::V0,
-        RequestApiKey::DescribeTopicPartitions
-        | RequestApiKey::ApiVersions
-        | RequestApiKey::Fetch => KafkaRequestHeaderVersion::V2,
+        RequestApiKey::Produce => match SupportProduceRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::Fetch => match SupportFetchRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::ListOffsets => {
+            match SupportListOffsetsRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::Metadata => match SupportMetadataRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::OffsetCommit => {
+            match SupportOffsetCommitRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::OffsetFetch => {
+            match SupportOffsetFetchRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::FindCoordinator => {
+            match SupportFindCoordinatorRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::JoinGroup => match SupportJoinGroupRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::Heartbeat => match SupportHeartbeatRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::LeaveGroup => match SupportLeaveGroupRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::SyncGroup => match SupportSyncGroupRequestVersion::try_from(api_version) {
+            Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+            _ => KafkaRequestHeaderVersion::V1,
+        },
+        RequestApiKey::CreateTopics => {
+            match SupportCreateTopicsRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::DeleteTopics => {
+            match SupportDeleteTopicsRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::InitProducerId => {
+            match SupportInitProducerIdRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::CreatePartitions => {
+            match SupportCreatePartitionsRequestVersion::try_from(api_version) {
+                Ok(version) if version.is_flexible() => KafkaRequestHeaderVersion::V2,
+                _ => KafkaRequestHeaderVersion::V1,
+            }
+        }
+        RequestApiKey::DescribeTopicPartitions | RequestApiKey::ApiVersions => {
+            KafkaRequestHeaderVersion::V2
+        }
     }
 }
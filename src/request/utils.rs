@@ -36,6 +36,19 @@ pub fn try_read_vec_from_compact_array<R: Read, Element, FnReadOne, FnError>(
     reader: &mut R,
     f: FnReadOne,
 ) -> Result<Vec<Element>, ReadCompactStringError<FnError>>
+where
+    FnReadOne: Fn(&mut R) -> Result<Element, FnError>,
+    FnError: std::error::Error,
+    FnError: 'static,
+{
+    try_read_nullable_vec_from_compact_array(reader, f).map(Option::unwrap_or_default)
+}
+
+/// Like [`try_read_vec_from_compact_array`], but a length of `0` decodes as `None`.
+pub fn try_read_nullable_vec_from_compact_array<R: Read, Element, FnReadOne, FnError>(
+    reader: &mut R,
+    f: FnReadOne,
+) -> Result<Option<Vec<Element>>, ReadCompactStringError<FnError>>
 where
     FnReadOne: Fn(&mut R) -> Result<Element, FnError>,
     FnError: std::error::Error,
@@ -43,9 +56,9 @@ where
 {
     let length: usize = reader
         .read_varint()
-        .map_err(|e| ReadCompactStringError::LengthError(e))?;
+        .map_err(ReadCompactStringError::LengthError)?;
     if length == 0 {
-        return Ok(Vec::new());
+        return Ok(None);
     }
     let length = length - 1;
     let mut results = Vec::with_capacity(length);
@@ -53,7 +66,43 @@ where
         let element = f(reader)?;
         results.push(element);
     }
-    Ok(results)
+    Ok(Some(results))
+}
+
+pub fn try_read_vec_from_array<R: Read, Element, FnReadOne, FnError>(
+    reader: &mut R,
+    f: FnReadOne,
+) -> Result<Vec<Element>, ReadCompactStringError<FnError>>
+where
+    FnReadOne: Fn(&mut R) -> Result<Element, FnError>,
+    FnError: std::error::Error,
+    FnError: 'static,
+{
+    try_read_nullable_vec_from_array(reader, f).map(Option::unwrap_or_default)
+}
+
+/// Like [`try_read_vec_from_array`], but a negative length decodes as `None`.
+pub fn try_read_nullable_vec_from_array<R: Read, Element, FnReadOne, FnError>(
+    reader: &mut R,
+    f: FnReadOne,
+) -> Result<Option<Vec<Element>>, ReadCompactStringError<FnError>>
+where
+    FnReadOne: Fn(&mut R) -> Result<Element, FnError>,
+    FnError: std::error::Error,
+    FnError: 'static,
+{
+    let length = reader
+        .read_i32::<BigEndian>()
+        .map_err(ReadCompactStringError::LengthError)?;
+    if length < 0 {
+        return Ok(None);
+    }
+    let mut results = Vec::with_capacity(length as usize);
+    for _ in 0..length {
+        let element = f(reader)?;
+        results.push(element);
+    }
+    Ok(Some(results))
 }
 
 pub fn try_read_compact_string<R: Read>(
@@ -70,6 +119,34 @@ pub fn try_read_compact_string<R: Read>(
     Ok(result)
 }
 
+pub fn try_read_optional_compact_string<R: Read>(
+    reader: &mut R,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let length: usize = reader.read_varint()?;
+    if length == 0usize {
+        return Ok(None);
+    }
+    let mut buf = vec![0u8; length - 1];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(String::from_utf8(buf)?))
+}
+
+pub fn try_read_optional_string<R: Read>(
+    reader: &mut R,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let length = reader.read_i16::<BigEndian>()?;
+    if length == -1 {
+        return Ok(None);
+    }
+    if length < 0 {
+        let err = ErrorField::from(Cow::from("invalid nullable string length"));
+        return Err(Box::new(err));
+    }
+    let mut buf = vec![0u8; length as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(String::from_utf8(buf)?))
+}
+
 pub fn try_read_nullable_string<R: Read>(
     reader: &mut R,
 ) -> Result<String, Box<dyn std::error::Error>> {
@@ -88,6 +165,28 @@ pub fn try_read_nullable_string<R: Read>(
     }
 }
 
+pub fn try_read_nullable_bytes<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, io::Error> {
+    let length = reader.read_i32::<BigEndian>()?;
+    if length < 0 {
+        return Ok(None);
+    }
+    let mut buf = vec![0u8; length as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(buf))
+}
+
+pub fn try_read_compact_nullable_bytes<R: Read>(
+    reader: &mut R,
+) -> Result<Option<Vec<u8>>, io::Error> {
+    let length: usize = reader.read_varint()?;
+    if length == 0 {
+        return Ok(None);
+    }
+    let mut buf = vec![0u8; length - 1];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(buf))
+}
+
 pub fn try_read_tagged_fields<R: Read>(reader: &mut R) -> Result<Vec<TaggedField>, io::Error> {
     let num: usize = reader.read_varint()?;
     let mut results = Vec::new();
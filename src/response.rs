@@ -1,6 +1,9 @@
-use std::io;
+use std::{io, sync::Arc, time::Duration};
+
+use tokio::sync::Notify;
 
 use crate::{
+    config::BrokerConfig,
     request::{body::KafkaRequestBody, error::RequestError},
     traits::KafkaSeriarize,
 };
@@ -32,18 +35,25 @@ impl KafkaResponse {
 }
 
 impl KafkaResponse {
-    pub fn from_request(request: &Result<KafkaRequest, RequestError>) -> Self {
+    pub fn from_request(
+        request: &Result<KafkaRequest, RequestError>,
+        config: &BrokerConfig,
+    ) -> Self {
         match request {
-            Ok(request) => Self::new(request),
+            Ok(request) => Self::new(request, config),
             Err(error) => Self::new_error_response(error),
         }
     }
 
-    fn new(request: &KafkaRequest) -> Self {
+    pub fn new(request: &KafkaRequest, config: &BrokerConfig) -> Self {
         let mut header = KafkaResponseHeader::new_v0(request.correlation_id());
         let body = match request.request_body() {
-            KafkaRequestBody::Empty => todo!(),
-            KafkaRequestBody::Produce => todo!(),
+            KafkaRequestBody::Produce(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_produce_request_body(body, config)
+            }
             KafkaRequestBody::ApiVersions(body) => {
                 KafkaResponseBody::from_api_versions_request_body(body)
             }
@@ -52,13 +62,117 @@ impl KafkaResponse {
                 KafkaResponseBody::from_describe_topic_partitions_request_body(body)
             }
             KafkaRequestBody::Fetch(body) => {
-                header = KafkaResponseHeader::new_v1(request.correlation_id());
-                KafkaResponseBody::from_fetch_request_body(body)
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_fetch_request_body(body, config)
+            }
+            KafkaRequestBody::FindCoordinator(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_find_coordinator_request_body(body, config)
+            }
+            KafkaRequestBody::JoinGroup(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_join_group_request_body(body)
+            }
+            KafkaRequestBody::Heartbeat(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_heartbeat_request_body(body)
+            }
+            KafkaRequestBody::LeaveGroup(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_leave_group_request_body(body)
+            }
+            KafkaRequestBody::SyncGroup(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_sync_group_request_body(body)
+            }
+            KafkaRequestBody::ListOffsets(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_list_offsets_request_body(body)
+            }
+            KafkaRequestBody::Metadata(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_metadata_request_body(body, config)
+            }
+            KafkaRequestBody::OffsetCommit(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_offset_commit_request_body(body, config)
+            }
+            KafkaRequestBody::OffsetFetch(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_offset_fetch_request_body(body)
+            }
+            KafkaRequestBody::CreateTopics(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_create_topics_request_body(body, config)
+            }
+            KafkaRequestBody::DeleteTopics(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_delete_topics_request_body(body)
+            }
+            KafkaRequestBody::InitProducerId(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_init_producer_id_request_body(body)
+            }
+            KafkaRequestBody::CreatePartitions(body) => {
+                if body.get_api_version().is_flexible() {
+                    header = KafkaResponseHeader::new_v1(request.correlation_id());
+                }
+                KafkaResponseBody::from_create_partitions_request_body(body, config)
             }
         };
         Self { header, body }
     }
 
+    /// For a fetch that found too little data, the notification of the next
+    /// append to its partitions and how long it may wait in all.
+    pub fn delay(&self) -> Option<(Arc<Notify>, Duration)> {
+        match &self.body {
+            KafkaResponseBody::Fetch(body) => body.delay(),
+            _ => None,
+        }
+    }
+
+    /// Read a parked fetch again, after an append or once it waited long
+    /// enough.
+    pub fn refresh(&mut self) {
+        if let KafkaResponseBody::Fetch(body) = &mut self.body {
+            body.read();
+        }
+    }
+
+    /// Settle a response that is about to be sent.
+    pub fn complete(&mut self) {
+        if let KafkaResponseBody::Fetch(body) = &mut self.body {
+            body.complete();
+        }
+    }
+
     // TODO: repair UnsupportedApiKey response body emtpy
     // TODO: repair InvalidFormat response body empty
     fn new_error_response(request_error: &RequestError) -> Self {
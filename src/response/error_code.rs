@@ -2,18 +2,70 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 use thiserror::Error;
 
 #[repr(i16)]
-#[derive(Error, Debug, TryFromPrimitive, IntoPrimitive)]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 pub enum KafkaError {
     #[error("UnknownServerError")]
     UnknownServerError = -1,
     #[error("None")]
     None = 0,
+    #[error("OffsetOutOfRange")]
+    OffsetOutOfRange = 1,
+    #[error("CorruptMessage")]
+    CorruptMessage = 2,
     #[error("UnknownTopicOrPartition")]
     UnknownTopicOrPartition = 3,
+    #[error("NotLeaderOrFollower")]
+    NotLeaderOrFollower = 6,
+    #[error("OffsetMetadataTooLarge")]
+    OffsetMetadataTooLarge = 12,
+    #[error("CoordinatorNotAvailable")]
+    CoordinatorNotAvailable = 15,
+    #[error("InvalidTopicException")]
+    InvalidTopicException = 17,
+    #[error("InvalidRequiredAcks")]
+    InvalidRequiredAcks = 21,
+    #[error("IllegalGeneration")]
+    IllegalGeneration = 22,
+    #[error("InconsistentGroupProtocol")]
+    InconsistentGroupProtocol = 23,
+    #[error("InvalidGroupId")]
+    InvalidGroupId = 24,
+    #[error("UnknownMemberId")]
+    UnknownMemberId = 25,
+    #[error("InvalidSessionTimeout")]
+    InvalidSessionTimeout = 26,
+    #[error("RebalanceInProgress")]
+    RebalanceInProgress = 27,
     #[error("UnsupportedVersion")]
     UnsupportedVersion = 35,
+    #[error("TopicAlreadyExists")]
+    TopicAlreadyExists = 36,
+    #[error("InvalidPartitions")]
+    InvalidPartitions = 37,
+    #[error("InvalidReplicationFactor")]
+    InvalidReplicationFactor = 38,
+    #[error("InvalidReplicaAssignment")]
+    InvalidReplicaAssignment = 39,
     #[error("InvalidRequest")]
     InvalidRequest = 42,
+    #[error("OutOfOrderSequenceNumber")]
+    OutOfOrderSequenceNumber = 45,
+    #[error("InvalidProducerEpoch")]
+    InvalidProducerEpoch = 47,
+    #[error("KafkaStorageError")]
+    KafkaStorageError = 56,
+    #[error("FetchSessionIdNotFound")]
+    FetchSessionIdNotFound = 70,
+    #[error("InvalidFetchSessionEpoch")]
+    InvalidFetchSessionEpoch = 71,
+    #[error("FencedLeaderEpoch")]
+    FencedLeaderEpoch = 74,
+    #[error("UnknownLeaderEpoch")]
+    UnknownLeaderEpoch = 76,
+    #[error("MemberIdRequired")]
+    MemberIdRequired = 79,
+    #[error("FencedInstanceId")]
+    FencedInstanceId = 82,
     #[error("UnknownTopicId")]
     UnknownTopicId = 100,
 }
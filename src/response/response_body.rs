@@ -1,23 +1,73 @@
 use api_versions::KafkaResponseBodyApiVersions;
+use create_partitions::KafkaResponseBodyCreatePartitions;
+use create_topics::KafkaResponseBodyCreateTopics;
+use delete_topics::KafkaResponseBodyDeleteTopics;
 use describe_topic_partitions::KafkaResponseBodyDescribeTopicPartitions;
 use fetch::KafkaResponseBodyFetch;
+use find_coordinator::KafkaResponseBodyFindCoordinator;
+use heartbeat::KafkaResponseBodyHeartbeat;
+use init_producer_id::KafkaResponseBodyInitProducerId;
+use join_group::KafkaResponseBodyJoinGroup;
+use leave_group::KafkaResponseBodyLeaveGroup;
+use list_offsets::KafkaResponseBodyListOffsets;
+use metadata::KafkaResponseBodyMetadata;
+use offset_commit::KafkaResponseBodyOffsetCommit;
+use offset_fetch::KafkaResponseBodyOffsetFetch;
+use produce::KafkaResponseBodyProduce;
+use sync_group::KafkaResponseBodySyncGroup;
 
 use crate::{
+    config::BrokerConfig,
     request::body::{
-        api_versions::ApiVersionsRequestBody,
+        api_versions::ApiVersionsRequestBody, create_partitions::CreatePartitionsRequestBody,
+        create_topics::CreateTopicsRequestBody, delete_topics::DeleteTopicsRequestBody,
         describe_topic_partitions::DescribeTopicPartitionsRequestBody, fetch::FetchRequestBody,
+        find_coordinator::FindCoordinatorRequestBody, heartbeat::HeartbeatRequestBody,
+        init_producer_id::InitProducerIdRequestBody, join_group::JoinGroupRequestBody,
+        leave_group::LeaveGroupRequestBody, list_offsets::ListOffsetsRequestBody,
+        metadata::MetadataRequestBody, offset_commit::OffsetCommitRequestBody,
+        offset_fetch::OffsetFetchRequestBody, produce::ProduceRequestBody,
+        sync_group::SyncGroupRequestBody,
     },
     traits::KafkaSeriarize,
 };
 
 mod api_versions;
+mod create_partitions;
+mod create_topics;
+mod delete_topics;
 mod describe_topic_partitions;
 mod fetch;
+mod find_coordinator;
+mod heartbeat;
+mod init_producer_id;
+mod join_group;
+mod leave_group;
+mod list_offsets;
+mod metadata;
+mod offset_commit;
+mod offset_fetch;
+mod produce;
+mod sync_group;
 
 pub enum KafkaResponseBody {
     Empty,
+    Produce(KafkaResponseBodyProduce),
     Fetch(KafkaResponseBodyFetch),
+    ListOffsets(KafkaResponseBodyListOffsets),
+    Metadata(KafkaResponseBodyMetadata),
+    OffsetCommit(KafkaResponseBodyOffsetCommit),
+    OffsetFetch(KafkaResponseBodyOffsetFetch),
+    FindCoordinator(KafkaResponseBodyFindCoordinator),
+    JoinGroup(KafkaResponseBodyJoinGroup),
+    Heartbeat(KafkaResponseBodyHeartbeat),
+    LeaveGroup(KafkaResponseBodyLeaveGroup),
+    SyncGroup(KafkaResponseBodySyncGroup),
     ApiVersions(KafkaResponseBodyApiVersions),
+    CreateTopics(KafkaResponseBodyCreateTopics),
+    DeleteTopics(KafkaResponseBodyDeleteTopics),
+    InitProducerId(KafkaResponseBodyInitProducerId),
+    CreatePartitions(KafkaResponseBodyCreatePartitions),
     DescribeTopicPartitions(KafkaResponseBodyDescribeTopicPartitions),
 }
 
@@ -32,6 +82,33 @@ impl KafkaResponseBody {
     }
 }
 
+// CreatePartitions
+impl KafkaResponseBody {
+    pub fn from_create_partitions_request_body(
+        body: &CreatePartitionsRequestBody,
+        config: &BrokerConfig,
+    ) -> Self {
+        Self::CreatePartitions(KafkaResponseBodyCreatePartitions::new(body, config))
+    }
+}
+
+// CreateTopics
+impl KafkaResponseBody {
+    pub fn from_create_topics_request_body(
+        body: &CreateTopicsRequestBody,
+        config: &BrokerConfig,
+    ) -> Self {
+        Self::CreateTopics(KafkaResponseBodyCreateTopics::new(body, config))
+    }
+}
+
+// DeleteTopics
+impl KafkaResponseBody {
+    pub fn from_delete_topics_request_body(body: &DeleteTopicsRequestBody) -> Self {
+        Self::DeleteTopics(KafkaResponseBodyDeleteTopics::new(body))
+    }
+}
+
 // DescribeTopicPartitions
 impl KafkaResponseBody {
     pub fn from_describe_topic_partitions_request_body(
@@ -43,8 +120,91 @@ impl KafkaResponseBody {
 
 // Fetch
 impl KafkaResponseBody {
-    pub fn from_fetch_request_body(body: &FetchRequestBody) -> Self {
-        Self::Fetch(KafkaResponseBodyFetch::new(body))
+    pub fn from_fetch_request_body(body: &FetchRequestBody, config: &BrokerConfig) -> Self {
+        Self::Fetch(KafkaResponseBodyFetch::new(body, config))
+    }
+}
+
+// FindCoordinator
+impl KafkaResponseBody {
+    pub fn from_find_coordinator_request_body(
+        body: &FindCoordinatorRequestBody,
+        config: &BrokerConfig,
+    ) -> Self {
+        Self::FindCoordinator(KafkaResponseBodyFindCoordinator::new(body, config))
+    }
+}
+
+// Heartbeat
+impl KafkaResponseBody {
+    pub fn from_heartbeat_request_body(body: &HeartbeatRequestBody) -> Self {
+        Self::Heartbeat(KafkaResponseBodyHeartbeat::new(body))
+    }
+}
+
+// InitProducerId
+impl KafkaResponseBody {
+    pub fn from_init_producer_id_request_body(body: &InitProducerIdRequestBody) -> Self {
+        Self::InitProducerId(KafkaResponseBodyInitProducerId::new(body))
+    }
+}
+
+// JoinGroup
+impl KafkaResponseBody {
+    pub fn from_join_group_request_body(body: &JoinGroupRequestBody) -> Self {
+        Self::JoinGroup(KafkaResponseBodyJoinGroup::new(body))
+    }
+}
+
+// LeaveGroup
+impl KafkaResponseBody {
+    pub fn from_leave_group_request_body(body: &LeaveGroupRequestBody) -> Self {
+        Self::LeaveGroup(KafkaResponseBodyLeaveGroup::new(body))
+    }
+}
+
+// ListOffsets
+impl KafkaResponseBody {
+    pub fn from_list_offsets_request_body(body: &ListOffsetsRequestBody) -> Self {
+        Self::ListOffsets(KafkaResponseBodyListOffsets::new(body))
+    }
+}
+
+// Metadata
+impl KafkaResponseBody {
+    pub fn from_metadata_request_body(body: &MetadataRequestBody, config: &BrokerConfig) -> Self {
+        Self::Metadata(KafkaResponseBodyMetadata::new(body, config))
+    }
+}
+
+// OffsetCommit
+impl KafkaResponseBody {
+    pub fn from_offset_commit_request_body(
+        body: &OffsetCommitRequestBody,
+        config: &BrokerConfig,
+    ) -> Self {
+        Self::OffsetCommit(KafkaResponseBodyOffsetCommit::new(body, config))
+    }
+}
+
+// OffsetFetch
+impl KafkaResponseBody {
+    pub fn from_offset_fetch_request_body(body: &OffsetFetchRequestBody) -> Self {
+        Self::OffsetFetch(KafkaResponseBodyOffsetFetch::new(body))
+    }
+}
+
+// Produce
+impl KafkaResponseBody {
+    pub fn from_produce_request_body(body: &ProduceRequestBody, config: &BrokerConfig) -> Self {
+        Self::Produce(KafkaResponseBodyProduce::new(body, config))
+    }
+}
+
+// SyncGroup
+impl KafkaResponseBody {
+    pub fn from_sync_group_request_body(body: &SyncGroupRequestBody) -> Self {
+        Self::SyncGroup(KafkaResponseBodySyncGroup::new(body))
     }
 }
 
@@ -60,8 +220,22 @@ impl KafkaSeriarize for KafkaResponseBody {
         match self {
             KafkaResponseBody::Empty => Ok(()),
             KafkaResponseBody::ApiVersions(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::CreatePartitions(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::CreateTopics(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::DeleteTopics(inner) => inner.serialize(writer, data),
             KafkaResponseBody::DescribeTopicPartitions(inner) => inner.serialize(writer, data),
             KafkaResponseBody::Fetch(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::FindCoordinator(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::Heartbeat(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::InitProducerId(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::JoinGroup(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::LeaveGroup(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::ListOffsets(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::Metadata(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::OffsetCommit(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::OffsetFetch(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::Produce(inner) => inner.serialize(writer, data),
+            KafkaResponseBody::SyncGroup(inner) => inner.serialize(writer, data),
         }
     }
 }
@@ -4,11 +4,37 @@ use crate::{
             SupportApiVersionsRequestVersion, API_VERSIONS_API_KEY, API_VERSIONS_MAX_VERSION,
             API_VERSIONS_MIN_VERSION,
         },
+        create_partitions::{
+            CREATE_PARTITIONS_API_KEY, CREATE_PARTITIONS_MAX_VERSION, CREATE_PARTITIONS_MIN_VERSION,
+        },
+        create_topics::{
+            CREATE_TOPICS_API_KEY, CREATE_TOPICS_MAX_VERSION, CREATE_TOPICS_MIN_VERSION,
+        },
+        delete_topics::{
+            DELETE_TOPICS_API_KEY, DELETE_TOPICS_MAX_VERSION, DELETE_TOPICS_MIN_VERSION,
+        },
         describe_topic_partitions::{
             DESCRIBE_TOPIC_MAX_VERSION, DESCRIBE_TOPIC_MIN_VERSION,
             DESCRIBE_TOPIC_PARTITIONS_API_KEY,
         },
         fetch::{FETCH_API_KEY, FETCH_MAX_VERSION, FETCH_MIN_VERSION},
+        find_coordinator::{
+            FIND_COORDINATOR_API_KEY, FIND_COORDINATOR_MAX_VERSION, FIND_COORDINATOR_MIN_VERSION,
+        },
+        heartbeat::{HEARTBEAT_API_KEY, HEARTBEAT_MAX_VERSION, HEARTBEAT_MIN_VERSION},
+        init_producer_id::{
+            INIT_PRODUCER_ID_API_KEY, INIT_PRODUCER_ID_MAX_VERSION, INIT_PRODUCER_ID_MIN_VERSION,
+        },
+        join_group::{JOIN_GROUP_API_KEY, JOIN_GROUP_MAX_VERSION, JOIN_GROUP_MIN_VERSION},
+        leave_group::{LEAVE_GROUP_API_KEY, LEAVE_GROUP_MAX_VERSION, LEAVE_GROUP_MIN_VERSION},
+        list_offsets::{LIST_OFFSETS_API_KEY, LIST_OFFSETS_MAX_VERSION, LIST_OFFSETS_MIN_VERSION},
+        metadata::{METADATA_API_KEY, METADATA_MAX_VERSION, METADATA_MIN_VERSION},
+        offset_commit::{
+            OFFSET_COMMIT_API_KEY, OFFSET_COMMIT_MAX_VERSION, OFFSET_COMMIT_MIN_VERSION,
+        },
+        offset_fetch::{OFFSET_FETCH_API_KEY, OFFSET_FETCH_MAX_VERSION, OFFSET_FETCH_MIN_VERSION},
+        produce::{PRODUCE_API_KEY, PRODUCE_MAX_VERSION, PRODUCE_MIN_VERSION},
+        sync_group::{SYNC_GROUP_API_KEY, SYNC_GROUP_MAX_VERSION, SYNC_GROUP_MIN_VERSION},
     },
     response::{
         error_code::KafkaError,
@@ -77,6 +103,30 @@ impl ApiKeyRange {
         }
     }
 
+    fn create_partitions() -> Self {
+        Self {
+            api_key: CREATE_PARTITIONS_API_KEY,
+            min_version: CREATE_PARTITIONS_MIN_VERSION,
+            max_version: CREATE_PARTITIONS_MAX_VERSION,
+        }
+    }
+
+    fn create_topics() -> Self {
+        Self {
+            api_key: CREATE_TOPICS_API_KEY,
+            min_version: CREATE_TOPICS_MIN_VERSION,
+            max_version: CREATE_TOPICS_MAX_VERSION,
+        }
+    }
+
+    fn delete_topics() -> Self {
+        Self {
+            api_key: DELETE_TOPICS_API_KEY,
+            min_version: DELETE_TOPICS_MIN_VERSION,
+            max_version: DELETE_TOPICS_MAX_VERSION,
+        }
+    }
+
     fn describe_topic_partitions() -> Self {
         Self {
             api_key: DESCRIBE_TOPIC_PARTITIONS_API_KEY,
@@ -92,6 +142,94 @@ impl ApiKeyRange {
             max_version: FETCH_MAX_VERSION,
         }
     }
+
+    fn find_coordinator() -> Self {
+        Self {
+            api_key: FIND_COORDINATOR_API_KEY,
+            min_version: FIND_COORDINATOR_MIN_VERSION,
+            max_version: FIND_COORDINATOR_MAX_VERSION,
+        }
+    }
+
+    fn heartbeat() -> Self {
+        Self {
+            api_key: HEARTBEAT_API_KEY,
+            min_version: HEARTBEAT_MIN_VERSION,
+            max_version: HEARTBEAT_MAX_VERSION,
+        }
+    }
+
+    fn init_producer_id() -> Self {
+        Self {
+            api_key: INIT_PRODUCER_ID_API_KEY,
+            min_version: INIT_PRODUCER_ID_MIN_VERSION,
+            max_version: INIT_PRODUCER_ID_MAX_VERSION,
+        }
+    }
+
+    fn join_group() -> Self {
+        Self {
+            api_key: JOIN_GROUP_API_KEY,
+            min_version: JOIN_GROUP_MIN_VERSION,
+            max_version: JOIN_GROUP_MAX_VERSION,
+        }
+    }
+
+    fn leave_group() -> Self {
+        Self {
+            api_key: LEAVE_GROUP_API_KEY,
+            min_version: LEAVE_GROUP_MIN_VERSION,
+            max_version: LEAVE_GROUP_MAX_VERSION,
+        }
+    }
+
+    fn list_offsets() -> Self {
+        Self {
+            api_key: LIST_OFFSETS_API_KEY,
+            min_version: LIST_OFFSETS_MIN_VERSION,
+            max_version: LIST_OFFSETS_MAX_VERSION,
+        }
+    }
+
+    fn metadata() -> Self {
+        Self {
+            api_key: METADATA_API_KEY,
+            min_version: METADATA_MIN_VERSION,
+            max_version: METADATA_MAX_VERSION,
+        }
+    }
+
+    fn offset_commit() -> Self {
+        Self {
+            api_key: OFFSET_COMMIT_API_KEY,
+            min_version: OFFSET_COMMIT_MIN_VERSION,
+            max_version: OFFSET_COMMIT_MAX_VERSION,
+        }
+    }
+
+    fn offset_fetch() -> Self {
+        Self {
+            api_key: OFFSET_FETCH_API_KEY,
+            min_version: OFFSET_FETCH_MIN_VERSION,
+            max_version: OFFSET_FETCH_MAX_VERSION,
+        }
+    }
+
+    fn produce() -> Self {
+        Self {
+            api_key: PRODUCE_API_KEY,
+            min_version: PRODUCE_MIN_VERSION,
+            max_version: PRODUCE_MAX_VERSION,
+        }
+    }
+
+    fn sync_group() -> Self {
+        Self {
+            api_key: SYNC_GROUP_API_KEY,
+            min_version: SYNC_GROUP_MIN_VERSION,
+            max_version: SYNC_GROUP_MAX_VERSION,
+        }
+    }
 }
 
 impl KafkaSeriarize for ApiKeyRange {
@@ -139,8 +277,22 @@ impl KafkaResponseBodyApiVersions {
                 throttle_time_ms,
             }),
             SupportApiVersionsRequestVersion::V4 => {
+                api_keys.push(ApiKeyRange::create_partitions());
+                api_keys.push(ApiKeyRange::create_topics());
+                api_keys.push(ApiKeyRange::delete_topics());
                 api_keys.push(ApiKeyRange::describe_topic_partitions());
                 api_keys.push(ApiKeyRange::fetch());
+                api_keys.push(ApiKeyRange::find_coordinator());
+                api_keys.push(ApiKeyRange::heartbeat());
+                api_keys.push(ApiKeyRange::init_producer_id());
+                api_keys.push(ApiKeyRange::join_group());
+                api_keys.push(ApiKeyRange::leave_group());
+                api_keys.push(ApiKeyRange::list_offsets());
+                api_keys.push(ApiKeyRange::metadata());
+                api_keys.push(ApiKeyRange::offset_commit());
+                api_keys.push(ApiKeyRange::offset_fetch());
+                api_keys.push(ApiKeyRange::produce());
+                api_keys.push(ApiKeyRange::sync_group());
                 Self::V4(ApiVersionsResponseBodyV4 {
                     error_code,
                     api_keys,
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    config::BrokerConfig,
+    consts::create_partitions::SupportCreatePartitionsRequestVersion,
+    metadata,
+    request::body::create_partitions::{CreatePartitionsRequestBody, CreatePartitionsTopic},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_nullable_string_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// CreatePartitions response, versions 0 through 3.
+pub struct KafkaResponseBodyCreatePartitions {
+    version: SupportCreatePartitionsRequestVersion,
+    throttle_time_ms: i32,
+    results: Vec<CreatePartitionsTopicResult>,
+}
+
+struct CreatePartitionsTopicResult {
+    name: String,
+    error_code: KafkaError,
+    error_message: Option<String>,
+}
+
+impl KafkaResponseBodyCreatePartitions {
+    pub fn new(request: &CreatePartitionsRequestBody, config: &BrokerConfig) -> Self {
+        let mut name_counts: HashMap<&str, usize> = HashMap::new();
+        for topic in &request.topics {
+            *name_counts.entry(topic.name.as_str()).or_default() += 1;
+        }
+        let results = request
+            .topics
+            .iter()
+            .map(|topic| {
+                if name_counts[topic.name.as_str()] > 1 {
+                    return CreatePartitionsTopicResult::error(
+                        topic,
+                        KafkaError::InvalidRequest,
+                        "Duplicate topic in request.".to_string(),
+                    );
+                }
+                CreatePartitionsTopicResult::create(topic, request.validate_only, config)
+            })
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            results,
+        }
+    }
+}
+
+impl CreatePartitionsTopicResult {
+    fn create(topic: &CreatePartitionsTopic, validate_only: bool, config: &BrokerConfig) -> Self {
+        if let Some(assignments) = &topic.assignments {
+            let Some(current) = metadata::topic_by_name(&topic.name) else {
+                return Self::error(
+                    topic,
+                    KafkaError::UnknownTopicOrPartition,
+                    "This server does not host this topic.".to_string(),
+                );
+            };
+            let added = topic.count - current.partitions.len() as i32;
+            if added > 0 && assignments.len() as i32 != added {
+                return Self::error(
+                    topic,
+                    KafkaError::InvalidReplicaAssignment,
+                    format!(
+                        "Increasing the number of partitions by {added} but {} assignments \
+                         provided.",
+                        assignments.len()
+                    ),
+                );
+            }
+            if assignments
+                .iter()
+                .any(|replicas| replicas != &[config.node_id])
+            {
+                return Self::error(
+                    topic,
+                    KafkaError::InvalidReplicaAssignment,
+                    format!(
+                        "New partitions must have broker {} as their only replica.",
+                        config.node_id
+                    ),
+                );
+            }
+        }
+        match metadata::create_partitions(&topic.name, topic.count, config.node_id, validate_only) {
+            Ok(()) => Self {
+                name: topic.name.clone(),
+                error_code: KafkaError::None,
+                error_message: None,
+            },
+            Err(e) => Self::error(topic, e.error_code(), e.to_string()),
+        }
+    }
+
+    fn error(topic: &CreatePartitionsTopic, error_code: KafkaError, error_message: String) -> Self {
+        Self {
+            name: topic.name.clone(),
+            error_code,
+            error_message: Some(error_message),
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyCreatePartitions {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        if self.version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.results, |writer, result| {
+                write_compact_string_stream(writer, &result.name)?;
+                let error_code: i16 = result.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)?;
+                write_compact_nullable_string_stream(writer, result.error_message)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, self.results, |writer, result| {
+                write_string_stream(writer, &result.name)?;
+                let error_code: i16 = result.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)?;
+                write_nullable_string_stream(writer, result.error_message)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV1},
+        },
+        traits::KafkaDeseriarize,
+    };
+
+    fn topic(name: &str, count: i32, assignments: Option<Vec<Vec<i32>>>) -> CreatePartitionsTopic {
+        CreatePartitionsTopic {
+            name: name.to_string(),
+            count,
+            assignments,
+        }
+    }
+
+    fn serialize(version: SupportCreatePartitionsRequestVersion) -> Vec<u8> {
+        let error = metadata::TopicError::InvalidPartitions("bad".to_string());
+        let body = KafkaResponseBodyCreatePartitions {
+            version,
+            throttle_time_ms: 0,
+            results: vec![
+                CreatePartitionsTopicResult {
+                    name: "foo".to_string(),
+                    error_code: KafkaError::None,
+                    error_message: None,
+                },
+                CreatePartitionsTopicResult::error(
+                    &topic("bar", 1, None),
+                    error.error_code(),
+                    error.to_string(),
+                ),
+            ],
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        // v0: an error code and nullable message per topic
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &2i32.to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"foo",
+            &0i16.to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"bar",
+            &37i16.to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"bad",
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportCreatePartitionsRequestVersion::V0),
+            expected
+        );
+
+        // v2: compact encoding and tagged fields
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[3, 4],
+            b"foo",
+            &0i16.to_be_bytes(),
+            &[0, 0, 4],
+            b"bar",
+            &37i16.to_be_bytes(),
+            &[4],
+            b"bad",
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportCreatePartitionsRequestVersion::V2),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_duplicate_topics_are_rejected() {
+        let topic = |count: i32| {
+            [
+                &3i16.to_be_bytes()[..],
+                b"foo",
+                &count.to_be_bytes(),
+                &(-1i32).to_be_bytes(),
+            ]
+            .concat()
+        };
+        let buf = [
+            &2i32.to_be_bytes()[..],
+            &topic(2),
+            &topic(3),
+            &0i32.to_be_bytes(),
+            &[1],
+        ]
+        .concat();
+        let header = KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+            request_api_key: RequestApiKey::CreatePartitions,
+            request_api_version: 0,
+            correlation_id: 1,
+            client_id: String::new(),
+        });
+        let request =
+            CreatePartitionsRequestBody::try_parse_from_reader(&mut &buf[..], &header).unwrap();
+        let response = KafkaResponseBodyCreatePartitions::new(&request, &BrokerConfig::default());
+        assert_eq!(response.results.len(), 2);
+        for result in &response.results {
+            assert_eq!(result.name, "foo");
+            assert_eq!(result.error_code, KafkaError::InvalidRequest);
+            assert_eq!(
+                result.error_message.as_deref(),
+                Some("Duplicate topic in request.")
+            );
+        }
+    }
+
+    #[test]
+    fn test_assignments_for_an_unknown_topic_are_rejected() {
+        let node_id = BrokerConfig::default().node_id;
+        let result = CreatePartitionsTopicResult::create(
+            &topic(
+                "create-partitions-test-unknown",
+                2,
+                Some(vec![vec![node_id]]),
+            ),
+            true,
+            &BrokerConfig::default(),
+        );
+        assert_eq!(result.error_code, KafkaError::UnknownTopicOrPartition);
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    config::BrokerConfig,
+    consts::create_topics::SupportCreateTopicsRequestVersion,
+    metadata,
+    request::body::create_topics::{CreatableTopic, CreateTopicsRequestBody},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_nullable_string_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// CreateTopics response, versions 2 through 7.
+///
+/// This broker is the only one in the cluster, so every partition gets it as
+/// its single replica and a replication factor above 1 is refused.
+pub struct KafkaResponseBodyCreateTopics {
+    version: SupportCreateTopicsRequestVersion,
+    throttle_time_ms: i32,
+    topics: Vec<CreatableTopicResult>,
+}
+
+struct CreatableTopicResult {
+    name: String,
+    topic_id: [u8; 16],
+    error_code: KafkaError,
+    error_message: Option<String>,
+    num_partitions: i32,
+    replication_factor: i16,
+}
+
+impl KafkaResponseBodyCreateTopics {
+    pub fn new(request: &CreateTopicsRequestBody, config: &BrokerConfig) -> Self {
+        let mut name_counts: HashMap<&str, usize> = HashMap::new();
+        for topic in &request.topics {
+            *name_counts.entry(topic.name.as_str()).or_default() += 1;
+        }
+        let topics = request
+            .topics
+            .iter()
+            .map(|topic| {
+                if name_counts[topic.name.as_str()] > 1 {
+                    return CreatableTopicResult::error(
+                        topic,
+                        KafkaError::InvalidRequest,
+                        "Create topics request contains duplicate entries for the topic."
+                            .to_string(),
+                    );
+                }
+                CreatableTopicResult::create(topic, request.validate_only, config)
+            })
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            topics,
+        }
+    }
+}
+
+impl CreatableTopicResult {
+    fn create(topic: &CreatableTopic, validate_only: bool, config: &BrokerConfig) -> Self {
+        let num_partitions = if topic.assignments.is_empty() {
+            if topic.replication_factor != -1 && topic.replication_factor < 1 {
+                return Self::error(
+                    topic,
+                    KafkaError::InvalidReplicationFactor,
+                    "Replication factor must be larger than 0, or -1 to use the default value."
+                        .to_string(),
+                );
+            }
+            if topic.replication_factor > 1 {
+                return Self::error(
+                    topic,
+                    KafkaError::InvalidReplicationFactor,
+                    format!(
+                        "Unable to replicate the partition {} time(s): only 1 broker is \
+                         registered.",
+                        topic.replication_factor
+                    ),
+                );
+            }
+            if topic.num_partitions == -1 {
+                config.num_partitions
+            } else {
+                topic.num_partitions
+            }
+        } else {
+            if topic.num_partitions != -1 || topic.replication_factor != -1 {
+                return Self::error(
+                    topic,
+                    KafkaError::InvalidRequest,
+                    "Both numPartitions or replicationFactor and replicasAssignments were set. \
+                     Both cannot be used at the same time."
+                        .to_string(),
+                );
+            }
+            let valid = topic
+                .assignments
+                .iter()
+                .enumerate()
+                .all(|(index, assignment)| {
+                    assignment.partition_index == index as i32
+                        && assignment.broker_ids == [config.node_id]
+                });
+            if !valid {
+                return Self::error(
+                    topic,
+                    KafkaError::InvalidReplicaAssignment,
+                    format!(
+                        "Partitions must be numbered from 0 with broker {} as their only \
+                         replica.",
+                        config.node_id
+                    ),
+                );
+            }
+            topic.assignments.len() as i32
+        };
+
+        match metadata::create_new_topic(&topic.name, num_partitions, config.node_id, validate_only)
+        {
+            Ok(topic_id) => Self {
+                name: topic.name.clone(),
+                topic_id,
+                error_code: KafkaError::None,
+                error_message: None,
+                num_partitions,
+                replication_factor: 1,
+            },
+            Err(e) => Self::error(topic, e.error_code(), e.to_string()),
+        }
+    }
+
+    fn error(topic: &CreatableTopic, error_code: KafkaError, error_message: String) -> Self {
+        Self {
+            name: topic.name.clone(),
+            topic_id: [0; 16],
+            error_code,
+            error_message: Some(error_message),
+            num_partitions: -1,
+            replication_factor: -1,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyCreateTopics {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.topics, |writer, topic| {
+                topic.serialize(writer, version)
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, self.topics, |writer, topic| {
+                topic.serialize(writer, version)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for CreatableTopicResult {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportCreateTopicsRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let flexible = version.is_flexible();
+        if flexible {
+            write_compact_string_stream(writer, &self.name)?;
+        } else {
+            write_string_stream(writer, &self.name)?;
+        }
+        if version >= SupportCreateTopicsRequestVersion::V7 {
+            writer.write_all(&self.topic_id)?;
+        }
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        if flexible {
+            write_compact_nullable_string_stream(writer, self.error_message)?;
+            writer.write_i32::<BigEndian>(self.num_partitions)?;
+            writer.write_i16::<BigEndian>(self.replication_factor)?;
+            // topic configs are not tracked: an empty compact array, or null
+            // on error
+            let configs_length: u8 = if self.error_code == KafkaError::None {
+                1
+            } else {
+                0
+            };
+            writer.write_u8(configs_length)?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_nullable_string_stream(writer, self.error_message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::body::create_topics::CreatableReplicaAssignment;
+
+    /// Requests refused before the metadata log is touched.
+    fn refused(topic: CreatableTopic) -> KafkaError {
+        let result = CreatableTopicResult::create(&topic, false, &BrokerConfig::default());
+        assert_eq!((result.num_partitions, result.replication_factor), (-1, -1));
+        assert!(result.error_message.is_some());
+        result.error_code
+    }
+
+    fn assignment(partition_index: i32, broker_ids: Vec<i32>) -> CreatableReplicaAssignment {
+        CreatableReplicaAssignment {
+            partition_index,
+            broker_ids,
+        }
+    }
+
+    #[test]
+    fn test_refuses_what_one_broker_cannot_host() {
+        let node_id = BrokerConfig::default().node_id;
+        for replication_factor in [0, -2, 2, 3] {
+            assert_eq!(
+                refused(CreatableTopic::new(
+                    "foo",
+                    1,
+                    replication_factor,
+                    Vec::new()
+                )),
+                KafkaError::InvalidReplicationFactor
+            );
+        }
+        assert_eq!(
+            refused(CreatableTopic::new(
+                "foo",
+                1,
+                -1,
+                vec![assignment(0, vec![node_id])]
+            )),
+            KafkaError::InvalidRequest
+        );
+        for assignments in [
+            vec![assignment(0, vec![node_id, node_id + 1])],
+            vec![assignment(0, vec![node_id + 1])],
+            vec![assignment(1, vec![node_id])],
+        ] {
+            assert_eq!(
+                refused(CreatableTopic::new("foo", -1, -1, assignments)),
+                KafkaError::InvalidReplicaAssignment
+            );
+        }
+    }
+
+    #[test]
+    fn test_serialize_topic_already_exists() {
+        let error = metadata::TopicError::AlreadyExists("foo".to_string());
+        let result = |version| {
+            let body = KafkaResponseBodyCreateTopics {
+                version,
+                throttle_time_ms: 0,
+                topics: vec![CreatableTopicResult::error(
+                    &CreatableTopic::new("foo", 1, -1, Vec::new()),
+                    error.error_code(),
+                    error.to_string(),
+                )],
+            };
+            let mut buf = Vec::new();
+            body.serialize(&mut buf, ()).unwrap();
+            buf
+        };
+        let message = "Topic 'foo' already exists.";
+
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &1i32.to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"foo",
+            &36i16.to_be_bytes(),
+            &(message.len() as i16).to_be_bytes(),
+            message.as_bytes(),
+        ]
+        .concat();
+        assert_eq!(result(SupportCreateTopicsRequestVersion::V2), expected);
+
+        // v5: compact encoding, partition count and replication factor, and
+        // null configs on error
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[2, 4],
+            b"foo",
+            &36i16.to_be_bytes(),
+            &[message.len() as u8 + 1],
+            message.as_bytes(),
+            &(-1i32).to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+            &[0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(result(SupportCreateTopicsRequestVersion::V5), expected);
+    }
+}
@@ -0,0 +1,229 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::delete_topics::SupportDeleteTopicsRequestVersion,
+    metadata,
+    request::body::delete_topics::{DeleteTopicState, DeleteTopicsRequestBody},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// DeleteTopics response, versions 1 through 6.
+pub struct KafkaResponseBodyDeleteTopics {
+    version: SupportDeleteTopicsRequestVersion,
+    throttle_time_ms: i32,
+    responses: Vec<DeletableTopicResult>,
+}
+
+struct DeletableTopicResult {
+    name: Option<String>,
+    topic_id: [u8; 16],
+    error_code: KafkaError,
+    error_message: Option<String>,
+}
+
+impl KafkaResponseBodyDeleteTopics {
+    pub fn new(request: &DeleteTopicsRequestBody) -> Self {
+        let responses = request
+            .topics
+            .iter()
+            .map(DeletableTopicResult::delete)
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            responses,
+        }
+    }
+}
+
+impl DeletableTopicResult {
+    fn delete(topic: &DeleteTopicState) -> Self {
+        let error = |error_code: KafkaError, error_message: &str| Self {
+            name: topic.name.clone(),
+            topic_id: topic.topic_id,
+            error_code,
+            error_message: Some(error_message.to_string()),
+        };
+        let topic_id = match &topic.name {
+            Some(_) if topic.topic_id != [0; 16] => {
+                return error(
+                    KafkaError::InvalidRequest,
+                    "A topic must be named by either name or id, not both.",
+                )
+            }
+            Some(name) => match metadata::topic_id_by_name(name) {
+                Some(topic_id) => topic_id,
+                None => {
+                    return error(
+                        KafkaError::UnknownTopicOrPartition,
+                        "This server does not host this topic.",
+                    )
+                }
+            },
+            None => topic.topic_id,
+        };
+        match metadata::delete_topic(&topic_id) {
+            Ok(deleted) => Self {
+                name: Some(deleted.name),
+                topic_id,
+                error_code: KafkaError::None,
+                error_message: None,
+            },
+            Err(metadata::TopicError::UnknownTopic) if topic.name.is_none() => error(
+                KafkaError::UnknownTopicId,
+                "This server does not host this topic ID.",
+            ),
+            Err(e) => error(e.error_code(), &e.to_string()),
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyDeleteTopics {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.responses, |writer, response| {
+                if version.uses_topic_ids() {
+                    write_compact_nullable_string_stream(writer, response.name)?;
+                    writer.write_all(&response.topic_id)?;
+                } else {
+                    write_compact_string_stream(writer, response.name.unwrap_or_default())?;
+                }
+                let error_code: i16 = response.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)?;
+                if version >= SupportDeleteTopicsRequestVersion::V5 {
+                    write_compact_nullable_string_stream(writer, response.error_message)?;
+                }
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, self.responses, |writer, response| {
+                write_string_stream(writer, response.name.unwrap_or_default())?;
+                let error_code: i16 = response.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOPIC_ID: [u8; 16] = [7; 16];
+
+    fn serialize(version: SupportDeleteTopicsRequestVersion) -> Vec<u8> {
+        let body = KafkaResponseBodyDeleteTopics {
+            version,
+            throttle_time_ms: 0,
+            responses: vec![
+                DeletableTopicResult {
+                    name: Some("foo".to_string()),
+                    topic_id: TOPIC_ID,
+                    error_code: KafkaError::None,
+                    error_message: None,
+                },
+                DeletableTopicResult {
+                    name: None,
+                    topic_id: [9; 16],
+                    error_code: KafkaError::UnknownTopicId,
+                    error_message: Some("gone".to_string()),
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        // v1: names and error codes
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &2i32.to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"foo",
+            &0i16.to_be_bytes(),
+            &0i16.to_be_bytes(),
+            &100i16.to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(serialize(SupportDeleteTopicsRequestVersion::V1), expected);
+
+        // v4: compact encoding and tagged fields
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[3, 4],
+            b"foo",
+            &0i16.to_be_bytes(),
+            &[0, 1],
+            &100i16.to_be_bytes(),
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportDeleteTopicsRequestVersion::V4), expected);
+
+        // v5 adds the error message
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[3, 4],
+            b"foo",
+            &0i16.to_be_bytes(),
+            &[0, 0, 1],
+            &100i16.to_be_bytes(),
+            &[5],
+            b"gone",
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportDeleteTopicsRequestVersion::V5), expected);
+
+        // v6 adds the topic id, and the name becomes nullable
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[3, 4],
+            b"foo",
+            &TOPIC_ID,
+            &0i16.to_be_bytes(),
+            &[0, 0, 0],
+            &[9; 16],
+            &100i16.to_be_bytes(),
+            &[5],
+            b"gone",
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportDeleteTopicsRequestVersion::V6), expected);
+    }
+
+    #[test]
+    fn test_name_and_id_together_are_rejected() {
+        let result = DeletableTopicResult::delete(&DeleteTopicState {
+            name: Some("foo".to_string()),
+            topic_id: TOPIC_ID,
+        });
+        assert_eq!(result.error_code, KafkaError::InvalidRequest);
+        assert_eq!(result.name.as_deref(), Some("foo"));
+        assert_eq!(result.topic_id, TOPIC_ID);
+        assert!(result.error_message.is_some());
+    }
+}
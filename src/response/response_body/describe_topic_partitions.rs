@@ -1,13 +1,11 @@
-use std::marker::PhantomData;
+use std::collections::BTreeSet;
 
 use crate::{
-    globals::RECORD_BATCHES,
-    records::{
-        record_value::{ClusterMetadataValue, PartitionRecord},
-        RecordBatch,
-    },
+    consts::describe_topic_partitions::DESCRIBE_TOPIC_MAX_PARTITIONS,
+    metadata::{self, TopicImage},
+    records::record_value::PartitionRecord,
     request::body::describe_topic_partitions::{
-        DescribeTopicPartitionsRequestBody, DescribeTopicPartitionsRequestBodyV0,
+        Cursor, DescribeTopicPartitionsRequestBody, DescribeTopicPartitionsRequestBodyV0,
     },
     response::{
         error_code::KafkaError,
@@ -26,7 +24,9 @@ pub enum KafkaResponseBodyDescribeTopicPartitions {
 pub struct KafkaResponseBodyDescribeTopicPartitionsV0 {
     throttle_time_ms: i32,
     topics: Vec<Topic>,
-    next_cursor: PhantomData<String>, // nullable field, here we use empty string
+    /// Where the next request should resume; `None` once every partition
+    /// has been described.
+    next_cursor: Option<Cursor>,
 }
 
 pub struct Topic {
@@ -41,8 +41,8 @@ pub struct Topic {
 pub struct Partition {
     error_code: KafkaError,
     index: i32,
-    leader_id: i16,
-    leader_epoch: i16,
+    leader_id: i32,
+    leader_epoch: i32,
     replicas: Vec<i32>,
     isrs: Vec<i32>,
     eligible_leader_replicas: Vec<i32>,
@@ -65,84 +65,128 @@ impl KafkaResponseBodyDescribeTopicPartitionsV0 {
 
     fn from_request_v0(request: &DescribeTopicPartitionsRequestBodyV0) -> Self {
         let throttle_time_ms = 0;
-        let mut topics = Vec::with_capacity(request.topics.len());
-        for topic in &request.topics {
-            if let Some(record_batches) = RECORD_BATCHES.get() {
-                let record_batches = record_batches.read();
-                if let Ok(record_batches_guard) = record_batches {
-                    topics.push(Topic::query_from_record_batches(
-                        topic.clone(),
-                        &record_batches_guard,
-                    ));
-                } else {
-                    topics.push(Topic::new_unknown(topic.clone()));
-                }
-            } else {
-                topics.push(Topic::new_unknown(topic.clone()));
+        let describe_all = request.topics.is_empty();
+        let names: BTreeSet<String> = if describe_all {
+            metadata::topics()
+                .into_iter()
+                .map(|topic| topic.name)
+                .collect()
+        } else {
+            request.topics.iter().cloned().collect()
+        };
+        if let Some(cursor) = &request.cursor {
+            if !describe_all && !names.contains(&cursor.topic_name) {
+                let topics = names
+                    .into_iter()
+                    .map(|name| Topic::error(name, KafkaError::InvalidRequest))
+                    .collect();
+                return Self {
+                    throttle_time_ms,
+                    topics,
+                    next_cursor: None,
+                };
             }
         }
+        let limit = request
+            .response_partition_limit
+            .clamp(1, DESCRIBE_TOPIC_MAX_PARTITIONS) as usize;
+        let (topics, next_cursor) = describe_topics(
+            names,
+            request.cursor.as_ref(),
+            limit,
+            describe_all,
+            metadata::topic_by_name,
+        );
         Self {
             throttle_time_ms,
             topics,
-            next_cursor: PhantomData,
+            next_cursor,
+        }
+    }
+}
+
+/// Describe the topics `names`, in order, starting at `cursor` and stopping
+/// once `limit` partitions are described. Returns the cursor of the first
+/// partition left out, if any.
+///
+/// Topics `lookup` does not know are reported as unknown, or skipped with
+/// `skip_unknown` since they were deleted after `names` was listed.
+fn describe_topics(
+    names: impl IntoIterator<Item = String>,
+    cursor: Option<&Cursor>,
+    limit: usize,
+    skip_unknown: bool,
+    lookup: impl Fn(&str) -> Option<TopicImage>,
+) -> (Vec<Topic>, Option<Cursor>) {
+    let mut names = names
+        .into_iter()
+        .skip_while(|name| cursor.is_some_and(|cursor| *name < cursor.topic_name));
+    let mut remaining = limit;
+    let mut topics = Vec::new();
+    while let Some(name) = names.next() {
+        let Some(topic_image) = lookup(&name) else {
+            if !skip_unknown {
+                topics.push(Topic::error(name, KafkaError::UnknownTopicOrPartition));
+            }
+            continue;
+        };
+        let first_partition = match cursor {
+            Some(cursor) if cursor.topic_name == name => cursor.partition_index,
+            _ => 0,
+        };
+        let mut partitions = topic_image
+            .partitions
+            .range(first_partition..)
+            .map(|(_, p)| p);
+        let described: Vec<Partition> = partitions
+            .by_ref()
+            .take(remaining)
+            .map(Partition::from_partition_record)
+            .collect();
+        remaining -= described.len();
+        let next_partition = partitions.next().map(|partition| partition.partition_id);
+        topics.push(Topic::from_image(&topic_image, described));
+
+        if let Some(partition_index) = next_partition {
+            return (
+                topics,
+                Some(Cursor {
+                    topic_name: name,
+                    partition_index,
+                }),
+            );
+        }
+        if remaining == 0 {
+            let next_cursor = names.next().map(|topic_name| Cursor {
+                topic_name,
+                partition_index: 0,
+            });
+            return (topics, next_cursor);
         }
     }
+    (topics, None)
 }
 
 impl Topic {
-    fn new_unknown(topic: String) -> Self {
+    fn error(topic: String, error_code: KafkaError) -> Self {
         Self {
-            error_code: KafkaError::UnknownTopicOrPartition,
+            error_code,
             name: topic,
             id: [0u8; 16],
-            is_internal: true,
+            is_internal: false,
             partitions: Vec::new(),
             authorized_operation: 0,
         }
     }
 
-    fn query_from_record_batches(topic: String, record_batches: &Vec<RecordBatch>) -> Self {
-        let mut is_found = false;
-        let mut partitions = Vec::new();
-        let mut topic_uuid = [0u8; 16];
-        for record_batch in record_batches {
-            let records = &record_batch.records;
-            if records.is_empty() {
-                continue;
-            }
-            let first_record = &records[0].value.payload;
-            match first_record {
-                ClusterMetadataValue::Topic(topic_record) => {
-                    if topic_record.topic_name != topic {
-                        continue;
-                    }
-                    is_found = true;
-                    topic_uuid = topic_record.uuid;
-                }
-                _ => continue,
-            }
-            for record in records[1..].iter() {
-                let record = &record.value.payload;
-                if let ClusterMetadataValue::Partition(inner) = record {
-                    if inner.topic_id != topic_uuid {
-                        continue;
-                    }
-                    let partition = Partition::from_partition_record(inner);
-                    partitions.push(partition);
-                }
-            }
-        }
-        if is_found {
-            Self {
-                error_code: KafkaError::None,
-                name: topic,
-                id: topic_uuid,
-                is_internal: true,
-                partitions,
-                authorized_operation: 0,
-            }
-        } else {
-            Self::new_unknown(topic)
+    fn from_image(topic_image: &TopicImage, partitions: Vec<Partition>) -> Self {
+        Self {
+            error_code: KafkaError::None,
+            name: topic_image.name.clone(),
+            id: topic_image.id,
+            is_internal: metadata::is_internal_topic(&topic_image.name),
+            partitions,
+            authorized_operation: 0,
         }
     }
 }
@@ -161,8 +205,8 @@ impl Partition {
         Self {
             error_code,
             index,
-            leader_id: leader_id as i16,
-            leader_epoch: leader_epoch as i16,
+            leader_id,
+            leader_epoch,
             replicas,
             isrs,
             eligible_leader_replicas,
@@ -197,7 +241,16 @@ impl KafkaSeriarize for KafkaResponseBodyDescribeTopicPartitionsV0 {
             topic.serialize(writer, ())?;
             write_kafka_tagged_fields_stream(writer, Vec::new())
         })?;
-        writer.write_all(&(-1i8).to_be_bytes())?;
+        match self.next_cursor {
+            // a nullable struct is prefixed with -1 when null, 1 otherwise
+            None => writer.write_all(&(-1i8).to_be_bytes())?,
+            Some(cursor) => {
+                writer.write_all(&1i8.to_be_bytes())?;
+                write_compact_string_stream(writer, cursor.topic_name)?;
+                writer.write_all(&cursor.partition_index.to_be_bytes())?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())?;
+            }
+        }
         write_kafka_tagged_fields_stream(writer, Vec::new())
     }
 }
@@ -211,7 +264,7 @@ impl KafkaSeriarize for Topic {
         writer.write_all(&error_code.to_be_bytes())?;
         write_compact_string_stream(writer, self.name)?;
         writer.write_all(&self.id)?;
-        let is_internal: i8 = if self.is_internal { 0 } else { 1 };
+        let is_internal = self.is_internal as i8;
         writer.write_all(&is_internal.to_be_bytes())?;
         write_kafka_compact_array_stream(writer, self.partitions, |writer, partition| {
             partition.serialize(writer, ())?;
@@ -250,3 +303,95 @@ impl KafkaSeriarize for Partition {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::record_value::{ClusterMetadataRecord, ClusterMetadataValue};
+
+    fn topic(name: &str, partition_count: i32) -> TopicImage {
+        let ClusterMetadataValue::Partition(record) =
+            ClusterMetadataRecord::mock_partition_record().payload
+        else {
+            unreachable!()
+        };
+        let partitions = (0..partition_count)
+            .map(|partition_id| {
+                let record = PartitionRecord {
+                    partition_id,
+                    ..record.clone()
+                };
+                (partition_id, record)
+            })
+            .collect();
+        TopicImage {
+            name: name.to_string(),
+            id: [name.len() as u8; 16],
+            partitions,
+        }
+    }
+
+    fn lookup(name: &str) -> Option<TopicImage> {
+        match name {
+            "a" => Some(topic("a", 3)),
+            "bb" => Some(topic("bb", 2)),
+            "ccc" => Some(topic("ccc", 1)),
+            _ => None,
+        }
+    }
+
+    fn page(
+        names: &[&str],
+        cursor: Option<&Cursor>,
+        limit: usize,
+    ) -> (Vec<(String, Vec<i32>)>, Option<Cursor>) {
+        let names = names.iter().map(|name| name.to_string());
+        let (topics, next_cursor) = describe_topics(names, cursor, limit, false, lookup);
+        let topics = topics
+            .into_iter()
+            .map(|topic| {
+                let partitions = topic.partitions.iter().map(|p| p.index).collect();
+                (topic.name, partitions)
+            })
+            .collect();
+        (topics, next_cursor)
+    }
+
+    fn cursor(topic_name: &str, partition_index: i32) -> Cursor {
+        Cursor {
+            topic_name: topic_name.to_string(),
+            partition_index,
+        }
+    }
+
+    #[test]
+    fn test_describe_topics_pages() {
+        let names = ["a", "bb", "ccc"];
+        let (topics, next) = page(&names, None, 2);
+        assert_eq!(topics, [("a".to_string(), vec![0, 1])]);
+        assert_eq!(next, Some(cursor("a", 2)));
+
+        let (topics, next) = page(&names, next.as_ref(), 3);
+        assert_eq!(
+            topics,
+            [("a".to_string(), vec![2]), ("bb".to_string(), vec![0, 1])]
+        );
+        assert_eq!(next, Some(cursor("ccc", 0)));
+
+        let (topics, next) = page(&names, next.as_ref(), 3);
+        assert_eq!(topics, [("ccc".to_string(), vec![0])]);
+        assert_eq!(next, None);
+
+        // unknown topics are reported without using up the limit
+        let (topics, next) = page(&["a", "b", "ccc"], Some(&cursor("a", 1)), 3);
+        assert_eq!(
+            topics,
+            [
+                ("a".to_string(), vec![1, 2]),
+                ("b".to_string(), vec![]),
+                ("ccc".to_string(), vec![0]),
+            ]
+        );
+        assert_eq!(next, None);
+    }
+}
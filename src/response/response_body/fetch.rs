@@ -1,94 +1,182 @@
-use std::io::Cursor;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
-    globals::RECORD_BATCHES,
-    records::RecordBatch,
-    request::{
-        self,
-        body::fetch::{FetchRequestBody, FetchRequestBodyV16},
-    },
+    common_structs::tagged_field::TaggedField,
+    config::BrokerConfig,
+    consts::{fetch::SupportFetchRequestVersion, list_offsets::READ_COMMITTED},
+    fetch_session::{FetchContext, FetchPosition, PartitionOffsets, SessionPartition},
+    globals::{FETCH_SESSION_CACHE, LOG_MANAGER},
+    metadata,
+    request::body::fetch::FetchRequestBody,
     response::{
         error_code::KafkaError,
         utils::{
-            write_compact_vec_u8_stream, write_kafka_compact_array_stream,
-            write_kafka_tagged_fields_stream,
+            write_compact_string_stream, write_compact_vec_u8_stream, write_kafka_array_stream,
+            write_kafka_compact_array_stream, write_kafka_tagged_fields_stream,
+            write_string_stream, write_vec_u8_stream,
         },
     },
     traits::KafkaSeriarize,
 };
-use binrw::BinWrite;
 use byteorder::{BigEndian, WriteBytesExt};
+use tokio::sync::Notify;
 
-pub enum KafkaResponseBodyFetch {
-    V16(FetchResponseBodyV16),
+/// Fetch response, versions 4 through 16, mirroring [`FetchRequestBody`].
+pub struct KafkaResponseBodyFetch {
+    version: SupportFetchRequestVersion,
+    throttle_time_ms: i32,
+    error_code: KafkaError,
+    /// This broker, which only serves the partitions it leads.
+    node_id: i32,
+    /// The fetch session, which decides which partitions are read and which
+    /// of them the response includes.
+    context: FetchContext,
+    max_bytes: i32,
+    /// Whether only committed records are read, up to the last stable offset.
+    read_committed: bool,
+    /// The partitions of `context`, as last read.
+    responses: Vec<(SessionPartition, Partition)>,
+    /// Woken by the next append to a fetched partition, if the fetch may
+    /// wait for more data.
+    appended: Option<Arc<Notify>>,
+    min_bytes: usize,
+    max_wait: Duration,
 }
 
 impl KafkaResponseBodyFetch {
-    pub fn new(request: &FetchRequestBody) -> Self {
-        match request {
-            FetchRequestBody::V16(request) => Self::V16(FetchResponseBodyV16::new(request)),
-        }
+    pub fn new(request: &FetchRequestBody, config: &BrokerConfig) -> Self {
+        let version = request.get_api_version();
+        let partitions = request
+            .topics
+            .iter()
+            .flat_map(|topic| {
+                topic.partitions.iter().map(|partition| {
+                    let position = FetchPosition {
+                        fetch_offset: partition.fetch_offset,
+                        partition_max_bytes: partition.partition_max_bytes,
+                        current_leader_epoch: partition.current_leader_epoch,
+                        last_fetched_epoch: partition.last_fetched_epoch,
+                    };
+                    (
+                        session_partition(&topic.name, topic.topic_id, partition.index),
+                        position,
+                    )
+                })
+            })
+            .collect();
+        let forgotten = request
+            .forgotten_topics
+            .iter()
+            .flat_map(|topic| {
+                topic
+                    .partitions
+                    .iter()
+                    .map(|index| session_partition(&topic.name, topic.topic_id, *index))
+            })
+            .collect();
+        let context = match FETCH_SESSION_CACHE.get() {
+            Some(cache) => cache.new_context(
+                request.session_id,
+                request.session_epoch,
+                partitions,
+                forgotten,
+            ),
+            None => Ok(FetchContext::sessionless(partitions)),
+        };
+        let (error_code, context) = match context {
+            Ok(context) => (KafkaError::None, context),
+            Err(error_code) => (error_code, FetchContext::sessionless(Vec::new())),
+        };
+
+        // watch the partitions while reading them, so that no append
+        // between the read and the wait goes unnoticed
+        let appended = (request.max_wait_ms > 0 && !context.partitions.is_empty())
+            .then(|| Arc::new(Notify::new()));
+        let mut body = Self {
+            version,
+            throttle_time_ms: 0,
+            error_code,
+            node_id: config.node_id,
+            context,
+            max_bytes: request.max_bytes,
+            read_committed: request.isolation_level == READ_COMMITTED,
+            responses: Vec::new(),
+            appended,
+            min_bytes: request.min_bytes.max(0) as usize,
+            max_wait: Duration::from_millis(request.max_wait_ms.max(0) as u64),
+        };
+        body.read();
+        body
     }
-}
 
-impl KafkaSeriarize for KafkaResponseBodyFetch {
-    type Error = std::io::Error;
-    type DependentData<'a> = ();
+    /// (Re)read every partition of the fetch, charging the bytes returned
+    /// against the request's `max_bytes`.
+    pub fn read(&mut self) {
+        let (version, node_id, read_committed) = (self.version, self.node_id, self.read_committed);
+        let mut budget = FetchBudget::new(self.max_bytes);
+        let appended = self.appended.as_ref();
+        self.responses = self
+            .context
+            .partitions
+            .iter()
+            .map(|(partition, position)| {
+                let response = Partition::fetch(
+                    partition,
+                    position,
+                    version,
+                    node_id,
+                    read_committed,
+                    &mut budget,
+                    appended,
+                );
+                (partition.clone(), response)
+            })
+            .collect();
+    }
 
-    fn serialize<W: std::io::Write>(
-        self,
-        writer: &mut W,
-        _data: Self::DependentData<'_>,
-    ) -> Result<(), Self::Error> {
-        match self {
-            KafkaResponseBodyFetch::V16(inner) => inner.serialize(writer, ()),
+    /// If fewer than `min_bytes` were read and no partition failed or
+    /// diverged, the
+    /// notification of the next append to a fetched partition and how long
+    /// the fetch may wait for it in all.
+    pub fn delay(&self) -> Option<(Arc<Notify>, Duration)> {
+        let appended = self.appended.as_ref()?;
+        let partitions = || self.responses.iter().map(|(_, partition)| partition);
+        if partitions().any(|partition| {
+            partition.error_code != KafkaError::None || partition.diverging_epoch.is_some()
+        }) {
+            return None;
         }
+        let bytes: usize = partitions().map(|partition| partition.records.len()).sum();
+        (bytes < self.min_bytes).then(|| (appended.clone(), self.max_wait))
     }
-}
-
-pub struct FetchResponseBodyV16 {
-    throttle_time_ms: i32,
-    error_code: KafkaError,
-    session_id: i32,
-    responses: Vec<Topic>,
-}
 
-impl FetchResponseBodyV16 {
-    fn new(request: &FetchRequestBodyV16) -> Self {
-        if request.topics.is_empty() {
-            return Self::empty();
-        } else {
-            let throttle_time_ms = 0;
-            let error_code = KafkaError::None;
-            let session_id = request.session_id;
-            let mut topics = Vec::new();
-            if let Some(records) = RECORD_BATCHES.get() {
-                if let Ok(records_guard) = records.read() {
-                    for topic in &request.topics {
-                        topics.push(Topic::new(topic, &records_guard));
-                    }
-                }
-            }
-            Self {
-                throttle_time_ms,
-                error_code,
-                session_id,
-                responses: topics,
-            }
-        }
+    /// Record the response in the fetch session about to be sent and, for an
+    /// incremental fetch, leave out the partitions with nothing new.
+    pub fn complete(&mut self) {
+        let Some(cache) = FETCH_SESSION_CACHE.get() else {
+            return;
+        };
+        let responses = self.responses.iter().map(|(key, partition)| {
+            let has_data = !partition.records.is_empty()
+                || partition.error_code != KafkaError::None
+                || partition.diverging_epoch.is_some();
+            (key, partition.offsets(), has_data)
+        });
+        let mut keep = cache.update(&self.context, responses).into_iter();
+        self.responses.retain(|_| keep.next().unwrap_or(true));
     }
+}
 
-    fn empty() -> Self {
-        Self {
-            throttle_time_ms: 0,
-            error_code: KafkaError::None,
-            session_id: 0,
-            responses: Vec::new(),
-        }
+/// The session key of a partition of a requested or forgotten topic.
+fn session_partition(topic: &str, topic_id: [u8; 16], partition: i32) -> SessionPartition {
+    SessionPartition {
+        topic: topic.to_string(),
+        topic_id,
+        partition,
     }
 }
 
-impl KafkaSeriarize for FetchResponseBodyV16 {
+impl KafkaSeriarize for KafkaResponseBodyFetch {
     type Error = std::io::Error;
     type DependentData<'a> = ();
 
@@ -97,118 +185,97 @@ impl KafkaSeriarize for FetchResponseBodyV16 {
         writer: &mut W,
         _data: Self::DependentData<'_>,
     ) -> Result<(), Self::Error> {
+        let version = self.version;
         writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
-        let error_code: i16 = self.error_code.into();
-        writer.write_i16::<BigEndian>(error_code)?;
-        writer.write_i32::<BigEndian>(self.session_id)?;
-        write_kafka_compact_array_stream(writer, self.responses, |writer, topic| {
-            topic.serialize(writer, ())?;
-            write_kafka_tagged_fields_stream(writer, Vec::new())
-        })?;
-        write_kafka_tagged_fields_stream(writer, Vec::new())?;
-        Ok(())
-    }
-}
-
-pub struct Topic {
-    topic_id: [u8; 16],
-    partitions: Vec<Partition>,
-}
-
-impl Topic {
-    pub fn new(topic: &request::body::fetch::Topic, record_batches: &[RecordBatch]) -> Self {
-        let mut is_found = false;
-        let mut partitions = Vec::new();
-        for record_batch in record_batches {
-            let records = &record_batch.records;
-            if records.is_empty() {
-                continue;
-            }
-            let topic_record = &records[0].value.payload;
-            if let crate::records::record_value::ClusterMetadataValue::Topic(topic_record) =
-                topic_record
-            {
-                if topic.topic_id == topic_record.uuid {
-                    is_found = true;
-                    let mut record_batch = record_batch.clone();
-                    record_batch.records = record_batch
-                        .records
-                        .into_iter()
-                        .skip(1) // skip firt topic record
-                        .filter(|record| {
-                            if let crate::records::record_value::ClusterMetadataValue::Partition(
-                                partition_record,
-                            ) = &record.value.payload
-                            {
-                                partition_record.topic_id == topic.topic_id
-                                    && topic
-                                        .partitions
-                                        .iter()
-                                        .any(|p| p.index == partition_record.partition_id)
-                            } else {
-                                false
-                            }
-                        })
-                        .enumerate()
-                        .map(|(index, mut record)| {
-                            record.offset_delta = index as i32;
-                            record
-                        })
-                        .collect();
-                    if record_batch.records.is_empty() {
-                        partitions.push(Partition::known_topic_emtpy_partition());
-                    } else {
-                        dbg!("record_batch.records.len() = {}", record_batch.records.len());
-                        partitions.push(Partition::known_topic_whole_records(record_batch));
-                    }
+        if version >= SupportFetchRequestVersion::V7 {
+            let error_code: i16 = self.error_code.into();
+            writer.write_i16::<BigEndian>(error_code)?;
+            writer.write_i32::<BigEndian>(self.context.session_id)?;
+        }
+        // consecutive partitions of a topic share its entry
+        let mut topics: Vec<Topic> = Vec::new();
+        for (key, partition) in self.responses {
+            match topics.last_mut() {
+                Some(topic) if topic.name == key.topic && topic.topic_id == key.topic_id => {
+                    topic.partitions.push(partition)
                 }
+                _ => topics.push(Topic {
+                    name: key.topic,
+                    topic_id: key.topic_id,
+                    partitions: vec![partition],
+                }),
             }
         }
-
-        match (is_found, partitions.is_empty()) {
-            (true, true) => Self::emtpy_topic(topic.topic_id.clone()),
-            (true, false) => Self::found(topic.topic_id.clone(), partitions),
-            (false, _) => Self::no_found(topic.topic_id.clone()),
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, topics, |writer, topic| {
+                topic.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, topics, |writer, topic| {
+                topic.serialize(writer, version)
+            })?;
         }
+        Ok(())
     }
+}
 
-    fn found(topic_id: [u8; 16], partitions: Vec<Partition>) -> Self {
-        Self {
-            topic_id,
-            partitions,
-        }
-    }
+/// Bytes left for the whole response, as limited by the request's `max_bytes`.
+struct FetchBudget {
+    remaining: usize,
+    /// Until some records are returned, the first batch may exceed the limits
+    /// so that a consumer can always make progress.
+    min_one_batch: bool,
+}
 
-    fn no_found(topic_id: [u8; 16]) -> Self {
+impl FetchBudget {
+    fn new(max_bytes: i32) -> Self {
         Self {
-            topic_id,
-            partitions: vec![Partition::unknown_topic_partition()],
+            remaining: max_bytes.max(0) as usize,
+            min_one_batch: true,
         }
     }
 
-    fn emtpy_topic(topic_id: [u8; 16]) -> Self {
-        Self {
-            topic_id,
-            partitions: vec![Partition::known_topic_emtpy_partition()],
+    fn consume(&mut self, bytes: usize) {
+        if bytes > 0 {
+            self.remaining = self.remaining.saturating_sub(bytes);
+            self.min_one_batch = false;
         }
     }
 }
 
+pub struct Topic {
+    name: String,
+    topic_id: [u8; 16],
+    partitions: Vec<Partition>,
+}
+
 impl KafkaSeriarize for Topic {
     type Error = std::io::Error;
-    type DependentData<'a> = ();
+    type DependentData<'a> = SupportFetchRequestVersion;
 
     fn serialize<W: std::io::Write>(
         self,
         writer: &mut W,
-        _data: Self::DependentData<'_>,
+        version: Self::DependentData<'_>,
     ) -> Result<(), Self::Error> {
-        writer.write_all(&self.topic_id)?;
-        write_kafka_compact_array_stream(writer, self.partitions, |writer, partition| {
-            partition.serialize(writer, ())?;
-            write_kafka_tagged_fields_stream(writer, Vec::new())
-        })?;
-        Ok(())
+        if version.uses_topic_ids() {
+            writer.write_all(&self.topic_id)?;
+        } else if version.is_flexible() {
+            write_compact_string_stream(writer, self.name)?;
+        } else {
+            write_string_stream(writer, self.name)?;
+        }
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.partitions, |writer, partition| {
+                partition.serialize(writer, version)
+            })
+        } else {
+            write_kafka_array_stream(writer, self.partitions, |writer, partition| {
+                partition.serialize(writer, version)
+            })
+        }
     }
 }
 
@@ -218,92 +285,238 @@ pub struct Partition {
     high_watermark: i64,
     last_stable_offset: i64,
     log_start_offset: i64,
+    /// Where the fetcher's log left the leader's, found from its
+    /// `last_fetched_epoch`. Sent from version 12.
+    diverging_epoch: Option<EpochEndOffset>,
     aborted_transactions: Vec<AbortedTransaction>,
     preferred_read_replica: i32,
-    records: Vec<RecordBatch>,
+    /// Raw record batches, exactly as stored in the partition log.
+    records: Vec<u8>,
+}
+
+/// The last epoch the fetcher's log shares with the leader's and the offset
+/// it ends at, from which the fetcher has to truncate.
+#[derive(Debug, Clone, Copy)]
+pub struct EpochEndOffset {
+    epoch: i32,
+    end_offset: i64,
 }
 
 impl Partition {
-    fn unknown_topic_partition() -> Self {
+    fn error(partition_index: i32, error_code: KafkaError) -> Self {
         Self {
-            partition_index: 0,
-            error_code: KafkaError::UnknownTopicId,
-            high_watermark: 0,
-            last_stable_offset: 0,
-            log_start_offset: 0,
+            partition_index,
+            error_code,
+            high_watermark: -1,
+            last_stable_offset: -1,
+            log_start_offset: -1,
+            diverging_epoch: None,
             aborted_transactions: Vec::new(),
             preferred_read_replica: -1,
             records: Vec::new(),
         }
     }
 
-    fn known_topic_emtpy_partition() -> Self {
-        Self {
-            partition_index: 0,
-            error_code: KafkaError::None,
-            high_watermark: 0,
-            last_stable_offset: 0,
-            log_start_offset: 0,
-            aborted_transactions: Vec::new(),
-            preferred_read_replica: -1,
-            records: Vec::new(),
+    /// Read `partition` from `position` if this broker, `node_id`, leads it
+    /// in the epoch the fetcher knows, resolving whichever half of the topic
+    /// name/id pair the request left out.
+    fn fetch(
+        partition: &SessionPartition,
+        position: &FetchPosition,
+        version: SupportFetchRequestVersion,
+        node_id: i32,
+        read_committed: bool,
+        budget: &mut FetchBudget,
+        appended: Option<&Arc<Notify>>,
+    ) -> Self {
+        let index = partition.partition;
+        let resolved = if version.uses_topic_ids() {
+            metadata::topic_name_by_id(&partition.topic_id).map(|name| (name, partition.topic_id))
+        } else {
+            metadata::topic_id_by_name(&partition.topic).map(|id| (partition.topic.clone(), id))
+        };
+        let (topic_name, record) = match resolved {
+            None if version.uses_topic_ids() => {
+                return Self::error(index, KafkaError::UnknownTopicId)
+            }
+            None => return Self::error(index, KafkaError::UnknownTopicOrPartition),
+            Some((topic_name, topic_id)) => match metadata::partition(&topic_id, index) {
+                Some(record) => (topic_name, record),
+                None => return Self::error(index, KafkaError::UnknownTopicOrPartition),
+            },
+        };
+        if record.leader_id != node_id {
+            return Self::error(index, KafkaError::NotLeaderOrFollower);
         }
+        let current_leader_epoch = position.current_leader_epoch;
+        if current_leader_epoch >= 0 && current_leader_epoch != record.leader_epoch {
+            let error_code = if current_leader_epoch < record.leader_epoch {
+                KafkaError::FencedLeaderEpoch
+            } else {
+                KafkaError::UnknownLeaderEpoch
+            };
+            return Self::error(index, error_code);
+        }
+        Self::read(
+            &topic_name,
+            index,
+            position,
+            read_committed,
+            budget,
+            appended,
+        )
     }
 
-    fn known_topic_whole_records(record_batch: RecordBatch) -> Self {
-        Self {
-            partition_index: 0,
-            error_code: KafkaError::None,
-            high_watermark: 0,
-            last_stable_offset: 0,
-            log_start_offset: 0,
-            aborted_transactions: Vec::new(),
-            preferred_read_replica: -1,
-            records: vec![record_batch],
+    /// Read the log of a led partition and register `appended` to be woken
+    /// by its next append. A `read_committed` fetch stops at the last stable
+    /// offset and lists the aborted transactions among the records returned.
+    fn read(
+        topic_name: &str,
+        index: i32,
+        position: &FetchPosition,
+        read_committed: bool,
+        budget: &mut FetchBudget,
+        appended: Option<&Arc<Notify>>,
+    ) -> Self {
+        let Some(log_manager) = LOG_MANAGER.get() else {
+            return Self::error(index, KafkaError::KafkaStorageError);
+        };
+        let log = match log_manager.get_or_create(topic_name, index) {
+            Ok(log) => log,
+            Err(_) => return Self::error(index, KafkaError::KafkaStorageError),
+        };
+        let mut log = log.lock().unwrap();
+        let fetch_offset = position.fetch_offset;
+        let (high_watermark, log_start_offset) = (log.high_watermark(), log.log_start_offset());
+        let mut response = Self {
+            high_watermark,
+            last_stable_offset: log.last_stable_offset(),
+            log_start_offset,
+            ..Self::error(index, KafkaError::None)
+        };
+
+        // logs written before epochs were tracked cannot be checked
+        if position.last_fetched_epoch >= 0 && log.leader_epochs().latest_epoch().is_some() {
+            let end_offset = log
+                .leader_epochs()
+                .end_offset_for(position.last_fetched_epoch, high_watermark);
+            match end_offset {
+                Some((epoch, end_offset))
+                    if epoch < position.last_fetched_epoch || end_offset < fetch_offset =>
+                {
+                    response.diverging_epoch = Some(EpochEndOffset { epoch, end_offset });
+                    return response;
+                }
+                Some(_) => {}
+                None => return Self::error(index, KafkaError::OffsetOutOfRange),
+            }
+        }
+        if fetch_offset < log_start_offset || fetch_offset > high_watermark {
+            return Self::error(index, KafkaError::OffsetOutOfRange);
+        }
+
+        let max_bytes = (position.partition_max_bytes.max(0) as usize).min(budget.remaining);
+        let read = if read_committed {
+            log.read_committed(fetch_offset, max_bytes, budget.min_one_batch)
+        } else {
+            log.read(fetch_offset, max_bytes, budget.min_one_batch)
+                .map(|records| (records, Vec::new()))
+        };
+        match read {
+            Ok((records, aborted)) => {
+                if let Some(appended) = appended {
+                    log.watch_appends(appended);
+                }
+                budget.consume(records.len());
+                response.records = records;
+                response.aborted_transactions = aborted
+                    .into_iter()
+                    .map(|aborted| AbortedTransaction {
+                        producer_id: aborted.producer_id,
+                        first_offset: aborted.first_offset,
+                    })
+                    .collect();
+                response
+            }
+            Err(_) => Self::error(index, KafkaError::KafkaStorageError),
+        }
+    }
+
+    fn offsets(&self) -> PartitionOffsets {
+        PartitionOffsets {
+            high_watermark: self.high_watermark,
+            last_stable_offset: self.last_stable_offset,
+            log_start_offset: self.log_start_offset,
         }
     }
 }
 
 impl KafkaSeriarize for Partition {
     type Error = std::io::Error;
-    type DependentData<'a> = ();
+    type DependentData<'a> = SupportFetchRequestVersion;
 
     fn serialize<W: std::io::Write>(
         self,
         writer: &mut W,
-        _data: Self::DependentData<'_>,
+        version: Self::DependentData<'_>,
     ) -> Result<(), Self::Error> {
+        let flexible = version.is_flexible();
         writer.write_i32::<BigEndian>(self.partition_index)?;
         let error_code: i16 = self.error_code.into();
         writer.write_i16::<BigEndian>(error_code)?;
         writer.write_i64::<BigEndian>(self.high_watermark)?;
         writer.write_i64::<BigEndian>(self.last_stable_offset)?;
-        writer.write_i64::<BigEndian>(self.log_start_offset)?;
-        write_kafka_compact_array_stream(
-            writer,
-            self.aborted_transactions,
-            |writer, transaction| {
-                transaction.serialize(writer, ())?;
-                write_kafka_tagged_fields_stream(writer, Vec::new())
-            },
-        )?;
-        writer.write_i32::<BigEndian>(self.preferred_read_replica)?;
-        let mut records_u8 = Vec::new();
-        let mut records_u8_cursor = Cursor::new(&mut records_u8);
-        for (i, mut record) in self.records.into_iter().enumerate() {
-            record.base_offset = i as i64;
-            record.write_be(&mut records_u8_cursor).map_err(|_| {
-                std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Failed to write record batch to u8",
-                )
+        if version >= SupportFetchRequestVersion::V5 {
+            writer.write_i64::<BigEndian>(self.log_start_offset)?;
+        }
+        if flexible {
+            write_kafka_compact_array_stream(
+                writer,
+                self.aborted_transactions,
+                |writer, transaction| {
+                    transaction.serialize(writer, ())?;
+                    write_kafka_tagged_fields_stream(writer, Vec::new())
+                },
+            )?;
+        } else {
+            write_kafka_array_stream(writer, self.aborted_transactions, |writer, transaction| {
+                transaction.serialize(writer, ())
             })?;
         }
-        write_compact_vec_u8_stream(writer, records_u8)?;
+        if version >= SupportFetchRequestVersion::V11 {
+            writer.write_i32::<BigEndian>(self.preferred_read_replica)?;
+        }
+        if flexible {
+            write_compact_vec_u8_stream(writer, self.records)?;
+            let mut tagged_fields = Vec::new();
+            if let Some(diverging_epoch) = self.diverging_epoch {
+                let mut data = Vec::new();
+                diverging_epoch.serialize(&mut data, ())?;
+                tagged_fields.push(TaggedField::new(0, data));
+            }
+            write_kafka_tagged_fields_stream(writer, tagged_fields)?;
+        } else {
+            write_vec_u8_stream(writer, self.records)?;
+        }
         Ok(())
     }
 }
 
+impl KafkaSeriarize for EpochEndOffset {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        writer.write_i32::<BigEndian>(self.epoch)?;
+        writer.write_i64::<BigEndian>(self.end_offset)?;
+        write_kafka_tagged_fields_stream(writer, Vec::new())
+    }
+}
+
 pub struct AbortedTransaction {
     producer_id: i64,
     first_offset: i64,
@@ -323,3 +536,128 @@ impl KafkaSeriarize for AbortedTransaction {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch_session::INVALID_SESSION_ID;
+
+    const TOPIC_ID: [u8; 16] = [7; 16];
+
+    fn serialize(version: SupportFetchRequestVersion) -> Vec<u8> {
+        let partition = Partition {
+            high_watermark: 8,
+            last_stable_offset: 6,
+            log_start_offset: 2,
+            aborted_transactions: vec![AbortedTransaction {
+                producer_id: 1000,
+                first_offset: 6,
+            }],
+            records: vec![0xab; 3],
+            ..Partition::error(0, KafkaError::None)
+        };
+        let body = KafkaResponseBodyFetch {
+            version,
+            throttle_time_ms: 0,
+            error_code: KafkaError::None,
+            node_id: 1,
+            context: FetchContext::sessionless(Vec::new()),
+            max_bytes: 0,
+            read_committed: true,
+            responses: vec![(session_partition("foo", TOPIC_ID, 0), partition)],
+            appended: None,
+            min_bytes: 0,
+            max_wait: Duration::ZERO,
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    /// The partition fields every version shares, up to the aborted
+    /// transactions.
+    fn partition_offsets() -> Vec<u8> {
+        [
+            &0i32.to_be_bytes()[..],
+            &0i16.to_be_bytes(),
+            &8i64.to_be_bytes(),
+            &6i64.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn aborted_transaction() -> Vec<u8> {
+        [1000i64.to_be_bytes(), 6i64.to_be_bytes()].concat()
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        // v4: no session, no log start offset, names and classic arrays
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &1i32.to_be_bytes(),
+            &3i16.to_be_bytes(),
+            b"foo",
+            &1i32.to_be_bytes(),
+            &partition_offsets(),
+            &1i32.to_be_bytes(),
+            &aborted_transaction(),
+            &3i32.to_be_bytes(),
+            &[0xab; 3],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportFetchRequestVersion::V4), expected);
+
+        // v5 adds the log start offset after the last stable offset
+        let v5 = serialize(SupportFetchRequestVersion::V5);
+        assert_eq!(v5.len(), expected.len() + 8);
+        let log_start_offset = 4 + 4 + 2 + 3 + 4 + partition_offsets().len();
+        assert_eq!(v5[log_start_offset..][..8], 2i64.to_be_bytes());
+
+        // v12: compact encoding and tagged fields, still by name
+        let flexible_partition = [
+            &partition_offsets()[..],
+            &2i64.to_be_bytes(),
+            &[2],
+            &aborted_transaction(),
+            &[0],
+            &(-1i32).to_be_bytes(),
+            &[4],
+            &[0xab; 3],
+            &[0],
+        ]
+        .concat();
+        let session = [
+            0i16.to_be_bytes().to_vec(),
+            INVALID_SESSION_ID.to_be_bytes().to_vec(),
+        ]
+        .concat();
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &session,
+            &[2, 4],
+            b"foo",
+            &[2],
+            &flexible_partition,
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportFetchRequestVersion::V12), expected);
+
+        // v13: topics by id
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &session,
+            &[2],
+            &TOPIC_ID,
+            &[2],
+            &flexible_partition,
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportFetchRequestVersion::V13), expected);
+
+        // v15 only changes the request
+        assert_eq!(serialize(SupportFetchRequestVersion::V15), expected);
+    }
+}
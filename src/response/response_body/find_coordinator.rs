@@ -0,0 +1,258 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    config::BrokerConfig,
+    consts::find_coordinator::{
+        SupportFindCoordinatorRequestVersion, GROUP_KEY_TYPE, TRANSACTION_KEY_TYPE,
+    },
+    request::body::find_coordinator::FindCoordinatorRequestBody,
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_compact_array_stream, write_kafka_tagged_fields_stream,
+            write_nullable_string_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// FindCoordinator response, versions 0 through 4. This broker coordinates
+/// every group and transactional id itself.
+pub struct KafkaResponseBodyFindCoordinator {
+    version: SupportFindCoordinatorRequestVersion,
+    throttle_time_ms: i32,
+    /// One entry per requested key; versions 0-3 only ever have one.
+    coordinators: Vec<Coordinator>,
+}
+
+struct Coordinator {
+    key: String,
+    node_id: i32,
+    host: String,
+    port: i32,
+    error_code: KafkaError,
+    error_message: Option<String>,
+}
+
+impl KafkaResponseBodyFindCoordinator {
+    pub fn new(request: &FindCoordinatorRequestBody, config: &BrokerConfig) -> Self {
+        let key_type_valid = matches!(request.key_type, GROUP_KEY_TYPE | TRANSACTION_KEY_TYPE);
+        let coordinators = request
+            .keys
+            .iter()
+            .map(|key| {
+                if key_type_valid {
+                    Coordinator {
+                        key: key.clone(),
+                        node_id: config.node_id,
+                        host: config.advertised_listener.host.clone(),
+                        port: config.advertised_listener.port as i32,
+                        error_code: KafkaError::None,
+                        error_message: None,
+                    }
+                } else {
+                    Coordinator {
+                        key: key.clone(),
+                        node_id: -1,
+                        host: String::new(),
+                        port: -1,
+                        error_code: KafkaError::InvalidRequest,
+                        error_message: Some(format!("unsupported key type {}", request.key_type)),
+                    }
+                }
+            })
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            coordinators,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyFindCoordinator {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version >= SupportFindCoordinatorRequestVersion::V1 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        if version.is_batched() {
+            write_kafka_compact_array_stream(writer, self.coordinators, |writer, coordinator| {
+                write_compact_string_stream(writer, coordinator.key)?;
+                writer.write_i32::<BigEndian>(coordinator.node_id)?;
+                write_compact_string_stream(writer, coordinator.host)?;
+                writer.write_i32::<BigEndian>(coordinator.port)?;
+                let error_code: i16 = coordinator.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)?;
+                write_compact_nullable_string_stream(writer, coordinator.error_message)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            return write_kafka_tagged_fields_stream(writer, Vec::new());
+        }
+
+        let coordinator = self.coordinators.into_iter().next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "no coordinator key")
+        })?;
+        let error_code: i16 = coordinator.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        if version >= SupportFindCoordinatorRequestVersion::V1 {
+            if version.is_flexible() {
+                write_compact_nullable_string_stream(writer, coordinator.error_message)?;
+            } else {
+                write_nullable_string_stream(writer, coordinator.error_message)?;
+            }
+        }
+        writer.write_i32::<BigEndian>(coordinator.node_id)?;
+        if version.is_flexible() {
+            write_compact_string_stream(writer, coordinator.host)?;
+        } else {
+            write_string_stream(writer, coordinator.host)?;
+        }
+        writer.write_i32::<BigEndian>(coordinator.port)?;
+        if version.is_flexible() {
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV1},
+        },
+        traits::KafkaDeseriarize,
+    };
+
+    fn serialize(version: SupportFindCoordinatorRequestVersion, keys: &[&str]) -> Vec<u8> {
+        let body = KafkaResponseBodyFindCoordinator {
+            version,
+            throttle_time_ms: 0,
+            coordinators: keys
+                .iter()
+                .map(|key| Coordinator {
+                    key: key.to_string(),
+                    node_id: 1,
+                    host: "h".to_string(),
+                    port: 9092,
+                    error_code: KafkaError::None,
+                    error_message: None,
+                })
+                .collect(),
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        let address = [
+            &1i32.to_be_bytes()[..],
+            &1i16.to_be_bytes(),
+            b"h",
+            &9092i32.to_be_bytes(),
+        ]
+        .concat();
+
+        // v0: error code and address
+        let expected = [&0i16.to_be_bytes()[..], &address].concat();
+        assert_eq!(
+            serialize(SupportFindCoordinatorRequestVersion::V0, &["g"]),
+            expected
+        );
+
+        // v1 adds the throttle time and error message
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &0i16.to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+            &address,
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportFindCoordinatorRequestVersion::V1, &["g"]),
+            expected
+        );
+
+        // v3: compact encoding and tagged fields
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &0i16.to_be_bytes(),
+            &[0],
+            &1i32.to_be_bytes(),
+            &[2],
+            b"h",
+            &9092i32.to_be_bytes(),
+            &[0],
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportFindCoordinatorRequestVersion::V3, &["g"]),
+            expected
+        );
+
+        // v4: one coordinator per key
+        let coordinator = |key: &[u8]| {
+            [
+                &[2][..],
+                key,
+                &1i32.to_be_bytes(),
+                &[2],
+                b"h",
+                &9092i32.to_be_bytes(),
+                &0i16.to_be_bytes(),
+                &[0, 0],
+            ]
+            .concat()
+        };
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[3],
+            &coordinator(b"a"),
+            &coordinator(b"b"),
+            &[0],
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportFindCoordinatorRequestVersion::V4, &["a", "b"]),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_type_is_rejected() {
+        let header = KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+            request_api_key: RequestApiKey::FindCoordinator,
+            request_api_version: 1,
+            correlation_id: 1,
+            client_id: String::new(),
+        });
+        let buf = [&1i16.to_be_bytes()[..], b"g", &[7]].concat();
+        let request =
+            FindCoordinatorRequestBody::try_parse_from_reader(&mut Cursor::new(buf), &header)
+                .unwrap();
+
+        let response = KafkaResponseBodyFindCoordinator::new(&request, &BrokerConfig::default());
+        let coordinator = &response.coordinators[0];
+        assert_eq!(coordinator.error_code, KafkaError::InvalidRequest);
+        assert_eq!(coordinator.node_id, -1);
+        assert_eq!(
+            coordinator.error_message.as_deref(),
+            Some("unsupported key type 7")
+        );
+    }
+}
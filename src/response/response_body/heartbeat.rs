@@ -0,0 +1,89 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::heartbeat::SupportHeartbeatRequestVersion,
+    globals::GROUP_COORDINATOR,
+    request::body::heartbeat::HeartbeatRequestBody,
+    response::{error_code::KafkaError, utils::write_kafka_tagged_fields_stream},
+    traits::KafkaSeriarize,
+};
+
+/// Heartbeat response, versions 0 through 4.
+pub struct KafkaResponseBodyHeartbeat {
+    version: SupportHeartbeatRequestVersion,
+    throttle_time_ms: i32,
+    error_code: KafkaError,
+}
+
+impl KafkaResponseBodyHeartbeat {
+    pub fn new(request: &HeartbeatRequestBody) -> Self {
+        let error_code = match GROUP_COORDINATOR.get() {
+            Some(coordinator) => coordinator
+                .heartbeat(
+                    &request.group_id,
+                    request.generation_id,
+                    &request.member_id,
+                    request.group_instance_id.as_deref(),
+                )
+                .err()
+                .unwrap_or(KafkaError::None),
+            None => KafkaError::CoordinatorNotAvailable,
+        };
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            error_code,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyHeartbeat {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        if self.version >= SupportHeartbeatRequestVersion::V1 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        if self.version.is_flexible() {
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize(version: SupportHeartbeatRequestVersion) -> Vec<u8> {
+        let body = KafkaResponseBodyHeartbeat {
+            version,
+            throttle_time_ms: 0,
+            error_code: KafkaError::RebalanceInProgress,
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        assert_eq!(
+            serialize(SupportHeartbeatRequestVersion::V0),
+            27i16.to_be_bytes()
+        );
+        let expected = [0i32.to_be_bytes().to_vec(), 27i16.to_be_bytes().to_vec()].concat();
+        assert_eq!(serialize(SupportHeartbeatRequestVersion::V1), expected);
+        assert_eq!(
+            serialize(SupportHeartbeatRequestVersion::V4),
+            [&expected[..], &[0]].concat()
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::init_producer_id::SupportInitProducerIdRequestVersion,
+    globals::PRODUCER_ID_MANAGER,
+    request::body::init_producer_id::InitProducerIdRequestBody,
+    response::{error_code::KafkaError, utils::write_kafka_tagged_fields_stream},
+    traits::KafkaSeriarize,
+};
+
+/// InitProducerId response, versions 0 through 5.
+///
+/// Only idempotent producers are served: like Kafka, every call without a
+/// `transactional_id` gets a fresh producer id at epoch 0, also when it asks
+/// to bump the epoch of its current one. There is no transaction coordinator
+/// to initialize a transactional producer.
+pub struct KafkaResponseBodyInitProducerId {
+    version: SupportInitProducerIdRequestVersion,
+    throttle_time_ms: i32,
+    error_code: KafkaError,
+    producer_id: i64,
+    producer_epoch: i16,
+}
+
+impl KafkaResponseBodyInitProducerId {
+    pub fn new(request: &InitProducerIdRequestBody) -> Self {
+        let result = match (&request.transactional_id, PRODUCER_ID_MANAGER.get()) {
+            (None, Some(manager)) => manager
+                .generate()
+                .map_err(|_| KafkaError::UnknownServerError),
+            _ => Err(KafkaError::CoordinatorNotAvailable),
+        };
+        let (error_code, producer_id, producer_epoch) = match result {
+            Ok(producer_id) => (KafkaError::None, producer_id, 0),
+            Err(error_code) => (error_code, -1, -1),
+        };
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            error_code,
+            producer_id,
+            producer_epoch,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyInitProducerId {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        writer.write_i64::<BigEndian>(self.producer_id)?;
+        writer.write_i16::<BigEndian>(self.producer_epoch)?;
+        if self.version.is_flexible() {
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV2},
+        },
+        traits::KafkaDeseriarize,
+    };
+
+    fn serialize(version: SupportInitProducerIdRequestVersion) -> Vec<u8> {
+        let body = KafkaResponseBodyInitProducerId {
+            version,
+            throttle_time_ms: 0,
+            error_code: KafkaError::None,
+            producer_id: 1000,
+            producer_epoch: 0,
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &0i16.to_be_bytes(),
+            &1000i64.to_be_bytes(),
+            &0i16.to_be_bytes(),
+        ]
+        .concat();
+        for version in [
+            SupportInitProducerIdRequestVersion::V0,
+            SupportInitProducerIdRequestVersion::V1,
+        ] {
+            assert_eq!(serialize(version), expected, "{version:?}");
+        }
+
+        // v2 adds tagged fields
+        let expected = [&expected[..], &[0]].concat();
+        for version in [
+            SupportInitProducerIdRequestVersion::V2,
+            SupportInitProducerIdRequestVersion::V3,
+            SupportInitProducerIdRequestVersion::V4,
+            SupportInitProducerIdRequestVersion::V5,
+        ] {
+            assert_eq!(serialize(version), expected, "{version:?}");
+        }
+    }
+
+    #[test]
+    fn test_transactional_producers_get_no_coordinator() {
+        let header = KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+            request_api_key: RequestApiKey::InitProducerId,
+            request_api_version: 4,
+            correlation_id: 1,
+            client_id: String::new(),
+        });
+        let buf = [
+            &[4][..],
+            b"txn",
+            &60_000i32.to_be_bytes(),
+            &(-1i64).to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+            &[0],
+        ]
+        .concat();
+        let request =
+            InitProducerIdRequestBody::try_parse_from_reader(&mut &buf[..], &header).unwrap();
+
+        let mut buf = Vec::new();
+        KafkaResponseBodyInitProducerId::new(&request)
+            .serialize(&mut buf, ())
+            .unwrap();
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &15i16.to_be_bytes(),
+            &(-1i64).to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+            &[0],
+        ]
+        .concat();
+        assert_eq!(buf, expected);
+    }
+}
@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::join_group::SupportJoinGroupRequestVersion,
+    globals::GROUP_COORDINATOR,
+    group::{JoinParams, JoinResult, JoinedMember, Protocol},
+    request::body::join_group::JoinGroupRequestBody,
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_bytes_stream, write_compact_nullable_string_stream,
+            write_compact_string_stream, write_kafka_array_stream,
+            write_kafka_compact_array_stream, write_kafka_tagged_fields_stream,
+            write_nullable_string_stream, write_string_stream, write_vec_u8_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// JoinGroup response, versions 0 through 9. Built once the rebalance the
+/// member joined has completed, so creating it may block.
+pub struct KafkaResponseBodyJoinGroup {
+    version: SupportJoinGroupRequestVersion,
+    throttle_time_ms: i32,
+    result: JoinResult,
+}
+
+impl KafkaResponseBodyJoinGroup {
+    pub fn new(request: &JoinGroupRequestBody) -> Self {
+        let version = request.get_api_version();
+        let Some(coordinator) = GROUP_COORDINATOR.get() else {
+            return Self {
+                version,
+                throttle_time_ms: 0,
+                result: JoinResult::error(
+                    KafkaError::CoordinatorNotAvailable,
+                    request.member_id.clone(),
+                ),
+            };
+        };
+        let timeout = |ms: i32| Duration::from_millis(ms.max(0) as u64);
+        let result = coordinator.join_group(JoinParams {
+            group_id: request.group_id.clone(),
+            member_id: request.member_id.clone(),
+            group_instance_id: request.group_instance_id.clone(),
+            client_id: request.client_id.clone(),
+            session_timeout: timeout(request.session_timeout_ms),
+            rebalance_timeout: timeout(request.rebalance_timeout_ms),
+            protocol_type: request.protocol_type.clone(),
+            protocols: request
+                .protocols
+                .iter()
+                .map(|protocol| Protocol {
+                    name: protocol.name.clone(),
+                    metadata: protocol.metadata.clone(),
+                })
+                .collect(),
+            require_known_member_id: version.requires_known_member_id(),
+        });
+        Self {
+            version,
+            throttle_time_ms: 0,
+            result,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyJoinGroup {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        let flexible = version.is_flexible();
+        let result = self.result;
+        if version >= SupportJoinGroupRequestVersion::V2 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        let error_code: i16 = result.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        writer.write_i32::<BigEndian>(result.generation_id)?;
+        if version >= SupportJoinGroupRequestVersion::V7 {
+            write_compact_nullable_string_stream(writer, result.protocol_type)?;
+            write_compact_nullable_string_stream(writer, result.protocol_name)?;
+        } else if flexible {
+            write_compact_string_stream(writer, result.protocol_name.unwrap_or_default())?;
+        } else {
+            write_string_stream(writer, result.protocol_name.unwrap_or_default())?;
+        }
+        if flexible {
+            write_compact_string_stream(writer, result.leader)?;
+        } else {
+            write_string_stream(writer, result.leader)?;
+        }
+        if version >= SupportJoinGroupRequestVersion::V9 {
+            // the leader always computes a fresh assignment
+            writer.write_u8(0)?;
+        }
+        if flexible {
+            write_compact_string_stream(writer, result.member_id)?;
+            write_kafka_compact_array_stream(writer, result.members, |writer, member| {
+                serialize_member(writer, member, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_string_stream(writer, result.member_id)?;
+            write_kafka_array_stream(writer, result.members, |writer, member| {
+                serialize_member(writer, member, version)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn serialize_member<W: std::io::Write>(
+    writer: &mut W,
+    member: JoinedMember,
+    version: SupportJoinGroupRequestVersion,
+) -> Result<(), std::io::Error> {
+    if version.is_flexible() {
+        write_compact_string_stream(writer, member.member_id)?;
+        write_compact_nullable_string_stream(writer, member.group_instance_id)?;
+        write_compact_bytes_stream(writer, member.metadata)
+    } else {
+        write_string_stream(writer, member.member_id)?;
+        if version >= SupportJoinGroupRequestVersion::V5 {
+            write_nullable_string_stream(writer, member.group_instance_id)?;
+        }
+        write_vec_u8_stream(writer, member.metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize(version: SupportJoinGroupRequestVersion, result: JoinResult) -> Vec<u8> {
+        let body = KafkaResponseBodyJoinGroup {
+            version,
+            throttle_time_ms: 0,
+            result,
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    fn member_id_required(version: SupportJoinGroupRequestVersion) -> Vec<u8> {
+        serialize(
+            version,
+            JoinResult::error(KafkaError::MemberIdRequired, "client-1"),
+        )
+    }
+
+    #[test]
+    fn test_known_member_id_is_required_from_v4() {
+        assert!(!SupportJoinGroupRequestVersion::V3.requires_known_member_id());
+        assert!(SupportJoinGroupRequestVersion::V4.requires_known_member_id());
+        assert!(SupportJoinGroupRequestVersion::V9.requires_known_member_id());
+    }
+
+    #[test]
+    fn test_serialize_member_id_required() {
+        // v4: the new member id comes back with no generation or leader
+        let header = [
+            &0i32.to_be_bytes()[..],
+            &79i16.to_be_bytes(),
+            &(-1i32).to_be_bytes(),
+        ]
+        .concat();
+        let expected = [
+            &header[..],
+            &0i16.to_be_bytes(),
+            &0i16.to_be_bytes(),
+            &8i16.to_be_bytes(),
+            b"client-1",
+            &0i32.to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(
+            member_id_required(SupportJoinGroupRequestVersion::V4),
+            expected
+        );
+
+        // v6: compact encoding and tagged fields
+        let expected = [&header[..], &[1, 1, 9], b"client-1", &[1, 0]].concat();
+        assert_eq!(
+            member_id_required(SupportJoinGroupRequestVersion::V6),
+            expected
+        );
+
+        // v7: a nullable protocol type and name
+        let expected = [&header[..], &[0, 0, 1, 9], b"client-1", &[1, 0]].concat();
+        assert_eq!(
+            member_id_required(SupportJoinGroupRequestVersion::V7),
+            expected
+        );
+
+        // v9 adds skip_assignment after the leader
+        let expected = [&header[..], &[0, 0, 1, 0, 9], b"client-1", &[1, 0]].concat();
+        assert_eq!(
+            member_id_required(SupportJoinGroupRequestVersion::V9),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_serialize_members_for_the_leader() {
+        let result = JoinResult {
+            error_code: KafkaError::None,
+            generation_id: 1,
+            protocol_type: Some("consumer".to_string()),
+            protocol_name: Some("range".to_string()),
+            leader: "m".to_string(),
+            member_id: "m".to_string(),
+            members: vec![JoinedMember {
+                member_id: "m".to_string(),
+                group_instance_id: Some("i".to_string()),
+                metadata: vec![7],
+            }],
+        };
+
+        // v0: no throttle time or group instance id
+        let expected = [
+            &0i16.to_be_bytes()[..],
+            &1i32.to_be_bytes(),
+            &5i16.to_be_bytes(),
+            b"range",
+            &1i16.to_be_bytes(),
+            b"m",
+            &1i16.to_be_bytes(),
+            b"m",
+            &1i32.to_be_bytes(),
+            &1i16.to_be_bytes(),
+            b"m",
+            &1i32.to_be_bytes(),
+            &[7],
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportJoinGroupRequestVersion::V0, result.clone()),
+            expected
+        );
+
+        // v5 adds the group instance id of every member
+        let member = [
+            &1i16.to_be_bytes()[..],
+            b"m",
+            &1i16.to_be_bytes(),
+            b"i",
+            &1i32.to_be_bytes(),
+            &[7],
+        ]
+        .concat();
+        assert!(serialize(SupportJoinGroupRequestVersion::V5, result).ends_with(&member));
+    }
+}
@@ -0,0 +1,174 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::leave_group::SupportLeaveGroupRequestVersion,
+    globals::GROUP_COORDINATOR,
+    group::LeavingMember,
+    request::body::leave_group::LeaveGroupRequestBody,
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_nullable_string_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// LeaveGroup response, versions 0 through 5.
+///
+/// Versions 0-2 report the outcome of their single member in `error_code`,
+/// versions 3 and above per member.
+pub struct KafkaResponseBodyLeaveGroup {
+    version: SupportLeaveGroupRequestVersion,
+    throttle_time_ms: i32,
+    error_code: KafkaError,
+    members: Vec<MemberResponse>,
+}
+
+struct MemberResponse {
+    member_id: String,
+    group_instance_id: Option<String>,
+    error_code: KafkaError,
+}
+
+impl KafkaResponseBodyLeaveGroup {
+    pub fn new(request: &LeaveGroupRequestBody) -> Self {
+        let version = request.get_api_version();
+        let Some(coordinator) = GROUP_COORDINATOR.get() else {
+            return Self {
+                version,
+                throttle_time_ms: 0,
+                error_code: KafkaError::CoordinatorNotAvailable,
+                members: Vec::new(),
+            };
+        };
+        let leaving: Vec<LeavingMember> = request
+            .members
+            .iter()
+            .map(|member| LeavingMember {
+                member_id: member.member_id.clone(),
+                group_instance_id: member.group_instance_id.clone(),
+            })
+            .collect();
+        let errors = coordinator.leave_group(&request.group_id, &leaving);
+        let members: Vec<MemberResponse> = leaving
+            .into_iter()
+            .zip(errors)
+            .map(|(member, error_code)| MemberResponse {
+                member_id: member.member_id,
+                group_instance_id: member.group_instance_id,
+                error_code,
+            })
+            .collect();
+        let error_code = if version.is_batched() {
+            KafkaError::None
+        } else {
+            members
+                .first()
+                .map_or(KafkaError::None, |member| member.error_code)
+        };
+        Self {
+            version,
+            throttle_time_ms: 0,
+            error_code,
+            members,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyLeaveGroup {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version >= SupportLeaveGroupRequestVersion::V1 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        if !version.is_batched() {
+            return Ok(());
+        }
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.members, |writer, member| {
+                write_compact_string_stream(writer, member.member_id)?;
+                write_compact_nullable_string_stream(writer, member.group_instance_id)?;
+                let error_code: i16 = member.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, self.members, |writer, member| {
+                write_string_stream(writer, member.member_id)?;
+                write_nullable_string_stream(writer, member.group_instance_id)?;
+                let error_code: i16 = member.error_code.into();
+                writer.write_i16::<BigEndian>(error_code)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize(version: SupportLeaveGroupRequestVersion) -> Vec<u8> {
+        let body = KafkaResponseBodyLeaveGroup {
+            version,
+            throttle_time_ms: 0,
+            error_code: KafkaError::None,
+            members: vec![MemberResponse {
+                member_id: "m".to_string(),
+                group_instance_id: None,
+                error_code: KafkaError::UnknownMemberId,
+            }],
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        // v0 and v1 only carry the top level error code
+        assert_eq!(
+            serialize(SupportLeaveGroupRequestVersion::V0),
+            0i16.to_be_bytes()
+        );
+        let header = [0i32.to_be_bytes().to_vec(), 0i16.to_be_bytes().to_vec()].concat();
+        assert_eq!(serialize(SupportLeaveGroupRequestVersion::V1), header);
+
+        // v3 reports every member
+        let expected = [
+            &header[..],
+            &1i32.to_be_bytes(),
+            &1i16.to_be_bytes(),
+            b"m",
+            &(-1i16).to_be_bytes(),
+            &25i16.to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(serialize(SupportLeaveGroupRequestVersion::V3), expected);
+
+        // v4: compact encoding and tagged fields
+        let expected = [
+            &header[..],
+            &[2, 2],
+            b"m",
+            &[0],
+            &25i16.to_be_bytes(),
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportLeaveGroupRequestVersion::V4), expected);
+    }
+}
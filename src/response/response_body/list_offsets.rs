@@ -0,0 +1,402 @@
+use std::io::{self, Cursor};
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::list_offsets::{
+        SupportListOffsetsRequestVersion, EARLIEST_LOCAL_TIMESTAMP, EARLIEST_TIMESTAMP,
+        LATEST_TIMESTAMP, MAX_TIMESTAMP, READ_COMMITTED,
+    },
+    globals::LOG_MANAGER,
+    metadata,
+    records::RecordBatch,
+    request::body::list_offsets::{ListOffsetsPartition, ListOffsetsRequestBody, ListOffsetsTopic},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_string_stream, write_kafka_array_stream,
+            write_kafka_compact_array_stream, write_kafka_tagged_fields_stream,
+            write_string_stream,
+        },
+    },
+    storage::{LogManager, PartitionLog},
+    traits::KafkaSeriarize,
+};
+
+/// ListOffsets response, versions 1 through 8, mirroring
+/// [`ListOffsetsRequestBody`].
+pub struct KafkaResponseBodyListOffsets {
+    version: SupportListOffsetsRequestVersion,
+    throttle_time_ms: i32,
+    topics: Vec<ListOffsetsTopicResponse>,
+}
+
+impl KafkaResponseBodyListOffsets {
+    pub fn new(request: &ListOffsetsRequestBody) -> Self {
+        let topics = request
+            .topics
+            .iter()
+            .map(|topic| ListOffsetsTopicResponse::new(topic, request.isolation_level))
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            topics,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyListOffsets {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version >= SupportListOffsetsRequestVersion::V2 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.topics, |writer, topic| {
+                topic.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, self.topics, |writer, topic| {
+                topic.serialize(writer, version)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+struct ListOffsetsTopicResponse {
+    name: String,
+    partitions: Vec<ListOffsetsPartitionResponse>,
+}
+
+impl ListOffsetsTopicResponse {
+    fn new(topic: &ListOffsetsTopic, isolation_level: i8) -> Self {
+        let topic_id = metadata::topic_id_by_name(&topic.name);
+        let partitions = topic
+            .partitions
+            .iter()
+            .map(|partition| {
+                let partition_index = partition.partition_index;
+                let record = topic_id.and_then(|id| metadata::partition(&id, partition_index));
+                match (record, LOG_MANAGER.get()) {
+                    (None, _) => ListOffsetsPartitionResponse::error(
+                        partition_index,
+                        KafkaError::UnknownTopicOrPartition,
+                    ),
+                    (Some(_), None) => ListOffsetsPartitionResponse::error(
+                        partition_index,
+                        KafkaError::KafkaStorageError,
+                    ),
+                    (Some(record), Some(log_manager)) => ListOffsetsPartitionResponse::lookup(
+                        log_manager,
+                        &topic.name,
+                        partition,
+                        record.leader_epoch,
+                        isolation_level,
+                    ),
+                }
+            })
+            .collect();
+        Self {
+            name: topic.name.clone(),
+            partitions,
+        }
+    }
+}
+
+impl KafkaSeriarize for ListOffsetsTopicResponse {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportListOffsetsRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        if version.is_flexible() {
+            write_compact_string_stream(writer, self.name)?;
+            write_kafka_compact_array_stream(writer, self.partitions, |writer, partition| {
+                partition.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })
+        } else {
+            write_string_stream(writer, self.name)?;
+            write_kafka_array_stream(writer, self.partitions, |writer, partition| {
+                partition.serialize(writer, version)
+            })
+        }
+    }
+}
+
+struct ListOffsetsPartitionResponse {
+    partition_index: i32,
+    error_code: KafkaError,
+    timestamp: i64,
+    offset: i64,
+    leader_epoch: i32,
+}
+
+impl ListOffsetsPartitionResponse {
+    fn error(partition_index: i32, error_code: KafkaError) -> Self {
+        Self {
+            partition_index,
+            error_code,
+            timestamp: -1,
+            offset: -1,
+            leader_epoch: -1,
+        }
+    }
+
+    /// Look up `partition` of `topic` in its log, if the client's leader
+    /// epoch is the current `leader_epoch`.
+    fn lookup(
+        log_manager: &LogManager,
+        topic: &str,
+        partition: &ListOffsetsPartition,
+        leader_epoch: i32,
+        isolation_level: i8,
+    ) -> Self {
+        let partition_index = partition.partition_index;
+        if partition.current_leader_epoch >= 0 && partition.current_leader_epoch != leader_epoch {
+            let error_code = if partition.current_leader_epoch < leader_epoch {
+                KafkaError::FencedLeaderEpoch
+            } else {
+                KafkaError::UnknownLeaderEpoch
+            };
+            return Self::error(partition_index, error_code);
+        }
+        let result = log_manager
+            .get_or_create(topic, partition_index)
+            .and_then(|log| {
+                let log = log.lock().unwrap();
+                match partition.timestamp {
+                    LATEST_TIMESTAMP if isolation_level == READ_COMMITTED => {
+                        Ok(Some((-1, log.last_stable_offset())))
+                    }
+                    LATEST_TIMESTAMP => Ok(Some((-1, log.high_watermark()))),
+                    EARLIEST_TIMESTAMP | EARLIEST_LOCAL_TIMESTAMP => {
+                        Ok(Some((-1, log.log_start_offset())))
+                    }
+                    MAX_TIMESTAMP => find_max_timestamp(&log),
+                    timestamp => find_timestamp(&log, timestamp),
+                }
+            });
+        match result {
+            Ok(found) => {
+                let (timestamp, offset) = found.unwrap_or((-1, -1));
+                Self {
+                    partition_index,
+                    error_code: KafkaError::None,
+                    timestamp,
+                    offset,
+                    leader_epoch,
+                }
+            }
+            Err(_) => Self::error(partition_index, KafkaError::KafkaStorageError),
+        }
+    }
+}
+
+/// The record batch starting at `base_offset`.
+fn read_batch(log: &PartitionLog, base_offset: i64) -> io::Result<Option<RecordBatch>> {
+    let bytes = log.read(base_offset, 0, true)?;
+    let batches =
+        RecordBatch::read_batches_from(&mut Cursor::new(bytes)).map_err(io::Error::other)?;
+    Ok(batches.into_iter().next())
+}
+
+/// `(timestamp, offset)` of the first record whose timestamp is at least
+/// `target`. The time index finds the batch, its records the exact offset.
+fn find_timestamp(log: &PartitionLog, target: i64) -> io::Result<Option<(i64, i64)>> {
+    let Some(base_offset) = log.offset_for_timestamp(target)? else {
+        return Ok(None);
+    };
+    let Some(batch) = read_batch(log, base_offset)? else {
+        return Ok(None);
+    };
+    let found = batch
+        .records
+        .iter()
+        .map(|record| {
+            (
+                batch.record_timestamp(record),
+                batch.base_offset + record.offset_delta as i64,
+            )
+        })
+        .find(|&(timestamp, _)| timestamp >= target);
+    // the batch's max timestamp reaches `target` even if no record claims to
+    Ok(found.or(Some((batch.max_timestamp, batch.base_offset))))
+}
+
+/// `(timestamp, offset)` of the first record with the largest timestamp.
+fn find_max_timestamp(log: &PartitionLog) -> io::Result<Option<(i64, i64)>> {
+    let Some((max_timestamp, base_offset)) = log.max_timestamp() else {
+        return Ok(None);
+    };
+    let offset = read_batch(log, base_offset)?
+        .and_then(|batch| {
+            batch
+                .records
+                .iter()
+                .find(|record| batch.record_timestamp(record) == max_timestamp)
+                .map(|record| batch.base_offset + record.offset_delta as i64)
+        })
+        .unwrap_or(base_offset);
+    Ok(Some((max_timestamp, offset)))
+}
+
+impl KafkaSeriarize for ListOffsetsPartitionResponse {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportListOffsetsRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        writer.write_i32::<BigEndian>(self.partition_index)?;
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        writer.write_i64::<BigEndian>(self.timestamp)?;
+        writer.write_i64::<BigEndian>(self.offset)?;
+        if version >= SupportListOffsetsRequestVersion::V4 {
+            writer.write_i32::<BigEndian>(self.leader_epoch)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use binrw::{BinWrite, Endian};
+
+    use super::*;
+    use crate::{records::Record, storage::LogConfig};
+
+    const LEADER_EPOCH: i32 = 2;
+
+    /// A batch of records at `base_timestamp` plus each of `deltas`.
+    fn batch(base_timestamp: i64, deltas: &[i64], attributes: i16, producer_id: i64) -> Vec<u8> {
+        let records = deltas
+            .iter()
+            .enumerate()
+            .map(|(offset_delta, &timestamp_delta)| Record {
+                attributes: 0,
+                timestamp_delta,
+                offset_delta: offset_delta as i32,
+                key: None,
+                value: Some(b"v".to_vec()),
+                headers: Vec::new(),
+            })
+            .collect();
+        let batch = RecordBatch {
+            base_offset: 0,
+            partition_leader_epoch: 0,
+            crc: 0,
+            attributes,
+            base_timestamp,
+            max_timestamp: base_timestamp + deltas.iter().max().unwrap(),
+            producer_id,
+            producer_epoch: 0,
+            base_sequence: 0,
+            records,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        batch.write_options(&mut bytes, Endian::Big, ()).unwrap();
+        bytes.into_inner()
+    }
+
+    fn lookup(
+        log_manager: &LogManager,
+        timestamp: i64,
+        current_leader_epoch: i32,
+        isolation_level: i8,
+    ) -> (KafkaError, i64, i64) {
+        let partition = ListOffsetsPartition {
+            partition_index: 0,
+            current_leader_epoch,
+            timestamp,
+        };
+        let response = ListOffsetsPartitionResponse::lookup(
+            log_manager,
+            "foo",
+            &partition,
+            LEADER_EPOCH,
+            isolation_level,
+        );
+        (response.error_code, response.timestamp, response.offset)
+    }
+
+    #[test]
+    fn test_lookup() {
+        let dir = std::env::temp_dir().join(format!("list-offsets-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log_manager = LogManager::new(&dir, LogConfig::default());
+        let log = log_manager.get_or_create("foo", 0).unwrap();
+        let find = |timestamp| {
+            let (error_code, timestamp, offset) = lookup(&log_manager, timestamp, -1, 0);
+            assert_eq!(error_code, KafkaError::None);
+            (timestamp, offset)
+        };
+        assert_eq!(find(MAX_TIMESTAMP), (-1, -1));
+
+        {
+            let mut log = log.lock().unwrap();
+            log.append(&batch(1000, &[0, 10, 20], 0, -1)).unwrap(); // offsets 0..=2
+            log.append(&batch(2000, &[5, 0], 0, -1)).unwrap(); // offsets 3..=4
+        }
+        // the largest timestamp need not be the batch's last record
+        assert_eq!(find(MAX_TIMESTAMP), (2005, 3));
+
+        let log_append_time = {
+            let mut log = log.lock().unwrap();
+            let info = log.append(&batch(0, &[0, 1], 0x08, -1)).unwrap(); // offsets 5..=6
+            log.append(&batch(3000, &[0], 0x10, 7)).unwrap(); // offset 7, left open
+            info.log_append_time_ms
+        };
+
+        // the exact record inside a batch
+        assert_eq!(find(1000), (1000, 0));
+        assert_eq!(find(1015), (1020, 2));
+        // the first record at or after the target, not the closest one
+        assert_eq!(find(1021), (2005, 3));
+        // every record of a LogAppendTime batch has the append time
+        assert_eq!(find(2006), (log_append_time, 5));
+        assert_eq!(find(log_append_time + 1), (-1, -1));
+        assert_eq!(find(MAX_TIMESTAMP), (log_append_time, 5));
+        assert_eq!(find(EARLIEST_TIMESTAMP), (-1, 0));
+        assert_eq!(find(LATEST_TIMESTAMP), (-1, 8));
+
+        // read_committed stops at the open transaction
+        assert_eq!(
+            lookup(&log_manager, LATEST_TIMESTAMP, -1, READ_COMMITTED),
+            (KafkaError::None, -1, 7)
+        );
+
+        assert_eq!(
+            lookup(&log_manager, LATEST_TIMESTAMP, LEADER_EPOCH, 0),
+            (KafkaError::None, -1, 8)
+        );
+        assert_eq!(
+            lookup(&log_manager, LATEST_TIMESTAMP, LEADER_EPOCH - 1, 0),
+            (KafkaError::FencedLeaderEpoch, -1, -1)
+        );
+        assert_eq!(
+            lookup(&log_manager, LATEST_TIMESTAMP, LEADER_EPOCH + 1, 0),
+            (KafkaError::UnknownLeaderEpoch, -1, -1)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,589 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    config::BrokerConfig,
+    consts::metadata::SupportMetadataRequestVersion,
+    metadata,
+    records::record_value::PartitionRecord,
+    request::body::metadata::{MetadataRequestBody, MetadataRequestTopic},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_nullable_string_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// Authorized operations value meaning "not requested".
+const AUTHORIZED_OPERATIONS_OMITTED: i32 = i32::MIN;
+/// Without an authorizer every topic operation is allowed: READ, WRITE,
+/// CREATE, DELETE, ALTER, DESCRIBE, DESCRIBE_CONFIGS and ALTER_CONFIGS.
+const TOPIC_AUTHORIZED_OPERATIONS_ALL: i32 = 0b1101_1111_1000;
+/// CREATE, ALTER, DESCRIBE, CLUSTER_ACTION, DESCRIBE_CONFIGS, ALTER_CONFIGS,
+/// IDEMPOTENT_WRITE, CREATE_TOKENS and DESCRIBE_TOKENS.
+const CLUSTER_AUTHORIZED_OPERATIONS_ALL: i32 = 0b0111_1111_1010_0000;
+
+pub struct KafkaResponseBodyMetadata {
+    version: SupportMetadataRequestVersion,
+    throttle_time_ms: i32,
+    brokers: Vec<MetadataResponseBroker>,
+    cluster_id: Option<String>,
+    controller_id: i32,
+    topics: Vec<MetadataResponseTopic>,
+    cluster_authorized_operations: i32,
+}
+
+impl KafkaResponseBodyMetadata {
+    pub fn new(request: &MetadataRequestBody, config: &BrokerConfig) -> Self {
+        let version = request.get_api_version();
+        let brokers = MetadataResponseBroker::live_brokers(config);
+        let topic_authorized_operations = if request.include_topic_authorized_operations {
+            TOPIC_AUTHORIZED_OPERATIONS_ALL
+        } else {
+            AUTHORIZED_OPERATIONS_OMITTED
+        };
+        let mut topics: Vec<MetadataResponseTopic> = match &request.topics {
+            None => metadata::topics()
+                .into_iter()
+                .map(|topic| MetadataResponseTopic::found(topic.name, topic.id, &brokers))
+                .collect(),
+            Some(topics) => topics
+                .iter()
+                .map(|topic| MetadataResponseTopic::lookup(topic, request, config, &brokers))
+                .collect(),
+        };
+        for topic in &mut topics {
+            topic.topic_authorized_operations = topic_authorized_operations;
+        }
+        Self {
+            version,
+            throttle_time_ms: 0,
+            brokers,
+            cluster_id: metadata::cluster_id(),
+            controller_id: config.node_id,
+            topics,
+            cluster_authorized_operations: if request.include_cluster_authorized_operations() {
+                CLUSTER_AUTHORIZED_OPERATIONS_ALL
+            } else {
+                AUTHORIZED_OPERATIONS_OMITTED
+            },
+        }
+    }
+}
+
+struct MetadataResponseBroker {
+    node_id: i32,
+    host: String,
+    port: i32,
+    rack: Option<String>,
+}
+
+impl MetadataResponseBroker {
+    /// Brokers registered in the metadata log, or this broker alone when the
+    /// log has no registrations (e.g. it was written by hand).
+    fn live_brokers(config: &BrokerConfig) -> Vec<Self> {
+        let brokers: Vec<_> = metadata::brokers()
+            .into_iter()
+            .filter_map(|broker| {
+                let end_point = broker.end_points.first()?;
+                Some(Self {
+                    node_id: broker.broker_id,
+                    host: end_point.host.clone(),
+                    port: end_point.port as i32,
+                    rack: broker.rack,
+                })
+            })
+            .collect();
+        if !brokers.is_empty() {
+            return brokers;
+        }
+        vec![Self {
+            node_id: config.node_id,
+            host: config.advertised_listener.host.clone(),
+            port: config.advertised_listener.port as i32,
+            rack: None,
+        }]
+    }
+}
+
+struct MetadataResponseTopic {
+    error_code: KafkaError,
+    name: Option<String>,
+    topic_id: [u8; 16],
+    is_internal: bool,
+    partitions: Vec<MetadataResponsePartition>,
+    topic_authorized_operations: i32,
+}
+
+impl MetadataResponseTopic {
+    fn error(error_code: KafkaError, name: Option<String>, topic_id: [u8; 16]) -> Self {
+        Self {
+            error_code,
+            name,
+            topic_id,
+            is_internal: false,
+            partitions: Vec::new(),
+            topic_authorized_operations: AUTHORIZED_OPERATIONS_OMITTED,
+        }
+    }
+
+    fn found(name: String, topic_id: [u8; 16], brokers: &[MetadataResponseBroker]) -> Self {
+        let partitions = metadata::partitions(&topic_id)
+            .iter()
+            .map(|partition| MetadataResponsePartition::new(partition, brokers))
+            .collect();
+        Self {
+            error_code: KafkaError::None,
+            is_internal: metadata::is_internal_topic(&name),
+            name: Some(name),
+            topic_id,
+            partitions,
+            topic_authorized_operations: AUTHORIZED_OPERATIONS_OMITTED,
+        }
+    }
+
+    /// Resolve a requested topic by name or, from version 10, by id, creating
+    /// unknown named topics when the client allows it.
+    fn lookup(
+        topic: &MetadataRequestTopic,
+        request: &MetadataRequestBody,
+        config: &BrokerConfig,
+        brokers: &[MetadataResponseBroker],
+    ) -> Self {
+        let Some(name) = &topic.name else {
+            return match metadata::topic_name_by_id(&topic.topic_id) {
+                Some(name) => Self::found(name, topic.topic_id, brokers),
+                None => Self::error(KafkaError::UnknownTopicId, None, topic.topic_id),
+            };
+        };
+        if let Some(topic_id) = metadata::topic_id_by_name(name) {
+            return Self::found(name.clone(), topic_id, brokers);
+        }
+        if !(request.allow_auto_topic_creation && config.auto_create_topics_enable)
+            || metadata::is_internal_topic(name)
+        {
+            return Self::error(
+                KafkaError::UnknownTopicOrPartition,
+                Some(name.clone()),
+                [0; 16],
+            );
+        }
+        if !metadata::is_valid_topic_name(name) {
+            return Self::error(
+                KafkaError::InvalidTopicException,
+                Some(name.clone()),
+                [0; 16],
+            );
+        }
+        match metadata::create_topic(name, config.num_partitions, config.node_id) {
+            Ok(topic_id) => Self::found(name.clone(), topic_id, brokers),
+            Err(_) => Self::error(KafkaError::UnknownServerError, Some(name.clone()), [0; 16]),
+        }
+    }
+}
+
+struct MetadataResponsePartition {
+    error_code: KafkaError,
+    partition_index: i32,
+    leader_id: i32,
+    leader_epoch: i32,
+    replica_nodes: Vec<i32>,
+    isr_nodes: Vec<i32>,
+    offline_replicas: Vec<i32>,
+}
+
+impl MetadataResponsePartition {
+    fn new(partition: &PartitionRecord, brokers: &[MetadataResponseBroker]) -> Self {
+        let offline_replicas = partition
+            .replicas
+            .iter()
+            .copied()
+            .filter(|replica| !brokers.iter().any(|broker| broker.node_id == *replica))
+            .collect();
+        Self {
+            error_code: KafkaError::None,
+            partition_index: partition.partition_id,
+            leader_id: partition.leader_id,
+            leader_epoch: partition.leader_epoch,
+            replica_nodes: partition.replicas.clone(),
+            isr_nodes: partition.isr.clone(),
+            offline_replicas,
+        }
+    }
+}
+
+/// Write `values` as an `int32` array, compact in flexible versions.
+fn write_i32_array<W: std::io::Write>(
+    writer: &mut W,
+    values: Vec<i32>,
+    flexible: bool,
+) -> std::io::Result<()> {
+    let write_one = |writer: &mut W, value: i32| writer.write_i32::<BigEndian>(value);
+    if flexible {
+        write_kafka_compact_array_stream(writer, values, write_one)
+    } else {
+        write_kafka_array_stream(writer, values, write_one)
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyMetadata {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        let flexible = version.is_flexible();
+        if version >= SupportMetadataRequestVersion::V3 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        if flexible {
+            write_kafka_compact_array_stream(writer, self.brokers, |writer, broker| {
+                broker.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+        } else {
+            write_kafka_array_stream(writer, self.brokers, |writer, broker| {
+                broker.serialize(writer, version)
+            })?;
+        }
+        if version >= SupportMetadataRequestVersion::V2 {
+            if flexible {
+                write_compact_nullable_string_stream(writer, self.cluster_id)?;
+            } else {
+                write_nullable_string_stream(writer, self.cluster_id)?;
+            }
+        }
+        if version >= SupportMetadataRequestVersion::V1 {
+            writer.write_i32::<BigEndian>(self.controller_id)?;
+        }
+        if flexible {
+            write_kafka_compact_array_stream(writer, self.topics, |writer, topic| {
+                topic.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+        } else {
+            write_kafka_array_stream(writer, self.topics, |writer, topic| {
+                topic.serialize(writer, version)
+            })?;
+        }
+        if (SupportMetadataRequestVersion::V8..=SupportMetadataRequestVersion::V10)
+            .contains(&version)
+        {
+            writer.write_i32::<BigEndian>(self.cluster_authorized_operations)?;
+        }
+        if flexible {
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for MetadataResponseBroker {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportMetadataRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let flexible = version.is_flexible();
+        writer.write_i32::<BigEndian>(self.node_id)?;
+        if flexible {
+            write_compact_string_stream(writer, self.host)?;
+        } else {
+            write_string_stream(writer, self.host)?;
+        }
+        writer.write_i32::<BigEndian>(self.port)?;
+        if version >= SupportMetadataRequestVersion::V1 {
+            if flexible {
+                write_compact_nullable_string_stream(writer, self.rack)?;
+            } else {
+                write_nullable_string_stream(writer, self.rack)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for MetadataResponseTopic {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportMetadataRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let flexible = version.is_flexible();
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        // the name only became nullable in version 12
+        let name = match self.name {
+            None if version < SupportMetadataRequestVersion::V12 => Some(String::new()),
+            name => name,
+        };
+        if flexible {
+            write_compact_nullable_string_stream(writer, name)?;
+        } else {
+            write_nullable_string_stream(writer, name)?;
+        }
+        if version >= SupportMetadataRequestVersion::V10 {
+            writer.write_all(&self.topic_id)?;
+        }
+        if version >= SupportMetadataRequestVersion::V1 {
+            writer.write_u8(self.is_internal as u8)?;
+        }
+        if flexible {
+            write_kafka_compact_array_stream(writer, self.partitions, |writer, partition| {
+                partition.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+        } else {
+            write_kafka_array_stream(writer, self.partitions, |writer, partition| {
+                partition.serialize(writer, version)
+            })?;
+        }
+        if version >= SupportMetadataRequestVersion::V8 {
+            writer.write_i32::<BigEndian>(self.topic_authorized_operations)?;
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for MetadataResponsePartition {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportMetadataRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let flexible = version.is_flexible();
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        writer.write_i32::<BigEndian>(self.partition_index)?;
+        writer.write_i32::<BigEndian>(self.leader_id)?;
+        if version >= SupportMetadataRequestVersion::V7 {
+            writer.write_i32::<BigEndian>(self.leader_epoch)?;
+        }
+        write_i32_array(writer, self.replica_nodes, flexible)?;
+        write_i32_array(writer, self.isr_nodes, flexible)?;
+        if version >= SupportMetadataRequestVersion::V5 {
+            write_i32_array(writer, self.offline_replicas, flexible)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV1},
+        },
+        traits::KafkaDeseriarize,
+    };
+
+    const TOPIC_ID: [u8; 16] = [7; 16];
+
+    fn response(version: SupportMetadataRequestVersion) -> KafkaResponseBodyMetadata {
+        KafkaResponseBodyMetadata {
+            version,
+            throttle_time_ms: 0,
+            brokers: vec![MetadataResponseBroker {
+                node_id: 1,
+                host: "h".to_string(),
+                port: 9092,
+                rack: Some("r".to_string()),
+            }],
+            cluster_id: Some("c".to_string()),
+            controller_id: 1,
+            topics: vec![MetadataResponseTopic {
+                error_code: KafkaError::None,
+                name: Some("foo".to_string()),
+                topic_id: TOPIC_ID,
+                is_internal: true,
+                partitions: vec![MetadataResponsePartition {
+                    error_code: KafkaError::None,
+                    partition_index: 0,
+                    leader_id: 1,
+                    leader_epoch: 5,
+                    replica_nodes: vec![1, 2],
+                    isr_nodes: vec![1],
+                    offline_replicas: vec![2],
+                }],
+                topic_authorized_operations: 8,
+            }],
+            cluster_authorized_operations: 9,
+        }
+    }
+
+    fn serialize(body: KafkaResponseBodyMetadata) -> Vec<u8> {
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    /// The fixture of `response`, laid out by hand for `version`.
+    fn expected(version: i16) -> Vec<u8> {
+        let flexible = version >= 9;
+        let mut buf = Vec::new();
+        let len = |buf: &mut Vec<u8>, len: usize| {
+            if flexible {
+                buf.push(len as u8 + 1);
+            } else {
+                buf.extend_from_slice(&(len as i32).to_be_bytes());
+            }
+        };
+        let string = |buf: &mut Vec<u8>, s: &str| {
+            if flexible {
+                buf.push(s.len() as u8 + 1);
+            } else {
+                buf.extend_from_slice(&(s.len() as i16).to_be_bytes());
+            }
+            buf.extend_from_slice(s.as_bytes());
+        };
+        let i32s = |buf: &mut Vec<u8>, values: &[i32]| {
+            len(buf, values.len());
+            for value in values {
+                buf.extend_from_slice(&value.to_be_bytes());
+            }
+        };
+        let tagged_fields = |buf: &mut Vec<u8>| {
+            if flexible {
+                buf.push(0);
+            }
+        };
+
+        if version >= 3 {
+            buf.extend_from_slice(&0i32.to_be_bytes());
+        }
+        len(&mut buf, 1);
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        string(&mut buf, "h");
+        buf.extend_from_slice(&9092i32.to_be_bytes());
+        if version >= 1 {
+            string(&mut buf, "r");
+        }
+        tagged_fields(&mut buf);
+        if version >= 2 {
+            string(&mut buf, "c");
+        }
+        if version >= 1 {
+            buf.extend_from_slice(&1i32.to_be_bytes());
+        }
+
+        len(&mut buf, 1);
+        buf.extend_from_slice(&0i16.to_be_bytes());
+        string(&mut buf, "foo");
+        if version >= 10 {
+            buf.extend_from_slice(&TOPIC_ID);
+        }
+        if version >= 1 {
+            buf.push(1);
+        }
+        len(&mut buf, 1);
+        buf.extend_from_slice(&0i16.to_be_bytes());
+        buf.extend_from_slice(&0i32.to_be_bytes());
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        if version >= 7 {
+            buf.extend_from_slice(&5i32.to_be_bytes());
+        }
+        i32s(&mut buf, &[1, 2]);
+        i32s(&mut buf, &[1]);
+        if version >= 5 {
+            i32s(&mut buf, &[2]);
+        }
+        tagged_fields(&mut buf);
+        if version >= 8 {
+            buf.extend_from_slice(&8i32.to_be_bytes());
+        }
+        tagged_fields(&mut buf);
+
+        if (8..=10).contains(&version) {
+            buf.extend_from_slice(&9i32.to_be_bytes());
+        }
+        tagged_fields(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        // v0 base, v1 rack, controller and is_internal, v2 cluster_id,
+        // v3 throttle, v5 offline replicas, v7 leader epoch, v8 authorized
+        // operations, v9 flexible, v10 topic id, v11 no cluster operations
+        for version in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12] {
+            let body = response(version.try_into().unwrap());
+            assert_eq!(serialize(body), expected(version), "v{version}");
+        }
+    }
+
+    #[test]
+    fn test_serialize_null_topic_name() {
+        let unnamed = |version: SupportMetadataRequestVersion| {
+            let mut body = response(version);
+            body.topics = vec![MetadataResponseTopic::error(
+                KafkaError::UnknownTopicId,
+                None,
+                TOPIC_ID,
+            )];
+            serialize(body)
+        };
+        let topic = |name: &[u8]| {
+            [
+                &[2][..],
+                &100i16.to_be_bytes(),
+                name,
+                &TOPIC_ID,
+                &[0, 1],
+                &i32::MIN.to_be_bytes(),
+                &[0, 0],
+            ]
+            .concat()
+        };
+        // an empty name before v12, null from v12
+        assert!(unnamed(SupportMetadataRequestVersion::V11).ends_with(&topic(&[1])));
+        assert!(unnamed(SupportMetadataRequestVersion::V12).ends_with(&topic(&[0])));
+    }
+
+    #[test]
+    fn test_internal_topics_are_not_auto_created() {
+        let header = KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+            request_api_key: RequestApiKey::Metadata,
+            request_api_version: 4,
+            correlation_id: 1,
+            client_id: String::new(),
+        });
+        let name = "__consumer_offsets";
+        let buf = [
+            &1i32.to_be_bytes()[..],
+            &(name.len() as i16).to_be_bytes(),
+            name.as_bytes(),
+            &[1],
+        ]
+        .concat();
+        let request =
+            MetadataRequestBody::try_parse_from_reader(&mut Cursor::new(buf), &header).unwrap();
+        assert!(request.allow_auto_topic_creation);
+
+        let response = KafkaResponseBodyMetadata::new(&request, &BrokerConfig::default());
+        assert!(matches!(
+            response.topics[0].error_code,
+            KafkaError::UnknownTopicOrPartition
+        ));
+        assert_eq!(response.topics[0].name.as_deref(), Some(name));
+    }
+}
@@ -0,0 +1,255 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    config::BrokerConfig,
+    consts::offset_commit::SupportOffsetCommitRequestVersion,
+    globals::GROUP_COORDINATOR,
+    group::PartitionCommit,
+    metadata,
+    request::body::offset_commit::OffsetCommitRequestBody,
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_string_stream, write_kafka_array_stream,
+            write_kafka_compact_array_stream, write_kafka_tagged_fields_stream,
+            write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// OffsetCommit response, versions 2 through 8, with an error code per
+/// requested partition.
+pub struct KafkaResponseBodyOffsetCommit {
+    version: SupportOffsetCommitRequestVersion,
+    throttle_time_ms: i32,
+    topics: Vec<OffsetCommitTopicResponse>,
+}
+
+struct OffsetCommitTopicResponse {
+    name: String,
+    /// `(partition_index, error_code)`
+    partitions: Vec<(i32, KafkaError)>,
+}
+
+impl KafkaResponseBodyOffsetCommit {
+    pub fn new(request: &OffsetCommitRequestBody, config: &BrokerConfig) -> Self {
+        // partitions that pass the per-partition checks are committed together
+        let mut commits = Vec::new();
+        let mut topics: Vec<OffsetCommitTopicResponse> = request
+            .topics
+            .iter()
+            .map(|topic| {
+                let topic_id = metadata::topic_id_by_name(&topic.name);
+                let partitions = topic
+                    .partitions
+                    .iter()
+                    .map(|partition| {
+                        let partition_index = partition.partition_index;
+                        let metadata = partition.committed_metadata.clone().unwrap_or_default();
+                        let error_code = if topic_id
+                            .and_then(|id| metadata::partition(&id, partition_index))
+                            .is_none()
+                        {
+                            KafkaError::UnknownTopicOrPartition
+                        } else if metadata.len() > config.offset_metadata_max_bytes {
+                            KafkaError::OffsetMetadataTooLarge
+                        } else {
+                            commits.push(PartitionCommit {
+                                topic: topic.name.clone(),
+                                partition: partition_index,
+                                offset: partition.committed_offset,
+                                leader_epoch: partition.committed_leader_epoch,
+                                metadata,
+                            });
+                            KafkaError::None
+                        };
+                        (partition_index, error_code)
+                    })
+                    .collect();
+                OffsetCommitTopicResponse {
+                    name: topic.name.clone(),
+                    partitions,
+                }
+            })
+            .collect();
+
+        if !commits.is_empty() {
+            let result = match GROUP_COORDINATOR.get() {
+                Some(coordinator) => coordinator.commit_offsets(
+                    &request.group_id,
+                    request.generation_id,
+                    &request.member_id,
+                    request.group_instance_id.as_deref(),
+                    commits,
+                ),
+                None => Err(KafkaError::CoordinatorNotAvailable),
+            };
+            if let Err(error_code) = result {
+                for (_, partition_error) in topics
+                    .iter_mut()
+                    .flat_map(|topic| topic.partitions.iter_mut())
+                    .filter(|(_, partition_error)| *partition_error == KafkaError::None)
+                {
+                    *partition_error = error_code;
+                }
+            }
+        }
+
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            topics,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyOffsetCommit {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version >= SupportOffsetCommitRequestVersion::V3 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        let write_partition = |writer: &mut W, (partition_index, error_code): (i32, KafkaError)| {
+            writer.write_i32::<BigEndian>(partition_index)?;
+            let error_code: i16 = error_code.into();
+            writer.write_i16::<BigEndian>(error_code)
+        };
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.topics, |writer, topic| {
+                write_compact_string_stream(writer, topic.name)?;
+                write_kafka_compact_array_stream(writer, topic.partitions, |writer, partition| {
+                    write_partition(writer, partition)?;
+                    write_kafka_tagged_fields_stream(writer, Vec::new())
+                })?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_kafka_array_stream(writer, self.topics, |writer, topic| {
+                write_string_stream(writer, topic.name)?;
+                write_kafka_array_stream(writer, topic.partitions, write_partition)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV1},
+        },
+        traits::KafkaDeseriarize,
+    };
+
+    fn serialize(version: SupportOffsetCommitRequestVersion) -> Vec<u8> {
+        let body = KafkaResponseBodyOffsetCommit {
+            version,
+            throttle_time_ms: 0,
+            topics: vec![OffsetCommitTopicResponse {
+                name: "foo".to_string(),
+                partitions: vec![
+                    (0, KafkaError::None),
+                    (1, KafkaError::OffsetMetadataTooLarge),
+                ],
+            }],
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        let partitions = |tagged_fields: &[u8]| {
+            [
+                &0i32.to_be_bytes()[..],
+                &0i16.to_be_bytes(),
+                tagged_fields,
+                &1i32.to_be_bytes(),
+                &12i16.to_be_bytes(),
+                tagged_fields,
+            ]
+            .concat()
+        };
+
+        // v2: no throttle time, an error code per partition
+        let topics = [
+            &1i32.to_be_bytes()[..],
+            &3i16.to_be_bytes(),
+            b"foo",
+            &2i32.to_be_bytes(),
+            &partitions(&[]),
+        ]
+        .concat();
+        assert_eq!(serialize(SupportOffsetCommitRequestVersion::V2), topics);
+
+        // v3 adds the throttle time
+        let expected = [&0i32.to_be_bytes()[..], &topics].concat();
+        assert_eq!(serialize(SupportOffsetCommitRequestVersion::V3), expected);
+
+        // v8: compact encoding and tagged fields
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[2, 4],
+            b"foo",
+            &[3],
+            &partitions(&[0]),
+            &[0, 0],
+        ]
+        .concat();
+        assert_eq!(serialize(SupportOffsetCommitRequestVersion::V8), expected);
+    }
+
+    #[test]
+    fn test_unknown_partitions_fail_on_their_own() {
+        let header = KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+            request_api_key: RequestApiKey::OffsetCommit,
+            request_api_version: 5,
+            correlation_id: 1,
+            client_id: String::new(),
+        });
+        let topic = "offset-commit-test-unknown";
+        let buf = [
+            &1i16.to_be_bytes()[..],
+            b"g",
+            &(-1i32).to_be_bytes(),
+            &0i16.to_be_bytes(),
+            &1i32.to_be_bytes(),
+            &(topic.len() as i16).to_be_bytes(),
+            topic.as_bytes(),
+            &2i32.to_be_bytes(),
+            &0i32.to_be_bytes(),
+            &42i64.to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+            &1i32.to_be_bytes(),
+            &42i64.to_be_bytes(),
+            &(-1i16).to_be_bytes(),
+        ]
+        .concat();
+        let request =
+            OffsetCommitRequestBody::try_parse_from_reader(&mut Cursor::new(buf), &header).unwrap();
+
+        let response = KafkaResponseBodyOffsetCommit::new(&request, &BrokerConfig::default());
+        assert_eq!(
+            response.topics[0].partitions,
+            [
+                (0, KafkaError::UnknownTopicOrPartition),
+                (1, KafkaError::UnknownTopicOrPartition)
+            ]
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::BTreeMap;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::offset_fetch::SupportOffsetFetchRequestVersion,
+    globals::GROUP_COORDINATOR,
+    group::GroupCoordinator,
+    request::body::offset_fetch::{OffsetFetchGroup, OffsetFetchRequestBody},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_nullable_string_stream, write_string_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// OffsetFetch response, versions 1 through 8.
+///
+/// Versions 1-7 answer for a single group, with its error code at the top
+/// level from version 2; version 8 answers per group.
+pub struct KafkaResponseBodyOffsetFetch {
+    version: SupportOffsetFetchRequestVersion,
+    throttle_time_ms: i32,
+    groups: Vec<OffsetFetchGroupResponse>,
+}
+
+struct OffsetFetchGroupResponse {
+    group_id: String,
+    topics: Vec<OffsetFetchTopicResponse>,
+    error_code: KafkaError,
+}
+
+struct OffsetFetchTopicResponse {
+    name: String,
+    partitions: Vec<OffsetFetchPartitionResponse>,
+}
+
+struct OffsetFetchPartitionResponse {
+    partition_index: i32,
+    /// `-1` when the group has not committed an offset for the partition.
+    committed_offset: i64,
+    committed_leader_epoch: i32,
+    metadata: Option<String>,
+    error_code: KafkaError,
+}
+
+impl KafkaResponseBodyOffsetFetch {
+    pub fn new(request: &OffsetFetchRequestBody) -> Self {
+        let groups = request
+            .groups
+            .iter()
+            .map(|group| match GROUP_COORDINATOR.get() {
+                Some(coordinator) => OffsetFetchGroupResponse::new(coordinator, group),
+                None => OffsetFetchGroupResponse::error(group, KafkaError::CoordinatorNotAvailable),
+            })
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            groups,
+        }
+    }
+}
+
+impl OffsetFetchGroupResponse {
+    fn new(coordinator: &GroupCoordinator, group: &OffsetFetchGroup) -> Self {
+        if group.group_id.is_empty() {
+            return Self::error(group, KafkaError::InvalidGroupId);
+        }
+        let requested: Vec<(String, Vec<i32>)> = match &group.topics {
+            Some(topics) => topics
+                .iter()
+                .map(|topic| (topic.name.clone(), topic.partition_indexes.clone()))
+                .collect(),
+            None => {
+                let mut by_topic: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+                for (topic, partition) in coordinator.committed_partitions(&group.group_id) {
+                    by_topic.entry(topic).or_default().push(partition);
+                }
+                by_topic.into_iter().collect()
+            }
+        };
+        let topics = requested
+            .into_iter()
+            .map(|(name, partition_indexes)| {
+                let partitions = partition_indexes
+                    .into_iter()
+                    .map(|partition_index| {
+                        match coordinator.fetch_offset(&group.group_id, &name, partition_index) {
+                            Some(committed) => OffsetFetchPartitionResponse {
+                                partition_index,
+                                committed_offset: committed.offset,
+                                committed_leader_epoch: committed.leader_epoch,
+                                metadata: Some(committed.metadata),
+                                error_code: KafkaError::None,
+                            },
+                            None => OffsetFetchPartitionResponse::uncommitted(
+                                partition_index,
+                                KafkaError::None,
+                            ),
+                        }
+                    })
+                    .collect();
+                OffsetFetchTopicResponse { name, partitions }
+            })
+            .collect();
+        Self {
+            group_id: group.group_id.clone(),
+            topics,
+            error_code: KafkaError::None,
+        }
+    }
+
+    /// A group-level error, repeated on every requested partition for
+    /// version 1, which has no group error code.
+    fn error(group: &OffsetFetchGroup, error_code: KafkaError) -> Self {
+        let topics = group
+            .topics
+            .iter()
+            .flatten()
+            .map(|topic| OffsetFetchTopicResponse {
+                name: topic.name.clone(),
+                partitions: topic
+                    .partition_indexes
+                    .iter()
+                    .map(|&partition_index| {
+                        OffsetFetchPartitionResponse::uncommitted(partition_index, error_code)
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            group_id: group.group_id.clone(),
+            topics,
+            error_code,
+        }
+    }
+}
+
+impl OffsetFetchPartitionResponse {
+    fn uncommitted(partition_index: i32, error_code: KafkaError) -> Self {
+        Self {
+            partition_index,
+            committed_offset: -1,
+            committed_leader_epoch: -1,
+            metadata: Some(String::new()),
+            error_code,
+        }
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodyOffsetFetch {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version >= SupportOffsetFetchRequestVersion::V3 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        if version.is_batched() {
+            write_kafka_compact_array_stream(writer, self.groups, |writer, group| {
+                write_compact_string_stream(writer, &group.group_id)?;
+                group.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+        } else if let Some(group) = self.groups.into_iter().next() {
+            group.serialize(writer, version)?;
+        }
+        if version.is_flexible() {
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for OffsetFetchGroupResponse {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportOffsetFetchRequestVersion;
+
+    /// Topics and error code; the group id is only written in batches.
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.topics, |writer, topic| {
+                write_compact_string_stream(writer, topic.name)?;
+                write_kafka_compact_array_stream(writer, topic.partitions, |writer, partition| {
+                    partition.serialize(writer, version)?;
+                    write_kafka_tagged_fields_stream(writer, Vec::new())
+                })?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+        } else {
+            write_kafka_array_stream(writer, self.topics, |writer, topic| {
+                write_string_stream(writer, topic.name)?;
+                write_kafka_array_stream(writer, topic.partitions, |writer, partition| {
+                    partition.serialize(writer, version)
+                })
+            })?;
+        }
+        if version >= SupportOffsetFetchRequestVersion::V2 {
+            let error_code: i16 = self.error_code.into();
+            writer.write_i16::<BigEndian>(error_code)?;
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for OffsetFetchPartitionResponse {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportOffsetFetchRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        writer.write_i32::<BigEndian>(self.partition_index)?;
+        writer.write_i64::<BigEndian>(self.committed_offset)?;
+        if version >= SupportOffsetFetchRequestVersion::V5 {
+            writer.write_i32::<BigEndian>(self.committed_leader_epoch)?;
+        }
+        if version.is_flexible() {
+            write_compact_nullable_string_stream(writer, self.metadata)?;
+        } else {
+            write_nullable_string_stream(writer, self.metadata)?;
+        }
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use super::*;
+    use crate::{
+        group::PartitionCommit,
+        request::{
+            api_key::RequestApiKey,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV2},
+        },
+        traits::KafkaDeseriarize,
+    };
+
+    fn serialize(body: KafkaResponseBodyOffsetFetch) -> Vec<u8> {
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    fn group(group_id: &str, error_code: KafkaError) -> OffsetFetchGroupResponse {
+        OffsetFetchGroupResponse {
+            group_id: group_id.to_string(),
+            topics: vec![OffsetFetchTopicResponse {
+                name: "foo".to_string(),
+                partitions: vec![OffsetFetchPartitionResponse {
+                    partition_index: 0,
+                    committed_offset: 42,
+                    committed_leader_epoch: 5,
+                    metadata: Some("meta".to_string()),
+                    error_code: KafkaError::None,
+                }],
+            }],
+            error_code,
+        }
+    }
+
+    fn single_group(version: SupportOffsetFetchRequestVersion) -> Vec<u8> {
+        serialize(KafkaResponseBodyOffsetFetch {
+            version,
+            throttle_time_ms: 0,
+            groups: vec![group("g", KafkaError::InvalidGroupId)],
+        })
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        let partition = |epoch: &[u8]| {
+            [
+                &0i32.to_be_bytes()[..],
+                &42i64.to_be_bytes(),
+                epoch,
+                &4i16.to_be_bytes(),
+                b"meta",
+                &0i16.to_be_bytes(),
+            ]
+            .concat()
+        };
+        let topics = |partition: &[u8]| {
+            [
+                &1i32.to_be_bytes()[..],
+                &3i16.to_be_bytes(),
+                b"foo",
+                &1i32.to_be_bytes(),
+                partition,
+            ]
+            .concat()
+        };
+
+        // v1: no throttle time or group error code
+        let expected = topics(&partition(&[]));
+        assert_eq!(single_group(SupportOffsetFetchRequestVersion::V1), expected);
+
+        // v2 adds the group error code
+        let expected = [&expected[..], &24i16.to_be_bytes()].concat();
+        assert_eq!(single_group(SupportOffsetFetchRequestVersion::V2), expected);
+
+        // v3 adds the throttle time
+        let expected = [&0i32.to_be_bytes()[..], &expected].concat();
+        assert_eq!(single_group(SupportOffsetFetchRequestVersion::V3), expected);
+
+        // v5 adds the committed leader epoch
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &topics(&partition(&5i32.to_be_bytes())),
+            &24i16.to_be_bytes(),
+        ]
+        .concat();
+        assert_eq!(single_group(SupportOffsetFetchRequestVersion::V5), expected);
+
+        // v6: compact encoding and tagged fields
+        let flexible_topics = [
+            &[2, 4][..],
+            b"foo",
+            &[2],
+            &0i32.to_be_bytes(),
+            &42i64.to_be_bytes(),
+            &5i32.to_be_bytes(),
+            &[5],
+            b"meta",
+            &0i16.to_be_bytes(),
+            &[0, 0],
+        ]
+        .concat();
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &flexible_topics,
+            &24i16.to_be_bytes(),
+            &[0],
+        ]
+        .concat();
+        assert_eq!(single_group(SupportOffsetFetchRequestVersion::V6), expected);
+    }
+
+    #[test]
+    fn test_serialize_several_groups() {
+        let body = KafkaResponseBodyOffsetFetch {
+            version: SupportOffsetFetchRequestVersion::V8,
+            throttle_time_ms: 0,
+            groups: vec![
+                group("g", KafkaError::None),
+                OffsetFetchGroupResponse {
+                    group_id: "h".to_string(),
+                    topics: Vec::new(),
+                    error_code: KafkaError::CoordinatorNotAvailable,
+                },
+            ],
+        };
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &[3, 2],
+            b"g",
+            &[2, 4],
+            b"foo",
+            &[2],
+            &0i32.to_be_bytes(),
+            &42i64.to_be_bytes(),
+            &5i32.to_be_bytes(),
+            &[5],
+            b"meta",
+            &0i16.to_be_bytes(),
+            &[0, 0],
+            &0i16.to_be_bytes(),
+            &[0],
+            &[2],
+            b"h",
+            &[1],
+            &15i16.to_be_bytes(),
+            &[0],
+            &[0],
+        ]
+        .concat();
+        assert_eq!(serialize(body), expected);
+    }
+
+    #[test]
+    fn test_committed_offsets_round_trip() {
+        let coordinator = GroupCoordinator::new(Duration::from_millis(1), Duration::from_secs(60));
+        coordinator
+            .commit_offsets(
+                "g",
+                -1,
+                "",
+                None,
+                vec![PartitionCommit {
+                    topic: "foo".to_string(),
+                    partition: 0,
+                    offset: 42,
+                    leader_epoch: 5,
+                    metadata: "meta".to_string(),
+                }],
+            )
+            .unwrap();
+
+        // a v8 fetch of partitions 0 and 1 for group "g", everything for "h"
+        // and a group without an id
+        let header = KafkaRequestHeader::V2(KafkaRequestHeaderV2 {
+            request_api_key: RequestApiKey::OffsetFetch,
+            request_api_version: 8,
+            correlation_id: 1,
+            client_id: String::new(),
+        });
+        let buf = [
+            &[4, 2][..],
+            b"g",
+            &[2, 4],
+            b"foo",
+            &[3],
+            &0i32.to_be_bytes(),
+            &1i32.to_be_bytes(),
+            &[0, 0],
+            &[2],
+            b"h",
+            &[0, 0],
+            &[1, 0, 0],
+            &[0, 0],
+        ]
+        .concat();
+        let request =
+            OffsetFetchRequestBody::try_parse_from_reader(&mut Cursor::new(buf), &header).unwrap();
+        let groups: Vec<_> = request
+            .groups
+            .iter()
+            .map(|group| OffsetFetchGroupResponse::new(&coordinator, group))
+            .collect();
+
+        let partitions = &groups[0].topics[0].partitions;
+        assert_eq!(partitions[0].committed_offset, 42);
+        assert_eq!(partitions[0].committed_leader_epoch, 5);
+        assert_eq!(partitions[0].metadata.as_deref(), Some("meta"));
+        assert_eq!(partitions[1].committed_offset, -1);
+        assert_eq!(partitions[1].committed_leader_epoch, -1);
+        assert_eq!(partitions[1].error_code, KafkaError::None);
+        assert!(groups[1].topics.is_empty());
+        assert_eq!(groups[1].error_code, KafkaError::None);
+        assert_eq!(groups[2].error_code, KafkaError::InvalidGroupId);
+
+        let body = KafkaResponseBodyOffsetFetch {
+            version: SupportOffsetFetchRequestVersion::V8,
+            throttle_time_ms: 0,
+            groups,
+        };
+        let committed = [
+            &0i32.to_be_bytes()[..],
+            &42i64.to_be_bytes(),
+            &5i32.to_be_bytes(),
+            &[5],
+            b"meta",
+            &0i16.to_be_bytes(),
+        ]
+        .concat();
+        let buf = serialize(body);
+        assert!(buf
+            .windows(committed.len())
+            .any(|window| window == committed));
+    }
+}
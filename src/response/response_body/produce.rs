@@ -0,0 +1,410 @@
+use std::{
+    borrow::Cow,
+    io::{self, Cursor},
+};
+
+use binrw::{BinWrite, Endian};
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    config::BrokerConfig,
+    consts::produce::SupportProduceRequestVersion,
+    globals::LOG_MANAGER,
+    metadata,
+    records::{compression::Compression, BatchReadError, RecordBatch},
+    request::body::produce::{PartitionProduceData, ProduceRequestBody},
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_nullable_string_stream, write_compact_string_stream,
+            write_kafka_array_stream, write_kafka_compact_array_stream,
+            write_kafka_tagged_fields_stream, write_nullable_string_stream, write_string_stream,
+        },
+    },
+    storage::AppendError,
+    traits::KafkaSeriarize,
+};
+
+pub struct KafkaResponseBodyProduce {
+    version: SupportProduceRequestVersion,
+    responses: Vec<TopicProduceResponse>,
+    throttle_time_ms: i32,
+}
+
+impl KafkaResponseBodyProduce {
+    pub fn new(request: &ProduceRequestBody, config: &BrokerConfig) -> Self {
+        let acks_valid = matches!(request.acks, -1..=1);
+        let responses = request
+            .topic_data
+            .iter()
+            .map(|topic| TopicProduceResponse {
+                name: topic.name.clone(),
+                partition_responses: topic
+                    .partition_data
+                    .iter()
+                    .map(|partition| {
+                        if acks_valid {
+                            PartitionProduceResponse::append(&topic.name, partition, config)
+                        } else {
+                            PartitionProduceResponse::error(
+                                partition.index,
+                                KafkaError::InvalidRequiredAcks,
+                            )
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            version: request.get_api_version(),
+            responses,
+            throttle_time_ms: 0,
+        }
+    }
+}
+
+struct TopicProduceResponse {
+    name: String,
+    partition_responses: Vec<PartitionProduceResponse>,
+}
+
+struct PartitionProduceResponse {
+    index: i32,
+    error_code: KafkaError,
+    base_offset: i64,
+    log_append_time_ms: i64,
+    log_start_offset: i64,
+    record_errors: Vec<BatchIndexAndErrorMessage>,
+    error_message: Option<String>,
+}
+
+struct BatchIndexAndErrorMessage {
+    batch_index: i32,
+    batch_index_error_message: Option<String>,
+}
+
+impl PartitionProduceResponse {
+    fn error(index: i32, error_code: KafkaError) -> Self {
+        Self {
+            index,
+            error_code,
+            base_offset: -1,
+            log_append_time_ms: -1,
+            log_start_offset: -1,
+            record_errors: Vec::new(),
+            error_message: None,
+        }
+    }
+
+    fn append(topic: &str, partition: &PartitionProduceData, config: &BrokerConfig) -> Self {
+        let Some(partition_record) = metadata::topic_id_by_name(topic)
+            .and_then(|topic_id| metadata::partition(&topic_id, partition.index))
+        else {
+            return Self::error(partition.index, KafkaError::UnknownTopicOrPartition);
+        };
+        let Some(records) = &partition.records else {
+            return Self::error(partition.index, KafkaError::CorruptMessage);
+        };
+        let records = match config.compression_type {
+            Some(compression) => match recompress(records, compression) {
+                Ok(records) => records,
+                Err(e) => {
+                    return Self {
+                        error_message: Some(e.to_string()),
+                        ..Self::error(partition.index, KafkaError::CorruptMessage)
+                    }
+                }
+            },
+            None => Cow::Borrowed(records.as_slice()),
+        };
+        let Some(log_manager) = LOG_MANAGER.get() else {
+            return Self::error(partition.index, KafkaError::KafkaStorageError);
+        };
+        let result = log_manager
+            .get_or_create(topic, partition.index)
+            .map_err(AppendError::from)
+            .and_then(|log| {
+                let mut log = log.lock().unwrap();
+                log.append_as_leader(&records, partition_record.leader_epoch)
+            });
+        match result {
+            Ok(info) => Self {
+                index: partition.index,
+                error_code: KafkaError::None,
+                base_offset: info.base_offset,
+                log_append_time_ms: info.log_append_time_ms,
+                log_start_offset: info.log_start_offset,
+                record_errors: Vec::new(),
+                error_message: None,
+            },
+            Err(AppendError::CorruptBatch {
+                batch_index,
+                reason,
+            }) => Self {
+                record_errors: vec![BatchIndexAndErrorMessage {
+                    batch_index: batch_index as i32,
+                    batch_index_error_message: Some(reason.to_string()),
+                }],
+                error_message: Some(reason.to_string()),
+                ..Self::error(partition.index, KafkaError::CorruptMessage)
+            },
+            Err(e @ AppendError::OutOfOrderSequence { .. }) => Self {
+                error_message: Some(e.to_string()),
+                ..Self::error(partition.index, KafkaError::OutOfOrderSequenceNumber)
+            },
+            Err(e @ AppendError::InvalidProducerEpoch { .. }) => Self {
+                error_message: Some(e.to_string()),
+                ..Self::error(partition.index, KafkaError::InvalidProducerEpoch)
+            },
+            Err(AppendError::Io(e)) => Self {
+                error_message: Some(e.to_string()),
+                ..Self::error(partition.index, KafkaError::KafkaStorageError)
+            },
+        }
+    }
+}
+
+/// Rewrite the batches of a record set whose codec differs from the broker's
+/// `compression.type`.
+fn recompress(records: &[u8], compression: Compression) -> Result<Cow<'_, [u8]>, BatchReadError> {
+    let batches = RecordBatch::read_batches_from(&mut Cursor::new(records))?;
+    if batches
+        .iter()
+        .all(|batch| batch.compression() == Some(compression))
+    {
+        return Ok(Cow::Borrowed(records));
+    }
+    let mut recompressed = Cursor::new(Vec::with_capacity(records.len()));
+    for mut batch in batches {
+        batch.attributes = compression.apply_to(batch.attributes);
+        batch
+            .write_options(&mut recompressed, Endian::Big, ())
+            .map_err(|e| BatchReadError::Io(io::Error::other(e)))?;
+    }
+    Ok(Cow::Owned(recompressed.into_inner()))
+}
+
+impl KafkaSeriarize for KafkaResponseBodyProduce {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version.is_flexible() {
+            write_kafka_compact_array_stream(writer, self.responses, |writer, topic| {
+                topic.serialize(writer, version)?;
+                write_kafka_tagged_fields_stream(writer, Vec::new())
+            })?;
+        } else {
+            write_kafka_array_stream(writer, self.responses, |writer, topic| {
+                topic.serialize(writer, version)
+            })?;
+        }
+        writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        if version.is_flexible() {
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        }
+        Ok(())
+    }
+}
+
+impl KafkaSeriarize for TopicProduceResponse {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportProduceRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        if version.is_flexible() {
+            write_compact_string_stream(writer, self.name)?;
+            write_kafka_compact_array_stream(
+                writer,
+                self.partition_responses,
+                |writer, partition| {
+                    partition.serialize(writer, version)?;
+                    write_kafka_tagged_fields_stream(writer, Vec::new())
+                },
+            )
+        } else {
+            write_string_stream(writer, self.name)?;
+            write_kafka_array_stream(writer, self.partition_responses, |writer, partition| {
+                partition.serialize(writer, version)
+            })
+        }
+    }
+}
+
+impl KafkaSeriarize for PartitionProduceResponse {
+    type Error = std::io::Error;
+    type DependentData<'a> = SupportProduceRequestVersion;
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        version: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        writer.write_i32::<BigEndian>(self.index)?;
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        writer.write_i64::<BigEndian>(self.base_offset)?;
+        writer.write_i64::<BigEndian>(self.log_append_time_ms)?;
+        if version >= SupportProduceRequestVersion::V5 {
+            writer.write_i64::<BigEndian>(self.log_start_offset)?;
+        }
+        if version >= SupportProduceRequestVersion::V8 {
+            if version.is_flexible() {
+                write_kafka_compact_array_stream(writer, self.record_errors, |writer, error| {
+                    writer.write_i32::<BigEndian>(error.batch_index)?;
+                    write_compact_nullable_string_stream(writer, error.batch_index_error_message)?;
+                    write_kafka_tagged_fields_stream(writer, Vec::new())
+                })?;
+                write_compact_nullable_string_stream(writer, self.error_message)?;
+            } else {
+                write_kafka_array_stream(writer, self.record_errors, |writer, error| {
+                    writer.write_i32::<BigEndian>(error.batch_index)?;
+                    write_nullable_string_stream(writer, error.batch_index_error_message)
+                })?;
+                write_nullable_string_stream(writer, self.error_message)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        config::BrokerConfig,
+        consts::produce::SupportProduceRequestVersion,
+        request::{
+            api_key::RequestApiKey,
+            body::produce::ProduceRequestBody,
+            header::{KafkaRequestHeader, KafkaRequestHeaderV1},
+        },
+        response::error_code::KafkaError,
+        traits::{KafkaDeseriarize, KafkaSeriarize},
+    };
+
+    use super::{KafkaResponseBodyProduce, PartitionProduceResponse, TopicProduceResponse};
+
+    fn request(topic: &str, acks: i16) -> ProduceRequestBody {
+        let header = KafkaRequestHeader::V1(KafkaRequestHeaderV1 {
+            request_api_key: RequestApiKey::Produce,
+            request_api_version: 3,
+            correlation_id: 7,
+            client_id: String::new(),
+        });
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(-1i16).to_be_bytes());
+        buf.extend_from_slice(&acks.to_be_bytes());
+        buf.extend_from_slice(&1500i32.to_be_bytes());
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        buf.extend_from_slice(&(topic.len() as i16).to_be_bytes());
+        buf.extend_from_slice(topic.as_bytes());
+        buf.extend_from_slice(&1i32.to_be_bytes());
+        buf.extend_from_slice(&0i32.to_be_bytes());
+        buf.extend_from_slice(&0i32.to_be_bytes());
+        ProduceRequestBody::try_parse_from_reader(&mut Cursor::new(buf), &header).unwrap()
+    }
+
+    fn only_error(response: &KafkaResponseBodyProduce) -> &KafkaError {
+        let [topic] = &response.responses[..] else {
+            panic!("expected a single topic");
+        };
+        let [partition] = &topic.partition_responses[..] else {
+            panic!("expected a single partition");
+        };
+        &partition.error_code
+    }
+
+    fn response(version: SupportProduceRequestVersion) -> KafkaResponseBodyProduce {
+        KafkaResponseBodyProduce {
+            version,
+            responses: vec![TopicProduceResponse {
+                name: "foo".to_string(),
+                partition_responses: vec![PartitionProduceResponse {
+                    error_message: Some("bad".to_string()),
+                    ..PartitionProduceResponse::error(2, KafkaError::CorruptMessage)
+                }],
+            }],
+            throttle_time_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_invalid_acks_fail_every_partition() {
+        let response = KafkaResponseBodyProduce::new(&request("foo", 5), &BrokerConfig::default());
+        assert!(matches!(
+            only_error(&response),
+            KafkaError::InvalidRequiredAcks
+        ));
+    }
+
+    #[test]
+    fn test_unknown_topic_is_reported_per_partition() {
+        let response = KafkaResponseBodyProduce::new(
+            &request("produce-test-unknown", 1),
+            &BrokerConfig::default(),
+        );
+        assert!(matches!(
+            only_error(&response),
+            KafkaError::UnknownTopicOrPartition
+        ));
+    }
+
+    #[test]
+    fn test_serialize_v8() {
+        let mut buf = Vec::new();
+        response(SupportProduceRequestVersion::V8)
+            .serialize(&mut buf, ())
+            .unwrap();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&1i32.to_be_bytes());
+        expected.extend_from_slice(&3i16.to_be_bytes());
+        expected.extend_from_slice(b"foo");
+        expected.extend_from_slice(&1i32.to_be_bytes());
+        expected.extend_from_slice(&2i32.to_be_bytes());
+        expected.extend_from_slice(&2i16.to_be_bytes());
+        expected.extend_from_slice(&(-1i64).to_be_bytes());
+        expected.extend_from_slice(&(-1i64).to_be_bytes());
+        expected.extend_from_slice(&(-1i64).to_be_bytes());
+        expected.extend_from_slice(&0i32.to_be_bytes());
+        expected.extend_from_slice(&3i16.to_be_bytes());
+        expected.extend_from_slice(b"bad");
+        expected.extend_from_slice(&0i32.to_be_bytes());
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_serialize_v9_flexible() {
+        let mut buf = Vec::new();
+        response(SupportProduceRequestVersion::V9)
+            .serialize(&mut buf, ())
+            .unwrap();
+
+        let mut expected = vec![2, 4];
+        expected.extend_from_slice(b"foo");
+        expected.push(2);
+        expected.extend_from_slice(&2i32.to_be_bytes());
+        expected.extend_from_slice(&2i16.to_be_bytes());
+        expected.extend_from_slice(&(-1i64).to_be_bytes());
+        expected.extend_from_slice(&(-1i64).to_be_bytes());
+        expected.extend_from_slice(&(-1i64).to_be_bytes());
+        expected.push(1);
+        expected.push(4);
+        expected.extend_from_slice(b"bad");
+        expected.extend_from_slice(&[0, 0]);
+        expected.extend_from_slice(&0i32.to_be_bytes());
+        expected.push(0);
+        assert_eq!(buf, expected);
+    }
+}
@@ -0,0 +1,162 @@
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    consts::sync_group::SupportSyncGroupRequestVersion,
+    globals::GROUP_COORDINATOR,
+    group::SyncParams,
+    request::body::sync_group::SyncGroupRequestBody,
+    response::{
+        error_code::KafkaError,
+        utils::{
+            write_compact_bytes_stream, write_compact_nullable_string_stream,
+            write_kafka_tagged_fields_stream, write_vec_u8_stream,
+        },
+    },
+    traits::KafkaSeriarize,
+};
+
+/// SyncGroup response, versions 0 through 5. A follower's response is only
+/// built once the leader has synced, so creating it may block.
+pub struct KafkaResponseBodySyncGroup {
+    version: SupportSyncGroupRequestVersion,
+    throttle_time_ms: i32,
+    error_code: KafkaError,
+    protocol_type: Option<String>,
+    protocol_name: Option<String>,
+    assignment: Vec<u8>,
+}
+
+impl KafkaResponseBodySyncGroup {
+    pub fn new(request: &SyncGroupRequestBody) -> Self {
+        let result = match GROUP_COORDINATOR.get() {
+            Some(coordinator) => coordinator.sync_group(SyncParams {
+                group_id: request.group_id.clone(),
+                generation_id: request.generation_id,
+                member_id: request.member_id.clone(),
+                group_instance_id: request.group_instance_id.clone(),
+                protocol_type: request.protocol_type.clone(),
+                protocol_name: request.protocol_name.clone(),
+                assignments: request
+                    .assignments
+                    .iter()
+                    .map(|assignment| (assignment.member_id.clone(), assignment.assignment.clone()))
+                    .collect(),
+            }),
+            None => Err(KafkaError::CoordinatorNotAvailable),
+        };
+        let mut response = Self {
+            version: request.get_api_version(),
+            throttle_time_ms: 0,
+            error_code: KafkaError::None,
+            protocol_type: None,
+            protocol_name: None,
+            assignment: Vec::new(),
+        };
+        match result {
+            Ok(result) => {
+                response.protocol_type = result.protocol_type;
+                response.protocol_name = result.protocol_name;
+                response.assignment = result.assignment;
+            }
+            Err(error_code) => response.error_code = error_code,
+        }
+        response
+    }
+}
+
+impl KafkaSeriarize for KafkaResponseBodySyncGroup {
+    type Error = std::io::Error;
+    type DependentData<'a> = ();
+
+    fn serialize<W: std::io::Write>(
+        self,
+        writer: &mut W,
+        _data: Self::DependentData<'_>,
+    ) -> Result<(), Self::Error> {
+        let version = self.version;
+        if version >= SupportSyncGroupRequestVersion::V1 {
+            writer.write_i32::<BigEndian>(self.throttle_time_ms)?;
+        }
+        let error_code: i16 = self.error_code.into();
+        writer.write_i16::<BigEndian>(error_code)?;
+        if version >= SupportSyncGroupRequestVersion::V5 {
+            write_compact_nullable_string_stream(writer, self.protocol_type)?;
+            write_compact_nullable_string_stream(writer, self.protocol_name)?;
+        }
+        if version.is_flexible() {
+            write_compact_bytes_stream(writer, self.assignment)?;
+            write_kafka_tagged_fields_stream(writer, Vec::new())?;
+        } else {
+            write_vec_u8_stream(writer, self.assignment)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialize(version: SupportSyncGroupRequestVersion, error_code: KafkaError) -> Vec<u8> {
+        let body = KafkaResponseBodySyncGroup {
+            version,
+            throttle_time_ms: 0,
+            error_code,
+            protocol_type: Some("consumer".to_string()),
+            protocol_name: Some("range".to_string()),
+            assignment: vec![9],
+        };
+        let mut buf = Vec::new();
+        body.serialize(&mut buf, ()).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_serialize_version_boundaries() {
+        // v0: no throttle time
+        let expected = [&0i16.to_be_bytes()[..], &1i32.to_be_bytes(), &[9]].concat();
+        assert_eq!(
+            serialize(SupportSyncGroupRequestVersion::V0, KafkaError::None),
+            expected
+        );
+
+        // v1 adds the throttle time
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &27i16.to_be_bytes(),
+            &1i32.to_be_bytes(),
+            &[9],
+        ]
+        .concat();
+        assert_eq!(
+            serialize(
+                SupportSyncGroupRequestVersion::V1,
+                KafkaError::RebalanceInProgress
+            ),
+            expected
+        );
+
+        // v4: compact encoding and tagged fields
+        let expected = [&0i32.to_be_bytes()[..], &0i16.to_be_bytes(), &[2, 9, 0]].concat();
+        assert_eq!(
+            serialize(SupportSyncGroupRequestVersion::V4, KafkaError::None),
+            expected
+        );
+
+        // v5 adds the protocol type and name
+        let expected = [
+            &0i32.to_be_bytes()[..],
+            &0i16.to_be_bytes(),
+            &[9],
+            b"consumer",
+            &[6],
+            b"range",
+            &[2, 9, 0],
+        ]
+        .concat();
+        assert_eq!(
+            serialize(SupportSyncGroupRequestVersion::V5, KafkaError::None),
+            expected
+        );
+    }
+}
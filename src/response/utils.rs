@@ -8,7 +8,41 @@ pub fn write_compact_string_stream(
     writer: &mut impl std::io::Write,
     input: impl AsRef<str>,
 ) -> Result<(), io::Error> {
-    write_compact_vec_u8_stream(writer, input.as_ref().as_bytes())
+    write_compact_bytes_stream(writer, input.as_ref().as_bytes())
+}
+
+pub fn write_string_stream(
+    writer: &mut impl std::io::Write,
+    input: impl AsRef<str>,
+) -> Result<(), io::Error> {
+    let input = input.as_ref().as_bytes();
+    writer.write_all(&(input.len() as i16).to_be_bytes())?;
+    writer.write_all(input)?;
+    Ok(())
+}
+
+pub fn write_nullable_string_stream(
+    writer: &mut impl std::io::Write,
+    input: Option<impl AsRef<str>>,
+) -> Result<(), io::Error> {
+    match input {
+        Some(input) => write_string_stream(writer, input),
+        None => writer.write_all(&(-1i16).to_be_bytes()),
+    }
+}
+
+pub fn write_compact_nullable_string_stream(
+    writer: &mut impl std::io::Write,
+    input: Option<impl AsRef<str>>,
+) -> Result<(), io::Error> {
+    match input {
+        Some(input) => {
+            let input = input.as_ref().as_bytes();
+            writer.write_all(&(input.len() + 1).encode_var_vec())?;
+            writer.write_all(input)
+        }
+        None => writer.write_all(&0usize.encode_var_vec()),
+    }
 }
 
 pub fn write_compact_vec_u8_stream(
@@ -23,7 +57,29 @@ pub fn write_compact_vec_u8_stream(
         encode_length + 1
     };
     writer.write_all(&encode_length.encode_var_vec())?;
-    writer.write_all(&v)?;
+    writer.write_all(v)?;
+    Ok(())
+}
+
+/// Non-nullable compact bytes: unlike [`write_compact_vec_u8_stream`], an
+/// empty input is an empty array rather than null.
+pub fn write_compact_bytes_stream(
+    writer: &mut impl std::io::Write,
+    v: impl AsRef<[u8]>,
+) -> Result<(), io::Error> {
+    let v = v.as_ref();
+    writer.write_all(&(v.len() + 1).encode_var_vec())?;
+    writer.write_all(v)?;
+    Ok(())
+}
+
+pub fn write_vec_u8_stream(
+    writer: &mut impl std::io::Write,
+    v: impl AsRef<[u8]>,
+) -> Result<(), io::Error> {
+    let v = v.as_ref();
+    writer.write_all(&(v.len() as i32).to_be_bytes())?;
+    writer.write_all(v)?;
     Ok(())
 }
 
@@ -50,13 +106,25 @@ where
     W: std::io::Write,
     FnWrite: FnMut(&mut W, T) -> Result<(), io::Error>,
 {
-    let encode_length = v.len();
-    let encode_length = if encode_length == 0 {
-        0
-    } else {
-        encode_length + 1
-    };
-    writer.write_all(&encode_length.encode_var_vec()).unwrap();
+    // a compact array length of 0 means null, so an empty array is encoded as 1
+    let encode_length = v.len() + 1;
+    writer.write_all(&encode_length.encode_var_vec())?;
+    for inner in v {
+        f(writer, inner)?;
+    }
+    Ok(())
+}
+
+pub fn write_kafka_array_stream<W, T, FnWrite>(
+    writer: &mut W,
+    v: Vec<T>,
+    mut f: FnWrite,
+) -> Result<(), io::Error>
+where
+    W: std::io::Write,
+    FnWrite: FnMut(&mut W, T) -> Result<(), io::Error>,
+{
+    writer.write_all(&(v.len() as i32).to_be_bytes())?;
     for inner in v {
         f(writer, inner)?;
     }
@@ -65,7 +133,12 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::response::utils::write_kafka_tagged_fields_stream;
+    use std::io::Write;
+
+    use crate::response::utils::{
+        write_compact_string_stream, write_kafka_compact_array_stream,
+        write_kafka_tagged_fields_stream,
+    };
 
     #[test]
     fn test_emtpy_vector() {
@@ -74,4 +147,21 @@ mod tests {
         write_kafka_tagged_fields_stream(&mut result, vec).unwrap();
         assert_eq!(result, vec![0u8])
     }
+
+    #[test]
+    fn test_empty_compact_array_is_not_null() {
+        let mut result = Vec::new();
+        write_kafka_compact_array_stream(&mut result, Vec::<i32>::new(), |w, v| {
+            w.write_all(&v.to_be_bytes())
+        })
+        .unwrap();
+        assert_eq!(result, vec![1u8])
+    }
+
+    #[test]
+    fn test_empty_compact_string_is_not_null() {
+        let mut result = Vec::new();
+        write_compact_string_stream(&mut result, "").unwrap();
+        assert_eq!(result, vec![1u8])
+    }
 }
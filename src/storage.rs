@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+mod batch;
+mod cleaner;
+mod index;
+pub mod leader_epoch;
+pub mod log_dir;
+pub mod partition_log;
+mod producer_state;
+mod segment;
+
+pub use index::AbortedTxn;
+pub use partition_log::{AppendError, AppendInfo, PartitionLog, RecoveryStats};
+
+pub const DEFAULT_LOG_DIR: &str = "/tmp/kraft-combined-logs";
+/// Topic of the KRaft metadata log, which lives in partition 0.
+pub const CLUSTER_METADATA_TOPIC: &str = "__cluster_metadata";
+/// Internal topic holding the committed offsets of consumer groups, in
+/// partition 0.
+pub const CONSUMER_OFFSETS_TOPIC: &str = "__consumer_offsets";
+
+/// Defaults of `log.segment.bytes`, `log.roll.ms` and `log.index.interval.bytes`.
+pub const DEFAULT_SEGMENT_BYTES: u64 = 1024 * 1024 * 1024;
+pub const DEFAULT_SEGMENT_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+pub const DEFAULT_INDEX_INTERVAL_BYTES: u64 = 4096;
+
+/// How partition logs are split into segments and indexed.
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// Largest size of a segment before a new one is rolled.
+    pub segment_bytes: u64,
+    /// Age of a segment's first batch after which a new one is rolled.
+    pub segment_ms: i64,
+    /// Bytes appended between two offset index entries.
+    pub index_interval_bytes: u64,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            segment_bytes: DEFAULT_SEGMENT_BYTES,
+            segment_ms: DEFAULT_SEGMENT_MS,
+            index_interval_bytes: DEFAULT_INDEX_INTERVAL_BYTES,
+        }
+    }
+}
+
+pub type SharedPartitionLog = Arc<Mutex<PartitionLog>>;
+
+/// Owns every partition log under a single log dir, opening them lazily.
+pub struct LogManager {
+    log_dir: PathBuf,
+    config: LogConfig,
+    logs: RwLock<HashMap<(String, i32), SharedPartitionLog>>,
+}
+
+impl LogManager {
+    pub fn new(log_dir: impl Into<PathBuf>, config: LogConfig) -> Self {
+        Self {
+            log_dir: log_dir.into(),
+            config,
+            logs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn log_dir(&self) -> &Path {
+        &self.log_dir
+    }
+
+    /// Directory holding the segments of `topic`/`partition`, e.g. `<log_dir>/foo-0`.
+    pub fn partition_dir(&self, topic: &str, partition: i32) -> PathBuf {
+        self.log_dir.join(format!("{topic}-{partition}"))
+    }
+
+    /// Open every partition log under the log dir, which runs their recovery
+    /// pass, and report what each one repaired.
+    pub fn load_logs(&self) -> io::Result<()> {
+        if !self.log_dir.exists() {
+            return Ok(());
+        }
+        let mut partitions = Vec::new();
+        for entry in fs::read_dir(&self.log_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = entry.file_name();
+            let Some((topic, partition)) = name
+                .to_str()
+                .and_then(|name| name.rsplit_once('-'))
+                .and_then(|(topic, partition)| Some((topic.to_string(), partition.parse().ok()?)))
+            else {
+                continue;
+            };
+            partitions.push((topic, partition));
+        }
+        partitions.sort();
+        for (topic, partition) in partitions {
+            let log = self.get_or_create(&topic, partition)?;
+            let log = log.lock().unwrap();
+            let RecoveryStats {
+                recovered_batches,
+                truncated_bytes,
+                rebuilt_indexes,
+            } = log.recovery();
+            println!(
+                "Recovered {topic}-{partition}: {recovered_batches} batches checked, \
+                 {truncated_bytes} bytes truncated, {rebuilt_indexes} indexes rebuilt, \
+                 high watermark {}",
+                log.high_watermark()
+            );
+        }
+        Ok(())
+    }
+
+    /// Snapshot the producer state of every open log, so that the next start
+    /// finds it up to date.
+    pub fn write_producer_snapshots(&self) -> io::Result<()> {
+        for log in self.logs.read().unwrap().values() {
+            log.lock().unwrap().write_producer_snapshot()?;
+        }
+        Ok(())
+    }
+
+    /// Close the partition's log and delete its directory.
+    pub fn remove(&self, topic: &str, partition: i32) -> io::Result<()> {
+        self.logs
+            .write()
+            .unwrap()
+            .remove(&(topic.to_string(), partition));
+        let dir = self.partition_dir(topic, partition);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Return the log for the partition, creating its directory if needed.
+    pub fn get_or_create(&self, topic: &str, partition: i32) -> io::Result<SharedPartitionLog> {
+        let key = (topic.to_string(), partition);
+        if let Some(log) = self.logs.read().unwrap().get(&key) {
+            return Ok(log.clone());
+        }
+        let mut logs = self.logs.write().unwrap();
+        if let Some(log) = logs.get(&key) {
+            return Ok(log.clone());
+        }
+        let log = Arc::new(Mutex::new(PartitionLog::open(
+            self.partition_dir(topic, partition),
+            self.config.clone(),
+        )?));
+        logs.insert(key, log.clone());
+        Ok(log)
+    }
+}
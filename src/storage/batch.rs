@@ -0,0 +1,160 @@
+//! Layout of the v2 record batch header, and a scanner over the batches of a
+//! segment file that only reads headers.
+
+use std::{fs::File, io, os::unix::fs::FileExt};
+
+use integer_encoding::VarInt;
+
+use crate::records::control_record::{ControlRecordKey, ControlRecordType, CONTROL_BATCH_MASK};
+
+// Byte offsets of the fixed-size record batch header fields (magic v2).
+pub(super) const BASE_OFFSET_OFFSET: usize = 0;
+pub(super) const BATCH_LENGTH_OFFSET: usize = 8;
+pub(super) const PARTITION_LEADER_EPOCH_OFFSET: usize = 12;
+pub(super) const MAGIC_OFFSET: usize = 16;
+pub(super) const CRC_OFFSET: usize = 17;
+pub(super) const ATTRIBUTES_OFFSET: usize = 21;
+pub(super) const LAST_OFFSET_DELTA_OFFSET: usize = 23;
+pub(super) const MAX_TIMESTAMP_OFFSET: usize = 35;
+pub(super) const PRODUCER_ID_OFFSET: usize = 43;
+pub(super) const PRODUCER_EPOCH_OFFSET: usize = 51;
+pub(super) const BASE_SEQUENCE_OFFSET: usize = 53;
+/// Size of the batch header up to and including `records_length`.
+pub(super) const BATCH_HEADER_SIZE: usize = 61;
+/// `base_offset` and `batch_length` are not counted in `batch_length`.
+pub(super) const BATCH_LENGTH_PREFIX: usize = 12;
+/// Attribute bit set on the batches of a transaction, data and markers alike.
+pub(super) const TRANSACTIONAL_MASK: i16 = 0x10;
+/// Enough of a control batch to hold the key of its first record.
+pub(super) const CONTROL_BATCH_PREFIX_SIZE: usize = BATCH_HEADER_SIZE + 32;
+
+/// Location, offset range, max timestamp and producer of one batch inside a
+/// segment file.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct BatchPosition {
+    pub position: u64,
+    pub size: u64,
+    pub base_offset: i64,
+    pub last_offset: i64,
+    pub max_timestamp: i64,
+    pub attributes: i16,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub base_sequence: i32,
+}
+
+impl BatchPosition {
+    /// Describe the batch whose header starts `header`, found at `position`.
+    pub fn from_header(header: &[u8], position: u64) -> Self {
+        let base_offset = read_i64(header, BASE_OFFSET_OFFSET);
+        Self {
+            position,
+            size: BATCH_LENGTH_PREFIX as u64 + read_i32(header, BATCH_LENGTH_OFFSET) as u64,
+            base_offset,
+            last_offset: base_offset + read_i32(header, LAST_OFFSET_DELTA_OFFSET) as i64,
+            max_timestamp: read_i64(header, MAX_TIMESTAMP_OFFSET),
+            attributes: read_i16(header, ATTRIBUTES_OFFSET),
+            producer_id: read_i64(header, PRODUCER_ID_OFFSET),
+            producer_epoch: read_i16(header, PRODUCER_EPOCH_OFFSET),
+            base_sequence: read_i32(header, BASE_SEQUENCE_OFFSET),
+        }
+    }
+
+    /// Sequence number of the last record, wrapping past `i32::MAX` to 0.
+    pub fn last_sequence(&self) -> i32 {
+        let delta = (self.last_offset - self.base_offset) as i32;
+        if self.base_sequence > i32::MAX - delta {
+            delta - (i32::MAX - self.base_sequence) - 1
+        } else {
+            self.base_sequence + delta
+        }
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.attributes & TRANSACTIONAL_MASK != 0
+    }
+
+    pub fn is_control(&self) -> bool {
+        self.attributes & CONTROL_BATCH_MASK != 0
+    }
+}
+
+/// Walks the batch headers of a segment file without reading the records.
+///
+/// Iteration stops quietly at a torn or zeroed tail.
+pub(super) struct BatchPositions<'a> {
+    file: &'a File,
+    len: u64,
+    position: u64,
+}
+
+impl<'a> BatchPositions<'a> {
+    /// Scan `file` from byte `position`, which must be the start of a batch.
+    pub fn from(file: &'a File, position: u64) -> io::Result<Self> {
+        Ok(Self {
+            file,
+            len: file.metadata()?.len(),
+            position,
+        })
+    }
+}
+
+impl Iterator for BatchPositions<'_> {
+    type Item = io::Result<BatchPosition>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position + BATCH_HEADER_SIZE as u64 > self.len {
+            return None;
+        }
+        let mut header = [0u8; BATCH_HEADER_SIZE];
+        if let Err(e) = self.file.read_exact_at(&mut header, self.position) {
+            return Some(Err(e));
+        }
+        if read_i32(&header, BATCH_LENGTH_OFFSET) <= 0 {
+            return None;
+        }
+        let batch = BatchPosition::from_header(&header, self.position);
+        if self.position + batch.size > self.len {
+            return None;
+        }
+        self.position += batch.size;
+        Some(Ok(batch))
+    }
+}
+
+/// Whether a complete batch has magic v2 and a CRC32C matching the bytes
+/// after the CRC field.
+pub(super) fn is_valid_batch(batch: &[u8]) -> bool {
+    batch.len() >= BATCH_HEADER_SIZE
+        && batch[MAGIC_OFFSET] == 2
+        && read_i32(batch, CRC_OFFSET) as u32 == crc32c::crc32c(&batch[ATTRIBUTES_OFFSET..])
+}
+
+/// Control type of the first record of a control batch, given at least its
+/// first [`CONTROL_BATCH_PREFIX_SIZE`] bytes. Control batches are never
+/// compressed, so the record can be read in place.
+pub(super) fn control_type(batch: &[u8]) -> Option<ControlRecordType> {
+    let mut rest = batch.get(BATCH_HEADER_SIZE..)?;
+    // record length, attributes, timestamp delta, offset delta, key length
+    let (_, read) = i64::decode_var(rest)?;
+    rest = rest.get(read + 1..)?;
+    for _ in 0..2 {
+        let (_, read) = i64::decode_var(rest)?;
+        rest = rest.get(read..)?;
+    }
+    let (key_length, read) = i64::decode_var(rest)?;
+    let key = rest.get(read..read + usize::try_from(key_length).ok()?)?;
+    ControlRecordKey::control_type(key)
+}
+
+pub(super) fn read_i16(buf: &[u8], at: usize) -> i16 {
+    i16::from_be_bytes(buf[at..at + 2].try_into().unwrap())
+}
+
+pub(super) fn read_i32(buf: &[u8], at: usize) -> i32 {
+    i32::from_be_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+pub(super) fn read_i64(buf: &[u8], at: usize) -> i64 {
+    i64::from_be_bytes(buf[at..at + 8].try_into().unwrap())
+}
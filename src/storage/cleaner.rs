@@ -0,0 +1,134 @@
+//! Compaction of logs that only need the last record of every key, like
+//! Kafka's log cleaner for `cleanup.policy=compact` topics.
+//!
+//! A pass maps every key to the offset of its last record, then rewrites the
+//! closed segments with only those records. Offsets never change: removed
+//! records leave gaps, and a rewritten batch keeps its offset range even when
+//! its last records are gone. A tombstone (a null value) is dropped along
+//! with the records it deleted. The active segment, which is still appended
+//! to, is left alone, and so are control and transactional batches.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Cursor, Write},
+    path::Path,
+};
+
+use binrw::{BinWrite, Endian};
+
+use crate::records::{Record, RecordBatch};
+
+use super::{
+    batch::{BatchPosition, ATTRIBUTES_OFFSET, CRC_OFFSET, LAST_OFFSET_DELTA_OFFSET},
+    segment::{file_path, LogSegment},
+};
+
+/// Offset of the last record of every key in `segments`.
+pub(super) fn last_offsets<'a>(
+    segments: impl IntoIterator<Item = &'a LogSegment>,
+) -> io::Result<HashMap<Vec<u8>, i64>> {
+    let mut last_offsets = HashMap::new();
+    for segment in segments {
+        for position in segment.batches()? {
+            if !is_cleanable(&position) {
+                continue;
+            }
+            let batch = decode(&segment.read_batch(&position)?)?;
+            for record in &batch.records {
+                if let Some(key) = &record.key {
+                    last_offsets.insert(key.clone(), offset_of(batch.base_offset, record));
+                }
+            }
+        }
+    }
+    Ok(last_offsets)
+}
+
+/// Rewrite the segment at `base_offset` with only the records that are the
+/// last of their key, reopening `segment` on the cleaned log. Returns whether
+/// anything was removed.
+pub(super) fn clean_segment(
+    dir: &Path,
+    base_offset: i64,
+    segment: &mut LogSegment,
+    last_offsets: &HashMap<Vec<u8>, i64>,
+    index_interval_bytes: u64,
+) -> io::Result<bool> {
+    let mut cleaned = Vec::new();
+    let mut removed = false;
+    for position in segment.batches()? {
+        let bytes = segment.read_batch(&position)?;
+        if !is_cleanable(&position) {
+            cleaned.extend_from_slice(&bytes);
+            continue;
+        }
+        let mut batch = decode(&bytes)?;
+        let (batch_offset, records) = (batch.base_offset, batch.records.len());
+        batch.records.retain(|record| match &record.key {
+            // keyless records are never superseded
+            None => true,
+            Some(key) => {
+                record.value.is_some()
+                    && last_offsets.get(key) == Some(&offset_of(batch_offset, record))
+            }
+        });
+        if batch.records.len() == records {
+            cleaned.extend_from_slice(&bytes);
+            continue;
+        }
+        removed = true;
+        if !batch.records.is_empty() {
+            cleaned.extend_from_slice(&encode(&batch, &bytes)?);
+        }
+    }
+    if !removed {
+        return Ok(false);
+    }
+
+    // the old indexes point into the old log, so they go before it is
+    // replaced; a crash in between leaves the old log to rebuild them from
+    let cleaned_path = file_path(dir, base_offset, "cleaned");
+    let mut file = File::create(&cleaned_path)?;
+    file.write_all(&cleaned)?;
+    file.sync_all()?;
+    for extension in ["index", "timeindex"] {
+        match fs::remove_file(file_path(dir, base_offset, extension)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    fs::rename(&cleaned_path, file_path(dir, base_offset, "log"))?;
+    *segment = LogSegment::open(dir, base_offset, index_interval_bytes)?;
+    Ok(true)
+}
+
+fn is_cleanable(batch: &BatchPosition) -> bool {
+    !batch.is_control() && !batch.is_transactional()
+}
+
+fn offset_of(base_offset: i64, record: &Record) -> i64 {
+    base_offset + record.offset_delta as i64
+}
+
+fn decode(bytes: &[u8]) -> io::Result<RecordBatch> {
+    RecordBatch::read_batches_from(&mut Cursor::new(bytes))
+        .map_err(io::Error::other)?
+        .pop()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "empty batch"))
+}
+
+/// Encode the remaining records of `batch`, keeping the offset range of the
+/// `original` batch, which the encoder would derive from the record count.
+fn encode(batch: &RecordBatch, original: &[u8]) -> io::Result<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    batch
+        .write_options(&mut bytes, Endian::Big, ())
+        .map_err(io::Error::other)?;
+    let mut bytes = bytes.into_inner();
+    let last_offset_delta = LAST_OFFSET_DELTA_OFFSET..LAST_OFFSET_DELTA_OFFSET + 4;
+    bytes[last_offset_delta.clone()].copy_from_slice(&original[last_offset_delta]);
+    let crc = crc32c::crc32c(&bytes[ATTRIBUTES_OFFSET..]);
+    bytes[CRC_OFFSET..ATTRIBUTES_OFFSET].copy_from_slice(&crc.to_be_bytes());
+    Ok(bytes)
+}
@@ -0,0 +1,273 @@
+//! Sparse `.index` and `.timeindex` files and the `.txnindex` file of a log
+//! segment.
+//!
+//! All are arrays of fixed-size big-endian entries, kept in memory and
+//! appended to on disk:
+//!
+//! - `.index`: 4-byte offset relative to the segment base, 4-byte position
+//! - `.timeindex`: 8-byte timestamp, 4-byte relative offset
+//! - `.txnindex`: 2-byte version, 8-byte producer id, 8-byte first offset,
+//!   8-byte last offset, 8-byte last stable offset, one per aborted
+//!   transaction whose abort marker is in the segment
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+const OFFSET_ENTRY_SIZE: usize = 8;
+const TIME_ENTRY_SIZE: usize = 12;
+const TXN_ENTRY_SIZE: usize = 34;
+const TXN_ENTRY_VERSION: i16 = 0;
+
+/// Maps offsets to positions in the segment file.
+pub(super) struct OffsetIndex {
+    file: File,
+    base_offset: i64,
+    /// `(offset, position)`, both increasing.
+    entries: Vec<(i64, u64)>,
+}
+
+impl OffsetIndex {
+    pub fn open(path: &Path, base_offset: i64) -> io::Result<Self> {
+        let (file, raw) = open_entries(path, OFFSET_ENTRY_SIZE)?;
+        let mut entries: Vec<(i64, u64)> = Vec::with_capacity(raw.len());
+        for entry in raw {
+            let offset = base_offset + u32::from_be_bytes(entry[0..4].try_into().unwrap()) as i64;
+            let position = u32::from_be_bytes(entry[4..8].try_into().unwrap()) as u64;
+            // a preallocated (zero filled) or damaged tail ends the index
+            if entries.last().is_some_and(|&(last_offset, last_position)| {
+                offset <= last_offset || position <= last_position
+            }) {
+                break;
+            }
+            entries.push((offset, position));
+        }
+        let mut index = Self {
+            file,
+            base_offset,
+            entries,
+        };
+        index.rewrite()?;
+        Ok(index)
+    }
+
+    pub fn append(&mut self, offset: i64, position: u64) -> io::Result<()> {
+        let mut entry = [0u8; OFFSET_ENTRY_SIZE];
+        entry[0..4].copy_from_slice(&((offset - self.base_offset) as u32).to_be_bytes());
+        entry[4..8].copy_from_slice(&(position as u32).to_be_bytes());
+        self.file.write_all(&entry)?;
+        self.entries.push((offset, position));
+        Ok(())
+    }
+
+    /// Largest entry whose offset is at most `offset`.
+    pub fn lookup(&self, offset: i64) -> Option<(i64, u64)> {
+        let index = self.entries.partition_point(|&(entry, _)| entry <= offset);
+        index.checked_sub(1).map(|index| self.entries[index])
+    }
+
+    pub fn last_entry(&self) -> Option<(i64, u64)> {
+        self.entries.last().copied()
+    }
+
+    /// Drop the entries pointing at or past `position`.
+    pub fn truncate_to_position(&mut self, position: u64) -> io::Result<()> {
+        let len = self.entries.partition_point(|&(_, entry)| entry < position);
+        if len < self.entries.len() {
+            self.entries.truncate(len);
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    /// Make the file match the in-memory entries exactly.
+    fn rewrite(&mut self) -> io::Result<()> {
+        self.file
+            .set_len((self.entries.len() * OFFSET_ENTRY_SIZE) as u64)
+    }
+}
+
+/// Maps timestamps to the first offset with at least that timestamp.
+pub(super) struct TimeIndex {
+    file: File,
+    base_offset: i64,
+    /// `(timestamp, offset)`, both increasing.
+    entries: Vec<(i64, i64)>,
+}
+
+impl TimeIndex {
+    pub fn open(path: &Path, base_offset: i64) -> io::Result<Self> {
+        let (file, raw) = open_entries(path, TIME_ENTRY_SIZE)?;
+        let mut entries: Vec<(i64, i64)> = Vec::with_capacity(raw.len());
+        for entry in raw {
+            let timestamp = i64::from_be_bytes(entry[0..8].try_into().unwrap());
+            let offset = base_offset + u32::from_be_bytes(entry[8..12].try_into().unwrap()) as i64;
+            if entries
+                .last()
+                .is_some_and(|&(last_timestamp, last_offset)| {
+                    timestamp <= last_timestamp || offset < last_offset
+                })
+            {
+                break;
+            }
+            entries.push((timestamp, offset));
+        }
+        let mut index = Self {
+            file,
+            base_offset,
+            entries,
+        };
+        index.rewrite()?;
+        Ok(index)
+    }
+
+    /// Record that `offset` is the first with a timestamp of `timestamp`;
+    /// ignored unless the timestamp is larger than every one indexed so far.
+    pub fn maybe_append(&mut self, timestamp: i64, offset: i64) -> io::Result<()> {
+        if self
+            .entries
+            .last()
+            .is_some_and(|&(last_timestamp, _)| timestamp <= last_timestamp)
+        {
+            return Ok(());
+        }
+        let mut entry = [0u8; TIME_ENTRY_SIZE];
+        entry[0..8].copy_from_slice(&timestamp.to_be_bytes());
+        entry[8..12].copy_from_slice(&((offset - self.base_offset) as u32).to_be_bytes());
+        self.file.write_all(&entry)?;
+        self.entries.push((timestamp, offset));
+        Ok(())
+    }
+
+    /// Largest entry whose timestamp is below `timestamp`: the records that
+    /// reach `timestamp` can only come after its offset.
+    pub fn lookup(&self, timestamp: i64) -> Option<(i64, i64)> {
+        let index = self
+            .entries
+            .partition_point(|&(entry, _)| entry < timestamp);
+        index.checked_sub(1).map(|index| self.entries[index])
+    }
+
+    pub fn last_entry(&self) -> Option<(i64, i64)> {
+        self.entries.last().copied()
+    }
+
+    /// Drop the entries pointing at or past `offset`.
+    pub fn truncate_to_offset(&mut self, offset: i64) -> io::Result<()> {
+        let len = self.entries.partition_point(|&(_, entry)| entry < offset);
+        if len < self.entries.len() {
+            self.entries.truncate(len);
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    fn rewrite(&mut self) -> io::Result<()> {
+        self.file
+            .set_len((self.entries.len() * TIME_ENTRY_SIZE) as u64)
+    }
+}
+
+/// A transaction that was aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbortedTxn {
+    pub producer_id: i64,
+    /// Offset of its first batch.
+    pub first_offset: i64,
+    /// Offset of its abort marker.
+    pub last_offset: i64,
+    /// Last stable offset of the log once it was aborted.
+    pub last_stable_offset: i64,
+}
+
+impl AbortedTxn {
+    fn to_bytes(self) -> [u8; TXN_ENTRY_SIZE] {
+        let mut entry = [0u8; TXN_ENTRY_SIZE];
+        entry[0..2].copy_from_slice(&TXN_ENTRY_VERSION.to_be_bytes());
+        entry[2..10].copy_from_slice(&self.producer_id.to_be_bytes());
+        entry[10..18].copy_from_slice(&self.first_offset.to_be_bytes());
+        entry[18..26].copy_from_slice(&self.last_offset.to_be_bytes());
+        entry[26..34].copy_from_slice(&self.last_stable_offset.to_be_bytes());
+        entry
+    }
+
+    fn from_bytes(entry: &[u8]) -> Option<Self> {
+        let read = |at: usize| i64::from_be_bytes(entry[at..at + 8].try_into().unwrap());
+        (i16::from_be_bytes(entry[0..2].try_into().unwrap()) == TXN_ENTRY_VERSION).then(|| Self {
+            producer_id: read(2),
+            first_offset: read(10),
+            last_offset: read(18),
+            last_stable_offset: read(26),
+        })
+    }
+}
+
+/// The aborted transactions whose abort marker is in the segment, in marker
+/// order.
+pub(super) struct TransactionIndex {
+    file: File,
+    entries: Vec<AbortedTxn>,
+}
+
+impl TransactionIndex {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let (file, raw) = open_entries(path, TXN_ENTRY_SIZE)?;
+        let mut entries: Vec<AbortedTxn> = Vec::with_capacity(raw.len());
+        for entry in raw {
+            // an unknown version or a marker out of order ends the index
+            match AbortedTxn::from_bytes(&entry) {
+                Some(entry)
+                    if !entries
+                        .last()
+                        .is_some_and(|last| last.last_offset >= entry.last_offset) =>
+                {
+                    entries.push(entry)
+                }
+                _ => break,
+            }
+        }
+        let index = Self { file, entries };
+        index
+            .file
+            .set_len((index.entries.len() * TXN_ENTRY_SIZE) as u64)?;
+        Ok(index)
+    }
+
+    pub fn append(&mut self, entry: AbortedTxn) -> io::Result<()> {
+        self.file.write_all(&entry.to_bytes())?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[AbortedTxn] {
+        &self.entries
+    }
+
+    /// Replace the entries with those found by rescanning the segment,
+    /// rewriting the file only if they differ.
+    pub fn reset(&mut self, entries: Vec<AbortedTxn>) -> io::Result<()> {
+        if entries == self.entries {
+            return Ok(());
+        }
+        self.file.set_len(0)?;
+        let bytes: Vec<u8> = entries.iter().flat_map(|entry| entry.to_bytes()).collect();
+        self.file.write_all(&bytes)?;
+        self.entries = entries;
+        Ok(())
+    }
+}
+
+/// Open an index file for appending and read its complete entries.
+fn open_entries(path: &Path, entry_size: usize) -> io::Result<(File, Vec<Vec<u8>>)> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    let entries = buf.chunks_exact(entry_size).map(<[u8]>::to_vec).collect();
+    Ok((file, entries))
+}
@@ -0,0 +1,159 @@
+//! Leader epoch cache of a partition: the first offset appended in every
+//! leader epoch, kept in the partition directory's `leader-epoch-checkpoint`
+//! in Kafka's checkpoint format:
+//!
+//! ```text
+//! 0
+//! <number of entries>
+//! <epoch> <start offset>
+//! ...
+//! ```
+//!
+//! It answers which offset a leader epoch ended at, which lets a fetch that
+//! says what epoch it last saw find where its log diverged from the leader's.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const LEADER_EPOCH_CHECKPOINT_FILE: &str = "leader-epoch-checkpoint";
+
+const CHECKPOINT_VERSION: i32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochEntry {
+    pub epoch: i32,
+    pub start_offset: i64,
+}
+
+pub struct LeaderEpochCache {
+    path: PathBuf,
+    /// Ordered by epoch and start offset alike.
+    entries: Vec<EpochEntry>,
+}
+
+impl LeaderEpochCache {
+    /// Load the checkpoint in `dir`; a missing one is an empty cache.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(LEADER_EPOCH_CHECKPOINT_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => parse_checkpoint(&contents).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed {}", path.display()),
+                )
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, entries })
+    }
+
+    pub fn latest_epoch(&self) -> Option<i32> {
+        self.entries.last().map(|entry| entry.epoch)
+    }
+
+    /// Record that `epoch` starts at `start_offset`, unless it is not newer
+    /// than the latest epoch.
+    pub fn assign(&mut self, epoch: i32, start_offset: i64) -> io::Result<()> {
+        if self.latest_epoch().is_some_and(|latest| latest >= epoch) {
+            return Ok(());
+        }
+        self.entries.push(EpochEntry {
+            epoch,
+            start_offset,
+        });
+        self.flush()
+    }
+
+    /// Forget the epochs starting at or past `end_offset`, once the log was
+    /// cut there.
+    pub fn truncate_from_end(&mut self, end_offset: i64) -> io::Result<()> {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.start_offset < end_offset);
+        if self.entries.len() == len {
+            return Ok(());
+        }
+        self.flush()
+    }
+
+    /// The largest known epoch not above `epoch` and the offset it ended at:
+    /// where the next epoch starts, or `log_end_offset` for the latest one.
+    /// An epoch older than every known one ends where the first one starts.
+    /// `None` for an epoch newer than the latest one.
+    pub fn end_offset_for(&self, epoch: i32, log_end_offset: i64) -> Option<(i32, i64)> {
+        if self.latest_epoch() == Some(epoch) {
+            return Some((epoch, log_end_offset));
+        }
+        let higher = self.entries.iter().position(|entry| entry.epoch > epoch)?;
+        let floor = match higher {
+            0 => epoch,
+            _ => self.entries[higher - 1].epoch,
+        };
+        Some((floor, self.entries[higher].start_offset))
+    }
+
+    /// Rewrite the checkpoint under a temporary name, then rename it over
+    /// the old one.
+    fn flush(&self) -> io::Result<()> {
+        let mut contents = format!("{}\n{}\n", CHECKPOINT_VERSION, self.entries.len());
+        for entry in &self.entries {
+            contents.push_str(&format!("{} {}\n", entry.epoch, entry.start_offset));
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+fn parse_checkpoint(contents: &str) -> Option<Vec<EpochEntry>> {
+    let mut lines = contents.lines();
+    if lines.next()?.trim().parse::<i32>().ok()? != CHECKPOINT_VERSION {
+        return None;
+    }
+    let count: usize = lines.next()?.trim().parse().ok()?;
+    let entries = lines
+        .take(count)
+        .map(|line| {
+            let (epoch, start_offset) = line.trim().split_once(' ')?;
+            Some(EpochEntry {
+                epoch: epoch.parse().ok()?,
+                start_offset: start_offset.parse().ok()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (entries.len() == count).then_some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_end_offsets_survive_reload() {
+        let dir = std::env::temp_dir().join(format!("leader-epoch-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut cache = LeaderEpochCache::load(&dir).unwrap();
+        assert_eq!(cache.latest_epoch(), None);
+        cache.assign(1, 0).unwrap();
+        cache.assign(1, 5).unwrap();
+        cache.assign(3, 10).unwrap();
+        cache.assign(4, 20).unwrap();
+        cache.truncate_from_end(15).unwrap();
+
+        let cache = LeaderEpochCache::load(&dir).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join(LEADER_EPOCH_CHECKPOINT_FILE)).unwrap(),
+            "0\n2\n1 0\n3 10\n"
+        );
+        assert_eq!(cache.latest_epoch(), Some(3));
+        assert_eq!(cache.end_offset_for(3, 15), Some((3, 15)));
+        assert_eq!(cache.end_offset_for(2, 15), Some((1, 10)));
+        assert_eq!(cache.end_offset_for(0, 15), Some((0, 0)));
+        assert_eq!(cache.end_offset_for(4, 15), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,722 @@
+//! Files that describe a log dir and the partitions in it:
+//!
+//! - `meta.properties`, written by `kafka-storage format`, names the cluster
+//!   and node the directory belongs to and gives the directory its own id.
+//! - `<topic>-<partition>/partition.metadata` records the id of the topic a
+//!   partition directory belongs to, so a recreated topic never reuses it.
+//! - `__cluster_metadata-0/quorum-state` holds the KRaft election state.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::config::parse_properties;
+
+use super::CLUSTER_METADATA_TOPIC;
+
+pub const META_PROPERTIES_FILE: &str = "meta.properties";
+pub const PARTITION_METADATA_FILE: &str = "partition.metadata";
+pub const QUORUM_STATE_FILE: &str = "quorum-state";
+
+/// Version of `meta.properties` written for KRaft nodes.
+const META_PROPERTIES_VERSION: i32 = 1;
+
+#[derive(Debug, Error)]
+pub enum LogDirError {
+    #[error("failed to access {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{path} is invalid: {reason}")]
+    Invalid { path: PathBuf, reason: String },
+    #[error("{path} is already formatted")]
+    AlreadyFormatted { path: PathBuf },
+    #[error(
+        "log dirs belong to different clusters: {first} in {first_dir}, {other} in {other_dir}"
+    )]
+    InconsistentClusterId {
+        first: String,
+        first_dir: PathBuf,
+        other: String,
+        other_dir: PathBuf,
+    },
+    #[error("{path} belongs to node {found}, but node.id is {expected}")]
+    InconsistentNodeId {
+        path: PathBuf,
+        found: i32,
+        expected: i32,
+    },
+}
+
+impl LogDirError {
+    fn io(path: &Path, source: io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    fn invalid(path: &Path, reason: impl ToString) -> Self {
+        Self::Invalid {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Contents of a log dir's `meta.properties`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetaProperties {
+    pub version: i32,
+    pub cluster_id: String,
+    pub node_id: i32,
+    /// Unique id of the log dir; absent in files written before Kafka 3.7.
+    pub directory_id: Option<[u8; 16]>,
+}
+
+impl MetaProperties {
+    /// Read `meta.properties` from `dir`, `None` if the directory is not
+    /// formatted.
+    pub fn read(dir: &Path) -> Result<Option<Self>, LogDirError> {
+        let path = dir.join(META_PROPERTIES_FILE);
+        let Some(contents) = read_optional(&path)? else {
+            return Ok(None);
+        };
+        let properties = parse_properties(&contents).map_err(|e| LogDirError::invalid(&path, e))?;
+        Self::from_properties(&properties)
+            .map(Some)
+            .map_err(|reason| LogDirError::invalid(&path, reason))
+    }
+
+    fn from_properties(properties: &HashMap<String, String>) -> Result<Self, String> {
+        let get = |key: &str| {
+            properties
+                .get(key)
+                .map(String::as_str)
+                .ok_or_else(|| format!("{key} is missing"))
+        };
+        let parse_number = |key: &str, value: &str| {
+            value
+                .parse()
+                .map_err(|_| format!("{key} {value:?} is not a number"))
+        };
+
+        let version = parse_number("version", get("version")?)?;
+        let node_id = match version {
+            1 => parse_number("node.id", get("node.id")?)?,
+            // written by ZooKeeper-mode brokers, which call it broker.id
+            0 => match get("node.id") {
+                Ok(node_id) => parse_number("node.id", node_id)?,
+                Err(_) => parse_number("broker.id", get("broker.id")?)?,
+            },
+            _ => return Err(format!("unsupported version {version}")),
+        };
+        if node_id < 0 {
+            return Err(format!("node.id {node_id} is negative"));
+        }
+        let cluster_id = get("cluster.id")?.to_string();
+        if cluster_id.is_empty() {
+            return Err("cluster.id is empty".to_string());
+        }
+        let directory_id = match properties.get("directory.id") {
+            Some(directory_id) => Some(
+                parse_uuid(directory_id)
+                    .ok_or_else(|| format!("directory.id {directory_id:?} is not a valid id"))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            version,
+            cluster_id,
+            node_id,
+            directory_id,
+        })
+    }
+
+    /// Write `meta.properties` into `dir`, replacing any previous one.
+    pub fn write(&self, dir: &Path) -> Result<(), LogDirError> {
+        let mut contents = format!("#\nnode.id={}\n", self.node_id);
+        if let Some(directory_id) = &self.directory_id {
+            contents.push_str(&format!("directory.id={}\n", format_uuid(directory_id)));
+        }
+        contents.push_str(&format!(
+            "version={}\ncluster.id={}\n",
+            self.version, self.cluster_id
+        ));
+        write_atomically(&dir.join(META_PROPERTIES_FILE), &contents)
+    }
+}
+
+/// Contents of a partition directory's `partition.metadata`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionMetadata {
+    pub topic_id: [u8; 16],
+}
+
+impl PartitionMetadata {
+    /// Read `partition.metadata` from the partition directory `dir`, `None`
+    /// if it has none yet.
+    pub fn read(dir: &Path) -> Result<Option<Self>, LogDirError> {
+        let path = dir.join(PARTITION_METADATA_FILE);
+        let Some(contents) = read_optional(&path)? else {
+            return Ok(None);
+        };
+        let mut version = None;
+        let mut topic_id = None;
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match line.split_once(':').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("version", value)) => version = Some(value),
+                Some(("topic_id", value)) => topic_id = Some(value),
+                _ => return Err(LogDirError::invalid(&path, format!("bad line {line:?}"))),
+            }
+        }
+        match version {
+            Some("0") => {}
+            Some(version) => {
+                return Err(LogDirError::invalid(
+                    &path,
+                    format!("unsupported version {version}"),
+                ))
+            }
+            None => return Err(LogDirError::invalid(&path, "version is missing")),
+        }
+        let topic_id =
+            topic_id.ok_or_else(|| LogDirError::invalid(&path, "topic_id is missing"))?;
+        let topic_id = parse_uuid(topic_id).ok_or_else(|| {
+            LogDirError::invalid(&path, format!("topic_id {topic_id:?} is not a valid id"))
+        })?;
+        Ok(Some(Self { topic_id }))
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), LogDirError> {
+        let contents = format!("version: 0\ntopic_id: {}\n", format_uuid(&self.topic_id));
+        write_atomically(&dir.join(PARTITION_METADATA_FILE), &contents)
+    }
+}
+
+/// Contents of the metadata partition's `quorum-state`: the latest known
+/// leader and this node's vote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumState {
+    /// `-1` when the leader is unknown.
+    pub leader_id: i32,
+    pub leader_epoch: i32,
+    /// `-1` when the node has not voted in `leader_epoch`.
+    pub voted_id: i32,
+    pub voted_directory_id: Option<[u8; 16]>,
+    /// Static voters, only kept by version 0.
+    pub current_voters: Vec<i32>,
+    pub data_version: i16,
+}
+
+impl Default for QuorumState {
+    fn default() -> Self {
+        Self {
+            leader_id: -1,
+            leader_epoch: 0,
+            voted_id: -1,
+            voted_directory_id: None,
+            current_voters: Vec::new(),
+            data_version: 1,
+        }
+    }
+}
+
+impl QuorumState {
+    /// Read `quorum-state` from the metadata partition directory `dir`,
+    /// `None` if the node never took part in an election.
+    pub fn read(dir: &Path) -> Result<Option<Self>, LogDirError> {
+        let path = dir.join(QUORUM_STATE_FILE);
+        let Some(contents) = read_optional(&path)? else {
+            return Ok(None);
+        };
+        Self::parse(&contents)
+            .map(Some)
+            .map_err(|reason| LogDirError::invalid(&path, reason))
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let Json::Object(fields) = Json::parse(contents)? else {
+            return Err("expected a JSON object".to_string());
+        };
+        let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, v)| v);
+        let int = |name: &str| match field(name) {
+            Some(Json::Number(n)) => Ok(Some(*n)),
+            Some(Json::Null) | None => Ok(None),
+            Some(_) => Err(format!("{name} is not a number")),
+        };
+        let i32_field = |name: &str, default: i32| {
+            int(name)?.map_or(Ok(default), |n| {
+                i32::try_from(n).map_err(|_| format!("{name} {n} is out of range"))
+            })
+        };
+
+        let data_version = i32_field("data_version", 0)?;
+        if !(0..=1).contains(&data_version) {
+            return Err(format!("unsupported data_version {data_version}"));
+        }
+        let voted_directory_id = match field("votedDirectoryId") {
+            Some(Json::String(id)) => Some(
+                parse_uuid(id).ok_or_else(|| format!("votedDirectoryId {id:?} is not valid"))?,
+            ),
+            Some(Json::Null) | None => None,
+            Some(_) => return Err("votedDirectoryId is not a string".to_string()),
+        };
+        let current_voters = match field("currentVoters") {
+            Some(Json::Array(voters)) => voters
+                .iter()
+                .map(|voter| match voter {
+                    Json::Object(voter) => voter
+                        .iter()
+                        .find_map(|(key, value)| match (key.as_str(), value) {
+                            ("voterId", Json::Number(id)) => i32::try_from(*id).ok(),
+                            _ => None,
+                        })
+                        .ok_or_else(|| "currentVoters entry has no voterId".to_string()),
+                    _ => Err("currentVoters entry is not an object".to_string()),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(Json::Null) | None => Vec::new(),
+            Some(_) => return Err("currentVoters is not an array".to_string()),
+        };
+        Ok(Self {
+            leader_id: i32_field("leaderId", -1)?,
+            leader_epoch: i32_field("leaderEpoch", 0)?,
+            voted_id: i32_field("votedId", -1)?,
+            voted_directory_id,
+            current_voters,
+            data_version: data_version as i16,
+        })
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), LogDirError> {
+        let mut contents = format!(
+            "{{\"leaderId\":{},\"leaderEpoch\":{},\"votedId\":{}",
+            self.leader_id, self.leader_epoch, self.voted_id
+        );
+        if let Some(voted_directory_id) = &self.voted_directory_id {
+            contents.push_str(&format!(
+                ",\"votedDirectoryId\":\"{}\"",
+                format_uuid(voted_directory_id)
+            ));
+        }
+        if self.data_version == 0 {
+            let voters: Vec<String> = self
+                .current_voters
+                .iter()
+                .map(|voter_id| format!("{{\"voterId\":{voter_id}}}"))
+                .collect();
+            contents.push_str(&format!(
+                ",\"appliedOffset\":0,\"currentVoters\":[{}]",
+                voters.join(",")
+            ));
+        }
+        contents.push_str(&format!(",\"data_version\":{}}}", self.data_version));
+        write_atomically(&dir.join(QUORUM_STATE_FILE), &contents)
+    }
+}
+
+/// Format a fresh log dir for `cluster_id` and `node_id`, as
+/// `kafka-storage format` does: write its `meta.properties` with a new
+/// directory id and an empty `quorum-state` for the metadata partition.
+pub fn format(dir: &Path, cluster_id: &str, node_id: i32) -> Result<MetaProperties, LogDirError> {
+    if dir.join(META_PROPERTIES_FILE).exists() {
+        return Err(LogDirError::AlreadyFormatted {
+            path: dir.to_path_buf(),
+        });
+    }
+    let metadata_dir = dir.join(format!("{CLUSTER_METADATA_TOPIC}-0"));
+    fs::create_dir_all(&metadata_dir).map_err(|e| LogDirError::io(&metadata_dir, e))?;
+    if QuorumState::read(&metadata_dir)?.is_none() {
+        QuorumState::default().write(&metadata_dir)?;
+    }
+    let meta_properties = MetaProperties {
+        version: META_PROPERTIES_VERSION,
+        cluster_id: cluster_id.to_string(),
+        node_id,
+        directory_id: Some(*Uuid::new_v4().as_bytes()),
+    };
+    meta_properties.write(dir)?;
+    Ok(meta_properties)
+}
+
+/// Read the `meta.properties` of every log dir and check they all belong to
+/// the same cluster and to node `node_id`.
+///
+/// Directories that are not formatted yet are formatted for that cluster, or
+/// for a new random one if none of them is; the properties of the first
+/// directory are returned.
+pub fn load_or_format(dirs: &[PathBuf], node_id: i32) -> Result<MetaProperties, LogDirError> {
+    let mut formatted: Vec<(&PathBuf, MetaProperties)> = Vec::new();
+    for dir in dirs {
+        let Some(meta_properties) = MetaProperties::read(dir)? else {
+            continue;
+        };
+        if meta_properties.node_id != node_id {
+            return Err(LogDirError::InconsistentNodeId {
+                path: dir.join(META_PROPERTIES_FILE),
+                found: meta_properties.node_id,
+                expected: node_id,
+            });
+        }
+        if let Some((first_dir, first)) = formatted.first() {
+            if first.cluster_id != meta_properties.cluster_id {
+                return Err(LogDirError::InconsistentClusterId {
+                    first: first.cluster_id.clone(),
+                    first_dir: first_dir.to_path_buf(),
+                    other: meta_properties.cluster_id,
+                    other_dir: dir.clone(),
+                });
+            }
+        }
+        formatted.push((dir, meta_properties));
+    }
+
+    let cluster_id = match formatted.first() {
+        Some((_, meta_properties)) => meta_properties.cluster_id.clone(),
+        None => format_uuid(Uuid::new_v4().as_bytes()),
+    };
+    let mut first = None;
+    for dir in dirs {
+        let meta_properties = match formatted.iter().find(|(formatted, _)| *formatted == dir) {
+            Some((_, meta_properties)) => meta_properties.clone(),
+            None => {
+                println!("Formatting {} for cluster {cluster_id}", dir.display());
+                format(dir, &cluster_id, node_id)?
+            }
+        };
+        first.get_or_insert(meta_properties);
+    }
+    first.ok_or_else(|| LogDirError::invalid(Path::new(""), "no log dir is configured"))
+}
+
+/// Kafka's string form of a UUID: unpadded URL-safe base64.
+pub fn format_uuid(uuid: &[u8; 16]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut encoded = String::with_capacity(22);
+    for chunk in uuid.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Parse a UUID written by [`format_uuid`].
+pub fn parse_uuid(s: &str) -> Option<[u8; 16]> {
+    if s.len() != 22 {
+        return None;
+    }
+    let mut bits = 0u128;
+    for (i, c) in s.bytes().enumerate() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        } as u128;
+        // 22 characters carry 132 bits; like Java's decoder, ignore the
+        // last 4
+        bits = if i < 21 {
+            bits << 6 | value
+        } else {
+            bits << 2 | value >> 4
+        };
+    }
+    Some(bits.to_be_bytes())
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, LogDirError> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(LogDirError::io(path, e)),
+    }
+}
+
+/// Write `contents` to a temporary file next to `path`, then rename it over
+/// `path` once synced.
+fn write_atomically(path: &Path, contents: &str) -> Result<(), LogDirError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let write = || -> io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        io::Write::write_all(&mut file, contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().map_err(|e| LogDirError::io(path, e))
+}
+
+/// Just enough JSON for `quorum-state`.
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.trim().chars().peekable();
+        let value = Self::parse_value(&mut chars)?;
+        match chars.find(|c| !c.is_whitespace()) {
+            Some(c) => Err(format!("unexpected {c:?} after the JSON value")),
+            None => Ok(value),
+        }
+    }
+
+    fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Self, String> {
+        skip_whitespace(chars);
+        match chars.peek().copied() {
+            Some('{') => {
+                chars.next();
+                let mut fields = Vec::new();
+                loop {
+                    skip_whitespace(chars);
+                    if chars.next_if_eq(&'}').is_some() && fields.is_empty() {
+                        break;
+                    }
+                    let Self::String(key) = Self::parse_value(chars)? else {
+                        return Err("object keys must be strings".to_string());
+                    };
+                    skip_whitespace(chars);
+                    if chars.next() != Some(':') {
+                        return Err(format!("expected ':' after {key:?}"));
+                    }
+                    fields.push((key, Self::parse_value(chars)?));
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => continue,
+                        Some('}') => break,
+                        _ => return Err("expected ',' or '}' in object".to_string()),
+                    }
+                }
+                Ok(Self::Object(fields))
+            }
+            Some('[') => {
+                chars.next();
+                let mut values = Vec::new();
+                loop {
+                    skip_whitespace(chars);
+                    if chars.next_if_eq(&']').is_some() && values.is_empty() {
+                        break;
+                    }
+                    values.push(Self::parse_value(chars)?);
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => continue,
+                        Some(']') => break,
+                        _ => return Err("expected ',' or ']' in array".to_string()),
+                    }
+                }
+                Ok(Self::Array(values))
+            }
+            Some('"') => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '/')) => s.push(c),
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            _ => return Err("unsupported escape in string".to_string()),
+                        },
+                        Some(c) => s.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                Ok(Self::String(s))
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| *c == '-' || c.is_ascii_digit()) {
+                    number.push(c);
+                }
+                number
+                    .parse()
+                    .map(Self::Number)
+                    .map_err(|_| format!("{number:?} is not an integer"))
+            }
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "null" => Ok(Self::Null),
+                    "true" => Ok(Self::Bool(true)),
+                    "false" => Ok(Self::Bool(false)),
+                    _ => Err(format!("unexpected {word:?}")),
+                }
+            }
+            None => Err("unexpected end of JSON".to_string()),
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("log-dir-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_uuid_strings() {
+        let uuid = Uuid::parse_str("f2b1c4e0-8d3a-4b5e-9c7f-1a2b3c4d5e6f").unwrap();
+        let s = format_uuid(uuid.as_bytes());
+        assert_eq!(s, "8rHE4I06S16cfxorPE1ebw");
+        assert_eq!(parse_uuid(&s), Some(*uuid.as_bytes()));
+        assert_eq!(format_uuid(&[0; 16]), "AAAAAAAAAAAAAAAAAAAAAA");
+        assert_eq!(parse_uuid("not-an-id"), None);
+    }
+
+    #[test]
+    fn test_meta_properties() {
+        let dir = temp_dir("meta");
+        assert_eq!(MetaProperties::read(&dir).unwrap(), None);
+
+        // as written by `kafka-storage format`
+        fs::write(
+            dir.join(META_PROPERTIES_FILE),
+            "#\n#Thu Jan 01 00:00:00 UTC 2025\nnode.id=1\ndirectory.id=8rHE4I06S16cfxorPE1ebw\n\
+             version=1\ncluster.id=MkU3OEVBNTcwNTJENDM2Qk\n",
+        )
+        .unwrap();
+        let meta_properties = MetaProperties::read(&dir).unwrap().unwrap();
+        assert_eq!(meta_properties.cluster_id, "MkU3OEVBNTcwNTJENDM2Qk");
+        assert_eq!(meta_properties.node_id, 1);
+        assert!(meta_properties.directory_id.is_some());
+        meta_properties.write(&dir).unwrap();
+        assert_eq!(MetaProperties::read(&dir).unwrap(), Some(meta_properties));
+
+        fs::write(dir.join(META_PROPERTIES_FILE), "version=1\nnode.id=1\n").unwrap();
+        assert!(matches!(
+            MetaProperties::read(&dir),
+            Err(LogDirError::Invalid { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_and_load() {
+        let dir = temp_dir("format");
+        let (first, second) = (dir.join("a"), dir.join("b"));
+
+        let meta_properties = load_or_format(std::slice::from_ref(&first), 1).unwrap();
+        let metadata_dir = first.join(format!("{CLUSTER_METADATA_TOPIC}-0"));
+        assert_eq!(
+            QuorumState::read(&metadata_dir).unwrap(),
+            Some(QuorumState::default())
+        );
+        assert!(matches!(
+            format(&first, "other", 1),
+            Err(LogDirError::AlreadyFormatted { .. })
+        ));
+
+        // a new directory joins the cluster of the existing one
+        let dirs = [first.clone(), second.clone()];
+        assert_eq!(load_or_format(&dirs, 1).unwrap(), meta_properties);
+        let joined = MetaProperties::read(&second).unwrap().unwrap();
+        assert_eq!(joined.cluster_id, meta_properties.cluster_id);
+        assert_ne!(joined.directory_id, meta_properties.directory_id);
+
+        assert!(matches!(
+            load_or_format(&dirs, 2),
+            Err(LogDirError::InconsistentNodeId { found: 1, .. })
+        ));
+        MetaProperties {
+            cluster_id: "other".to_string(),
+            ..joined
+        }
+        .write(&second)
+        .unwrap();
+        assert!(matches!(
+            load_or_format(&dirs, 1),
+            Err(LogDirError::InconsistentClusterId { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dirs_of_different_clusters_are_refused() {
+        let dir = temp_dir("clusters");
+        let (first, second) = (dir.join("a"), dir.join("b"));
+        let first_cluster = format_uuid(Uuid::new_v4().as_bytes());
+        let second_cluster = format_uuid(Uuid::new_v4().as_bytes());
+        format(&first, &first_cluster, 1).unwrap();
+        format(&second, &second_cluster, 1).unwrap();
+
+        let error = load_or_format(&[first.clone(), second.clone()], 1).unwrap_err();
+        match error {
+            LogDirError::InconsistentClusterId {
+                first: found_first,
+                first_dir,
+                other,
+                other_dir,
+            } => {
+                assert_eq!((found_first, first_dir), (first_cluster, first));
+                assert_eq!((other, other_dir), (second_cluster, second));
+            }
+            e => panic!("unexpected error {e}"),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_partition_metadata_and_quorum_state() {
+        let dir = temp_dir("partition");
+        let partition = PartitionMetadata { topic_id: [7; 16] };
+        partition.write(&dir).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join(PARTITION_METADATA_FILE)).unwrap(),
+            "version: 0\ntopic_id: BwcHBwcHBwcHBwcHBwcHBw\n"
+        );
+        assert_eq!(PartitionMetadata::read(&dir).unwrap(), Some(partition));
+
+        // as written by a Kafka 3.x controller
+        fs::write(
+            dir.join(QUORUM_STATE_FILE),
+            r#"{"clusterId":"","leaderId":1,"leaderEpoch":5,"votedId":-1,"appliedOffset":0,"currentVoters":[{"voterId":1},{"voterId":2}],"data_version":0}"#,
+        )
+        .unwrap();
+        let state = QuorumState::read(&dir).unwrap().unwrap();
+        assert_eq!(state.leader_id, 1);
+        assert_eq!(state.leader_epoch, 5);
+        assert_eq!(state.current_voters, [1, 2]);
+        state.write(&dir).unwrap();
+        assert_eq!(QuorumState::read(&dir).unwrap(), Some(state));
+
+        let state = QuorumState {
+            voted_id: 3,
+            voted_directory_id: Some([9; 16]),
+            ..QuorumState::default()
+        };
+        state.write(&dir).unwrap();
+        assert_eq!(QuorumState::read(&dir).unwrap(), Some(state));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
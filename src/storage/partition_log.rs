@@ -0,0 +1,1259 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+use tokio::sync::Notify;
+
+use crate::records::{compression::Compression, control_record::ControlRecordType};
+
+use super::{
+    batch::{
+        self, read_i16, read_i32, read_i64, BatchPosition, ATTRIBUTES_OFFSET, BASE_OFFSET_OFFSET,
+        BATCH_HEADER_SIZE, BATCH_LENGTH_OFFSET, BATCH_LENGTH_PREFIX, CRC_OFFSET,
+        LAST_OFFSET_DELTA_OFFSET, MAGIC_OFFSET, MAX_TIMESTAMP_OFFSET,
+        PARTITION_LEADER_EPOCH_OFFSET,
+    },
+    cleaner,
+    index::AbortedTxn,
+    leader_epoch::LeaderEpochCache,
+    producer_state::ProducerStateManager,
+    segment::{self, LogSegment},
+    LogConfig,
+};
+
+const TIMESTAMP_TYPE_MASK: i16 = 0x08;
+
+#[derive(Debug, Error)]
+pub enum AppendError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("corrupt record batch #{batch_index}: {reason}")]
+    CorruptBatch {
+        batch_index: usize,
+        reason: &'static str,
+    },
+    #[error(
+        "out of order sequence number for producer {producer_id}: {sequence} does not follow \
+         {last_sequence}"
+    )]
+    OutOfOrderSequence {
+        producer_id: i64,
+        last_sequence: i32,
+        sequence: i32,
+    },
+    #[error(
+        "producer {producer_id} epoch {epoch} is older than its current epoch {current_epoch}"
+    )]
+    InvalidProducerEpoch {
+        producer_id: i64,
+        epoch: i16,
+        current_epoch: i16,
+    },
+}
+
+impl AppendError {
+    fn corrupt(batch_index: usize, reason: &'static str) -> Self {
+        Self::CorruptBatch {
+            batch_index,
+            reason,
+        }
+    }
+}
+
+/// Result of a successful append.
+#[derive(Debug, Clone, Copy)]
+pub struct AppendInfo {
+    pub base_offset: i64,
+    /// Broker timestamp stamped on `LogAppendTime` batches, `-1` otherwise.
+    pub log_append_time_ms: i64,
+    pub log_start_offset: i64,
+}
+
+/// What opening a partition log checked and repaired.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RecoveryStats {
+    /// Batches of the active segment whose CRC was verified.
+    pub recovered_batches: usize,
+    /// Bytes of torn or corrupt batches cut from the active segment.
+    pub truncated_bytes: u64,
+    /// Segments whose index files were missing or corrupt and got rebuilt.
+    pub rebuilt_indexes: usize,
+}
+
+/// A single partition's log, stored as a sequence of segments that are
+/// rolled by size and age.
+pub struct PartitionLog {
+    dir: PathBuf,
+    config: LogConfig,
+    /// Keyed by base offset; the last one is the active segment.
+    segments: BTreeMap<i64, LogSegment>,
+    log_start_offset: i64,
+    recovery: RecoveryStats,
+    leader_epochs: LeaderEpochCache,
+    producer_state: ProducerStateManager,
+    /// First offset of every open transaction, by producer id.
+    ongoing_transactions: BTreeMap<i64, i64>,
+    /// Delayed fetches to wake on the next append.
+    append_watchers: Vec<Arc<Notify>>,
+}
+
+impl PartitionLog {
+    /// Open the log in `dir`, running the recovery pass over its active
+    /// segment: a crash can only have torn the batches written last.
+    pub fn open(dir: impl Into<PathBuf>, config: LogConfig) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let mut segments = BTreeMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("log") => {}
+                // a compaction that did not get to replace the segment
+                Some("cleaned") => {
+                    fs::remove_file(&path)?;
+                    continue;
+                }
+                _ => continue,
+            }
+            let Some(base_offset) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<i64>().ok())
+            else {
+                continue;
+            };
+            let segment = LogSegment::open(&dir, base_offset, config.index_interval_bytes)?;
+            segments.insert(base_offset, segment);
+        }
+        if segments.is_empty() {
+            segments.insert(0, LogSegment::open(&dir, 0, config.index_interval_bytes)?);
+        }
+        let rebuilt_indexes = segments
+            .values()
+            .filter(|segment| segment.index_rebuilt())
+            .count();
+        let active = segments.values_mut().next_back().unwrap().recover()?;
+        let log_start_offset = *segments.keys().next().unwrap();
+        // forget the epochs that started in what recovery cut off
+        let mut leader_epochs = LeaderEpochCache::load(&dir)?;
+        let log_end_offset = segments.values().next_back().unwrap().next_offset();
+        leader_epochs.truncate_from_end(log_end_offset)?;
+        let (producer_state, snapshot_offset, ongoing_transactions) =
+            ProducerStateManager::load(&dir, log_end_offset)?;
+        let mut log = Self {
+            dir,
+            config,
+            segments,
+            log_start_offset,
+            recovery: RecoveryStats {
+                recovered_batches: active.valid_batches,
+                truncated_bytes: active.truncated_bytes,
+                rebuilt_indexes,
+            },
+            leader_epochs,
+            producer_state,
+            ongoing_transactions: BTreeMap::new(),
+            append_watchers: Vec::new(),
+        };
+        log.replay_batches(snapshot_offset, ongoing_transactions)?;
+        Ok(log)
+    }
+
+    /// Bring the producer state and the open transactions up to date with
+    /// the batches from `snapshot_offset` on, starting from the transactions
+    /// `ongoing` there, and rebuild the transaction indexes of the segments
+    /// replayed. The indexes of the segments before are trusted, unless one
+    /// is missing: the whole log is then replayed to find the transactions
+    /// it aborted.
+    fn replay_batches(
+        &mut self,
+        snapshot_offset: i64,
+        ongoing: BTreeMap<i64, i64>,
+    ) -> io::Result<()> {
+        let index_missing = self
+            .segments
+            .range(..snapshot_offset)
+            .any(|(_, segment)| segment.txn_index_missing());
+        let replay_from = match index_missing {
+            true => self.log_start_offset,
+            false => {
+                self.ongoing_transactions = ongoing;
+                snapshot_offset
+            }
+        };
+        let first = self.segment_containing(replay_from);
+        let replayed: Vec<i64> = self
+            .segments
+            .range(first..)
+            .map(|(&base, _)| base)
+            .collect();
+        for base_offset in replayed {
+            let segment = &self.segments[&base_offset];
+            // the markers before the replay are already indexed
+            let mut aborted: Vec<AbortedTxn> = segment
+                .aborted_transactions()
+                .iter()
+                .filter(|aborted| aborted.last_offset < replay_from)
+                .copied()
+                .collect();
+            for batch in segment.batches()? {
+                if batch.base_offset < replay_from {
+                    continue;
+                }
+                if batch.base_offset >= snapshot_offset {
+                    self.producer_state.update(&batch);
+                }
+                let control_type = match batch.is_control() {
+                    true => segment.control_type(&batch)?,
+                    false => None,
+                };
+                aborted.extend(track_transaction(
+                    &mut self.ongoing_transactions,
+                    &batch,
+                    control_type,
+                ));
+            }
+            let segment = self.segments.get_mut(&base_offset).unwrap();
+            segment.reset_aborted_transactions(aborted)?;
+        }
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn log_start_offset(&self) -> i64 {
+        self.log_start_offset
+    }
+
+    pub fn recovery(&self) -> RecoveryStats {
+        self.recovery
+    }
+
+    /// Offset that the next appended record will receive.
+    pub fn high_watermark(&self) -> i64 {
+        self.active_segment().next_offset()
+    }
+
+    /// Offset below which every transaction is decided: the first offset of
+    /// the oldest open transaction, or the high watermark.
+    pub fn last_stable_offset(&self) -> i64 {
+        last_stable_offset(&self.ongoing_transactions, self.high_watermark())
+    }
+
+    pub fn leader_epochs(&self) -> &LeaderEpochCache {
+        &self.leader_epochs
+    }
+
+    /// Snapshot the producer state at the log end, so that reopening the log
+    /// finds it up to date.
+    pub fn write_producer_snapshot(&self) -> io::Result<()> {
+        self.producer_state
+            .write_snapshot(self.high_watermark(), &self.ongoing_transactions)
+    }
+
+    /// Compact the closed segments down to the last record of every key, see
+    /// [`cleaner`](super::cleaner). Segments left empty are deleted, except
+    /// the first, which keeps the log start offset.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let last_offsets = cleaner::last_offsets(self.segments.values())?;
+        let active = *self.segments.keys().next_back().unwrap();
+        let closed: Vec<i64> = self
+            .segments
+            .range(..active)
+            .map(|(&base, _)| base)
+            .collect();
+        for base_offset in closed {
+            let segment = self.segments.get_mut(&base_offset).unwrap();
+            let cleaned = cleaner::clean_segment(
+                &self.dir,
+                base_offset,
+                segment,
+                &last_offsets,
+                self.config.index_interval_bytes,
+            )?;
+            if cleaned && segment.size() == 0 && base_offset != self.log_start_offset {
+                self.segments.remove(&base_offset);
+                segment::delete_files(&self.dir, base_offset)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wake `watcher` on the next append to this log.
+    pub fn watch_appends(&mut self, watcher: &Arc<Notify>) {
+        // forget the watchers of fetches that completed without an append
+        self.append_watchers
+            .retain(|watcher| Arc::strong_count(watcher) > 1);
+        self.append_watchers.push(watcher.clone());
+    }
+
+    /// Base offsets of the segments, oldest first.
+    pub fn segment_base_offsets(&self) -> Vec<i64> {
+        self.segments.keys().copied().collect()
+    }
+
+    fn active_segment(&self) -> &LogSegment {
+        self.segments.values().next_back().unwrap()
+    }
+
+    fn active_segment_mut(&mut self) -> &mut LogSegment {
+        self.segments.values_mut().next_back().unwrap()
+    }
+
+    /// Roll to a new segment when `batch_size` more bytes would overflow the
+    /// active one, or when the batch is more than `segment_ms` newer than the
+    /// first batch of the active one. The producer state is snapshotted at
+    /// the base offset of the new segment.
+    fn maybe_roll(&mut self, batch_size: u64, max_timestamp: i64) -> io::Result<()> {
+        let active = self.active_segment();
+        if active.size() == 0 {
+            return Ok(());
+        }
+        let full = active.size() + batch_size > self.config.segment_bytes;
+        let expired = active
+            .rolling_base_timestamp()
+            .filter(|&base| base >= 0)
+            .is_some_and(|base| max_timestamp - base > self.config.segment_ms);
+        if full || expired {
+            let base_offset = active.next_offset();
+            self.producer_state
+                .write_snapshot(base_offset, &self.ongoing_transactions)?;
+            let segment =
+                LogSegment::open(&self.dir, base_offset, self.config.index_interval_bytes)?;
+            self.segments.insert(base_offset, segment);
+        }
+        Ok(())
+    }
+
+    /// Append raw record batches, assigning offsets.
+    ///
+    /// Every batch is validated before anything is written so that a bad batch
+    /// never leaves a partially appended record set behind.
+    pub fn append(&mut self, records: &[u8]) -> Result<AppendInfo, AppendError> {
+        self.append_records(records, None)
+    }
+
+    /// Append the raw record batches of a produce request as the partition
+    /// leader in `leader_epoch`, which is stamped on every batch and recorded
+    /// in the leader epoch cache.
+    ///
+    /// The producer epoch and sequence numbers of idempotent batches are
+    /// checked against the producer state. A retry of a recently appended
+    /// batch is not appended again; the offset of the earlier append is
+    /// returned instead.
+    pub fn append_as_leader(
+        &mut self,
+        records: &[u8],
+        leader_epoch: i32,
+    ) -> Result<AppendInfo, AppendError> {
+        self.append_records(records, Some(leader_epoch))
+    }
+
+    fn append_records(
+        &mut self,
+        records: &[u8],
+        leader_epoch: Option<i32>,
+    ) -> Result<AppendInfo, AppendError> {
+        let mut buf = records.to_vec();
+        let batches = split_batches(&buf)?;
+        if batches.is_empty() {
+            return Err(AppendError::corrupt(0, "empty record set"));
+        }
+
+        let base_offset = self.high_watermark();
+        let mut log_append_time_ms = -1;
+        let mut next_offset = base_offset;
+        for &(start, end) in &batches {
+            let batch = &mut buf[start..end];
+            batch[BASE_OFFSET_OFFSET..BATCH_LENGTH_OFFSET]
+                .copy_from_slice(&next_offset.to_be_bytes());
+            // like the base offset, not covered by the CRC
+            if let Some(leader_epoch) = leader_epoch {
+                batch[PARTITION_LEADER_EPOCH_OFFSET..MAGIC_OFFSET]
+                    .copy_from_slice(&leader_epoch.to_be_bytes());
+            }
+            let attributes = read_i16(batch, ATTRIBUTES_OFFSET);
+            if attributes & TIMESTAMP_TYPE_MASK != 0 {
+                let now = now_ms();
+                batch[MAX_TIMESTAMP_OFFSET..MAX_TIMESTAMP_OFFSET + 8]
+                    .copy_from_slice(&now.to_be_bytes());
+                let crc = crc32c::crc32c(&batch[ATTRIBUTES_OFFSET..]);
+                batch[CRC_OFFSET..ATTRIBUTES_OFFSET].copy_from_slice(&crc.to_be_bytes());
+                log_append_time_ms = now;
+            }
+            next_offset += read_i32(batch, LAST_OFFSET_DELTA_OFFSET) as i64 + 1;
+        }
+        if leader_epoch.is_some() {
+            let positions: Vec<_> = batches
+                .iter()
+                .map(|&(start, _)| BatchPosition::from_header(&buf[start..], 0))
+                .collect();
+            if let Some(duplicate) = self.producer_state.validate(&positions)? {
+                return Ok(AppendInfo {
+                    base_offset: duplicate.first_offset,
+                    log_append_time_ms: -1,
+                    log_start_offset: self.log_start_offset,
+                });
+            }
+        }
+
+        for (start, end) in batches {
+            let batch = &buf[start..end];
+            self.maybe_roll(batch.len() as u64, read_i64(batch, MAX_TIMESTAMP_OFFSET))?;
+            self.active_segment_mut().append(batch)?;
+            let position = BatchPosition::from_header(batch, 0);
+            self.producer_state.update(&position);
+            let control_type = position.is_control().then(|| batch::control_type(batch));
+            let aborted = track_transaction(
+                &mut self.ongoing_transactions,
+                &position,
+                control_type.flatten(),
+            );
+            if let Some(aborted) = aborted {
+                self.active_segment_mut()
+                    .append_aborted_transaction(aborted)?;
+            }
+        }
+        self.active_segment_mut().flush()?;
+        if let Some(leader_epoch) = leader_epoch {
+            self.leader_epochs.assign(leader_epoch, base_offset)?;
+        }
+        for watcher in self.append_watchers.drain(..) {
+            watcher.notify_one();
+        }
+        Ok(AppendInfo {
+            base_offset,
+            log_append_time_ms,
+            log_start_offset: self.log_start_offset,
+        })
+    }
+
+    /// Read whole batches, starting with the one that contains `fetch_offset`.
+    ///
+    /// At most `max_bytes` are returned, except that with `min_one_batch` the
+    /// first batch is returned even when it is larger, so that an oversized
+    /// batch cannot stall a consumer forever. Like Kafka, a read never spans
+    /// more than one segment.
+    pub fn read(
+        &self,
+        fetch_offset: i64,
+        max_bytes: usize,
+        min_one_batch: bool,
+    ) -> io::Result<Vec<u8>> {
+        self.read_until(fetch_offset, i64::MAX, max_bytes, min_one_batch)
+    }
+
+    /// Like [`read`](Self::read), but only the batches up to the last stable
+    /// offset, with the aborted transactions that overlap them, oldest
+    /// marker first.
+    pub fn read_committed(
+        &self,
+        fetch_offset: i64,
+        max_bytes: usize,
+        min_one_batch: bool,
+    ) -> io::Result<(Vec<u8>, Vec<AbortedTxn>)> {
+        let last_stable_offset = self.last_stable_offset();
+        let records =
+            self.read_until(fetch_offset, last_stable_offset, max_bytes, min_one_batch)?;
+        let Some(end_offset) = end_offset(&records)? else {
+            return Ok((records, Vec::new()));
+        };
+        let aborted = self
+            .segments
+            .range(self.segment_containing(fetch_offset)..)
+            .flat_map(|(_, segment)| segment.aborted_transactions())
+            .filter(|aborted| {
+                aborted.last_offset >= fetch_offset && aborted.first_offset < end_offset
+            })
+            .copied()
+            .collect();
+        Ok((records, aborted))
+    }
+
+    /// Base offset of the segment holding `offset`.
+    fn segment_containing(&self, offset: i64) -> i64 {
+        self.segments
+            .range(..=offset)
+            .next_back()
+            .map_or(self.log_start_offset, |(&base_offset, _)| base_offset)
+    }
+
+    /// Read from `fetch_offset`, stopping before the batch at `end_offset`.
+    fn read_until(
+        &self,
+        fetch_offset: i64,
+        end_offset: i64,
+        max_bytes: usize,
+        min_one_batch: bool,
+    ) -> io::Result<Vec<u8>> {
+        let first = self.segment_containing(fetch_offset);
+        for segment in self.segments.range(first..).map(|(_, segment)| segment) {
+            // the segment holds `fetch_offset`, so an empty read means the
+            // first batch did not fit
+            if segment.next_offset() > fetch_offset {
+                return segment.read(fetch_offset, end_offset, max_bytes, min_one_batch);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Largest timestamp in the log and the base offset of the first batch
+    /// that carries it, from the time indexes.
+    pub fn max_timestamp(&self) -> Option<(i64, i64)> {
+        self.segments
+            .values()
+            .filter_map(LogSegment::max_timestamp)
+            .fold(None, |max, entry| match max {
+                Some((timestamp, _)) if timestamp >= entry.0 => max,
+                _ => Some(entry),
+            })
+    }
+
+    /// Base offset of the first batch with a max timestamp of at least
+    /// `timestamp`, or `None` when every batch is older.
+    pub fn offset_for_timestamp(&self, timestamp: i64) -> io::Result<Option<i64>> {
+        for segment in self.segments.values() {
+            if segment.max_timestamp().is_some_and(|(max, _)| max < timestamp) {
+                continue;
+            }
+            if let Some(batch) = segment.find_batch_by_timestamp(timestamp)? {
+                return Ok(Some(batch.base_offset));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Follow the transaction `batch` belongs to in `ongoing`, the first offset
+/// of every open transaction by producer id. Returns the transaction `batch`
+/// aborts, if it is an abort marker.
+fn track_transaction(
+    ongoing: &mut BTreeMap<i64, i64>,
+    batch: &BatchPosition,
+    control_type: Option<ControlRecordType>,
+) -> Option<AbortedTxn> {
+    if !batch.is_transactional() {
+        return None;
+    }
+    if !batch.is_control() {
+        ongoing
+            .entry(batch.producer_id)
+            .or_insert(batch.base_offset);
+        return None;
+    }
+    // a marker ends the transaction whatever it decides
+    let first_offset = ongoing
+        .remove(&batch.producer_id)
+        .unwrap_or(batch.base_offset);
+    (control_type == Some(ControlRecordType::Abort)).then(|| AbortedTxn {
+        producer_id: batch.producer_id,
+        first_offset,
+        last_offset: batch.base_offset,
+        last_stable_offset: last_stable_offset(ongoing, batch.last_offset + 1),
+    })
+}
+
+fn last_stable_offset(ongoing: &BTreeMap<i64, i64>, high_watermark: i64) -> i64 {
+    ongoing.values().min().copied().unwrap_or(high_watermark)
+}
+
+/// Split a record set into `(start, end)` byte ranges, one per batch.
+fn split_batches(records: &[u8]) -> Result<Vec<(usize, usize)>, AppendError> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    while start < records.len() {
+        let corrupt = |reason| AppendError::corrupt(batches.len(), reason);
+        if records.len() - start < BATCH_HEADER_SIZE {
+            return Err(corrupt("truncated batch header"));
+        }
+        let batch_length = read_i32(records, start + BATCH_LENGTH_OFFSET);
+        if batch_length < (BATCH_HEADER_SIZE - BATCH_LENGTH_PREFIX) as i32 {
+            return Err(corrupt("invalid batch_length"));
+        }
+        let end = start + BATCH_LENGTH_PREFIX + batch_length as usize;
+        if end > records.len() {
+            return Err(corrupt("batch_length exceeds record set"));
+        }
+        if records[start + MAGIC_OFFSET] != 2 {
+            return Err(corrupt("unsupported magic"));
+        }
+        if Compression::from_attributes(read_i16(records, start + ATTRIBUTES_OFFSET)).is_none() {
+            return Err(corrupt("unknown compression codec"));
+        }
+        if read_i32(records, start + LAST_OFFSET_DELTA_OFFSET) < 0 {
+            return Err(corrupt("negative last_offset_delta"));
+        }
+        // checked before the broker restamps any field the CRC covers
+        if !batch::is_valid_batch(&records[start..end]) {
+            return Err(corrupt("crc mismatch"));
+        }
+        batches.push((start, end));
+        start = end;
+    }
+    Ok(batches)
+}
+
+/// Offset after the last batch of `records`, as returned by a read, or
+/// `None` if there is none. Compaction may have removed the last records of
+/// a batch, so this can be past the last record.
+pub(crate) fn end_offset(records: &[u8]) -> io::Result<Option<i64>> {
+    let batches = split_batches(records).map_err(io::Error::other)?;
+    Ok(batches.last().map(|&(start, _)| {
+        read_i64(records, start + BASE_OFFSET_OFFSET)
+            + read_i32(records, start + LAST_OFFSET_DELTA_OFFSET) as i64
+            + 1
+    }))
+}
+
+pub(crate) fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use binrw::{BinWrite, Endian};
+
+    use super::*;
+    use crate::{
+        records::{control_record::CONTROL_BATCH_MASK, Record, RecordBatch},
+        storage::batch::{
+            BASE_SEQUENCE_OFFSET, PRODUCER_EPOCH_OFFSET, PRODUCER_ID_OFFSET, TRANSACTIONAL_MASK,
+        },
+    };
+
+    fn batch(records: i32) -> Vec<u8> {
+        idempotent_batch(records, -1, -1, -1)
+    }
+
+    fn idempotent_batch(records: i32, producer_id: i64, epoch: i16, base_sequence: i32) -> Vec<u8> {
+        let mut batch = vec![0u8; BATCH_HEADER_SIZE];
+        let batch_length = (BATCH_HEADER_SIZE - BATCH_LENGTH_PREFIX) as i32;
+        batch[BATCH_LENGTH_OFFSET..BATCH_LENGTH_OFFSET + 4]
+            .copy_from_slice(&batch_length.to_be_bytes());
+        batch[MAGIC_OFFSET] = 2;
+        batch[LAST_OFFSET_DELTA_OFFSET..LAST_OFFSET_DELTA_OFFSET + 4]
+            .copy_from_slice(&(records - 1).to_be_bytes());
+        batch[PRODUCER_ID_OFFSET..PRODUCER_EPOCH_OFFSET]
+            .copy_from_slice(&producer_id.to_be_bytes());
+        batch[PRODUCER_EPOCH_OFFSET..BASE_SEQUENCE_OFFSET].copy_from_slice(&epoch.to_be_bytes());
+        batch[BASE_SEQUENCE_OFFSET..BASE_SEQUENCE_OFFSET + 4]
+            .copy_from_slice(&base_sequence.to_be_bytes());
+        seal(&mut batch);
+        batch
+    }
+
+    /// A batch of `records` in a transaction of `producer_id`.
+    fn txn_batch(records: i32, producer_id: i64) -> Vec<u8> {
+        let mut batch = batch(records);
+        batch[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2]
+            .copy_from_slice(&TRANSACTIONAL_MASK.to_be_bytes());
+        batch[PRODUCER_ID_OFFSET..PRODUCER_ID_OFFSET + 8]
+            .copy_from_slice(&producer_id.to_be_bytes());
+        seal(&mut batch);
+        batch
+    }
+
+    /// A commit or abort marker ending the transaction of `producer_id`.
+    fn marker(control_type: ControlRecordType, producer_id: i64) -> Vec<u8> {
+        let mut batch = txn_batch(1, producer_id);
+        let attributes = TRANSACTIONAL_MASK | CONTROL_BATCH_MASK;
+        batch[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2].copy_from_slice(&attributes.to_be_bytes());
+        // length, attributes, deltas, key length, key, null value, no headers
+        let control_type = i16::from(control_type).to_be_bytes();
+        let record = [20, 0, 0, 0, 8, 0, 0, control_type[0], control_type[1], 1, 0];
+        batch.extend(record);
+        let batch_length = (batch.len() - BATCH_LENGTH_PREFIX) as i32;
+        batch[BATCH_LENGTH_OFFSET..BATCH_LENGTH_OFFSET + 4]
+            .copy_from_slice(&batch_length.to_be_bytes());
+        seal(&mut batch);
+        batch
+    }
+
+    /// A batch of records with the given keys and values.
+    fn keyed_batch(records: &[(&str, Option<&str>)]) -> Vec<u8> {
+        let records = records
+            .iter()
+            .enumerate()
+            .map(|(offset_delta, (key, value))| Record {
+                attributes: 0,
+                timestamp_delta: 0,
+                offset_delta: offset_delta as i32,
+                key: Some(key.as_bytes().to_vec()),
+                value: value.map(|value| value.as_bytes().to_vec()),
+                headers: Vec::new(),
+            })
+            .collect();
+        let batch = RecordBatch {
+            base_offset: 0,
+            partition_leader_epoch: 0,
+            crc: 0,
+            attributes: 0,
+            base_timestamp: 0,
+            max_timestamp: 0,
+            producer_id: -1,
+            producer_epoch: -1,
+            base_sequence: -1,
+            records,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        batch.write_options(&mut bytes, Endian::Big, ()).unwrap();
+        bytes.into_inner()
+    }
+
+    /// `(offset, key, value)` of every record in the log.
+    fn keyed_records(log: &PartitionLog) -> Vec<(i64, String, Option<String>)> {
+        let mut records = Vec::new();
+        let mut offset = log.log_start_offset();
+        while offset < log.high_watermark() {
+            let chunk = log.read(offset, usize::MAX, true).unwrap();
+            let next_offset = end_offset(&chunk).unwrap().unwrap();
+            for batch in RecordBatch::read_batches_from(&mut Cursor::new(chunk)).unwrap() {
+                records.extend(batch.records.iter().map(|record| {
+                    let string = |bytes: &Vec<u8>| String::from_utf8(bytes.clone()).unwrap();
+                    (
+                        batch.base_offset + record.offset_delta as i64,
+                        string(record.key.as_ref().unwrap()),
+                        record.value.as_ref().map(string),
+                    )
+                }));
+            }
+            offset = next_offset;
+        }
+        records
+    }
+
+    fn seal(batch: &mut [u8]) {
+        let crc = crc32c::crc32c(&batch[ATTRIBUTES_OFFSET..]);
+        batch[CRC_OFFSET..ATTRIBUTES_OFFSET].copy_from_slice(&crc.to_be_bytes());
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("partition-log-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_append_assigns_offsets_and_survives_reopen() {
+        let dir = temp_dir("append");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        assert_eq!(log.append(&batch(3)).unwrap().base_offset, 0);
+        let mut two_batches = batch(1);
+        two_batches.extend(batch(2));
+        assert_eq!(log.append(&two_batches).unwrap().base_offset, 3);
+        assert_eq!(log.high_watermark(), 6);
+
+        let log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        assert_eq!(log.high_watermark(), 6);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_leader_appends_record_epochs() {
+        let dir = temp_dir("epochs");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        log.append_as_leader(&batch(2), 0).unwrap();
+        log.append_as_leader(&batch(1), 2).unwrap();
+        log.append_as_leader(&batch(1), 2).unwrap();
+
+        let records = log.read(2, 1024, true).unwrap();
+        let epoch = read_i32(&records, PARTITION_LEADER_EPOCH_OFFSET);
+        assert_eq!(epoch, 2);
+        // stamping the epoch keeps the batch CRC valid
+        let crc = crc32c::crc32c(&records[ATTRIBUTES_OFFSET..BATCH_HEADER_SIZE]);
+        assert_eq!(read_i32(&records, CRC_OFFSET) as u32, crc);
+
+        let log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        assert_eq!(log.leader_epochs().latest_epoch(), Some(2));
+        assert_eq!(log.leader_epochs().end_offset_for(1, 4), Some((0, 2)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_transactions_hold_back_the_last_stable_offset() {
+        let dir = temp_dir("txn");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        log.append(&txn_batch(2, 7)).unwrap(); // 0..2
+        log.append(&batch(1)).unwrap(); // 2
+        log.append(&txn_batch(1, 8)).unwrap(); // 3
+        assert_eq!(log.last_stable_offset(), 0);
+        log.append(&marker(ControlRecordType::Abort, 7)).unwrap(); // 4
+        assert_eq!(log.last_stable_offset(), 3);
+
+        // only what is below the last stable offset is read
+        let (records, aborted) = log.read_committed(0, 1024, true).unwrap();
+        assert_eq!(split_batches(&records).unwrap().len(), 2);
+        let aborted_txn = AbortedTxn {
+            producer_id: 7,
+            first_offset: 0,
+            last_offset: 4,
+            last_stable_offset: 3,
+        };
+        assert_eq!(aborted, [aborted_txn]);
+
+        log.append(&marker(ControlRecordType::Commit, 8)).unwrap(); // 5
+        assert_eq!(log.last_stable_offset(), 6);
+        let (records, aborted) = log.read_committed(3, 1024, true).unwrap();
+        assert_eq!(split_batches(&records).unwrap().len(), 3);
+        assert_eq!(aborted, [aborted_txn]);
+        assert!(log.read_committed(5, 1024, true).unwrap().1.is_empty());
+
+        // the open transaction and the index are rebuilt on reopen
+        log.append(&txn_batch(1, 9)).unwrap(); // 6
+        fs::remove_file(dir.join("00000000000000000000.txnindex")).unwrap();
+        let log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        assert_eq!(log.last_stable_offset(), 6);
+        assert_eq!(log.read_committed(0, 1024, true).unwrap().1, [aborted_txn]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_only_missing_or_recovered_txn_indexes_are_rebuilt() {
+        let dir = temp_dir("txnindex");
+        let config = LogConfig {
+            segment_bytes: 1,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        for batch in [
+            txn_batch(1, 7),
+            marker(ControlRecordType::Abort, 7),
+            txn_batch(1, 8),
+            marker(ControlRecordType::Abort, 8),
+        ] {
+            log.append(&batch).unwrap();
+        }
+        assert_eq!(log.segment_base_offsets(), [0, 1, 2, 3]);
+        drop(log);
+
+        // an emptied index before the snapshot is trusted, a missing one is not
+        let txn_index = |base_offset: i64| dir.join(format!("{base_offset:020}.txnindex"));
+        fs::write(txn_index(1), []).unwrap();
+        fs::remove_file(txn_index(3)).unwrap();
+        let aborted_producers = |log: &PartitionLog| -> Vec<i64> {
+            let segments = log.segments.values();
+            let aborted = segments.flat_map(LogSegment::aborted_transactions);
+            aborted.map(|aborted| aborted.producer_id).collect()
+        };
+        let log = PartitionLog::open(&dir, config.clone()).unwrap();
+        assert_eq!(aborted_producers(&log), [8]);
+        assert_eq!(fs::metadata(txn_index(1)).unwrap().len(), 0);
+        drop(log);
+
+        fs::remove_file(txn_index(1)).unwrap();
+        let log = PartitionLog::open(&dir, config).unwrap();
+        assert_eq!(aborted_producers(&log), [7, 8]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reopen_resumes_open_transactions_from_the_snapshot() {
+        let dir = temp_dir("txn-snapshot");
+        let config = LogConfig {
+            segment_bytes: 1,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        log.append(&txn_batch(2, 7)).unwrap(); // 0..2
+        log.append(&batch(1)).unwrap(); // 2
+        log.append(&txn_batch(1, 8)).unwrap(); // 3
+        log.append(&marker(ControlRecordType::Abort, 8)).unwrap(); // 4
+        log.write_producer_snapshot().unwrap();
+        assert_eq!(log.last_stable_offset(), 0);
+        drop(log);
+
+        // only a replay of the whole log would see the transaction go
+        let first = dir.join(format!("{:020}.log", 0));
+        let mut bytes = fs::read(&first).unwrap();
+        bytes[ATTRIBUTES_OFFSET..ATTRIBUTES_OFFSET + 2].copy_from_slice(&0i16.to_be_bytes());
+        fs::write(&first, bytes).unwrap();
+
+        let log = PartitionLog::open(&dir, config.clone()).unwrap();
+        assert_eq!(log.ongoing_transactions, BTreeMap::from([(7, 0)]));
+        assert_eq!(log.last_stable_offset(), 0);
+        drop(log);
+
+        // from the snapshot of the last roll, the abort marker is replayed
+        fs::remove_file(dir.join(format!("{:020}.snapshot", 5))).unwrap();
+        fs::remove_file(dir.join(format!("{:020}.txnindex", 4))).unwrap();
+        let log = PartitionLog::open(&dir, config).unwrap();
+        assert_eq!(log.ongoing_transactions, BTreeMap::from([(7, 0)]));
+        let aborted = log.segments[&4].aborted_transactions();
+        assert_eq!(
+            aborted,
+            [AbortedTxn {
+                producer_id: 8,
+                first_offset: 3,
+                last_offset: 4,
+                last_stable_offset: 0,
+            }]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_old_producer_snapshots_are_pruned() {
+        let dir = temp_dir("snapshot-prune");
+        let config = LogConfig {
+            segment_bytes: 1,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        // every append after the first rolls, snapshotting at its base offset
+        for _ in 0..5 {
+            log.append(&batch(1)).unwrap();
+        }
+        log.write_producer_snapshot().unwrap();
+        let snapshots = |dir: &Path| {
+            let mut names: Vec<String> = fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                .filter(|name| name.ends_with(".snapshot"))
+                .collect();
+            names.sort();
+            names
+        };
+        let expected = [format!("{:020}.snapshot", 4), format!("{:020}.snapshot", 5)];
+        assert_eq!(snapshots(&dir), expected);
+
+        // the log still reopens from the latest of them
+        drop(log);
+        let log = PartitionLog::open(&dir, config).unwrap();
+        assert_eq!(log.high_watermark(), 5);
+        assert_eq!(snapshots(&dir), expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_idempotent_producers() {
+        let dir = temp_dir("idempotent");
+        let config = LogConfig {
+            segment_bytes: 2 * BATCH_HEADER_SIZE as u64,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        let mut append = |records, producer_id, epoch, base_sequence| {
+            log.append_as_leader(
+                &idempotent_batch(records, producer_id, epoch, base_sequence),
+                0,
+            )
+            .map(|info| info.base_offset)
+        };
+        assert_eq!(append(2, 7, 0, 0).unwrap(), 0);
+        assert_eq!(append(1, 8, 0, 0).unwrap(), 2);
+        // rolls, snapshotting the state at offset 3
+        assert_eq!(append(3, 7, 0, 2).unwrap(), 3);
+
+        // a retry is not appended again
+        assert_eq!(append(2, 7, 0, 0).unwrap(), 0);
+        assert!(matches!(
+            append(1, 7, 0, 6),
+            Err(AppendError::OutOfOrderSequence {
+                last_sequence: 4,
+                sequence: 6,
+                ..
+            })
+        ));
+        // a new epoch starts over at 0 and fences the old one
+        assert!(matches!(
+            append(1, 7, 1, 5),
+            Err(AppendError::OutOfOrderSequence {
+                last_sequence: -1,
+                ..
+            })
+        ));
+        assert_eq!(append(1, 7, 1, 0).unwrap(), 6);
+        assert!(matches!(
+            append(1, 7, 0, 5),
+            Err(AppendError::InvalidProducerEpoch {
+                epoch: 0,
+                current_epoch: 1,
+                ..
+            })
+        ));
+        assert_eq!(log.high_watermark(), 7);
+        drop(log);
+
+        // producer 8 is only known from the snapshot once the first segment
+        // is gone, producer 7's new epoch from replaying the second one
+        for extension in ["log", "index", "timeindex", "txnindex"] {
+            fs::remove_file(dir.join(format!("{:020}.{extension}", 0))).unwrap();
+        }
+        assert!(dir.join(format!("{:020}.snapshot", 3)).exists());
+        let mut log = PartitionLog::open(&dir, config).unwrap();
+        assert!(matches!(
+            log.append_as_leader(&idempotent_batch(1, 8, 0, 5), 0),
+            Err(AppendError::OutOfOrderSequence {
+                last_sequence: 0,
+                ..
+            })
+        ));
+        let retry = log.append_as_leader(&idempotent_batch(1, 7, 1, 0), 0);
+        assert_eq!(retry.unwrap().base_offset, 6);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_append_wakes_watchers() {
+        let dir = temp_dir("watch");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        let watcher = Arc::new(Notify::new());
+        log.watch_appends(&watcher);
+        log.watch_appends(&Arc::new(Notify::new()));
+        // the watcher of a completed fetch is dropped on the next registration
+        log.watch_appends(&watcher);
+        assert_eq!(log.append_watchers.len(), 2);
+
+        log.append(&batch(1)).unwrap();
+        assert!(log.append_watchers.is_empty());
+        let woken = tokio::time::timeout(Duration::from_secs(1), watcher.notified()).await;
+        assert!(woken.is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_from_fetch_offset_respects_max_bytes() {
+        let dir = temp_dir("read");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        for records in [2, 3, 1] {
+            log.append(&batch(records)).unwrap();
+        }
+        let batch_size = BATCH_HEADER_SIZE;
+
+        // offset 3 lives in the second batch [2, 4]
+        let read = log.read(3, usize::MAX, false).unwrap();
+        assert_eq!(read.len(), 2 * batch_size);
+        assert_eq!(read_i64(&read, BASE_OFFSET_OFFSET), 2);
+
+        assert_eq!(
+            log.read(0, batch_size + 1, false).unwrap().len(),
+            batch_size
+        );
+        assert!(log.read(0, batch_size - 1, false).unwrap().is_empty());
+        assert_eq!(log.read(0, 1, true).unwrap().len(), batch_size);
+        assert!(log.read(6, usize::MAX, true).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_rejects_truncated_batch() {
+        let dir = temp_dir("truncated");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        let mut records = batch(1);
+        records.truncate(BATCH_HEADER_SIZE - 1);
+        assert!(matches!(
+            log.append(&records),
+            Err(AppendError::CorruptBatch { .. })
+        ));
+        assert_eq!(log.high_watermark(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_rejects_crc_mismatch() {
+        let dir = temp_dir("crc");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        log.append(&batch(1)).unwrap();
+
+        // a LogAppendTime batch would otherwise be resealed over the bad bytes
+        let mut records = batch(2);
+        records[ATTRIBUTES_OFFSET + 1] |= TIMESTAMP_TYPE_MASK as u8;
+        for records in [batch(2), records] {
+            let mut records = [batch(1), records].concat();
+            let last = records.len() - 1;
+            records[last] ^= 0xff;
+            assert!(matches!(
+                log.append(&records),
+                Err(AppendError::CorruptBatch {
+                    batch_index: 1,
+                    reason: "crc mismatch"
+                })
+            ));
+            assert_eq!(log.high_watermark(), 1);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compact_keeps_the_last_record_of_every_key() {
+        let dir = temp_dir("compact");
+        // every batch gets a segment of its own
+        let config = LogConfig {
+            segment_bytes: 1,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        for records in [
+            &[("a", Some("1")), ("b", Some("1"))][..], // offsets 0, 1
+            &[("a", Some("2")), ("c", Some("1"))],     // offsets 2, 3
+            &[("b", None)],                            // offset 4
+            &[("d", Some("1")), ("c", Some("2"))],     // offsets 5, 6
+            &[("a", Some("3")), ("c", Some("3"))],     // offsets 7, 8
+        ] {
+            log.append(&keyed_batch(records)).unwrap();
+        }
+        assert_eq!(log.segment_base_offsets(), [0, 2, 4, 5, 7]);
+
+        log.compact().unwrap();
+        let string = |s: &str| s.to_string();
+        let expected = vec![
+            (5, string("d"), Some(string("1"))),
+            (7, string("a"), Some(string("3"))),
+            (8, string("c"), Some(string("3"))),
+        ];
+        assert_eq!(keyed_records(&log), expected);
+        // the first segment keeps the log start offset, however empty; the
+        // active one is never cleaned
+        assert_eq!(log.segment_base_offsets(), [0, 5, 7]);
+        assert_eq!(log.log_start_offset(), 0);
+        assert_eq!(log.high_watermark(), 9);
+        // the batch at 5 lost its last record but not its offsets
+        assert_eq!(
+            end_offset(&log.read(5, usize::MAX, true).unwrap()).unwrap(),
+            Some(7)
+        );
+        assert!(!dir.join(format!("{:020}.log", 2)).exists());
+
+        drop(log);
+        let mut log = PartitionLog::open(&dir, config).unwrap();
+        assert_eq!(keyed_records(&log), expected);
+        assert_eq!(
+            log.append(&keyed_batch(&[("e", Some("1"))]))
+                .unwrap()
+                .base_offset,
+            9
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_segments_roll_and_reads_cross_them() {
+        let dir = temp_dir("roll");
+        let config = LogConfig {
+            segment_bytes: 2 * BATCH_HEADER_SIZE as u64,
+            index_interval_bytes: 1,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        for records in [2, 3, 1, 4, 1] {
+            log.append(&batch(records)).unwrap();
+        }
+        assert_eq!(log.segment_base_offsets(), [0, 5, 10]);
+        for extension in ["log", "index", "timeindex"] {
+            assert!(dir.join(format!("{:020}.{extension}", 5)).exists());
+        }
+
+        // a read stops at the end of the segment holding the fetch offset
+        let read = log.read(3, usize::MAX, false).unwrap();
+        assert_eq!(read.len(), BATCH_HEADER_SIZE);
+        assert_eq!(read_i64(&read, BASE_OFFSET_OFFSET), 2);
+        let read = log.read(5, usize::MAX, false).unwrap();
+        assert_eq!(read.len(), 2 * BATCH_HEADER_SIZE);
+        assert_eq!(read_i64(&read, BASE_OFFSET_OFFSET), 5);
+        assert_eq!(
+            read_i64(
+                &log.read(10, usize::MAX, false).unwrap(),
+                BASE_OFFSET_OFFSET
+            ),
+            10
+        );
+        assert!(log.read(11, usize::MAX, true).unwrap().is_empty());
+
+        let mut log = PartitionLog::open(&dir, config).unwrap();
+        assert_eq!(log.segment_base_offsets(), [0, 5, 10]);
+        assert_eq!(log.high_watermark(), 11);
+        assert_eq!(log.append(&batch(1)).unwrap().base_offset, 11);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reopen_cuts_torn_tail() {
+        let dir = temp_dir("torn");
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        log.append(&batch(2)).unwrap();
+        log.append(&batch(1)).unwrap();
+        drop(log);
+
+        let path = dir.join(format!("{:020}.log", 0));
+        let len = fs::metadata(&path).unwrap().len();
+        let file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 10).unwrap();
+
+        let mut log = PartitionLog::open(&dir, LogConfig::default()).unwrap();
+        assert_eq!(log.high_watermark(), 2);
+        assert_eq!(fs::metadata(&path).unwrap().len(), BATCH_HEADER_SIZE as u64);
+        assert_eq!(log.append(&batch(1)).unwrap().base_offset, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_offset_for_timestamp() {
+        let dir = temp_dir("timestamp");
+        let config = LogConfig {
+            segment_bytes: 2 * BATCH_HEADER_SIZE as u64,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config).unwrap();
+        for (records, timestamp) in [(2, 100i64), (1, 300), (3, 200), (1, 500)] {
+            let mut batch = batch(records);
+            batch[MAX_TIMESTAMP_OFFSET..MAX_TIMESTAMP_OFFSET + 8]
+                .copy_from_slice(&timestamp.to_be_bytes());
+            seal(&mut batch);
+            log.append(&batch).unwrap();
+        }
+        assert_eq!(log.offset_for_timestamp(0).unwrap(), Some(0));
+        assert_eq!(log.offset_for_timestamp(150).unwrap(), Some(2));
+        assert_eq!(log.offset_for_timestamp(300).unwrap(), Some(2));
+        assert_eq!(log.offset_for_timestamp(400).unwrap(), Some(6));
+        assert_eq!(log.offset_for_timestamp(501).unwrap(), None);
+        assert_eq!(log.max_timestamp(), Some((500, 6)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recovery_truncates_corrupt_batches_and_rebuilds_indexes() {
+        let dir = temp_dir("recovery");
+        let config = LogConfig {
+            segment_bytes: 2 * BATCH_HEADER_SIZE as u64,
+            ..LogConfig::default()
+        };
+        let mut log = PartitionLog::open(&dir, config.clone()).unwrap();
+        for records in [2, 1, 3, 1] {
+            log.append(&batch(records)).unwrap();
+        }
+        assert_eq!(log.segment_base_offsets(), [0, 3]);
+        drop(log);
+
+        // flip a byte covered by the CRC of the last batch of the active segment
+        let active = dir.join(format!("{:020}.log", 3));
+        let mut bytes = fs::read(&active).unwrap();
+        bytes[BATCH_HEADER_SIZE + ATTRIBUTES_OFFSET + 1] ^= 0x01;
+        fs::write(&active, bytes).unwrap();
+        fs::remove_file(dir.join(format!("{:020}.index", 0))).unwrap();
+
+        let log = PartitionLog::open(&dir, config).unwrap();
+        let recovery = log.recovery();
+        assert_eq!(recovery.recovered_batches, 1);
+        assert_eq!(recovery.truncated_bytes, BATCH_HEADER_SIZE as u64);
+        assert_eq!(recovery.rebuilt_indexes, 1);
+        assert_eq!(log.high_watermark(), 6);
+        assert!(dir.join(format!("{:020}.index", 0)).exists());
+        assert_eq!(
+            read_i64(&log.read(2, usize::MAX, false).unwrap(), BASE_OFFSET_OFFSET),
+            2
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
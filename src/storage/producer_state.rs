@@ -0,0 +1,355 @@
+//! Idempotent producer state of a partition: the epoch of every producer that
+//! wrote to it and the sequence numbers of its latest batches, so that a
+//! retried batch is recognised and a sequence gap or a fenced producer is
+//! rejected.
+//!
+//! The state is snapshotted to `.snapshot` files named after the offset they
+//! cover the log up to, in Kafka's format (version 1), so that reopening the
+//! log only replays the batches appended after the latest snapshot:
+//!
+//! ```text
+//! version: int16, crc: uint32 (CRC32C of everything after it)
+//! count: int32, then per producer:
+//!   producer_id: int64, producer_epoch: int16, last_sequence: int32,
+//!   last_offset: int64, offset_delta: int32, timestamp: int64,
+//!   coordinator_epoch: int32, current_txn_first_offset: int64
+//! ```
+//!
+//! Only the last batch of a producer is snapshotted, like in Kafka.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{batch::BatchPosition, partition_log::AppendError, segment::file_path};
+
+/// Producer id of batches written without idempotence.
+pub(super) const NO_PRODUCER_ID: i64 = -1;
+const NO_PRODUCER_EPOCH: i16 = -1;
+const NO_SEQUENCE: i32 = -1;
+
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+const SNAPSHOT_VERSION: i16 = 1;
+/// The CRC covers the snapshot from the producer count on.
+const SNAPSHOT_ENTRIES_OFFSET: usize = 6;
+const SNAPSHOT_ENTRY_SIZE: usize = 46;
+/// Snapshots kept after writing a new one: the latest, and one to fall back
+/// on should the log be truncated below it.
+const SNAPSHOTS_TO_RETAIN: usize = 2;
+/// Latest batches remembered per producer to recognise retries, as many as
+/// an idempotent producer may have in flight.
+const BATCHES_TO_RETAIN: usize = 5;
+
+/// Sequence numbers and offsets of one appended batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BatchMetadata {
+    pub first_sequence: i32,
+    pub last_sequence: i32,
+    pub first_offset: i64,
+    pub last_offset: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone)]
+struct ProducerEntry {
+    epoch: i16,
+    /// Oldest first.
+    batches: VecDeque<BatchMetadata>,
+}
+
+impl ProducerEntry {
+    fn new() -> Self {
+        Self {
+            epoch: NO_PRODUCER_EPOCH,
+            batches: VecDeque::new(),
+        }
+    }
+
+    fn last_sequence(&self) -> i32 {
+        self.batches
+            .back()
+            .map_or(NO_SEQUENCE, |batch| batch.last_sequence)
+    }
+
+    /// The earlier append of `batch`, if it is a retry of one of the
+    /// remembered batches.
+    fn duplicate_of(&self, batch: &BatchPosition) -> Option<BatchMetadata> {
+        if batch.producer_epoch != self.epoch {
+            return None;
+        }
+        self.batches
+            .iter()
+            .find(|metadata| {
+                metadata.first_sequence == batch.base_sequence
+                    && metadata.last_sequence == batch.last_sequence()
+            })
+            .copied()
+    }
+
+    /// Check that a client may append `batch`: its epoch is not older than
+    /// the producer's and its first sequence follows the last one, or is 0
+    /// in a new epoch. A producer without an epoch yet may start anywhere.
+    fn check(&self, producer_id: i64, batch: &BatchPosition) -> Result<(), AppendError> {
+        if batch.producer_epoch < self.epoch {
+            return Err(AppendError::InvalidProducerEpoch {
+                producer_id,
+                epoch: batch.producer_epoch,
+                current_epoch: self.epoch,
+            });
+        }
+        // markers carry no sequence numbers
+        if batch.is_control() || self.epoch == NO_PRODUCER_EPOCH {
+            return Ok(());
+        }
+        let last_sequence = match batch.producer_epoch == self.epoch {
+            true => self.last_sequence(),
+            false => NO_SEQUENCE,
+        };
+        if !in_sequence(last_sequence, batch.base_sequence) {
+            return Err(AppendError::OutOfOrderSequence {
+                producer_id,
+                last_sequence,
+                sequence: batch.base_sequence,
+            });
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, batch: &BatchPosition) {
+        if batch.producer_epoch != self.epoch {
+            self.epoch = batch.producer_epoch;
+            self.batches.clear();
+        }
+        if batch.is_control() {
+            return;
+        }
+        if self.batches.len() == BATCHES_TO_RETAIN {
+            self.batches.pop_front();
+        }
+        self.batches.push_back(BatchMetadata {
+            first_sequence: batch.base_sequence,
+            last_sequence: batch.last_sequence(),
+            first_offset: batch.base_offset,
+            last_offset: batch.last_offset,
+            timestamp: batch.max_timestamp,
+        });
+    }
+}
+
+/// Whether `sequence` directly follows `last_sequence`, wrapping past
+/// `i32::MAX` to 0.
+fn in_sequence(last_sequence: i32, sequence: i32) -> bool {
+    sequence == last_sequence.wrapping_add(1) || (sequence == 0 && last_sequence == i32::MAX)
+}
+
+/// `sequence` less `delta`, wrapping below 0 back to `i32::MAX`.
+fn decrement_sequence(sequence: i32, delta: i32) -> i32 {
+    if sequence < delta {
+        i32::MAX - (delta - sequence) + 1
+    } else {
+        sequence - delta
+    }
+}
+
+pub(super) struct ProducerStateManager {
+    dir: PathBuf,
+    producers: HashMap<i64, ProducerEntry>,
+}
+
+impl ProducerStateManager {
+    /// Restore the state of the log in `dir` from its latest snapshot at or
+    /// below `log_end_offset`, and return the offset the log has to be
+    /// replayed from, with the first offset of every transaction open there.
+    /// Snapshots past the log end, left behind by a truncated log, and
+    /// corrupt ones are deleted.
+    pub fn load(dir: &Path, log_end_offset: i64) -> io::Result<(Self, i64, BTreeMap<i64, i64>)> {
+        let mut manager = Self {
+            dir: dir.to_path_buf(),
+            producers: HashMap::new(),
+        };
+        let mut offsets = snapshot_offsets(dir)?;
+        while let Some(offset) = offsets.pop() {
+            let path = file_path(dir, offset, SNAPSHOT_EXTENSION);
+            if offset <= log_end_offset {
+                if let Some((producers, ongoing)) = parse_snapshot(&fs::read(&path)?) {
+                    manager.producers = producers;
+                    return Ok((manager, offset, ongoing));
+                }
+            }
+            fs::remove_file(&path)?;
+        }
+        Ok((manager, 0, BTreeMap::new()))
+    }
+
+    /// Validate the batches of a client's record set, in order, before any
+    /// of them is appended. Returns the earlier append of a retried batch,
+    /// which must then not be appended again.
+    pub fn validate(
+        &self,
+        batches: &[BatchPosition],
+    ) -> Result<Option<BatchMetadata>, AppendError> {
+        // the state as of the batches validated so far
+        let mut pending: HashMap<i64, ProducerEntry> = HashMap::new();
+        for batch in batches {
+            let producer_id = batch.producer_id;
+            if producer_id == NO_PRODUCER_ID {
+                continue;
+            }
+            let entry = pending
+                .get(&producer_id)
+                .or_else(|| self.producers.get(&producer_id));
+            if let Some(entry) = entry {
+                if let Some(duplicate) = entry.duplicate_of(batch) {
+                    return Ok(Some(duplicate));
+                }
+                entry.check(producer_id, batch)?;
+            }
+            pending
+                .entry(producer_id)
+                .or_insert_with(|| {
+                    self.producers
+                        .get(&producer_id)
+                        .cloned()
+                        .unwrap_or_else(ProducerEntry::new)
+                })
+                .update(batch);
+        }
+        Ok(None)
+    }
+
+    /// Record an appended batch.
+    pub fn update(&mut self, batch: &BatchPosition) {
+        if batch.producer_id == NO_PRODUCER_ID {
+            return;
+        }
+        self.producers
+            .entry(batch.producer_id)
+            .or_insert_with(ProducerEntry::new)
+            .update(batch);
+    }
+
+    /// Snapshot the state of the log up to `offset`, with the first offset of
+    /// every open transaction in `ongoing_transactions`. Only the latest
+    /// [`SNAPSHOTS_TO_RETAIN`] snapshots are kept.
+    pub fn write_snapshot(
+        &self,
+        offset: i64,
+        ongoing_transactions: &BTreeMap<i64, i64>,
+    ) -> io::Result<()> {
+        let mut producer_ids: Vec<i64> = self.producers.keys().copied().collect();
+        producer_ids.sort_unstable();
+        let mut buf = Vec::with_capacity(
+            SNAPSHOT_ENTRIES_OFFSET + 4 + producer_ids.len() * SNAPSHOT_ENTRY_SIZE,
+        );
+        buf.extend(SNAPSHOT_VERSION.to_be_bytes());
+        buf.extend(0u32.to_be_bytes());
+        buf.extend((producer_ids.len() as i32).to_be_bytes());
+        for producer_id in producer_ids {
+            let entry = &self.producers[&producer_id];
+            let (last_sequence, last_offset, offset_delta, timestamp) = match entry.batches.back() {
+                Some(batch) => (
+                    batch.last_sequence,
+                    batch.last_offset,
+                    (batch.last_offset - batch.first_offset) as i32,
+                    batch.timestamp,
+                ),
+                None => (NO_SEQUENCE, -1, 0, -1),
+            };
+            let txn_first_offset = ongoing_transactions
+                .get(&producer_id)
+                .copied()
+                .unwrap_or(-1);
+            buf.extend(producer_id.to_be_bytes());
+            buf.extend(entry.epoch.to_be_bytes());
+            buf.extend(last_sequence.to_be_bytes());
+            buf.extend(last_offset.to_be_bytes());
+            buf.extend(offset_delta.to_be_bytes());
+            buf.extend(timestamp.to_be_bytes());
+            // coordinator epoch, unknown without a transaction coordinator
+            buf.extend((-1i32).to_be_bytes());
+            buf.extend(txn_first_offset.to_be_bytes());
+        }
+        let crc = crc32c::crc32c(&buf[SNAPSHOT_ENTRIES_OFFSET..]);
+        buf[2..SNAPSHOT_ENTRIES_OFFSET].copy_from_slice(&crc.to_be_bytes());
+
+        let path = file_path(&self.dir, offset, SNAPSHOT_EXTENSION);
+        let tmp = path.with_extension("snapshot.tmp");
+        fs::write(&tmp, buf)?;
+        fs::rename(&tmp, &path)?;
+
+        let offsets = snapshot_offsets(&self.dir)?;
+        let stale = offsets.len().saturating_sub(SNAPSHOTS_TO_RETAIN);
+        for &offset in &offsets[..stale] {
+            fs::remove_file(file_path(&self.dir, offset, SNAPSHOT_EXTENSION))?;
+        }
+        Ok(())
+    }
+}
+
+/// Offsets of the snapshots in `dir`, ascending.
+fn snapshot_offsets(dir: &Path) -> io::Result<Vec<i64>> {
+    let mut offsets = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(SNAPSHOT_EXTENSION) {
+            continue;
+        }
+        if let Some(offset) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<i64>().ok())
+        {
+            offsets.push(offset);
+        }
+    }
+    offsets.sort_unstable();
+    Ok(offsets)
+}
+
+/// The producers of a snapshot, and the first offset of their open
+/// transactions.
+fn parse_snapshot(buf: &[u8]) -> Option<(HashMap<i64, ProducerEntry>, BTreeMap<i64, i64>)> {
+    let read_i16 = |at: usize| Some(i16::from_be_bytes(buf.get(at..at + 2)?.try_into().ok()?));
+    let read_i32 = |at: usize| Some(i32::from_be_bytes(buf.get(at..at + 4)?.try_into().ok()?));
+    let read_i64 = |at: usize| Some(i64::from_be_bytes(buf.get(at..at + 8)?.try_into().ok()?));
+
+    if read_i16(0)? != SNAPSHOT_VERSION
+        || read_i32(2)? as u32 != crc32c::crc32c(buf.get(SNAPSHOT_ENTRIES_OFFSET..)?)
+    {
+        return None;
+    }
+    let count = usize::try_from(read_i32(SNAPSHOT_ENTRIES_OFFSET)?).ok()?;
+    if buf.len() != SNAPSHOT_ENTRIES_OFFSET + 4 + count * SNAPSHOT_ENTRY_SIZE {
+        return None;
+    }
+    let mut producers = HashMap::with_capacity(count);
+    let mut ongoing_transactions = BTreeMap::new();
+    for i in 0..count {
+        let at = SNAPSHOT_ENTRIES_OFFSET + 4 + i * SNAPSHOT_ENTRY_SIZE;
+        let producer_id = read_i64(at)?;
+        let last_sequence = read_i32(at + 10)?;
+        let last_offset = read_i64(at + 14)?;
+        let offset_delta = read_i32(at + 22)?;
+        let mut entry = ProducerEntry {
+            epoch: read_i16(at + 8)?,
+            batches: VecDeque::new(),
+        };
+        if last_offset >= 0 {
+            entry.batches.push_back(BatchMetadata {
+                first_sequence: decrement_sequence(last_sequence, offset_delta),
+                last_sequence,
+                first_offset: last_offset - offset_delta as i64,
+                last_offset,
+                timestamp: read_i64(at + 26)?,
+            });
+        }
+        let txn_first_offset = read_i64(at + 38)?;
+        if txn_first_offset >= 0 {
+            ongoing_transactions.insert(producer_id, txn_first_offset);
+        }
+        producers.insert(producer_id, entry);
+    }
+    Some((producers, ongoing_transactions))
+}
@@ -0,0 +1,327 @@
+//! One `NNNNNNNNNNNNNNNNNNNN.log` segment and its indexes.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+};
+
+use crate::records::control_record::ControlRecordType;
+
+use super::{
+    batch::{self, is_valid_batch, BatchPosition, BatchPositions, CONTROL_BATCH_PREFIX_SIZE},
+    index::{AbortedTxn, OffsetIndex, TimeIndex, TransactionIndex},
+};
+
+/// Files that make up a segment.
+const SEGMENT_FILE_EXTENSIONS: [&str; 4] = ["log", "index", "timeindex", "txnindex"];
+
+pub(super) struct LogSegment {
+    base_offset: i64,
+    log: File,
+    offset_index: OffsetIndex,
+    time_index: TimeIndex,
+    txn_index: TransactionIndex,
+    index_interval_bytes: u64,
+    size: u64,
+    /// Offset after the last batch of the segment.
+    next_offset: i64,
+    /// Max timestamp of the first batch, which the segment age is measured from.
+    rolling_base_timestamp: Option<i64>,
+    bytes_since_last_index_entry: u64,
+    /// Whether `open` found the index files missing or corrupt.
+    index_rebuilt: bool,
+    /// Whether `open` found no transaction index, which only replaying the
+    /// log can fill in.
+    txn_index_missing: bool,
+}
+
+/// What `LogSegment::recover` checked and cut off.
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct SegmentRecovery {
+    pub valid_batches: usize,
+    pub truncated_bytes: u64,
+}
+
+impl LogSegment {
+    /// Open (or create) the segment starting at `base_offset` in `dir`.
+    ///
+    /// Only the batches after the last offset index entry are scanned, which
+    /// also fills in index entries a crash may have lost. Indexes that are
+    /// missing or do not point at batches are rebuilt from the whole log. A
+    /// torn batch at the end of the log is cut off.
+    pub fn open(dir: &Path, base_offset: i64, index_interval_bytes: u64) -> io::Result<Self> {
+        let log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(file_path(dir, base_offset, "log"))?;
+        let index_path = file_path(dir, base_offset, "index");
+        let time_index_path = file_path(dir, base_offset, "timeindex");
+        let indexes_exist = index_path.exists() && time_index_path.exists();
+        let offset_index = OffsetIndex::open(&index_path, base_offset)?;
+        let time_index = TimeIndex::open(&time_index_path, base_offset)?;
+        let txn_index_path = file_path(dir, base_offset, "txnindex");
+        let txn_index_missing = !txn_index_path.exists();
+        let txn_index = TransactionIndex::open(&txn_index_path)?;
+        let len = log.metadata()?.len();
+
+        let mut segment = Self {
+            base_offset,
+            log,
+            offset_index,
+            time_index,
+            txn_index,
+            index_interval_bytes,
+            size: 0,
+            next_offset: base_offset,
+            rolling_base_timestamp: None,
+            bytes_since_last_index_entry: 0,
+            index_rebuilt: false,
+            txn_index_missing,
+        };
+        let first_batch = BatchPositions::from(&segment.log, 0)?.next().transpose()?;
+        segment.rolling_base_timestamp = first_batch.map(|batch| batch.max_timestamp);
+
+        if len > 0 && !(indexes_exist && segment.indexes_look_sane()?) {
+            segment.offset_index.truncate_to_position(0)?;
+            segment.time_index.truncate_to_offset(base_offset)?;
+            segment.index_rebuilt = true;
+        }
+
+        // resume after the last indexed batch, which is the first one scanned
+        let (resume_offset, resume_position) = segment
+            .offset_index
+            .last_entry()
+            .unwrap_or((base_offset, 0));
+        segment.size = resume_position;
+        segment.next_offset = resume_offset;
+        segment.time_index.truncate_to_offset(resume_offset)?;
+        let tail: Vec<BatchPosition> =
+            BatchPositions::from(&segment.log, resume_position)?.collect::<io::Result<_>>()?;
+        for batch in &tail {
+            segment.index(batch)?;
+        }
+        segment.offset_index.truncate_to_position(segment.size)?;
+        if segment.size < len {
+            segment.log.set_len(segment.size)?;
+        }
+        Ok(segment)
+    }
+
+    /// Whether the last offset index entry points at the header of the batch
+    /// it names, and the time index is not empty while the offset index is not.
+    fn indexes_look_sane(&self) -> io::Result<bool> {
+        let Some((offset, position)) = self.offset_index.last_entry() else {
+            return Ok(self.time_index.last_entry().is_none());
+        };
+        let batch = BatchPositions::from(&self.log, position)?
+            .next()
+            .transpose()?;
+        Ok(self.time_index.last_entry().is_some()
+            && batch.is_some_and(|batch| batch.base_offset == offset))
+    }
+
+    /// Rescan the whole segment, checking the magic and CRC of every batch,
+    /// and rebuild both indexes. The log is cut at the first invalid batch,
+    /// since nothing after a torn write can be trusted.
+    pub fn recover(&mut self) -> io::Result<SegmentRecovery> {
+        let len = self.log.metadata()?.len();
+        self.offset_index.truncate_to_position(0)?;
+        self.time_index.truncate_to_offset(self.base_offset)?;
+        self.size = 0;
+        self.next_offset = self.base_offset;
+        self.rolling_base_timestamp = None;
+        self.bytes_since_last_index_entry = 0;
+
+        let batches: Vec<BatchPosition> =
+            BatchPositions::from(&self.log, 0)?.collect::<io::Result<_>>()?;
+        let mut valid_batches = 0;
+        for batch in &batches {
+            if !is_valid_batch(&self.read_batch(batch)?) {
+                break;
+            }
+            if self.rolling_base_timestamp.is_none() {
+                self.rolling_base_timestamp = Some(batch.max_timestamp);
+            }
+            self.index(batch)?;
+            valid_batches += 1;
+        }
+        if self.size < len {
+            self.log.set_len(self.size)?;
+        }
+        Ok(SegmentRecovery {
+            valid_batches,
+            truncated_bytes: len - self.size,
+        })
+    }
+
+    pub fn index_rebuilt(&self) -> bool {
+        self.index_rebuilt
+    }
+
+    pub fn txn_index_missing(&self) -> bool {
+        self.txn_index_missing
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn next_offset(&self) -> i64 {
+        self.next_offset
+    }
+
+    pub fn rolling_base_timestamp(&self) -> Option<i64> {
+        self.rolling_base_timestamp
+    }
+
+    /// Append one batch whose header has already been stamped with its offsets.
+    pub fn append(&mut self, batch: &[u8]) -> io::Result<()> {
+        let position = BatchPosition::from_header(batch, self.size);
+        self.log.write_all(batch)?;
+        if self.rolling_base_timestamp.is_none() {
+            self.rolling_base_timestamp = Some(position.max_timestamp);
+        }
+        self.index(&position)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.log.flush()
+    }
+
+    /// Account for a batch written at the end of the log, adding index
+    /// entries every `index_interval_bytes`.
+    fn index(&mut self, batch: &BatchPosition) -> io::Result<()> {
+        let indexed = self
+            .offset_index
+            .last_entry()
+            .is_some_and(|(_, position)| position == batch.position);
+        if !indexed
+            && (self.offset_index.last_entry().is_none()
+                || self.bytes_since_last_index_entry >= self.index_interval_bytes)
+        {
+            self.offset_index
+                .append(batch.base_offset, batch.position)?;
+            self.bytes_since_last_index_entry = 0;
+        }
+        self.time_index
+            .maybe_append(batch.max_timestamp, batch.base_offset)?;
+        self.bytes_since_last_index_entry += batch.size;
+        self.size = batch.position + batch.size;
+        self.next_offset = batch.last_offset + 1;
+        Ok(())
+    }
+
+    /// Read whole batches, starting with the one that contains `fetch_offset`
+    /// and stopping before the first one at or past `end_offset`, under the
+    /// same size rules as `PartitionLog::read`.
+    pub fn read(
+        &self,
+        fetch_offset: i64,
+        end_offset: i64,
+        max_bytes: usize,
+        min_one_batch: bool,
+    ) -> io::Result<Vec<u8>> {
+        let start = self
+            .offset_index
+            .lookup(fetch_offset)
+            .map_or(0, |(_, position)| position);
+        let mut range: Option<(u64, u64)> = None;
+        for batch in BatchPositions::from(&self.log, start)? {
+            let batch = batch?;
+            if batch.base_offset >= end_offset {
+                break;
+            }
+            let batch_end = batch.position + batch.size;
+            range = match range {
+                None if batch.last_offset < fetch_offset => None,
+                None if batch.size as usize > max_bytes && !min_one_batch => break,
+                None => Some((batch.position, batch_end)),
+                Some((start, _)) if (batch_end - start) as usize > max_bytes => break,
+                Some((start, _)) => Some((start, batch_end)),
+            };
+        }
+        let Some((start, end)) = range else {
+            return Ok(Vec::new());
+        };
+        let mut buf = vec![0u8; (end - start) as usize];
+        self.log.read_exact_at(&mut buf, start)?;
+        Ok(buf)
+    }
+
+    /// First batch whose max timestamp is at least `timestamp`.
+    pub fn find_batch_by_timestamp(&self, timestamp: i64) -> io::Result<Option<BatchPosition>> {
+        let start_offset = self
+            .time_index
+            .lookup(timestamp)
+            .map_or(self.base_offset, |(_, offset)| offset);
+        let start = self
+            .offset_index
+            .lookup(start_offset)
+            .map_or(0, |(_, position)| position);
+        for batch in BatchPositions::from(&self.log, start)? {
+            let batch = batch?;
+            if batch.max_timestamp >= timestamp {
+                return Ok(Some(batch));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Largest timestamp in the segment and the base offset of the first
+    /// batch that reaches it, from the time index.
+    pub fn max_timestamp(&self) -> Option<(i64, i64)> {
+        self.time_index.last_entry()
+    }
+
+    /// Every batch of the segment, oldest first.
+    pub fn batches(&self) -> io::Result<Vec<BatchPosition>> {
+        BatchPositions::from(&self.log, 0)?.collect()
+    }
+
+    /// The whole of `batch`, header included.
+    pub fn read_batch(&self, batch: &BatchPosition) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0u8; batch.size as usize];
+        self.log.read_exact_at(&mut buf, batch.position)?;
+        Ok(buf)
+    }
+
+    /// Control type of the control batch at `batch`.
+    pub fn control_type(&self, batch: &BatchPosition) -> io::Result<Option<ControlRecordType>> {
+        let mut buf = vec![0u8; (batch.size as usize).min(CONTROL_BATCH_PREFIX_SIZE)];
+        self.log.read_exact_at(&mut buf, batch.position)?;
+        Ok(batch::control_type(&buf))
+    }
+
+    pub fn aborted_transactions(&self) -> &[AbortedTxn] {
+        self.txn_index.entries()
+    }
+
+    pub fn append_aborted_transaction(&mut self, aborted: AbortedTxn) -> io::Result<()> {
+        self.txn_index.append(aborted)
+    }
+
+    /// Replace the aborted transactions, once found by replaying the log.
+    pub fn reset_aborted_transactions(&mut self, aborted: Vec<AbortedTxn>) -> io::Result<()> {
+        self.txn_index.reset(aborted)
+    }
+}
+
+/// Remove the log and index files of the segment at `base_offset`.
+pub(super) fn delete_files(dir: &Path, base_offset: i64) -> io::Result<()> {
+    for extension in SEGMENT_FILE_EXTENSIONS {
+        match fs::remove_file(file_path(dir, base_offset, extension)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Path of the segment file with the given extension, named after the base
+/// offset zero padded to 20 digits.
+pub(super) fn file_path(dir: &Path, base_offset: i64, extension: &str) -> PathBuf {
+    dir.join(format!("{base_offset:020}.{extension}"))
+}